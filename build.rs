@@ -0,0 +1,5 @@
+// `sqlx::migrate!` embeds the migrations at compile time, so a new file in
+// migrations/ has to trigger a rebuild.
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+}
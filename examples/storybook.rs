@@ -2,18 +2,34 @@ use actix_web::{web, App, HttpResponse, HttpServer, Responder};
 use askama::Template;
 use chrono::Utc;
 use somerville_events::{
+    config::FooterLink,
     features::{
+        about::About,
         common::{
-            get_color_for_type, get_icon_for_type, EventLocation, EventTypeLink, EventViewModel,
-            SimpleEventViewModel,
+            get_icon_for_type, Clock, EventLocation, EventTypeLink, EventViewModel,
+            SimpleEventViewModel, TagLink,
         },
         upload::{SuccessTemplate, UploadTemplate},
-        view::{DaySection, IndexQuery, IndexTemplate, ShowTemplate},
+        view::{
+            DaySection, IndexQuery, IndexTemplate, LabeledValue, ListedEvent, RelatedSection,
+            ShowTemplate, WeeklySeries,
+        },
     },
-    models::EventType,
+    i18n::Lang,
+    models::{tel_link, EventSource, EventStatus, EventType, SourceInfo},
 };
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::sync::{LazyLock, Mutex};
+
+static ABOUT: LazyLock<About> = LazyLock::new(|| {
+    About::new(
+        "Events in Camberville. Locally made, and locally focused.\n\n<!-- more -->\n\nMore here.",
+        vec![FooterLink {
+            label: "Contact".to_string(),
+            url: "mailto:hello@example.com".to_string(),
+        }],
+    )
+});
 
 #[derive(Template)]
 #[template(
@@ -57,11 +73,20 @@ fn to_simple(vm: &EventViewModel) -> SimpleEventViewModel {
 
     SimpleEventViewModel {
         name: vm.name.clone(),
+        start_iso: vm.start_iso.clone(),
         start_formatted: vm.start_formatted.clone(),
         end_formatted: vm.end_formatted.clone(),
         location: vm.location.clone(),
         icon,
         detail_url: format!("/event/{}", vm.id),
+        status: vm.status,
+    }
+}
+
+fn to_listed(vm: &EventViewModel) -> ListedEvent {
+    ListedEvent {
+        event: to_simple(vm),
+        series: None,
     }
 }
 
@@ -73,6 +98,8 @@ async fn index() -> impl Responder {
 async fn story_upload() -> impl Responder {
     let template = UploadTemplate {
         idempotency_key: "00000000-0000-0000-0000-000000000000".to_string(),
+        page_idempotency_key: "00000000-0000-0000-0000-000000000001".to_string(),
+        admin: false,
     };
     HttpResponse::Ok()
         .content_type("text/html")
@@ -80,7 +107,7 @@ async fn story_upload() -> impl Responder {
 }
 
 async fn story_upload_success() -> impl Responder {
-    let template = SuccessTemplate;
+    let template = SuccessTemplate { pending: true };
     HttpResponse::Ok()
         .content_type("text/html")
         .body(template.render().unwrap())
@@ -98,6 +125,11 @@ struct MockEventBuilder {
     url: Option<String>,
     age_restrictions: Option<String>,
     price: Option<f64>,
+    contact_email: Option<String>,
+    contact_phone: Option<String>,
+    registration_required: bool,
+    tags: Vec<String>,
+    status: EventStatus,
 }
 
 impl MockEventBuilder {
@@ -110,6 +142,7 @@ impl MockEventBuilder {
                 name: "The Burren".to_string(),
                 address: "247 Elm St, Somerville, MA".to_string(),
                 google_maps_link: "#".to_string(),
+                venue_url: "#".to_string(),
             }),
             description: "A spooky halloween event.".to_string(),
             full_text: "Join us for a spooky night of fun! Costumes encouraged.".to_string(),
@@ -117,6 +150,11 @@ impl MockEventBuilder {
             url: Some("https://example.com".to_string()),
             age_restrictions: Some("21+".to_string()),
             price: Some(15.0),
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            tags: vec![],
+            status: EventStatus::Scheduled,
         }
     }
 
@@ -156,6 +194,27 @@ impl MockEventBuilder {
         self
     }
 
+    fn with_contact(mut self, email: &str, phone: &str) -> Self {
+        self.contact_email = Some(email.to_string());
+        self.contact_phone = Some(phone.to_string());
+        self
+    }
+
+    fn with_tags(mut self, tags: &[&str]) -> Self {
+        self.tags = tags.iter().map(|t| t.to_string()).collect();
+        self
+    }
+
+    fn with_registration_required(mut self) -> Self {
+        self.registration_required = true;
+        self
+    }
+
+    fn with_status(mut self, status: EventStatus) -> Self {
+        self.status = status;
+        self
+    }
+
     fn with_url(mut self, url: Option<String>) -> Self {
         self.url = url;
         self
@@ -174,7 +233,7 @@ impl MockEventBuilder {
                 url: format!("/view/filtered?type={}", t),
                 label: t.to_string(),
                 icon: get_icon_for_type(t).to_string(),
-                color: get_color_for_type(t),
+                value: t.value(),
             })
             .collect();
 
@@ -194,10 +253,28 @@ impl MockEventBuilder {
                 .filter(|s| !s.is_empty())
                 .collect(),
             event_types,
+            tags: self
+                .tags
+                .into_iter()
+                .map(|tag| TagLink {
+                    url: format!("/view/filtered?tag={}", tag),
+                    label: tag,
+                })
+                .collect(),
             website_link: self.url,
             google_calendar_url: "#".to_string(),
             age_restrictions: self.age_restrictions,
             price: self.price,
+            contact_phone_link: self
+                .contact_phone
+                .as_deref()
+                .map(tel_link)
+                .unwrap_or_default(),
+            contact_email: self.contact_email,
+            contact_url: None,
+            contact_phone: self.contact_phone,
+            registration_required: self.registration_required,
+            status: self.status,
         }
     }
 }
@@ -264,6 +341,9 @@ fn ensure_mock_events(data: &web::Data<StorybookState>) {
             .with_price(Some(100.50))
             .with_age(Some("18+".to_string()))
             .with_url(Some("https://example.com".to_string()))
+            .with_contact("info@example.com", "(617) 555-0123")
+            .with_registration_required()
+            .with_tags(&["vegan", "pop-up"])
             .with_types(vec![EventType::Music, EventType::Social])
             .build(id_counter),
 
@@ -326,6 +406,7 @@ fn ensure_mock_events(data: &web::Data<StorybookState>) {
                 name: "The Center for Very Long Addresses".to_string(),
                 address: "1234567890 This Street Name Is Intentionally Excessively Long To Test How The UI Handles Text Wrapping When The Content Exceeds The Container Width And Might Break The Layout If Not Handled Correctly, Somerville, MA 02144".to_string(),
                 google_maps_link: "#".to_string(),
+                venue_url: "#".to_string(),
             })
             .build(id_counter + 4),
 
@@ -334,6 +415,7 @@ fn ensure_mock_events(data: &web::Data<StorybookState>) {
                 name: "The Super Duper Extremely Long Place Name That Goes On Forever And Ever And Ever To Test UI Resilience".to_string(),
                 address: "123 Normal St, Somerville, MA".to_string(),
                 google_maps_link: "#".to_string(),
+                venue_url: "#".to_string(),
             })
             .build(id_counter + 5),
 
@@ -366,6 +448,14 @@ fn ensure_mock_events(data: &web::Data<StorybookState>) {
             .with_description("🎉 🎃 🦃 🎅 🎄 🎆 🎇 🧨 ✨ 🎈 🧧 🎍 🎎 🎏 🎐 🎑 🎒 🎓 🎖 🎗 🎙 🎚 🎛 🎚 🎙 🎚 🎛")
             .with_full_text("Zalgotext: T̶o̶ ̶i̶n̶v̶o̶k̶e̶ ̶t̶h̶e̶ ̶h̶i̶v̶e̶-m̶i̶n̶d̶ ̶r̶e̶p̶r̶e̶s̶e̶n̶t̶i̶n̶g̶ ̶c̶h̶a̶o̶s̶.\nIñtërnâtiônàlizætiøn\n\n(ノಠ益ಠ)ノ彡┻━┻")
             .build(id_counter + 10),
+
+        MockEventBuilder::new("Cancelled Event")
+            .with_status(EventStatus::Cancelled)
+            .build(id_counter + 11),
+
+        MockEventBuilder::new("Postponed Event")
+            .with_status(EventStatus::Postponed)
+            .build(id_counter + 12),
     ];
 
     for event in edge_case_events {
@@ -389,7 +479,7 @@ async fn story_view_index(data: web::Data<StorybookState>) -> impl Responder {
     days.push(DaySection {
         day_id: "day-types".to_string(),
         date_header: "All Event Types".to_string(),
-        events: type_events.iter().map(to_simple).collect(),
+        events: type_events.iter().map(to_listed).collect(),
     });
 
     // Group 2: Variations (next 5)
@@ -402,7 +492,24 @@ async fn story_view_index(data: web::Data<StorybookState>) -> impl Responder {
     days.push(DaySection {
         day_id: "day-variations".to_string(),
         date_header: "Field Variations".to_string(),
-        events: variation_events.iter().map(to_simple).collect(),
+        events: variation_events
+            .iter()
+            .enumerate()
+            .map(|(i, vm)| ListedEvent {
+                // One collapsed weekly series, to see how its dates fold.
+                series: (i == 0).then(|| WeeklySeries {
+                    label: "Every Tuesday".to_string(),
+                    dates: ["Tue, Mar 4", "Tue, Mar 11", "Tue, Mar 18", "Tue, Mar 25"]
+                        .iter()
+                        .map(|date| LabeledValue {
+                            value: format!("/event/{}", vm.id),
+                            label: date.to_string(),
+                        })
+                        .collect(),
+                }),
+                ..to_listed(vm)
+            })
+            .collect(),
     });
 
     // Group 3: Text (next 4)
@@ -415,12 +522,26 @@ async fn story_view_index(data: web::Data<StorybookState>) -> impl Responder {
     days.push(DaySection {
         day_id: "day-text".to_string(),
         date_header: "Text Lengths".to_string(),
-        events: text_events.iter().map(to_simple).collect(),
+        events: text_events.iter().map(to_listed).collect(),
+    });
+
+    // Group 4: Cancelled and postponed (the last two edge cases)
+    let status_events: Vec<EventViewModel> = all_events
+        .iter()
+        .filter(|e| e.status != EventStatus::Scheduled)
+        .map(|e| (*e).clone())
+        .collect();
+    days.push(DaySection {
+        day_id: "day-status".to_string(),
+        date_header: "Cancelled and Postponed".to_string(),
+        events: status_events.iter().map(to_listed).collect(),
     });
 
     let template = IndexTemplate {
+        featured: vec![],
         days,
         is_past_view: false,
+        heading: None,
         all_event_types: vec![],
         all_sources: vec![],
         all_locations: vec![],
@@ -430,7 +551,13 @@ async fn story_view_index(data: web::Data<StorybookState>) -> impl Responder {
         atom_url: "/events.atom".to_string(),
         webcal_url: "#".to_string(),
         https_url: "#".to_string(),
+        download_url: Some("#".to_string()),
         google_cal_link: "#".to_string(),
+        clock: Clock::default(),
+        lang: Lang::default(),
+        back_url: "/".to_string(),
+        show_intro: true,
+        about: &ABOUT,
     };
     HttpResponse::Ok()
         .content_type("text/html")
@@ -441,9 +568,15 @@ async fn story_view_show(data: web::Data<StorybookState>, path: web::Path<i64>)
     ensure_mock_events(&data);
     let events_map = data.events.lock().unwrap();
 
-    if let Some(event) = events_map.get(&path.into_inner()) {
+    let id = path.into_inner();
+    if let Some(event) = events_map.get(&id) {
         let template = ShowTemplate {
             event: event.clone(),
+            page_url: format!("/event/{id}"),
+            card_url: format!("/event/{id}/card.png"),
+            related: Vec::new(),
+            source: None,
+            lang: Lang::default(),
         };
         HttpResponse::Ok()
             .content_type("text/html")
@@ -459,6 +592,44 @@ async fn story_view_show_default() -> impl Responder {
             .with_full_text("This is the full text view.\n\nIt supports multiple paragraphs.\n\nAnd lists all details.")
             .with_types(vec![EventType::Art, EventType::Food])
             .build(999),
+        page_url: "/event/999".to_string(),
+        card_url: "/event/999/card.png".to_string(),
+        source: Some(SourceInfo {
+            source: EventSource::SomervilleTheatre,
+            display_name: "Somerville Theatre".to_string(),
+            homepage_url: Some("https://www.somervilletheatre.com".to_string()),
+            feed_name: None,
+            default_event_type: None,
+            default_confidence: None,
+        }),
+        related: vec![
+            RelatedSection {
+                id: "more-at-venue".to_string(),
+                heading: "More at this venue".to_string(),
+                events: vec![to_simple(
+                    &MockEventBuilder::new("Open Studio Night")
+                        .with_types(vec![EventType::Art])
+                        .build(1000),
+                )],
+            },
+            RelatedSection {
+                id: "more-food".to_string(),
+                heading: "Other Food events".to_string(),
+                events: vec![
+                    to_simple(
+                        &MockEventBuilder::new("Dumpling Pop-up")
+                            .with_types(vec![EventType::Food])
+                            .build(1001),
+                    ),
+                    to_simple(
+                        &MockEventBuilder::new("Farmers Market")
+                            .with_types(vec![EventType::Food, EventType::Market])
+                            .build(1002),
+                    ),
+                ],
+            },
+        ],
+        lang: Lang::default(),
     };
     HttpResponse::Ok()
         .content_type("text/html")
@@ -482,12 +653,14 @@ async fn story_view_filtered(data: web::Data<StorybookState>) -> impl Responder
         .collect();
 
     let example_1 = IndexTemplate {
+        featured: vec![],
         days: vec![DaySection {
             day_id: "day-1".to_string(),
             date_header: "Filtered Results".to_string(),
-            events: music_social_events.iter().map(to_simple).collect(),
+            events: music_social_events.iter().map(to_listed).collect(),
         }],
         is_past_view: false,
+        heading: None,
         all_event_types: vec![],
         all_sources: vec![],
         all_locations: vec![],
@@ -500,19 +673,27 @@ async fn story_view_filtered(data: web::Data<StorybookState>) -> impl Responder
         atom_url: "/events.atom?type=music&type=social".to_string(),
         webcal_url: "#".to_string(),
         https_url: "#".to_string(),
+        download_url: Some("#".to_string()),
         google_cal_link: "#".to_string(),
+        clock: Clock::default(),
+        lang: Lang::default(),
+        back_url: "/".to_string(),
+        show_intro: false,
+        about: &ABOUT,
     };
 
     // Example 2: Past Events
     let past_events: Vec<EventViewModel> =
         all_events.iter().take(3).map(|e| (*e).clone()).collect();
     let example_2 = IndexTemplate {
+        featured: vec![],
         days: vec![DaySection {
             day_id: "day-past".to_string(),
             date_header: "Yesterday".to_string(),
-            events: past_events.iter().map(to_simple).collect(),
+            events: past_events.iter().map(to_listed).collect(),
         }],
         is_past_view: true,
+        heading: None,
         all_event_types: vec![],
         all_sources: vec![],
         all_locations: vec![],
@@ -522,7 +703,13 @@ async fn story_view_filtered(data: web::Data<StorybookState>) -> impl Responder
         atom_url: "/events.atom?past=true".to_string(),
         webcal_url: "#".to_string(),
         https_url: "#".to_string(),
+        download_url: Some("#".to_string()),
         google_cal_link: "#".to_string(),
+        clock: Clock::default(),
+        lang: Lang::default(),
+        back_url: "/".to_string(),
+        show_intro: false,
+        about: &ABOUT,
     };
 
     let html = format!(
@@ -560,6 +747,8 @@ async fn story_view_details_gallery(data: web::Data<StorybookState>) -> impl Res
         40, // Zero Types
         41, // HTML Injection
         42, // Unicode/Emoji
+        43, // Cancelled
+        44, // Postponed
     ];
 
     let mut html = String::from("<h1>Details View Gallery</h1><p>Rendering multiple detail views sequentially to verify edge cases.</p>");
@@ -568,6 +757,11 @@ async fn story_view_details_gallery(data: web::Data<StorybookState>) -> impl Res
         if let Some(event) = events_map.get(&id) {
             let template = ShowTemplate {
                 event: event.clone(),
+                page_url: format!("/event/{id}"),
+                card_url: format!("/event/{id}/card.png"),
+                related: Vec::new(),
+                source: None,
+                lang: Lang::default(),
             };
             html.push_str(&format!("<hr><h2>Event ID {}: {}</h2>", id, event.name));
             html.push_str(&template.render().unwrap());
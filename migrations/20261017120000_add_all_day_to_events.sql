@@ -0,0 +1 @@
+ALTER TABLE app.events ADD COLUMN all_day BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,3 @@
+INSERT INTO app.source_names (name, url) VALUES
+('CityOfSomerville', 'https://www.somervillema.gov')
+ON CONFLICT DO NOTHING;
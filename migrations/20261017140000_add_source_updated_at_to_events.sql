@@ -0,0 +1,3 @@
+-- When the source last changed this event, as reported by the feed. Kept
+-- separate from updated_at, which tracks our own writes.
+ALTER TABLE app.events ADD COLUMN source_updated_at TIMESTAMPTZ;
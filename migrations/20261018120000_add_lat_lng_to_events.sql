@@ -0,0 +1,4 @@
+-- Coordinates of the event's location, so events can be plotted on a map.
+-- Null when we couldn't geocode the location.
+ALTER TABLE app.events ADD COLUMN lat DOUBLE PRECISION;
+ALTER TABLE app.events ADD COLUMN lng DOUBLE PRECISION;
@@ -0,0 +1,5 @@
+-- Contact details and registration flag from the ingest feed. Uploaded and
+-- hand-entered events leave these empty.
+ALTER TABLE app.events ADD COLUMN contact_email TEXT;
+ALTER TABLE app.events ADD COLUMN contact_phone TEXT;
+ALTER TABLE app.events ADD COLUMN registration_required BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,6 @@
+-- Free-form keywords ("vegan", "queer", "pop-up") that the fixed event
+-- types can't express. Always lowercase and deduplicated by the app.
+ALTER TABLE app.events ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+-- For the ?tag= filter, which uses array containment.
+CREATE INDEX idx_events_tags ON app.events USING GIN (tags);
@@ -0,0 +1,13 @@
+-- Problems with an event reported by visitors (wrong date, spam...), kept
+-- until an admin deals with them. They go with the event if it's deleted.
+CREATE TABLE app.user_reports (
+    id BIGSERIAL PRIMARY KEY,
+    event_id BIGINT NOT NULL REFERENCES app.events(id) ON DELETE CASCADE,
+    reason TEXT NOT NULL,
+    email TEXT,
+    -- Only used to rate-limit reports from one address.
+    reporter_ip TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_user_reports_reporter_ip ON app.user_reports (reporter_ip, created_at);
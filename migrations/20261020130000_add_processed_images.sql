@@ -0,0 +1,9 @@
+-- Perceptual hashes of flyers we've already sent to the LLM, so a second
+-- upload of the same poster can skip that call. `event_ids` are what the
+-- first upload produced.
+CREATE TABLE app.processed_images (
+    id BIGSERIAL PRIMARY KEY,
+    dhash BIGINT NOT NULL,
+    event_ids BIGINT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,3 @@
+-- The ?q= search also matches the text read off a flyer, which catches
+-- performers and venues that only appear in the small print.
+CREATE INDEX IF NOT EXISTS idx_events_full_text_trgm ON app.events USING gin (full_text gin_trgm_ops);
@@ -0,0 +1,6 @@
+-- Deleting an event only sets deleted_at, so an admin can undo it from the
+-- trash. Rows that have been there a while are purged for good.
+ALTER TABLE app.events ADD COLUMN deleted_at TIMESTAMPTZ;
+
+CREATE INDEX idx_events_deleted_at ON app.events (deleted_at)
+    WHERE deleted_at IS NOT NULL;
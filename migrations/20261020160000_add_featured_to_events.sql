@@ -0,0 +1,6 @@
+-- Admin-picked events shown above the day-by-day list on the front page.
+-- They still appear on their own day too.
+ALTER TABLE app.events ADD COLUMN featured BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX idx_events_featured ON app.events (start_date)
+    WHERE featured AND deleted_at IS NULL;
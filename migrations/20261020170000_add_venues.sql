@@ -0,0 +1,33 @@
+-- One row per place Google knows, so a venue's name and address live in
+-- one spot instead of being copied, with slight variations, onto every
+-- event there. Events keep their own location columns as what the flyer
+-- or feed said; `google_place_id` now has to point at a venue.
+CREATE TABLE app.venues (
+    google_place_id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    address TEXT,
+    lat DOUBLE PRECISION,
+    lng DOUBLE PRECISION,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- The most common spelling wins, and the most recently updated event's
+-- coordinates.
+INSERT INTO app.venues (google_place_id, name, address, lat, lng)
+SELECT
+    google_place_id,
+    COALESCE(
+        mode() WITHIN GROUP (ORDER BY location_name),
+        mode() WITHIN GROUP (ORDER BY address),
+        google_place_id
+    ),
+    mode() WITHIN GROUP (ORDER BY address),
+    (array_agg(lat ORDER BY updated_at DESC) FILTER (WHERE lat IS NOT NULL))[1],
+    (array_agg(lng ORDER BY updated_at DESC) FILTER (WHERE lng IS NOT NULL))[1]
+FROM app.events
+WHERE google_place_id IS NOT NULL
+GROUP BY google_place_id;
+
+ALTER TABLE app.events
+    ADD CONSTRAINT events_google_place_id_fkey
+    FOREIGN KEY (google_place_id) REFERENCES app.venues (google_place_id);
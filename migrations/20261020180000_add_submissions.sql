@@ -0,0 +1,16 @@
+-- Flyers sent in by the public wait here until an admin approves them.
+-- Pending events are left out of everything visitors see. Whoever sent
+-- one in can leave a name and email, kept with the event and with the
+-- upload's idempotency key, which is the only record of a submission
+-- that turned out to have no events on it.
+ALTER TABLE app.events
+    ADD COLUMN pending BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN submitter_name TEXT,
+    ADD COLUMN submitter_email TEXT;
+
+CREATE INDEX idx_events_pending ON app.events (created_at)
+    WHERE pending AND deleted_at IS NULL;
+
+ALTER TABLE app.idempotency_keys
+    ADD COLUMN submitter_name TEXT,
+    ADD COLUMN submitter_email TEXT;
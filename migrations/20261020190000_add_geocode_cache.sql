@@ -0,0 +1,17 @@
+-- What Google said about each raw address the ingest has looked up, so
+-- the next run doesn't pay for it again. A row with no place_id means
+-- Google had no match. Lookups that errored are never stored, so the next
+-- run tries them again.
+CREATE TABLE app.geocode_cache (
+    address TEXT PRIMARY KEY,
+    place_id TEXT,
+    name TEXT,
+    formatted_address TEXT,
+    lat DOUBLE PRECISION,
+    lng DOUBLE PRECISION,
+    looked_up_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CHECK (
+        (place_id IS NULL AND name IS NULL AND formatted_address IS NULL AND lat IS NULL AND lng IS NULL)
+        OR (place_id IS NOT NULL AND name IS NOT NULL AND formatted_address IS NOT NULL AND lat IS NOT NULL AND lng IS NOT NULL)
+    )
+);
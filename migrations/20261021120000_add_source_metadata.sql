@@ -0,0 +1,39 @@
+-- What the site shows and assumes about each source, kept with the source
+-- so it can change without a deploy.
+ALTER TABLE app.source_names
+    ADD COLUMN display_name TEXT,
+    -- What the aggregator feed calls the source, for ingest to match on.
+    ADD COLUMN feed_name TEXT UNIQUE,
+    -- For feed events whose category doesn't map to an event type.
+    ADD COLUMN default_event_type TEXT REFERENCES app.event_types(name),
+    ADD COLUMN default_confidence DOUBLE PRECISION
+        CHECK (default_confidence >= 0 AND default_confidence <= 1);
+
+UPDATE app.source_names SET display_name = d.display_name, feed_name = d.feed_name
+FROM (VALUES
+    ('AeronautBrewing', 'Aeronaut Brewing', 'Aeronaut Brewing'),
+    ('AmericanRepertoryTheater', 'American Repertory Theater', 'American Repertory Theater'),
+    ('ArtsAtTheArmory', 'Arts at the Armory', 'Arts at the Armory'),
+    ('BostonSwingCentral', 'Boston Swing Central', 'Boston Swing Central'),
+    ('BostonShowsOrg', 'BostonShows.org', 'BostonShows.org'),
+    ('BrattleTheatre', 'Brattle Theatre', 'Brattle Theatre'),
+    ('CentralSquareTheater', 'Central Square Theater', 'Central Square Theater'),
+    ('CityOfCambridge', 'City of Cambridge', 'City of Cambridge'),
+    ('CityOfSomerville', 'City of Somerville', 'City of Somerville'),
+    ('FirstParishInCambridge', 'First Parish in Cambridge', 'First Parish in Cambridge'),
+    ('GrolierPoetryBookShop', 'Grolier Poetry Book Shop', 'Grolier Poetry Book Shop'),
+    ('HarvardArtMuseums', 'Harvard Art Museums', 'Harvard Art Museums'),
+    ('HarvardBookStore', 'Harvard Book Store', 'Harvard Book Store'),
+    ('ImageUpload', 'Image Upload', NULL),
+    ('LamplighterBrewing', 'Lamplighter Brewing', 'Lamplighter Brewing'),
+    ('PorterSquareBooks', 'Porter Square Books', 'Porter Square Books'),
+    ('PorticoBrewing', 'Portico Brewing', 'Portico Brewing'),
+    ('SandersTheatre', 'Sanders Theatre', 'Sanders Theatre'),
+    ('SomervilleTheatre', 'Somerville Theatre', 'Somerville Theatre'),
+    ('TheComedyStudio', 'The Comedy Studio', 'The Comedy Studio'),
+    ('TheDanceComplex', 'The Dance Complex', NULL),
+    ('TheLilyPad', 'The Lily Pad', 'The Lily Pad'),
+    ('TheMiddleEast', 'The Middle East', 'The Middle East'),
+    ('UserSubmitted', 'User Submitted', 'User Submitted')
+) AS d (name, display_name, feed_name)
+WHERE app.source_names.name = d.name;
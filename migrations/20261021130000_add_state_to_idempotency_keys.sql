@@ -0,0 +1,10 @@
+-- How far each upload got. Only one that finished blocks its key for good;
+-- a failed one, or one claimed so long ago its task must have died, can
+-- be tried again with the same key. Keys from before this were all
+-- handled one way or another, so they count as finished.
+ALTER TABLE app.idempotency_keys
+    ADD COLUMN state TEXT NOT NULL DEFAULT 'succeeded'
+        CHECK (state IN ('claimed', 'succeeded', 'failed')),
+    ADD COLUMN claimed_at TIMESTAMPTZ NOT NULL DEFAULT now();
+
+UPDATE app.idempotency_keys SET claimed_at = created_at;
@@ -0,0 +1,16 @@
+-- Messages readers sent an event's organizer through the site. The
+-- organizer's address is never shown, so this is the only record of who
+-- wrote to whom, kept for dealing with abuse. They go with the event if
+-- it's deleted.
+CREATE TABLE app.contact_messages (
+    id BIGSERIAL PRIMARY KEY,
+    event_id BIGINT NOT NULL REFERENCES app.events(id) ON DELETE CASCADE,
+    sender_email TEXT NOT NULL,
+    message TEXT NOT NULL,
+    -- For rate-limiting one address.
+    sender_ip TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_contact_messages_sender_ip ON app.contact_messages (sender_ip, created_at);
+CREATE INDEX idx_contact_messages_event_id ON app.contact_messages (event_id, created_at);
@@ -0,0 +1,6 @@
+-- Whether an event is still happening. A cancelled or postponed event stays
+-- listed, marked as such, so people who planned on it find out; deleting it
+-- would just make it vanish.
+ALTER TABLE app.events
+    ADD COLUMN status TEXT NOT NULL DEFAULT 'scheduled'
+        CHECK (status IN ('scheduled', 'cancelled', 'postponed'));
@@ -0,0 +1,180 @@
+use actix_web::web;
+use argon2::password_hash::PasswordHash;
+use argon2::{Argon2, PasswordVerifier};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use subtle::ConstantTimeEq;
+
+/// Wrong passwords one address can try before it's turned away. Enough
+/// for an admin fumbling a paste; far too few to guess a password with.
+const MAX_FAILED_LOGINS: usize = 10;
+
+/// How long a failed login counts against its address.
+pub const FAILED_LOGIN_WINDOW: Duration = Duration::from_secs(15 * 60);
+
+/// `BASIC_AUTH_PASS` can hold an Argon2 hash in PHC format
+/// (`$argon2id$v=19$...`) instead of the password itself, so the server
+/// never has the plaintext. Anything else is treated as a plaintext
+/// password, which is how older deployments are configured.
+pub fn is_password_hash(configured: &str) -> bool {
+    configured.starts_with("$argon2")
+}
+
+/// Checks a basic-auth login against the configured one. Plain `==` bails
+/// out at the first differing byte, which lets an attacker recover the
+/// password one character at a time by timing responses. The username and
+/// password are both always checked for the same reason.
+pub fn credentials_match(
+    configured_username: &str,
+    configured_password: &str,
+    username: &str,
+    password: &str,
+) -> bool {
+    let username_ok: bool = username
+        .as_bytes()
+        .ct_eq(configured_username.as_bytes())
+        .into();
+    let password_ok = password_matches(configured_password, password);
+    username_ok & password_ok
+}
+
+/// `credentials_match` on the blocking thread pool. Argon2 is made to take
+/// tens of milliseconds of CPU, and run on an async worker that would stall
+/// every other request the worker is serving.
+pub async fn verify_credentials(
+    configured_username: &str,
+    configured_password: &str,
+    username: &str,
+    password: &str,
+) -> bool {
+    let configured_username = configured_username.to_string();
+    let configured_password = configured_password.to_string();
+    let username = username.to_string();
+    let password = password.to_string();
+    web::block(move || {
+        credentials_match(
+            &configured_username,
+            &configured_password,
+            &username,
+            &password,
+        )
+    })
+    .await
+    .unwrap_or_else(|e| {
+        log::error!("Failed to check credentials: {e}");
+        false
+    })
+}
+
+/// Failed logins by address, across the login form and basic auth, so the
+/// admin password can't be guessed online. In memory: a restart forgets
+/// them, which only gives an attacker one more window's worth.
+#[derive(Default)]
+pub struct LoginThrottle {
+    failures: Mutex<HashMap<String, Vec<Instant>>>,
+}
+
+impl LoginThrottle {
+    /// Whether `ip` has used up its tries. Checked before the password, so
+    /// a locked-out address doesn't get Argon2 run for it either.
+    pub fn is_locked(&self, ip: &str) -> bool {
+        let mut failures = self.failures.lock().unwrap();
+        let Some(times) = failures.get_mut(ip) else {
+            return false;
+        };
+        times.retain(|at| at.elapsed() < FAILED_LOGIN_WINDOW);
+        times.len() >= MAX_FAILED_LOGINS
+    }
+
+    pub fn record_failure(&self, ip: &str) {
+        let mut failures = self.failures.lock().unwrap();
+        // Each guessing address leaves an entry, so sweep out the ones
+        // that have run out rather than keep them all.
+        failures.retain(|_, times| {
+            times.retain(|at| at.elapsed() < FAILED_LOGIN_WINDOW);
+            !times.is_empty()
+        });
+        failures
+            .entry(ip.to_string())
+            .or_default()
+            .push(Instant::now());
+    }
+
+    pub fn record_success(&self, ip: &str) {
+        self.failures.lock().unwrap().remove(ip);
+    }
+}
+
+fn password_matches(configured: &str, candidate: &str) -> bool {
+    if !is_password_hash(configured) {
+        return candidate.as_bytes().ct_eq(configured.as_bytes()).into();
+    }
+
+    // Config::validate rejects malformed hashes, so this only fails if the
+    // environment changed underneath us.
+    match PasswordHash::new(configured) {
+        Ok(hash) => Argon2::default()
+            .verify_password(candidate.as_bytes(), &hash)
+            .is_ok(),
+        Err(e) => {
+            log::error!("BASIC_AUTH_PASS is not a valid Argon2 hash: {e}");
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use argon2::password_hash::{PasswordHasher, SaltString};
+
+    #[test]
+    fn test_plaintext_password() {
+        assert!(credentials_match("admin", "hunter2", "admin", "hunter2"));
+        assert!(!credentials_match("admin", "hunter2", "admin", "hunter3"));
+        assert!(!credentials_match("admin", "hunter2", "admin", "hunter"));
+        assert!(!credentials_match("admin", "hunter2", "root", "hunter2"));
+        assert!(!credentials_match("admin", "hunter2", "admin", ""));
+    }
+
+    #[test]
+    fn test_hashed_password() {
+        let salt = SaltString::encode_b64(b"somerville-salt").unwrap();
+        let hash = Argon2::default()
+            .hash_password(b"hunter2", &salt)
+            .unwrap()
+            .to_string();
+        assert!(is_password_hash(&hash));
+
+        assert!(credentials_match("admin", &hash, "admin", "hunter2"));
+        assert!(!credentials_match("admin", &hash, "admin", "hunter3"));
+        assert!(!credentials_match("admin", &hash, "root", "hunter2"));
+        // The hash itself is not a valid password.
+        assert!(!credentials_match("admin", &hash, "admin", &hash));
+    }
+
+    #[test]
+    fn test_login_throttle() {
+        let throttle = LoginThrottle::default();
+        for _ in 0..MAX_FAILED_LOGINS {
+            assert!(!throttle.is_locked("203.0.113.5"));
+            throttle.record_failure("203.0.113.5");
+        }
+        assert!(throttle.is_locked("203.0.113.5"));
+        assert!(!throttle.is_locked("192.0.2.1"));
+
+        throttle.record_success("192.0.2.1");
+        assert!(throttle.is_locked("203.0.113.5"));
+    }
+
+    #[test]
+    fn test_malformed_hash_rejects_everything() {
+        assert!(!credentials_match(
+            "admin",
+            "$argon2id$nope",
+            "admin",
+            "$argon2id$nope"
+        ));
+    }
+}
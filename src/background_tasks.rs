@@ -0,0 +1,178 @@
+//! Work that outlives the request that started it, like parsing an uploaded
+//! flyer. Tracking it lets a shutdown wait for that work to finish instead of
+//! dropping it halfway through an OpenAI call.
+
+use actix_web::rt::time::{sleep, Instant};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+#[derive(Clone, Default)]
+pub struct BackgroundTasks {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    running: AtomicUsize,
+    shutting_down: AtomicBool,
+}
+
+/// Decrements the running count however the task ends, including being
+/// dropped mid-flight when its worker stops.
+struct RunningGuard(Arc<Inner>);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.running.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl BackgroundTasks {
+    /// Spawns `task` on the current worker. Returns false, without running
+    /// it, once shutdown has started.
+    pub fn spawn<F>(&self, task: F) -> bool
+    where
+        F: Future<Output = ()> + 'static,
+    {
+        // Count first, then check, so `shutdown` can never see zero running
+        // tasks while one is about to start.
+        self.inner.running.fetch_add(1, Ordering::SeqCst);
+        let guard = RunningGuard(self.inner.clone());
+        if self.is_shutting_down() {
+            return false;
+        }
+
+        actix_web::rt::spawn(async move {
+            let _guard = guard;
+            task.await;
+        });
+        true
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.inner.shutting_down.load(Ordering::SeqCst)
+    }
+
+    pub fn running(&self) -> usize {
+        self.inner.running.load(Ordering::SeqCst)
+    }
+
+    /// Stops accepting new tasks and waits up to `timeout` for the running
+    /// ones to finish. Returns how many were still running at the deadline.
+    pub async fn shutdown(&self, timeout: Duration) -> usize {
+        self.inner.shutting_down.store(true, Ordering::SeqCst);
+
+        let deadline = Instant::now() + timeout;
+        while self.running() > 0 && Instant::now() < deadline {
+            sleep(Duration::from_millis(100)).await;
+        }
+        self.running()
+    }
+}
+
+/// Caps how many of one kind of work run at once. The rest wait their
+/// turn, first come first served.
+pub struct ConcurrencyLimit {
+    permits: Semaphore,
+    waiting: AtomicUsize,
+}
+
+/// Counts a caller as waiting until it gets a permit or gives up.
+struct WaitingGuard<'a>(&'a AtomicUsize);
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl ConcurrencyLimit {
+    /// At least one runs at a time, whatever `limit` says.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            permits: Semaphore::new(limit.max(1)),
+            waiting: AtomicUsize::new(0),
+        }
+    }
+
+    /// Waits for a turn. The work runs until the permit is dropped.
+    pub async fn acquire(&self) -> SemaphorePermit<'_> {
+        self.waiting.fetch_add(1, Ordering::SeqCst);
+        let _guard = WaitingGuard(&self.waiting);
+        self.permits
+            .acquire()
+            .await
+            .expect("the semaphore is never closed")
+    }
+
+    /// How many callers are queued behind the ones running.
+    pub fn waiting(&self) -> usize {
+        self.waiting.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[actix_web::test]
+    async fn test_shutdown_waits_for_running_tasks() {
+        let tasks = BackgroundTasks::default();
+
+        assert!(tasks.spawn(async {
+            sleep(Duration::from_millis(200)).await;
+        }));
+        assert_eq!(tasks.running(), 1);
+
+        let remaining = tasks.shutdown(Duration::from_secs(5)).await;
+        assert_eq!(remaining, 0);
+
+        assert!(
+            !tasks.spawn(async {}),
+            "New tasks should be refused after shutdown"
+        );
+        assert_eq!(tasks.running(), 0);
+    }
+
+    #[actix_web::test]
+    async fn test_shutdown_gives_up_after_timeout() {
+        let tasks = BackgroundTasks::default();
+
+        tasks.spawn(async {
+            sleep(Duration::from_secs(60)).await;
+        });
+
+        let remaining = tasks.shutdown(Duration::from_millis(100)).await;
+        assert_eq!(remaining, 1);
+    }
+
+    #[actix_web::test]
+    async fn test_concurrency_limit_queues_the_rest_in_order() {
+        let limit = std::rc::Rc::new(ConcurrencyLimit::new(2));
+        let order = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+
+        let first = limit.acquire().await;
+        let _second = limit.acquire().await;
+        assert_eq!(limit.waiting(), 0);
+
+        for n in [3, 4] {
+            let (limit, order) = (limit.clone(), order.clone());
+            actix_web::rt::spawn(async move {
+                let _permit = limit.acquire().await;
+                order.borrow_mut().push(n);
+                sleep(Duration::from_millis(10)).await;
+            });
+        }
+        actix_web::rt::task::yield_now().await;
+        assert_eq!(limit.waiting(), 2);
+        assert!(order.borrow().is_empty());
+
+        drop(first);
+        sleep(Duration::from_millis(50)).await;
+        assert_eq!(limit.waiting(), 0);
+        assert_eq!(*order.borrow(), vec![3, 4]);
+    }
+}
@@ -0,0 +1,164 @@
+//! The newline-delimited JSON format behind `/edit/export.json` and the
+//! `import_events` command: one full `Event` per line, so a backup can be
+//! restored or used to seed a fresh instance.
+
+use crate::database::{find_duplicate, save_event_with_outcome, SaveOutcome};
+use crate::models::{Event, NewEvent};
+use anyhow::{anyhow, Result};
+use std::io::BufRead;
+
+/// Appends each event as one line of JSON.
+pub fn write_ndjson(events: &[Event], out: &mut Vec<u8>) -> Result<()> {
+    for event in events {
+        serde_json::to_writer(&mut *out, event)?;
+        out.push(b'\n');
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportCounts {
+    pub inserted: usize,
+    pub deduplicated: usize,
+    pub failed: usize,
+}
+
+/// Inserts every exported event through the same duplicate check as any
+/// other new event, so importing into a populated database, or importing
+/// the same file twice, doesn't double anything up. Ids and timestamps are
+/// assigned afresh. A bad line is logged and counted rather than aborting
+/// the rest of the file.
+pub async fn import_events(
+    pool: &sqlx::Pool<sqlx::Postgres>,
+    reader: impl BufRead,
+    dry_run: bool,
+) -> Result<ImportCounts> {
+    let mut counts = ImportCounts::default();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| anyhow!("Failed to read line {}: {e}", index + 1))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let event: NewEvent = match serde_json::from_str::<Event>(&line) {
+            Ok(event) => event.into(),
+            Err(e) => {
+                log::warn!("Skipping line {}: {e}", index + 1);
+                counts.failed += 1;
+                continue;
+            }
+        };
+
+        if dry_run {
+            match find_duplicate(pool, &event).await {
+                Ok(Some(_)) => counts.deduplicated += 1,
+                Ok(None) => counts.inserted += 1,
+                Err(e) => {
+                    log::error!("Failed to check '{}': {e:#}", event.name);
+                    counts.failed += 1;
+                }
+            }
+            continue;
+        }
+
+        match save_event_with_outcome(pool, &event).await {
+            Ok(SaveOutcome::Inserted(_)) => counts.inserted += 1,
+            Ok(SaveOutcome::Duplicate(_)) => counts.deduplicated += 1,
+            Err(e) => {
+                log::error!("Failed to import '{}': {e:#}", event.name);
+                counts.failed += 1;
+            }
+        }
+    }
+
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{save_event_to_db, EventsRepo};
+    use crate::models::{EventSource, EventStatus, EventType};
+    use chrono::{TimeZone, Utc};
+
+    fn new_event(name: &str, day: u32) -> NewEvent {
+        NewEvent {
+            name: name.to_string(),
+            description: format!("All about {name}"),
+            full_text: "".to_string(),
+            start_date: Utc.with_ymd_and_hms(2024, 5, day, 18, 0, 0).unwrap(),
+            end_date: None,
+            all_day: false,
+            address: Some("1 Davis Sq, Somerville, MA".to_string()),
+            original_location: Some("Davis".to_string()),
+            google_place_id: None,
+            lat: Some(42.396),
+            lng: Some(-71.122),
+            location_name: None,
+            event_types: vec![EventType::Music],
+            tags: vec!["jazz".to_string()],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: Some(5.0),
+            source: EventSource::ImageUpload,
+            external_id: Some(format!("ext-{day}")),
+            contact_email: Some("hi@example.org".to_string()),
+            contact_phone: None,
+            registration_required: true,
+            status: EventStatus::Scheduled,
+        }
+    }
+
+    #[sqlx::test]
+    async fn test_export_then_import_preserves_events(pool: sqlx::PgPool) -> Result<()> {
+        for (name, day) in [("Jazz Night", 1), ("Open Mic", 2), ("Swing Dance", 3)] {
+            save_event_to_db(&pool, &new_event(name, day)).await?;
+        }
+        let exported_events = pool.list_all_after(0, 100).await?;
+        let mut exported = Vec::new();
+        write_ndjson(&exported_events, &mut exported)?;
+
+        sqlx::query!("DELETE FROM app.events")
+            .execute(&pool)
+            .await?;
+
+        let dry = import_events(&pool, exported.as_slice(), true).await?;
+        assert_eq!(dry.inserted, 3);
+        assert!(pool.list_all_after(0, 100).await?.is_empty());
+
+        let counts = import_events(&pool, exported.as_slice(), false).await?;
+        assert_eq!(
+            counts,
+            ImportCounts {
+                inserted: 3,
+                deduplicated: 0,
+                failed: 0
+            }
+        );
+        let imported = pool.list_all_after(0, 100).await?;
+        assert_eq!(imported.len(), exported_events.len());
+        for (before, after) in exported_events.iter().zip(&imported) {
+            assert_eq!(
+                NewEvent::from(before.clone()),
+                NewEvent::from(after.clone())
+            );
+        }
+
+        // A second run finds everything already there.
+        let mut with_junk = exported.clone();
+        with_junk.extend_from_slice(b"not json\n");
+        let counts = import_events(&pool, with_junk.as_slice(), false).await?;
+        assert_eq!(
+            counts,
+            ImportCounts {
+                inserted: 0,
+                deduplicated: 3,
+                failed: 1
+            }
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+//! One-off maintenance: geocodes events that have a written-down location
+//! but never got matched to a place, e.g. ones saved before geocoding was
+//! reliable. Safe to run again; anything it resolves drops out of the next
+//! run, and failed lookups are simply tried again.
+//!
+//! Usage: cargo run --bin backfill_geocode [-- --dry-run]
+
+use anyhow::{anyhow, Result};
+use chrono::Utc;
+use somerville_events::{
+    config::Config,
+    database::{cache_geocodes, cached_geocodes, events_missing_place_ids, set_event_place},
+    geocoding::{
+        canonicalize_address, canonicalize_addresses, queries_by_venue_key, venue_key, Geocoded,
+        RETRY_DELAY,
+    },
+};
+use std::collections::BTreeMap;
+
+#[actix_web::main]
+async fn main() -> Result<()> {
+    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+
+    let dry_run = std::env::args().any(|arg| arg == "--dry-run");
+    if dry_run {
+        log::info!("Running in DRY-RUN mode. No changes will be saved to DB and no Geocoding API calls will be made.");
+    }
+
+    let config = Config::from_env();
+    let pool = config
+        .pool_options()
+        .connect(&config.get_db_url())
+        .await
+        .map_err(|e| anyhow!("Failed to connect to database: {}", e))?;
+
+    // Many events share a location, or spell one venue differently, so
+    // each venue is looked up once, under its key.
+    let missing = events_missing_place_ids(&pool).await?;
+    let queries = queries_by_venue_key(missing.iter().map(|(_, location)| location.clone()));
+    let mut ids_by_location: BTreeMap<String, Vec<i64>> = BTreeMap::new();
+    for (id, location) in missing {
+        let key = venue_key(&location);
+        if !key.is_empty() {
+            ids_by_location.entry(key).or_default().push(id);
+        }
+    }
+    let event_count: usize = ids_by_location.values().map(Vec::len).sum();
+
+    let locations: Vec<String> = ids_by_location.keys().cloned().collect();
+    let mut geocodes = cached_geocodes(&pool, &locations, Utc::now()).await?;
+    let to_look_up: Vec<String> = if config.geocoding_enabled {
+        locations
+            .into_iter()
+            .filter(|location| !geocodes.contains_key(location))
+            .collect()
+    } else {
+        log::warn!("Geocoding is off, so only cached locations will get a place");
+        Vec::new()
+    };
+
+    log::info!(
+        "Found {} events without a place across {} locations ({} cached, {} to look up)",
+        event_count,
+        ids_by_location.len(),
+        geocodes.len(),
+        to_look_up.len()
+    );
+
+    if dry_run {
+        let resolvable: usize = geocodes
+            .iter()
+            .filter(|(_, result)| result.location().is_some())
+            .map(|(location, _)| ids_by_location[location].len())
+            .sum();
+        log::info!(
+            "DRY-RUN: {} events could be resolved from the cache; would geocode {} locations",
+            resolvable,
+            to_look_up.len()
+        );
+        return Ok(());
+    }
+
+    let client = awc::Client::default();
+    let client = &client;
+    let config = &config;
+    let queries = &queries;
+    let lookups = canonicalize_addresses(
+        to_look_up,
+        config.geocoding_concurrency,
+        RETRY_DELAY,
+        |key| async move {
+            canonicalize_address(
+                client,
+                &queries[&key],
+                &config.google_maps_api_key,
+                config.api_timeouts.geocoding,
+            )
+            .await
+        },
+    )
+    .await;
+    if let Err(e) = cache_geocodes(&pool, &lookups.results).await {
+        log::warn!("Failed to update the geocode cache: {}", e);
+    }
+    geocodes.extend(lookups.results);
+
+    let (mut resolved, mut not_found, mut failed, mut db_errors) = (0, 0, 0, 0);
+    for (location, ids) in &ids_by_location {
+        match geocodes.get(location) {
+            Some(Geocoded::Found(place)) => match set_event_place(&pool, ids, place).await {
+                Ok(updated) => resolved += updated,
+                Err(e) => {
+                    log::error!("Failed to update events at '{}': {}", queries[location], e);
+                    db_errors += ids.len();
+                }
+            },
+            Some(Geocoded::NotFound) => {
+                log::warn!("Could not geocode location: {}", queries[location]);
+                not_found += ids.len();
+            }
+            Some(Geocoded::Failed) | None => failed += ids.len(),
+        }
+    }
+
+    log::info!(
+        "Backfill complete. Resolved: {}, Not found: {}, Failed (try again later): {}, DB Errors: {}, Retries: {}",
+        resolved,
+        not_found,
+        failed,
+        db_errors,
+        lookups.retries
+    );
+
+    Ok(())
+}
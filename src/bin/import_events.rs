@@ -0,0 +1,52 @@
+use anyhow::{anyhow, Result};
+use somerville_events::{backup::import_events, config::Config};
+use std::env;
+use std::fs::File;
+use std::io::BufReader;
+
+// Loads a file written by /edit/export.json:
+//   cargo run --bin import_events -- [--dry-run] events.ndjson
+#[actix_web::main]
+async fn main() -> Result<()> {
+    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let dry_run = args.contains(&"--dry-run".to_string());
+    let path = args
+        .iter()
+        .find(|arg| !arg.starts_with("--"))
+        .ok_or_else(|| anyhow!("Usage: import_events [--dry-run] <export.ndjson>"))?;
+
+    if dry_run {
+        log::info!("Running in DRY-RUN mode. No changes will be saved to DB.");
+    }
+
+    let file = File::open(path).map_err(|e| anyhow!("Failed to open {path}: {e}"))?;
+
+    let config = Config::from_env();
+    let pool = config
+        .pool_options()
+        .connect(&config.get_db_url())
+        .await
+        .map_err(|e| anyhow!("Failed to connect to database: {}", e))?;
+
+    let counts = import_events(&pool, BufReader::new(file), dry_run).await?;
+
+    if dry_run {
+        log::info!(
+            "DRY-RUN: Would insert {} events and skip {} duplicates. Failed: {}",
+            counts.inserted,
+            counts.deduplicated,
+            counts.failed
+        );
+    } else {
+        log::info!(
+            "Import complete. Inserted: {}, Duplicates: {}, Failed: {}",
+            counts.inserted,
+            counts.deduplicated,
+            counts.failed
+        );
+    }
+
+    Ok(())
+}
@@ -1,14 +1,26 @@
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use serde::Deserialize;
 use somerville_events::{
+    background_tasks::BackgroundTasks,
     config::Config,
-    database::save_event_to_db,
-    geocoding::{canonicalize_address, GeocodedLocation},
-    models::{EventSource, EventType, NewEvent},
+    database::{
+        cache_geocodes, cached_geocodes, list_sources, prune_stale_events, purge_deleted_events,
+        upsert_external_event, UpsertOutcome, TRASH_RETENTION,
+    },
+    error_reporting,
+    geocoding::{
+        canonicalize_address, canonicalize_addresses, queries_by_venue_key, venue_key, Geocoded,
+        GeocodedLocation, RETRY_DELAY,
+    },
+    models::{
+        normalize_tags, sanitize_email, sanitize_phone, sanitize_url, EventSource, EventStatus,
+        EventType, NewEvent, SourceInfo,
+    },
+    webhooks::{self, Webhooks},
 };
-use sqlx::postgres::PgPoolOptions;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::env;
 
 #[derive(Debug, Deserialize, Clone)]
@@ -42,6 +54,113 @@ struct ExternalEvent {
     website_url: Option<String>,
     image_url: Option<String>,
     recurring_pattern: Option<String>,
+    /// "cancelled", "postponed" or "scheduled". Older feeds leave it out
+    /// and put "CANCELLED:" in the title instead.
+    #[serde(default)]
+    status: Option<String>,
+}
+
+/// Most events the feed is asked for in one fetch. A response this long
+/// may have been cut off.
+const FEED_LIMIT: usize = 5000;
+
+/// The feed, read into events worth saving.
+struct ParsedFeed {
+    /// New or changed since we last saw them, with when the feed says
+    /// they last changed.
+    events: Vec<(ExternalEvent, DateTime<Utc>)>,
+    /// Every id in the feed that could be read, for `--prune`.
+    seen_ids_by_source: HashMap<EventSource, Vec<String>>,
+    /// Records that couldn't be read, so their ids are unknown.
+    error_count: usize,
+    unchanged_count: usize,
+}
+
+// We deserialize to Value first to handle individual errors gracefully
+fn parse_feed(
+    raw_events: Vec<serde_json::Value>,
+    sources: &HashMap<String, SourceInfo>,
+    existing_ids: &HashMap<String, Option<DateTime<Utc>>>,
+    tz: Tz,
+) -> ParsedFeed {
+    let mut feed = ParsedFeed {
+        events: Vec::new(),
+        seen_ids_by_source: HashMap::new(),
+        error_count: 0,
+        unchanged_count: 0,
+    };
+
+    for raw in raw_events {
+        match serde_json::from_value::<ExternalEvent>(raw) {
+            Ok(ext_event) => {
+                feed.seen_ids_by_source
+                    .entry(map_source(&ext_event.source_name, sources))
+                    .or_default()
+                    .push(ext_event.id.clone());
+                let last_updated = match parse_feed_datetime(&ext_event.last_updated, tz) {
+                    Ok(last_updated) => last_updated,
+                    Err(e) => {
+                        log::warn!("Skipping event '{}': {}", ext_event.id, e);
+                        feed.error_count += 1;
+                        continue;
+                    }
+                };
+                // If event already exists in DB and hasn't changed since, skip it entirely
+                if let Some(stored) = existing_ids.get(&ext_event.id) {
+                    if stored.is_some_and(|stored| stored >= last_updated) {
+                        feed.unchanged_count += 1;
+                        continue;
+                    }
+                }
+                feed.events.push((ext_event, last_updated));
+            }
+            Err(e) => {
+                log::warn!("Skipping invalid event schema: {}", e);
+                feed.error_count += 1;
+            }
+        }
+    }
+    feed
+}
+
+/// Trashes upcoming events the feed no longer lists, per source. Returns
+/// how many, or `None` when the fetch can't be trusted to list everything:
+/// a record we couldn't read is an id we didn't see, and a response at
+/// `FEED_LIMIT` may have been cut off, either way leaving live events out.
+async fn prune_unseen(
+    pool: &sqlx::Pool<sqlx::Postgres>,
+    seen_ids_by_source: &HashMap<EventSource, Vec<String>>,
+    error_count: usize,
+    fetched_count: usize,
+) -> Option<u64> {
+    if error_count > 0 {
+        log::warn!("Not pruning: {error_count} feed records couldn't be read");
+        return None;
+    }
+    if fetched_count >= FEED_LIMIT {
+        log::warn!("Not pruning: the feed returned {fetched_count} events, its limit");
+        return None;
+    }
+
+    let mut pruned_count = 0;
+    for (source, seen_ids) in seen_ids_by_source {
+        // Unknown feed sources fall back to ImageUpload, and pruning
+        // that would delete flyers people uploaded by hand.
+        if matches!(
+            source,
+            EventSource::ImageUpload | EventSource::UserSubmitted
+        ) {
+            continue;
+        }
+        match prune_stale_events(pool, source, seen_ids).await {
+            Ok(count) => pruned_count += count,
+            Err(e) => {
+                log::error!("Failed to prune events from {}: {}", source, e);
+                error_reporting::capture(&e, &[("source", source)]);
+            }
+        }
+    }
+    Some(pruned_count)
 }
 
 #[actix_web::main]
@@ -52,6 +171,9 @@ async fn main() -> Result<()> {
     // Check for dry-run flag
     let args: Vec<String> = env::args().collect();
     let dry_run = args.contains(&"--dry-run".to_string());
+    // Opt-in because a truncated or partially failed fetch would otherwise
+    // look like every missing event was cancelled.
+    let prune = args.contains(&"--prune".to_string());
 
     if dry_run {
         log::info!("Running in DRY-RUN mode. No changes will be saved to DB and no Geocoding API calls will be made.");
@@ -59,11 +181,12 @@ async fn main() -> Result<()> {
 
     // Load config
     let config = Config::from_env();
+    let _error_reporting = error_reporting::init(config.sentry_dsn.as_deref());
     let db_url = config.get_db_url();
 
     // Connect to database
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
+    let pool = config
+        .pool_options()
         .connect(&db_url)
         .await
         .map_err(|e| anyhow!("Failed to connect to database: {}", e))?;
@@ -71,26 +194,39 @@ async fn main() -> Result<()> {
     log::info!("Connected to database");
 
     // Fetch existing external IDs to avoid re-processing and paying for geocoding
-    // We fetch all external_ids that are not null.
+    // We fetch all external_ids that are not null, along with when the source last
+    // changed them so we can pick up reschedules.
     // Ideally we should filter by source if we knew it ahead of time, but we process
     // all sources from the feed.
-    let existing_ids: HashSet<String> =
-        sqlx::query!("SELECT external_id FROM app.events WHERE external_id IS NOT NULL")
-            .fetch_all(&pool)
-            .await?
-            .into_iter()
-            .filter_map(|r| r.external_id)
-            .collect();
+    let existing_ids: HashMap<String, Option<DateTime<Utc>>> = sqlx::query!(
+        "SELECT external_id, source_updated_at FROM app.events WHERE external_id IS NOT NULL"
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .filter_map(|r| r.external_id.map(|id| (id, r.source_updated_at)))
+    .collect();
 
     log::info!("Found {} existing events in database", existing_ids.len());
 
+    // Which feed names are which source, and what to assume about each, is
+    // kept in the database so a renamed or new feed source is a row to
+    // add rather than a release.
+    let sources: HashMap<String, SourceInfo> = list_sources(&pool)
+        .await?
+        .into_iter()
+        .filter_map(|info| Some((info.feed_name.clone()?, info)))
+        .collect();
+
     // Fetch events
-    let url = "https://web-production-00281.up.railway.app/events?upcoming_only=true&limit=5000";
+    let url = format!(
+        "https://web-production-00281.up.railway.app/events?upcoming_only=true&limit={FEED_LIMIT}"
+    );
     log::info!("Fetching events from {}", url);
 
     let client = awc::Client::default();
     let mut response = client
-        .get(url)
+        .get(&url)
         .send()
         .await
         .map_err(|e| anyhow!("Failed to fetch events: {}", e))?;
@@ -99,7 +235,6 @@ async fn main() -> Result<()> {
         return Err(anyhow!("Request failed with status: {}", response.status()));
     }
 
-    // We deserialize to Value first to handle individual errors gracefully
     let raw_events: Vec<serde_json::Value> = response
         .json()
         .limit(20 * 1024 * 1024) // 20MB limit
@@ -108,104 +243,189 @@ async fn main() -> Result<()> {
 
     log::info!("Fetched {} raw events", raw_events.len());
 
-    let mut valid_external_events = Vec::new();
-    let mut error_count = 0;
-
-    // Parse all events first
-    for raw in raw_events {
-        match serde_json::from_value::<ExternalEvent>(raw) {
-            Ok(ext_event) => {
-                // If event already exists in DB, skip it entirely
-                if existing_ids.contains(&ext_event.id) {
-                    continue;
-                }
-                valid_external_events.push(ext_event);
-            }
-            Err(e) => {
-                log::warn!("Skipping invalid event schema: {}", e);
-                error_count += 1;
-            }
-        }
-    }
+    let fetched_count = raw_events.len();
+    let ParsedFeed {
+        events: valid_external_events,
+        seen_ids_by_source,
+        error_count,
+        unchanged_count,
+    } = parse_feed(raw_events, &sources, &existing_ids, config.timezone);
 
     log::info!(
-        "Identified {} new/changed events to process ({} skipped as existing, {} schema errors)",
+        "Identified {} new/changed events to process ({} skipped as unchanged, {} schema errors)",
         valid_external_events.len(),
-        existing_ids.len(), // Approximate since we didn't count overlaps exactly, but close enough
+        unchanged_count,
         error_count
     );
 
-    // Deduplicate addresses for geocoding
-    // Map Raw Address String -> Option<GeocodedLocation>
-    let mut address_cache: HashMap<String, Option<GeocodedLocation>> = HashMap::new();
-    let mut unique_addresses_to_geocode = HashSet::new();
+    // Deduplicate addresses for geocoding. Looked up and cached by venue
+    // key, so the ways feeds spell one venue share a lookup.
+    let queries = queries_by_venue_key(
+        valid_external_events
+            .iter()
+            .filter_map(|(ext, _)| build_raw_address(ext)),
+    );
 
-    for ext in &valid_external_events {
-        let raw_addr = build_raw_address(ext);
-        if let Some(addr) = raw_addr {
-            unique_addresses_to_geocode.insert(addr);
-        }
-    }
+    let unique_addresses: Vec<String> = queries.keys().cloned().collect();
+    // A cache failure only costs some lookups, not the run.
+    let mut geocodes = cached_geocodes(&pool, &unique_addresses, Utc::now())
+        .await
+        .unwrap_or_else(|e| {
+            log::warn!("Failed to read the geocode cache: {}", e);
+            HashMap::new()
+        });
+    let addresses_to_look_up: Vec<String> = if config.geocoding_enabled {
+        unique_addresses
+            .into_iter()
+            .filter(|address| !geocodes.contains_key(address))
+            .collect()
+    } else {
+        log::warn!("Geocoding is off, so only cached addresses will get a place");
+        Vec::new()
+    };
 
     log::info!(
-        "Need to geocode {} unique addresses",
-        unique_addresses_to_geocode.len()
+        "Need to geocode {} unique addresses ({} more were cached)",
+        addresses_to_look_up.len(),
+        geocodes.len()
     );
 
     if dry_run {
         log::info!(
             "DRY-RUN: Would geocode {} addresses",
-            unique_addresses_to_geocode.len()
+            addresses_to_look_up.len()
         );
         log::info!(
-            "DRY-RUN: Would insert {} events",
+            "DRY-RUN: Would insert or update {} events",
             valid_external_events.len()
         );
+        if prune {
+            log::info!(
+                "DRY-RUN: Would prune stale events from {} sources",
+                seen_ids_by_source.len()
+            );
+        }
         return Ok(());
     }
 
+    if prune {
+        if let Some(pruned_count) =
+            prune_unseen(&pool, &seen_ids_by_source, error_count, fetched_count).await
+        {
+            log::info!("Moved {} stale events to the trash", pruned_count);
+        }
+    }
+
+    match purge_deleted_events(&pool, Utc::now() - TRASH_RETENTION).await {
+        Ok(count) => log::info!("Purged {} events from the trash", count),
+        Err(e) => {
+            log::error!("Failed to purge deleted events: {}", e);
+            error_reporting::capture(&e, &[]);
+        }
+    }
+
     // Geocode addresses
-    for raw_addr in unique_addresses_to_geocode {
-        match canonicalize_address(&client, &raw_addr, &config.google_maps_api_key).await {
-            Ok(loc) => {
-                if loc.is_none() {
-                    log::warn!("Could not geocode address: {}", raw_addr);
-                }
-                address_cache.insert(raw_addr, loc);
-            }
-            Err(e) => {
-                log::error!("Failed to geocode address '{}': {}", raw_addr, e);
-                // Insert None to avoid retrying if we logic-looped, but here we just iterate set once
-                address_cache.insert(raw_addr, None);
+    let client = &client;
+    let queries = &queries;
+    let lookups = canonicalize_addresses(
+        addresses_to_look_up,
+        config.geocoding_concurrency,
+        RETRY_DELAY,
+        |key| async move {
+            canonicalize_address(
+                client,
+                &queries[&key],
+                &config.google_maps_api_key,
+                config.api_timeouts.geocoding,
+            )
+            .await
+        },
+    )
+    .await;
+    let (mut found, mut not_found, mut failed) = (0, 0, 0);
+    for (raw_addr, result) in &lookups.results {
+        match result {
+            Geocoded::Found(_) => found += 1,
+            Geocoded::NotFound => {
+                log::warn!("Could not geocode address: {}", raw_addr);
+                not_found += 1;
             }
+            Geocoded::Failed => failed += 1,
         }
     }
+    log::info!(
+        "Geocoding complete. Found: {}, Not found: {}, Failed (retried next run): {}, Retries: {}",
+        found,
+        not_found,
+        failed,
+        lookups.retries
+    );
+    if let Err(e) = cache_geocodes(&pool, &lookups.results).await {
+        log::warn!("Failed to update the geocode cache: {}", e);
+    }
+    geocodes.extend(lookups.results);
+
+    let tasks = BackgroundTasks::default();
+    let webhooks = Webhooks::new(
+        config.webhook_urls.clone(),
+        config.webhook_secret.clone(),
+        &config.public_url,
+        tasks.clone(),
+    );
 
-    let mut success_count = 0;
+    let mut inserted_count = 0;
+    let mut updated_count = 0;
     let mut db_error_count = 0;
 
-    for ext_event in valid_external_events {
+    for (ext_event, last_updated) in valid_external_events {
         let raw_addr = build_raw_address(&ext_event);
-        let geocoded = raw_addr
-            .as_ref()
-            .and_then(|a| address_cache.get(a).cloned().flatten());
-
-        match map_and_save_event(&pool, ext_event, geocoded).await {
-            Ok(_) => success_count += 1,
+        let geocode = raw_addr.as_ref().and_then(|a| geocodes.get(&venue_key(a)));
+        let geocoded = geocode.and_then(Geocoded::location).cloned();
+        // Saved with the raw address for now, and without the feed's
+        // timestamp so the next run picks it up again and fills it in.
+        let last_updated = if geocode == Some(&Geocoded::Failed) {
+            None
+        } else {
+            Some(last_updated)
+        };
+
+        let external_id = ext_event.id.clone();
+        match map_and_save_event(
+            &pool,
+            &webhooks,
+            &sources,
+            ext_event,
+            geocoded,
+            last_updated,
+            config.timezone,
+        )
+        .await
+        {
+            Ok(UpsertOutcome::Inserted(_)) => inserted_count += 1,
+            Ok(UpsertOutcome::Updated(_) | UpsertOutcome::StatusChanged(_)) => updated_count += 1,
+            Ok(UpsertOutcome::Unchanged(_)) => {}
             Err(e) => {
                 log::error!("Failed to save event: {}", e);
+                error_reporting::capture(&e, &[("external_id", &external_id)]);
                 db_error_count += 1;
             }
         }
     }
 
     log::info!(
-        "Ingestion complete. Success: {}, DB Errors: {}, Schema Errors: {}",
-        success_count,
+        "Ingestion complete. Inserted: {}, Updated: {}, DB Errors: {}, Schema Errors: {}",
+        inserted_count,
+        updated_count,
         db_error_count,
         error_count
     );
 
+    // Deliveries still retrying would die with the process.
+    let undelivered = tasks.shutdown(webhooks::DRAIN_TIMEOUT).await;
+    if undelivered > 0 {
+        log::warn!("Gave up waiting on {} webhook deliveries", undelivered);
+    }
+
     Ok(())
 }
 
@@ -238,79 +458,59 @@ fn build_raw_address(ext: &ExternalEvent) -> Option<String> {
 
 async fn map_and_save_event(
     pool: &sqlx::Pool<sqlx::Postgres>,
+    webhooks: &Webhooks,
+    sources: &HashMap<String, SourceInfo>,
     ext: ExternalEvent,
     geocoded: Option<GeocodedLocation>,
-) -> Result<()> {
+    last_updated: Option<DateTime<Utc>>,
+    tz: Tz,
+) -> Result<UpsertOutcome> {
     // Parse timestamps
-    let start_date = DateTime::parse_from_rfc3339(&ext.start_datetime)
-        .map(|dt| dt.with_timezone(&Utc))
-        .or_else(|_| {
-            use chrono::NaiveDateTime;
-            use chrono::TimeZone;
-            use chrono_tz::America::New_York;
-
-            NaiveDateTime::parse_from_str(&ext.start_datetime, "%Y-%m-%dT%H:%M:%S")
-                .map(|ndt| {
-                    New_York
-                        .from_local_datetime(&ndt)
-                        .single()
-                        .unwrap()
-                        .with_timezone(&Utc)
-                })
-                .map_err(|e| anyhow!("Failed to parse start date '{}': {}", ext.start_datetime, e))
-        })
+    let start_date = parse_feed_datetime(&ext.start_datetime, tz)
         .map_err(|e| anyhow!("Date parsing error: {}", e))?;
 
-    let end_date = if let Some(ref end_str) = ext.end_datetime {
-        Some(
-            DateTime::parse_from_rfc3339(end_str)
-                .map(|dt| dt.with_timezone(&Utc))
-                .or_else(|_| {
-                    use chrono::NaiveDateTime;
-                    use chrono::TimeZone;
-                    use chrono_tz::America::New_York;
-
-                    NaiveDateTime::parse_from_str(end_str, "%Y-%m-%dT%H:%M:%S")
-                        .map(|ndt| {
-                            New_York
-                                .from_local_datetime(&ndt)
-                                .single()
-                                .unwrap()
-                                .with_timezone(&Utc)
-                        })
-                        .map_err(|e| anyhow!("Failed to parse end date '{}': {}", end_str, e))
-                })?,
-        )
-    } else {
-        None
-    };
+    let end_date = ext
+        .end_datetime
+        .as_deref()
+        .map(|value| parse_feed_datetime(value, tz))
+        .transpose()?;
 
-    // Map source
-    let source = map_source(&ext.source_name);
+    let info = sources.get(&ext.source_name);
+    let source = map_source(&ext.source_name, sources);
 
-    // Map category to event types
-    let mut event_types = Vec::new();
-    if let Some(et) = map_category(&ext.category) {
-        event_types.push(et);
-    }
+    let mut event_types = vec![map_category(&ext.category)
+        .or_else(|| info.and_then(|info| info.default_event_type.clone()))
+        .unwrap_or(EventType::Other)];
     if ext.family_friendly {
         event_types.push(EventType::ChildFriendly);
     }
 
+    // The feed's own coordinates are for the exact venue, so they win over
+    // wherever Google's text search landed.
+    let feed_coordinates = ext.latitude.zip(ext.longitude);
+
     // Determine address fields based on geocoding result or fallback to raw
-    let (address, google_place_id, location_name, original_location) = if let Some(geo) = geocoded {
-        (
-            Some(geo.formatted_address),
-            Some(geo.place_id),
-            // Use venue name from source if available, otherwise name from Google (which might be the venue name)
-            ext.venue_name.clone().or(Some(geo.name)),
-            build_raw_address(&ext), // Original location is the raw string we built
-        )
-    } else {
-        // Fallback to raw construction
-        let raw = build_raw_address(&ext);
-        (raw.clone(), None, ext.venue_name.clone(), raw)
-    };
+    let (address, google_place_id, location_name, original_location, coordinates) =
+        if let Some(geo) = geocoded {
+            (
+                Some(geo.formatted_address),
+                Some(geo.place_id),
+                // Use venue name from source if available, otherwise name from Google (which might be the venue name)
+                ext.venue_name.clone().or(Some(geo.name)),
+                build_raw_address(&ext), // Original location is the raw string we built
+                feed_coordinates.or(Some((geo.lat, geo.lng))),
+            )
+        } else {
+            // Fallback to raw construction
+            let raw = build_raw_address(&ext);
+            (
+                raw.clone(),
+                None,
+                ext.venue_name.clone(),
+                raw,
+                feed_coordinates,
+            )
+        };
 
     // Parse price
     let price = ext.cost.as_ref().and_then(|c| {
@@ -319,54 +519,73 @@ async fn map_and_save_event(
         cleaned.parse::<f64>().ok()
     });
 
-    let event = NewEvent {
+    let mut event = NewEvent {
         name: ext.title,
         description: ext.description.clone(),
         full_text: "".to_string(),
         start_date,
         end_date,
+        all_day: ext.all_day,
         address,
         original_location,
         google_place_id,
+        lat: coordinates.map(|(lat, _)| lat),
+        lng: coordinates.map(|(_, lng)| lng),
         location_name,
         event_types,
-        url: ext.source_url.or(ext.website_url),
-        confidence: 1.0,
+        tags: normalize_tags(&ext.tags),
+        url: sanitize_url(ext.source_url).or_else(|| sanitize_url(ext.website_url)),
+        confidence: Config::from_env()
+            .default_confidence(&source, info.and_then(|info| info.default_confidence)),
         age_restrictions: ext.age_restrictions,
         price,
         source,
         external_id: Some(ext.id),
+        contact_email: sanitize_email(ext.contact_email),
+        contact_phone: sanitize_phone(ext.contact_phone),
+        registration_required: ext.registration_required,
+        status: ext
+            .status
+            .as_deref()
+            .map(EventStatus::from_source)
+            .unwrap_or_default(),
     };
+    event.take_status_from_name();
 
-    save_event_to_db(pool, &event).await?;
+    let outcome = upsert_external_event(pool, &event, last_updated).await?;
+    match outcome {
+        UpsertOutcome::Inserted(id) => webhooks.notify_new_event(id, &event),
+        UpsertOutcome::StatusChanged(id) => webhooks.notify_updated_event(id, &event),
+        UpsertOutcome::Updated(_) | UpsertOutcome::Unchanged(_) => {}
+    }
+    Ok(outcome)
+}
 
-    Ok(())
+/// The feed mixes RFC 3339 timestamps with naive local times, which are in
+/// the site's timezone.
+fn parse_feed_datetime(value: &str, tz: Tz) -> Result<DateTime<Utc>> {
+    use chrono::NaiveDateTime;
+    use chrono::TimeZone;
+
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+
+    let ndt = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S")
+        .map_err(|e| anyhow!("Failed to parse date '{}': {}", value, e))?;
+    tz.from_local_datetime(&ndt)
+        .single()
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok_or_else(|| anyhow!("Ambiguous local time '{}'", value))
 }
 
-fn map_source(source_name: &str) -> EventSource {
-    match source_name {
-        "Aeronaut Brewing" => EventSource::AeronautBrewing,
-        "American Repertory Theater" => EventSource::AmericanRepertoryTheater,
-        "Arts at the Armory" => EventSource::ArtsAtTheArmory,
-        "Boston Swing Central" => EventSource::BostonSwingCentral,
-        "BostonShows.org" => EventSource::BostonShowsOrg,
-        "Brattle Theatre" => EventSource::BrattleTheatre,
-        "Central Square Theater" => EventSource::CentralSquareTheater,
-        "City of Cambridge" => EventSource::CityOfCambridge,
-        "Harvard Art Museums" => EventSource::HarvardArtMuseums,
-        "Harvard Book Store" => EventSource::HarvardBookStore,
-        "Lamplighter Brewing" => EventSource::LamplighterBrewing,
-        "Porter Square Books" => EventSource::PorterSquareBooks,
-        "Portico Brewing" => EventSource::PorticoBrewing,
-        "Sanders Theatre" => EventSource::SandersTheatre,
-        "Somerville Theatre" => EventSource::SomervilleTheatre,
-        "The Comedy Studio" => EventSource::TheComedyStudio,
-        "The Lily Pad" => EventSource::TheLilyPad,
-        "First Parish in Cambridge" => EventSource::FirstParishInCambridge,
-        "Grolier Poetry Book Shop" => EventSource::GrolierPoetryBookShop,
-        "User Submitted" => EventSource::UserSubmitted,
-        "The Middle East" => EventSource::TheMiddleEast,
-        _ => {
+/// The source the feed's `source_name` stands for, matched against
+/// `feed_name` in `app.source_names`. A name nobody has added there yet
+/// falls back to ImageUpload, as flyers do.
+fn map_source(source_name: &str, sources: &HashMap<String, SourceInfo>) -> EventSource {
+    match sources.get(source_name) {
+        Some(info) => info.source.clone(),
+        None => {
             log::warn!(
                 "Unknown source: '{}', defaulting to ImageUpload (which is used as fallback)",
                 source_name
@@ -376,6 +595,8 @@ fn map_source(source_name: &str) -> EventSource {
     }
 }
 
+/// `None` for "other" and categories we don't know, which get the
+/// source's default event type instead.
 fn map_category(category: &str) -> Option<EventType> {
     match category.to_lowercase().as_str() {
         "music" => Some(EventType::Music),
@@ -385,10 +606,107 @@ fn map_category(category: &str) -> Option<EventType> {
         "lectures" => Some(EventType::Workshop),
         "community" => Some(EventType::Meeting),
         "sports" => Some(EventType::Sports),
-        "other" => Some(EventType::Other),
+        "other" => None,
         _ => {
-            log::debug!("Unknown category: '{}', mapping to Other", category);
-            Some(EventType::Other)
+            log::debug!("Unknown category: '{}'", category);
+            None
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(id: &str) -> serde_json::Value {
+        json!({
+            "id": id,
+            "title": "Trivia Night",
+            "description": "",
+            "start_datetime": "2099-01-01T19:00:00-05:00",
+            "end_datetime": null,
+            "all_day": false,
+            "venue_name": "Aeronaut Brewing",
+            "street_address": null,
+            "city": null,
+            "state": null,
+            "zip_code": null,
+            "latitude": null,
+            "longitude": null,
+            "category": "other",
+            "tags": [],
+            "family_friendly": false,
+            "age_restrictions": null,
+            "cost": null,
+            "registration_required": false,
+            "source_url": null,
+            "source_name": "Aeronaut Brewing",
+            "scraped_at": "2098-12-01T00:00:00Z",
+            "last_updated": "2098-12-01T00:00:00Z",
+            "contact_email": null,
+            "contact_phone": null,
+            "website_url": null,
+            "image_url": null,
+            "recurring_pattern": null
+        })
+    }
+
+    async fn is_listed(pool: &sqlx::PgPool, external_id: &str) -> Result<bool> {
+        Ok(sqlx::query_scalar::<_, i64>(
+            "SELECT count(*) FROM app.events WHERE external_id = $1 AND deleted_at IS NULL",
+        )
+        .bind(external_id)
+        .fetch_one(pool)
+        .await?
+            == 1)
+    }
+
+    #[sqlx::test]
+    async fn test_unreadable_record_is_not_pruned(pool: sqlx::PgPool) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO app.events (name, full_text, start_date, confidence, source, external_id)
+            SELECT 'Trivia Night', '', now() + interval '1 day', 1.0, 'AeronautBrewing', id
+            FROM unnest(ARRAY['good', 'broken']) AS id
+            "#,
+        )
+        .execute(&pool)
+        .await?;
+        let sources: HashMap<String, SourceInfo> = list_sources(&pool)
+            .await?
+            .into_iter()
+            .filter_map(|info| Some((info.feed_name.clone()?, info)))
+            .collect();
+        let tz = chrono_tz::America::New_York;
+
+        // "broken" is still in the feed, but with a field we can't read.
+        let mut broken = record("broken");
+        broken["all_day"] = json!("sometimes");
+        let feed = parse_feed(vec![record("good"), broken], &sources, &HashMap::new(), tz);
+        assert_eq!(feed.error_count, 1);
+        assert_eq!(
+            prune_unseen(&pool, &feed.seen_ids_by_source, feed.error_count, 2).await,
+            None
+        );
+        assert!(is_listed(&pool, "broken").await?);
+
+        // A response at the limit may have been cut off before "broken".
+        let feed = parse_feed(vec![record("good")], &sources, &HashMap::new(), tz);
+        assert_eq!(
+            prune_unseen(&pool, &feed.seen_ids_by_source, 0, FEED_LIMIT).await,
+            None
+        );
+        assert!(is_listed(&pool, "broken").await?);
+
+        // A whole, readable feed without it does prune it.
+        assert_eq!(
+            prune_unseen(&pool, &feed.seen_ids_by_source, 0, 1).await,
+            Some(1)
+        );
+        assert!(!is_listed(&pool, "broken").await?);
+        assert!(is_listed(&pool, "good").await?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,77 @@
+use anyhow::{anyhow, Result};
+use somerville_events::{
+    config::Config,
+    database::{applied_migration_versions, run_migrations, MIGRATOR},
+};
+use std::env;
+
+const USAGE: &str = "Usage: migrate [run | status | revert <target version>]";
+
+// Runs the migrations built into this binary as the `migrator` role:
+//   cargo run --bin migrate -- run
+//   cargo run --bin migrate -- status
+//   cargo run --bin migrate -- revert 20261020130000
+#[actix_web::main]
+async fn main() -> Result<()> {
+    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = args.first().map(String::as_str).unwrap_or("run");
+
+    let config = Config::from_env();
+    let url = config
+        .get_migrator_db_url()
+        .ok_or_else(|| anyhow!("DB_MIGRATOR_PASS must be set to run migrations"))?;
+    let pool = config
+        .pool_options()
+        .max_connections(1)
+        .connect(&url)
+        .await
+        .map_err(|e| anyhow!("Failed to connect to database: {}", e))?;
+
+    match command {
+        "run" => {
+            run_migrations(&pool).await?;
+        }
+        "status" => {
+            let applied = applied_migration_versions(&pool).await?;
+            for migration in MIGRATOR
+                .iter()
+                .filter(|m| !m.migration_type.is_down_migration())
+            {
+                let state = if applied.contains(&migration.version) {
+                    "applied"
+                } else {
+                    "pending"
+                };
+                println!("{} {state:7} {}", migration.version, migration.description);
+            }
+        }
+        "revert" => {
+            let target: i64 = args
+                .get(1)
+                .and_then(|version| version.parse().ok())
+                .ok_or_else(|| anyhow!(USAGE))?;
+            // sqlx only reverts migrations that have a `.down.sql`; without
+            // one it would quietly do nothing.
+            let irreversible: Vec<i64> = MIGRATOR
+                .iter()
+                .filter(|m| m.version > target && !m.migration_type.is_reversible())
+                .map(|m| m.version)
+                .collect();
+            if !irreversible.is_empty() {
+                return Err(anyhow!(
+                    "Can't revert to {target}: these migrations have no down script: {irreversible:?}"
+                ));
+            }
+            MIGRATOR
+                .undo(&pool, target)
+                .await
+                .map_err(|e| anyhow!("Failed to revert migrations: {e}"))?;
+            log::info!("Reverted migrations after {target}");
+        }
+        _ => return Err(anyhow!(USAGE)),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,8 @@
+use anyhow::Result;
+use somerville_events::scraper::{run_scraper, somerville_gov::SomervilleGov};
+
+#[actix_web::main]
+async fn main() -> Result<()> {
+    env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+    run_scraper(SomervilleGov).await
+}
@@ -1,8 +1,17 @@
 use crate::features::view::IndexQuery;
-use crate::models::{Event, EventSource, EventType, LocationOption, NewEvent, SimpleEvent};
+use crate::geocoding::{Geocoded, GeocodedLocation};
+use crate::models::{
+    normalize_tag, normalize_url, DeletedEvent, Event, EventSource, EventStatus, EventType,
+    LocationOption, NewContactMessage, NewEvent, NewUserReport, PendingEvent, RelatedEvent,
+    SimpleEvent, SiteStats, SourceInfo, Submitter, UserReport, Venue,
+};
 use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use sqlx::migrate::{Migrate, Migration, Migrator};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
 use strsim::jaro_winkler;
 
 #[async_trait]
@@ -13,17 +22,226 @@ pub trait EventsRepo: Send + Sync {
         since: Option<DateTime<Utc>>,
         until: Option<DateTime<Utc>>,
     ) -> Result<Vec<SimpleEvent>>;
+    /// How many events `list` would return for the same arguments.
+    async fn count(
+        &self,
+        query: IndexQuery,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> Result<i64> {
+        Ok(self.list(query, since, until).await?.len() as i64)
+    }
     async fn list_full(
         &self,
         query: IndexQuery,
         since: Option<DateTime<Utc>>,
         until: Option<DateTime<Utc>>,
     ) -> Result<Vec<Event>>;
+    /// One page of `list_full`, ordered by `(start_date, id)` and starting
+    /// after the `after` cursor, so big feeds can be streamed instead of
+    /// loaded all at once.
+    async fn list_full_page(
+        &self,
+        query: IndexQuery,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        after: Option<(DateTime<Utc>, i64)>,
+        limit: i64,
+    ) -> Result<Vec<Event>> {
+        let mut events = self.list_full(query, since, until).await?;
+        events.sort_by_key(|e| (e.start_date, e.id));
+        Ok(events
+            .into_iter()
+            .filter(|e| after.is_none_or(|cursor| (e.start_date, e.id) > cursor))
+            .take(usize::try_from(limit).unwrap_or(0))
+            .collect())
+    }
     async fn get_distinct_locations(&self) -> Result<Vec<LocationOption>>;
     async fn get(&self, id: i64) -> Result<Option<Event>>;
-    async fn claim_idempotency_key(&self, idempotency_key: uuid::Uuid) -> Result<bool>;
-    async fn insert(&self, event: &NewEvent) -> Result<i64>;
+    async fn get_venue(&self, google_place_id: &str) -> Result<Option<Venue>>;
+    /// What `app.source_names` says about `source`, see `SourceInfo`.
+    async fn get_source(&self, source: &EventSource) -> Result<Option<SourceInfo>>;
+    /// Every event with an id above `after_id`, oldest id first, at most
+    /// `limit` of them. Past ones included; this is for exports, which page
+    /// through the whole table rather than load it at once.
+    async fn list_all_after(&self, after_id: i64, limit: i64) -> Result<Vec<Event>>;
+    /// Claims the key for one upload, so a retried POST isn't processed
+    /// twice. The submitter is kept with it, since an upload with no events
+    /// on it leaves no other trace of who sent it. A key whose upload
+    /// failed, or was claimed more than `IDEMPOTENCY_CLAIM_TIMEOUT` ago
+    /// and never finished, can be claimed again.
+    async fn claim_idempotency_key(
+        &self,
+        idempotency_key: uuid::Uuid,
+        submitter: &Submitter,
+    ) -> Result<bool>;
+    /// Records how a claimed upload ended. Only a success blocks the key
+    /// for good.
+    async fn finish_idempotency_key(
+        &self,
+        idempotency_key: uuid::Uuid,
+        succeeded: bool,
+    ) -> Result<()>;
+    /// Saves the event, unless `find_duplicate` matches an existing one.
+    async fn insert(&self, event: &NewEvent) -> Result<SaveOutcome>;
+    /// Like `insert`, for an event that came in through the upload form. A
+    /// `pending` one is left out of every listing, and `get`, until it's
+    /// approved.
+    async fn insert_submitted(
+        &self,
+        event: &NewEvent,
+        submitter: &Submitter,
+        pending: bool,
+    ) -> Result<SaveOutcome>;
+    /// Submitted events waiting for an admin, oldest first.
+    async fn list_pending(&self) -> Result<Vec<PendingEvent>>;
+    /// Publishes every pending event in `ids`. Returns the ones that were
+    /// still waiting, so they can be announced as new.
+    async fn approve(&self, ids: &[i64]) -> Result<Vec<i64>>;
+    /// Who sent the event in, if it came through the upload form and they
+    /// left a name or email.
+    async fn get_submitter(&self, id: i64) -> Result<Option<Submitter>>;
+    /// Moves the event to the trash. It disappears from every listing but
+    /// can be restored until `purge_deleted_events` removes it for good.
     async fn delete(&self, id: i64) -> Result<()>;
+    /// Moves every event in `ids` to the trash in one go. Returns how many
+    /// went; ids that were already gone don't count.
+    async fn delete_many(&self, ids: &[i64]) -> Result<u64>;
+    /// Replaces the event types of every event in `ids`, all in one
+    /// transaction. Returns how many events were changed.
+    async fn set_event_types(&self, ids: &[i64], event_types: &[EventType]) -> Result<u64>;
+    /// Takes an event back out of the trash.
+    async fn restore(&self, id: i64) -> Result<()>;
+    /// The trash, most recently deleted first.
+    async fn list_deleted(&self) -> Result<Vec<DeletedEvent>>;
+    /// Folds the `from_id` event into `into_id` and deletes it. Whatever
+    /// the survivor is missing (a geocoded location, a URL, a price...) is
+    /// taken from the other event, and their event types are combined.
+    async fn merge(&self, from_id: i64, into_id: i64) -> Result<()>;
+    /// Featured events that haven't ended by `now`, soonest first. An event
+    /// without an end date counts as running for a day, as on the index.
+    async fn list_featured(&self, now: DateTime<Utc>) -> Result<Vec<SimpleEvent>>;
+    /// Every listed event, least confident first, so admins can check the
+    /// shakiest extractions before the rest. Ties go by start date.
+    async fn list_ordered_by_confidence(&self) -> Result<Vec<SimpleEvent>>;
+    /// Events worth a look from `event_id`'s page that haven't ended by
+    /// `now`: up to `limit` at the same venue, soonest first, then up to
+    /// `limit` elsewhere sharing an event type, those sharing the most types
+    /// first.
+    async fn list_related(
+        &self,
+        event_id: i64,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<RelatedEvent>>;
+    /// Adds the event to, or takes it out of, the front page's "Featured"
+    /// section.
+    async fn set_featured(&self, id: i64, featured: bool) -> Result<()>;
+    /// Marks the event cancelled or postponed, or back on. It stays listed
+    /// either way, unlike `delete`.
+    async fn set_status(&self, id: i64, status: EventStatus) -> Result<()>;
+    async fn insert_report(&self, report: &NewUserReport) -> Result<i64>;
+    /// How many reports `reporter_ip` has sent since `since`, for rate
+    /// limiting.
+    async fn count_reports_since(&self, reporter_ip: &str, since: DateTime<Utc>) -> Result<i64>;
+    /// Records a message relayed to an event's organizer.
+    async fn insert_contact_message(&self, message: &NewContactMessage) -> Result<i64>;
+    /// How many messages `sender_ip` has sent organizers since `since`, for
+    /// rate limiting.
+    async fn count_contact_messages_since(
+        &self,
+        sender_ip: &str,
+        since: DateTime<Utc>,
+    ) -> Result<i64>;
+    /// How many messages `sender_ip` has sent `event_id`'s organizer since
+    /// `since`, so no one sender can flood them.
+    async fn count_contact_messages_to_event_since(
+        &self,
+        event_id: i64,
+        sender_ip: &str,
+        since: DateTime<Utc>,
+    ) -> Result<i64>;
+    /// Open reports, newest first, with the name of the event each is about.
+    async fn list_reports(&self) -> Result<Vec<UserReport>>;
+    async fn delete_report(&self, id: i64) -> Result<()>;
+    /// Events from an earlier upload whose perceptual hash is within
+    /// `max_distance` bits of `dhash`, closest first. `None` when no earlier
+    /// upload was that similar. Only listed events count: an upload whose
+    /// events are all still pending or since deleted is as good as unseen,
+    /// since skipping it would leave nothing published. One that found no
+    /// events at all still counts, with no ids.
+    async fn find_processed_image(&self, dhash: u64, max_distance: u32)
+        -> Result<Option<Vec<i64>>>;
+    async fn record_processed_image(&self, dhash: u64, event_ids: &[i64]) -> Result<()>;
+    /// Totals for the admin stats page, see `SiteStats`. Events that end
+    /// from `now` on count as upcoming; weekly additions are counted from
+    /// `added_since`, in `tz`'s weeks. At most `top_venues` venues.
+    async fn stats(
+        &self,
+        now: DateTime<Utc>,
+        added_since: DateTime<Utc>,
+        tz: Tz,
+        top_venues: i64,
+    ) -> Result<SiteStats> {
+        let mut events = Vec::new();
+        loop {
+            let page = self
+                .list_all_after(events.last().map_or(0, |e: &Event| e.id), 500)
+                .await?;
+            if page.is_empty() {
+                break;
+            }
+            events.extend(page);
+        }
+
+        let mut by_source: HashMap<EventSource, i64> = HashMap::new();
+        let mut by_type: HashMap<EventType, i64> = HashMap::new();
+        let mut by_week: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+        let mut by_venue: HashMap<(String, String), i64> = HashMap::new();
+        for event in &events {
+            *by_source.entry(event.source.clone()).or_default() += 1;
+            for t in &event.event_types {
+                *by_type.entry(t.clone()).or_default() += 1;
+            }
+            if event.created_at >= added_since {
+                let day = event.created_at.with_timezone(&tz).date_naive();
+                let monday = day - Duration::days(i64::from(day.weekday().num_days_from_monday()));
+                *by_week.entry(monday).or_default() += 1;
+            }
+            if let Some(place_id) = &event.google_place_id {
+                let name = event
+                    .location_name
+                    .clone()
+                    .unwrap_or_else(|| place_id.clone());
+                *by_venue.entry((place_id.clone(), name)).or_default() += 1;
+            }
+        }
+
+        let mut by_source: Vec<_> = by_source.into_iter().collect();
+        by_source.sort_by_key(|(source, count)| (-count, source.to_string()));
+        let mut by_type: Vec<_> = by_type.into_iter().collect();
+        by_type.sort_by_key(|(t, count)| (-count, t.to_string()));
+        let mut venues: Vec<_> = by_venue
+            .into_iter()
+            .map(|((id, name), count)| (id, name, count))
+            .collect();
+        venues.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.1.cmp(&b.1)));
+        venues.truncate(usize::try_from(top_venues).unwrap_or(0));
+
+        Ok(SiteStats {
+            total: events.len() as i64,
+            upcoming: events
+                .iter()
+                .filter(|e| e.end_date.unwrap_or(e.start_date) >= now)
+                .count() as i64,
+            pending: self.list_pending().await?.len() as i64,
+            open_reports: self.list_reports().await?.len() as i64,
+            by_source,
+            by_type,
+            added_per_week: by_week.into_iter().collect(),
+            top_venues: venues,
+        })
+    }
 }
 
 #[async_trait]
@@ -44,9 +262,11 @@ impl EventsRepo for sqlx::Pool<sqlx::Postgres> {
             .iter()
             .map(|s| s.as_ref().to_string())
             .collect();
+        let near = query.near().map_err(|e| anyhow!(e))?;
         let locations = query.location;
         let free_only = query.free.unwrap_or(false);
         let name_query = query.q;
+        let tag = query.tag.as_deref().and_then(normalize_tag);
 
         let events = sqlx::query_as!(
             SimpleEvent,
@@ -55,22 +275,37 @@ impl EventsRepo for sqlx::Pool<sqlx::Postgres> {
                 SELECT DISTINCT e.id
                 FROM app.events e
                 LEFT JOIN app.event_event_types et ON e.id = et.event_id
-                WHERE (cardinality($1::text[]) = 0 OR et.event_type_name = ANY($1::text[]))
+                WHERE e.deleted_at IS NULL AND NOT e.pending
+                AND (cardinality($1::text[]) = 0 OR et.event_type_name = ANY($1::text[]))
                 AND (cardinality($2::text[]) = 0 OR e.source = ANY($2::text[]))
                 AND (cardinality($3::text[]) = 0 OR e.google_place_id = ANY($3::text[]))
                 AND ($4::boolean = false OR e.price = 0 OR e.price IS NULL)
-                AND ($5::text IS NULL OR e.name ILIKE ('%' || $5::text || '%'))
+                AND ($5::text IS NULL OR e.name ILIKE ('%' || $5::text || '%') OR e.full_text ILIKE ('%' || $5::text || '%'))
                 AND ($6::timestamptz IS NULL OR e.start_date >= $6)
                 AND ($7::timestamptz IS NULL OR e.start_date <= $7)
+                AND ($8::float8 IS NULL OR (
+                    e.lat IS NOT NULL AND e.lng IS NOT NULL
+                    AND 12742 * asin(sqrt(
+                        power(sin(radians(e.lat - $8) / 2), 2)
+                        + cos(radians($8)) * cos(radians(e.lat)) * power(sin(radians(e.lng - $9) / 2), 2)
+                    )) <= $10
+                ))
+                AND ($11::text IS NULL OR e.tags @> ARRAY[$11::text])
             )
             SELECT
                 e.id,
+                e.updated_at,
                 e.name,
                 e.start_date,
                 e.end_date,
+                e.all_day,
                 e.original_location,
                 e.location_name,
-                COALESCE(array_agg(et.event_type_name ORDER BY et.event_type_name) FILTER (WHERE et.event_type_name IS NOT NULL), '{}') as "event_types!: Vec<EventType>"
+                e.lat,
+                e.lng,
+                COALESCE(array_agg(et.event_type_name ORDER BY et.event_type_name) FILTER (WHERE et.event_type_name IS NOT NULL), '{}') as "event_types!: Vec<EventType>",
+                e.confidence,
+                e.status as "status: EventStatus"
             FROM app.events e
             JOIN filtered_events fe ON e.id = fe.id
             LEFT JOIN app.event_event_types et ON e.id = et.event_id
@@ -83,7 +318,11 @@ impl EventsRepo for sqlx::Pool<sqlx::Postgres> {
             free_only,
             name_query,
             since,
-            until
+            until,
+            near.map(|p| p.lat),
+            near.map(|p| p.lng),
+            near.map(|p| p.radius_km),
+            tag
         )
         .fetch_all(self)
         .await?;
@@ -91,11 +330,90 @@ impl EventsRepo for sqlx::Pool<sqlx::Postgres> {
         Ok(events)
     }
 
+    /// Keep the WHERE clause identical to `list`'s, or a page of results
+    /// and its total will disagree.
+    async fn count(
+        &self,
+        query: IndexQuery,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> Result<i64> {
+        let categories: Vec<String> = query
+            .event_types
+            .iter()
+            .map(|c| c.as_ref().to_string())
+            .collect();
+        let sources: Vec<String> = query
+            .source
+            .iter()
+            .map(|s| s.as_ref().to_string())
+            .collect();
+        let near = query.near().map_err(|e| anyhow!(e))?;
+        let locations = query.location;
+        let free_only = query.free.unwrap_or(false);
+        let name_query = query.q;
+        let tag = query.tag.as_deref().and_then(normalize_tag);
+
+        let count = sqlx::query_scalar!(
+            r#"
+            WITH filtered_events AS (
+                SELECT DISTINCT e.id
+                FROM app.events e
+                LEFT JOIN app.event_event_types et ON e.id = et.event_id
+                WHERE e.deleted_at IS NULL AND NOT e.pending
+                AND (cardinality($1::text[]) = 0 OR et.event_type_name = ANY($1::text[]))
+                AND (cardinality($2::text[]) = 0 OR e.source = ANY($2::text[]))
+                AND (cardinality($3::text[]) = 0 OR e.google_place_id = ANY($3::text[]))
+                AND ($4::boolean = false OR e.price = 0 OR e.price IS NULL)
+                AND ($5::text IS NULL OR e.name ILIKE ('%' || $5::text || '%') OR e.full_text ILIKE ('%' || $5::text || '%'))
+                AND ($6::timestamptz IS NULL OR e.start_date >= $6)
+                AND ($7::timestamptz IS NULL OR e.start_date <= $7)
+                AND ($8::float8 IS NULL OR (
+                    e.lat IS NOT NULL AND e.lng IS NOT NULL
+                    AND 12742 * asin(sqrt(
+                        power(sin(radians(e.lat - $8) / 2), 2)
+                        + cos(radians($8)) * cos(radians(e.lat)) * power(sin(radians(e.lng - $9) / 2), 2)
+                    )) <= $10
+                ))
+                AND ($11::text IS NULL OR e.tags @> ARRAY[$11::text])
+            )
+            SELECT COUNT(*) as "count!" FROM filtered_events
+            "#,
+            &categories,
+            &sources,
+            &locations,
+            free_only,
+            name_query,
+            since,
+            until,
+            near.map(|p| p.lat),
+            near.map(|p| p.lng),
+            near.map(|p| p.radius_km),
+            tag
+        )
+        .fetch_one(self)
+        .await?;
+
+        Ok(count)
+    }
+
     async fn list_full(
         &self,
         query: IndexQuery,
         since: Option<DateTime<Utc>>,
         until: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Event>> {
+        self.list_full_page(query, since, until, None, i64::MAX)
+            .await
+    }
+
+    async fn list_full_page(
+        &self,
+        query: IndexQuery,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        after: Option<(DateTime<Utc>, i64)>,
+        limit: i64,
     ) -> Result<Vec<Event>> {
         let categories: Vec<String> = query
             .event_types
@@ -107,9 +425,11 @@ impl EventsRepo for sqlx::Pool<sqlx::Postgres> {
             .iter()
             .map(|s| s.as_ref().to_string())
             .collect();
+        let near = query.near().map_err(|e| anyhow!(e))?;
         let locations = query.location;
         let free_only = query.free.unwrap_or(false);
         let name_query = query.q;
+        let tag = query.tag.as_deref().and_then(normalize_tag);
 
         let events = sqlx::query_as!(
             Event,
@@ -118,13 +438,23 @@ impl EventsRepo for sqlx::Pool<sqlx::Postgres> {
                 SELECT DISTINCT e.id
                 FROM app.events e
                 LEFT JOIN app.event_event_types et ON e.id = et.event_id
-                WHERE (cardinality($1::text[]) = 0 OR et.event_type_name = ANY($1::text[]))
+                WHERE e.deleted_at IS NULL AND NOT e.pending
+                AND (cardinality($1::text[]) = 0 OR et.event_type_name = ANY($1::text[]))
                 AND (cardinality($2::text[]) = 0 OR e.source = ANY($2::text[]))
                 AND (cardinality($3::text[]) = 0 OR e.google_place_id = ANY($3::text[]))
                 AND ($4::boolean = false OR e.price = 0 OR e.price IS NULL)
-                AND ($5::text IS NULL OR e.name ILIKE ('%' || $5::text || '%'))
+                AND ($5::text IS NULL OR e.name ILIKE ('%' || $5::text || '%') OR e.full_text ILIKE ('%' || $5::text || '%'))
                 AND ($6::timestamptz IS NULL OR e.start_date >= $6)
                 AND ($7::timestamptz IS NULL OR e.start_date <= $7)
+                AND ($8::timestamptz IS NULL OR (e.start_date, e.id) > ($8, $9))
+                AND ($11::float8 IS NULL OR (
+                    e.lat IS NOT NULL AND e.lng IS NOT NULL
+                    AND 12742 * asin(sqrt(
+                        power(sin(radians(e.lat - $11) / 2), 2)
+                        + cos(radians($11)) * cos(radians(e.lat)) * power(sin(radians(e.lng - $12) / 2), 2)
+                    )) <= $13
+                ))
+                AND ($14::text IS NULL OR e.tags @> ARRAY[$14::text])
             )
             SELECT
                 e.id,
@@ -135,22 +465,32 @@ impl EventsRepo for sqlx::Pool<sqlx::Postgres> {
                 e.full_text,
                 e.start_date,
                 e.end_date,
+                e.all_day,
                 e.address,
                 e.original_location,
                 e.google_place_id,
+                e.lat,
+                e.lng,
                 e.location_name,
                 COALESCE(array_agg(et.event_type_name ORDER BY et.event_type_name) FILTER (WHERE et.event_type_name IS NOT NULL), '{}') as "event_types!: Vec<EventType>",
+                e.tags,
                 e.url,
                 e.confidence,
                 e.age_restrictions,
                 e.price,
+                e.contact_email,
+                e.contact_phone,
+                e.registration_required,
+                e.featured,
+                e.status as "status: EventStatus",
                 e.source as "source: EventSource",
                 e.external_id
             FROM app.events e
             JOIN filtered_events fe ON e.id = fe.id
             LEFT JOIN app.event_event_types et ON e.id = et.event_id
             GROUP BY e.id
-            ORDER BY e.start_date ASC NULLS LAST
+            ORDER BY e.start_date ASC NULLS LAST, e.id ASC
+            LIMIT $10
             "#,
             &categories,
             &sources,
@@ -158,7 +498,14 @@ impl EventsRepo for sqlx::Pool<sqlx::Postgres> {
             free_only,
             name_query,
             since,
-            until
+            until,
+            after.map(|(start_date, _)| start_date),
+            after.map(|(_, id)| id),
+            limit,
+            near.map(|p| p.lat),
+            near.map(|p| p.lng),
+            near.map(|p| p.radius_km),
+            tag
         )
         .fetch_all(self)
         .await?;
@@ -170,13 +517,14 @@ impl EventsRepo for sqlx::Pool<sqlx::Postgres> {
         let options = sqlx::query_as!(
             LocationOption,
             r#"
-            SELECT
-                google_place_id as "id!",
-                MIN(location_name) as "name!"
-            FROM app.events
-            WHERE google_place_id IS NOT NULL AND location_name IS NOT NULL
-            GROUP BY google_place_id
-            ORDER BY "name!"
+            SELECT v.google_place_id as id, v.name
+            FROM app.venues v
+            WHERE EXISTS (
+                SELECT 1 FROM app.events e
+                WHERE e.google_place_id = v.google_place_id
+                AND e.deleted_at IS NULL AND NOT e.pending
+            )
+            ORDER BY v.name
             "#
         )
         .fetch_all(self)
@@ -198,20 +546,29 @@ impl EventsRepo for sqlx::Pool<sqlx::Postgres> {
                 e.full_text,
                 e.start_date,
                 e.end_date,
+                e.all_day,
                 e.address,
                 e.original_location,
                 e.google_place_id,
+                e.lat,
+                e.lng,
                 e.location_name,
                 COALESCE(array_agg(et.event_type_name ORDER BY et.event_type_name) FILTER (WHERE et.event_type_name IS NOT NULL), '{}') as "event_types!: Vec<EventType>",
+                e.tags,
                 e.url,
                 e.confidence,
                 e.age_restrictions,
                 e.price,
+                e.contact_email,
+                e.contact_phone,
+                e.registration_required,
+                e.featured,
+                e.status as "status: EventStatus",
                 e.source as "source: EventSource",
                 e.external_id
             FROM app.events e
             LEFT JOIN app.event_event_types et ON e.id = et.event_id
-            WHERE e.id = $1
+            WHERE e.id = $1 AND e.deleted_at IS NULL AND NOT e.pending
             GROUP BY e.id
             "#,
             id,
@@ -222,131 +579,35 @@ impl EventsRepo for sqlx::Pool<sqlx::Postgres> {
         Ok(event)
     }
 
-    async fn claim_idempotency_key(&self, idempotency_key: uuid::Uuid) -> Result<bool> {
-        let insert_result = sqlx::query(
+    async fn get_venue(&self, google_place_id: &str) -> Result<Option<Venue>> {
+        let venue = sqlx::query_as!(
+            Venue,
             r#"
-            INSERT INTO app.idempotency_keys (idempotency_key)
-            VALUES ($1)
-            ON CONFLICT DO NOTHING
-            RETURNING idempotency_key
+            SELECT google_place_id, name, address, lat, lng
+            FROM app.venues
+            WHERE google_place_id = $1
             "#,
+            google_place_id
         )
-        .bind(idempotency_key)
         .fetch_optional(self)
         .await?;
 
-        Ok(insert_result.is_some())
-    }
-
-    async fn insert(&self, event: &NewEvent) -> Result<i64> {
-        save_event_to_db(self, event).await
-    }
-
-    async fn delete(&self, id: i64) -> Result<()> {
-        let result = sqlx::query(
-            r#"
-            DELETE FROM app.events
-            WHERE id = $1
-            "#,
-        )
-        .bind(id)
-        .execute(self)
-        .await?;
-
-        if result.rows_affected() == 0 {
-            return Err(anyhow!("Event with id {} not found", id));
-        }
-
-        Ok(())
-    }
-}
-
-pub async fn save_event_to_db(
-    executor: &sqlx::Pool<sqlx::Postgres>,
-    event: &NewEvent,
-) -> Result<i64> {
-    // If the event already exists, instead of saving a new one just
-    // return the ID for the existing one.
-    if let Some(duplicate_id) = find_duplicate(executor, event)
-        .await
-        .map_err(|e| anyhow!("Database lookup failed: {e}"))?
-    {
-        return Ok(duplicate_id);
+        Ok(venue)
     }
 
-    let mut tx = executor.begin().await?;
-
-    let id = sqlx::query_scalar!(
-        r#"
-            INSERT INTO app.events (
-                name,
-                description,
-                full_text,
-                start_date,
-                end_date,
-                address,
-                original_location,
-                google_place_id,
-                location_name,
-                url,
-                confidence,
-                age_restrictions,
-                price,
-                source,
-                external_id
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
-            RETURNING id
-            "#,
-        event.name,
-        event.description,
-        event.full_text,
-        event.start_date,
-        event.end_date,
-        event.address,
-        event.original_location,
-        event.google_place_id,
-        event.location_name,
-        event.url,
-        event.confidence,
-        event.age_restrictions,
-        event.price,
-        event.source.as_ref(),
-        event.external_id
-    )
-    .fetch_one(&mut *tx)
-    .await
-    .map_err(|e| anyhow!("Database insert failed: {e}"))?;
-
-    for et in &event.event_types {
-        let et_str = et.as_ref();
-        sqlx::query!(
-            r#"
-                INSERT INTO app.event_event_types (event_id, event_type_name)
-                VALUES ($1, $2)
-                ON CONFLICT DO NOTHING
-                "#,
-            id,
-            et_str
-        )
-        .execute(&mut *tx)
-        .await?;
+    async fn get_source(&self, source: &EventSource) -> Result<Option<SourceInfo>> {
+        // A couple dozen rows; not worth a second query to keep in step.
+        Ok(list_sources(self)
+            .await?
+            .into_iter()
+            .find(|info| &info.source == source))
     }
 
-    tx.commit().await?;
-
-    Ok(id)
-}
-
-async fn find_duplicate(
-    executor: &sqlx::Pool<sqlx::Postgres>,
-    event: &NewEvent,
-) -> Result<Option<i64>> {
-    // We map directly to Event struct for cleaner code
-    let potential_duplicates = sqlx::query_as!(
+    async fn list_all_after(&self, after_id: i64, limit: i64) -> Result<Vec<Event>> {
+        let events = sqlx::query_as!(
             Event,
             r#"
-            SELECT 
+            SELECT
                 e.id,
                 e.created_at,
                 e.updated_at,
@@ -355,284 +616,2849 @@ async fn find_duplicate(
                 e.full_text,
                 e.start_date,
                 e.end_date,
+                e.all_day,
                 e.address,
                 e.original_location,
                 e.google_place_id,
+                e.lat,
+                e.lng,
                 e.location_name,
                 COALESCE(array_agg(et.event_type_name ORDER BY et.event_type_name) FILTER (WHERE et.event_type_name IS NOT NULL), '{}') as "event_types!: Vec<EventType>",
+                e.tags,
                 e.url,
                 e.confidence,
                 e.age_restrictions,
                 e.price,
+                e.contact_email,
+                e.contact_phone,
+                e.registration_required,
+                e.featured,
+                e.status as "status: EventStatus",
                 e.source as "source: EventSource",
                 e.external_id
             FROM app.events e
             LEFT JOIN app.event_event_types et ON e.id = et.event_id
-            WHERE e.start_date = $1
-            AND e.end_date IS NOT DISTINCT FROM $2
-            AND e.address IS NOT DISTINCT FROM $3
+            WHERE e.id > $1 AND e.deleted_at IS NULL AND NOT e.pending
             GROUP BY e.id
+            ORDER BY e.id
+            LIMIT $2
             "#,
-            event.start_date,
-            event.end_date,
-            event.address
+            after_id,
+            limit,
         )
-        .fetch_all(executor)
+        .fetch_all(self)
         .await?;
 
-    for row in potential_duplicates {
-        if is_duplicate(&row, event) {
-            log::info!("Found duplicate {row:?}. Using it instead of {event:?}");
-            return Ok(Some(row.id));
-        }
+        Ok(events)
     }
 
-    Ok(None)
-}
+    async fn claim_idempotency_key(
+        &self,
+        idempotency_key: uuid::Uuid,
+        submitter: &Submitter,
+    ) -> Result<bool> {
+        let insert_result = sqlx::query(
+            r#"
+            INSERT INTO app.idempotency_keys
+                (idempotency_key, submitter_name, submitter_email, state)
+            VALUES ($1, $2, $3, 'claimed')
+            ON CONFLICT (idempotency_key) DO UPDATE
+            SET state = 'claimed',
+                claimed_at = now(),
+                submitter_name = EXCLUDED.submitter_name,
+                submitter_email = EXCLUDED.submitter_email
+            WHERE app.idempotency_keys.state = 'failed'
+               OR (app.idempotency_keys.state = 'claimed'
+                   AND app.idempotency_keys.claimed_at < now() - $4::interval)
+            RETURNING idempotency_key
+            "#,
+        )
+        .bind(idempotency_key)
+        .bind(&submitter.name)
+        .bind(&submitter.email)
+        .bind(IDEMPOTENCY_CLAIM_TIMEOUT)
+        .fetch_optional(self)
+        .await?;
 
-fn is_duplicate(a: &Event, b: &NewEvent) -> bool {
-    // start_date, end_date, and address are equal because of a
-    // previous database query.
+        Ok(insert_result.is_some())
+    }
 
-    // High threshold for name to avoid false positives (Workshop A vs B)
-    // 0.98 matches "Workshop A" vs "Workshop B", so we need > 0.98.
-    let name_match = jaro_winkler(&a.name, &b.name) > 0.985;
-    let desc_match = jaro_winkler(&a.description, &b.description) > 0.95;
-    name_match && desc_match
-}
+    async fn finish_idempotency_key(
+        &self,
+        idempotency_key: uuid::Uuid,
+        succeeded: bool,
+    ) -> Result<()> {
+        sqlx::query!(
+            "UPDATE app.idempotency_keys SET state = $2 WHERE idempotency_key = $1",
+            idempotency_key,
+            if succeeded { "succeeded" } else { "failed" }
+        )
+        .execute(self)
+        .await?;
+        Ok(())
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use chrono::{TimeZone, Utc};
+    async fn insert(&self, event: &NewEvent) -> Result<SaveOutcome> {
+        save_event_with_outcome(self, event).await
+    }
 
-    fn create_event(name: &str, description: &str, address: Option<&str>) -> NewEvent {
-        NewEvent {
-            name: name.to_string(),
-            description: description.to_string(),
-            full_text: description.to_string(),
-            start_date: Utc.timestamp_opt(1672531200, 0).unwrap(), // 2023-01-01
-            end_date: None,
-            address: address.map(|s| s.to_string()),
-            original_location: address.map(|s| s.to_string()),
-            google_place_id: None,
-            location_name: None,
-            event_types: vec![],
-            url: None,
-            confidence: 1.0,
-            age_restrictions: None,
-            price: None,
-            source: EventSource::ImageUpload,
-            external_id: None,
-        }
+    async fn insert_submitted(
+        &self,
+        event: &NewEvent,
+        submitter: &Submitter,
+        pending: bool,
+    ) -> Result<SaveOutcome> {
+        save_submitted_event(self, event, submitter, pending).await
     }
 
-    fn create_event_row(event: &NewEvent) -> Event {
-        Event {
-            id: 1,
-            created_at: Utc.timestamp_opt(1672531200, 0).unwrap(),
+    async fn list_pending(&self) -> Result<Vec<PendingEvent>> {
+        let events = sqlx::query_as!(
+            PendingEvent,
+            r#"
+            SELECT
+                id,
+                name,
+                description,
+                start_date,
+                all_day,
+                original_location,
+                location_name,
+                submitter_name,
+                submitter_email,
+                created_at
+            FROM app.events
+            WHERE pending AND deleted_at IS NULL
+            ORDER BY created_at ASC, id ASC
+            "#,
+        )
+        .fetch_all(self)
+        .await?;
+
+        Ok(events)
+    }
+
+    async fn approve(&self, ids: &[i64]) -> Result<Vec<i64>> {
+        let approved = sqlx::query_scalar!(
+            r#"
+            UPDATE app.events SET pending = false, updated_at = now()
+            WHERE id = ANY($1) AND pending AND deleted_at IS NULL
+            RETURNING id
+            "#,
+            ids
+        )
+        .fetch_all(self)
+        .await?;
+
+        Ok(approved)
+    }
+
+    async fn get_submitter(&self, id: i64) -> Result<Option<Submitter>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT submitter_name, submitter_email
+            FROM app.events
+            WHERE id = $1 AND (submitter_name IS NOT NULL OR submitter_email IS NOT NULL)
+            "#,
+            id
+        )
+        .fetch_optional(self)
+        .await?;
+
+        Ok(row.map(|row| Submitter {
+            name: row.submitter_name,
+            email: row.submitter_email,
+        }))
+    }
+
+    async fn delete(&self, id: i64) -> Result<()> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE app.events SET deleted_at = now()
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            id
+        )
+        .execute(self)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow!("Event with id {} not found", id));
+        }
+
+        Ok(())
+    }
+
+    async fn delete_many(&self, ids: &[i64]) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE app.events SET deleted_at = now()
+            WHERE id = ANY($1) AND deleted_at IS NULL
+            "#,
+            ids
+        )
+        .execute(self)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn set_event_types(&self, ids: &[i64], event_types: &[EventType]) -> Result<u64> {
+        let event_types: Vec<String> = event_types.iter().map(|t| t.as_ref().to_string()).collect();
+        let mut tx = self.begin().await?;
+
+        // Touching updated_at both locks the rows and lets caches notice
+        // the change, which the updated_at trigger won't for the join table.
+        let live_ids = sqlx::query_scalar!(
+            r#"
+            UPDATE app.events SET updated_at = now()
+            WHERE id = ANY($1) AND deleted_at IS NULL
+            RETURNING id
+            "#,
+            ids
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "DELETE FROM app.event_event_types WHERE event_id = ANY($1)",
+            &live_ids
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO app.event_event_types (event_id, event_type_name)
+            SELECT event_id, event_type_name
+            FROM unnest($1::bigint[]) AS event_id
+            CROSS JOIN unnest($2::text[]) AS event_type_name
+            "#,
+            &live_ids,
+            &event_types
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(live_ids.len() as u64)
+    }
+
+    async fn restore(&self, id: i64) -> Result<()> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE app.events SET deleted_at = NULL
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            "#,
+            id
+        )
+        .execute(self)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow!("Event {id} isn't in the trash"));
+        }
+
+        Ok(())
+    }
+
+    async fn list_deleted(&self) -> Result<Vec<DeletedEvent>> {
+        let events = sqlx::query_as!(
+            DeletedEvent,
+            r#"
+            SELECT id, name, start_date, deleted_at as "deleted_at!"
+            FROM app.events
+            WHERE deleted_at IS NOT NULL
+            ORDER BY deleted_at DESC, id DESC
+            "#,
+        )
+        .fetch_all(self)
+        .await?;
+
+        Ok(events)
+    }
+
+    async fn merge(&self, from_id: i64, into_id: i64) -> Result<()> {
+        let mut tx = self.begin().await?;
+
+        // The location columns move together: a geocoded location beats a
+        // raw one, and mixing an address from one event with a place ID
+        // from the other would point at two different places.
+        let result = sqlx::query!(
+            r#"
+            UPDATE app.events AS w SET
+                end_date = COALESCE(w.end_date, l.end_date),
+                description = CASE WHEN w.description = '' THEN l.description ELSE w.description END,
+                url = COALESCE(w.url, l.url),
+                age_restrictions = COALESCE(w.age_restrictions, l.age_restrictions),
+                price = COALESCE(w.price, l.price),
+                contact_email = COALESCE(w.contact_email, l.contact_email),
+                contact_phone = COALESCE(w.contact_phone, l.contact_phone),
+                registration_required = w.registration_required OR l.registration_required,
+                featured = w.featured OR l.featured,
+                tags = w.tags || ARRAY(SELECT t FROM unnest(l.tags) AS t WHERE t <> ALL(w.tags)),
+                address = CASE WHEN w.google_place_id IS NULL AND l.google_place_id IS NOT NULL
+                    THEN l.address ELSE w.address END,
+                original_location = CASE WHEN w.google_place_id IS NULL AND l.google_place_id IS NOT NULL
+                    THEN l.original_location ELSE w.original_location END,
+                location_name = CASE WHEN w.google_place_id IS NULL AND l.google_place_id IS NOT NULL
+                    THEN l.location_name ELSE w.location_name END,
+                google_place_id = COALESCE(w.google_place_id, l.google_place_id),
+                lat = CASE WHEN w.google_place_id IS NULL AND l.google_place_id IS NOT NULL
+                    THEN l.lat ELSE w.lat END,
+                lng = CASE WHEN w.google_place_id IS NULL AND l.google_place_id IS NOT NULL
+                    THEN l.lng ELSE w.lng END
+            FROM app.events AS l
+            WHERE w.id = $1 AND l.id = $2
+            AND w.deleted_at IS NULL AND l.deleted_at IS NULL
+            "#,
+            into_id,
+            from_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow!(
+                "Can't merge event {from_id} into {into_id}: one of them doesn't exist"
+            ));
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO app.event_event_types (event_id, event_type_name)
+            SELECT $1, event_type_name
+            FROM app.event_event_types
+            WHERE event_id = $2
+            ON CONFLICT DO NOTHING
+            "#,
+            into_id,
+            from_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // Into the trash like any other delete, so a wrong merge can be
+        // partly undone.
+        sqlx::query!(
+            "UPDATE app.events SET deleted_at = now() WHERE id = $1",
+            from_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn list_featured(&self, now: DateTime<Utc>) -> Result<Vec<SimpleEvent>> {
+        let events = sqlx::query_as!(
+            SimpleEvent,
+            r#"
+            SELECT
+                e.id,
+                e.updated_at,
+                e.name,
+                e.start_date,
+                e.end_date,
+                e.all_day,
+                e.original_location,
+                e.location_name,
+                e.lat,
+                e.lng,
+                COALESCE(array_agg(et.event_type_name ORDER BY et.event_type_name) FILTER (WHERE et.event_type_name IS NOT NULL), '{}') as "event_types!: Vec<EventType>",
+                e.confidence,
+                e.status as "status: EventStatus"
+            FROM app.events e
+            LEFT JOIN app.event_event_types et ON e.id = et.event_id
+            WHERE e.featured AND e.deleted_at IS NULL AND NOT e.pending
+            AND COALESCE(e.end_date, e.start_date + interval '1 day') >= $1
+            GROUP BY e.id
+            ORDER BY e.start_date ASC, e.id ASC
+            "#,
+            now
+        )
+        .fetch_all(self)
+        .await?;
+
+        Ok(events)
+    }
+
+    async fn list_ordered_by_confidence(&self) -> Result<Vec<SimpleEvent>> {
+        let events = sqlx::query_as!(
+            SimpleEvent,
+            r#"
+            SELECT
+                e.id,
+                e.updated_at,
+                e.name,
+                e.start_date,
+                e.end_date,
+                e.all_day,
+                e.original_location,
+                e.location_name,
+                e.lat,
+                e.lng,
+                COALESCE(array_agg(et.event_type_name ORDER BY et.event_type_name) FILTER (WHERE et.event_type_name IS NOT NULL), '{}') as "event_types!: Vec<EventType>",
+                e.confidence,
+                e.status as "status: EventStatus"
+            FROM app.events e
+            LEFT JOIN app.event_event_types et ON e.id = et.event_id
+            WHERE e.deleted_at IS NULL AND NOT e.pending
+            GROUP BY e.id
+            ORDER BY e.confidence ASC, e.start_date ASC, e.id ASC
+            "#
+        )
+        .fetch_all(self)
+        .await?;
+
+        Ok(events)
+    }
+
+    async fn list_related(
+        &self,
+        event_id: i64,
+        now: DateTime<Utc>,
+        limit: i64,
+    ) -> Result<Vec<RelatedEvent>> {
+        let rows = sqlx::query!(
+            r#"
+            WITH current_event AS (
+                SELECT
+                    e.google_place_id,
+                    ARRAY(
+                        SELECT t.event_type_name FROM app.event_event_types t
+                        WHERE t.event_id = e.id
+                    ) AS event_types
+                FROM app.events e
+                WHERE e.id = $1
+            ),
+            candidates AS (
+                SELECT
+                    e.id,
+                    e.start_date,
+                    COALESCE(e.google_place_id = c.google_place_id, false) AS same_venue,
+                    shared.first_type,
+                    shared.type_count
+                FROM app.events e
+                CROSS JOIN current_event c
+                CROSS JOIN LATERAL (
+                    SELECT min(t.event_type_name) AS first_type, count(*) AS type_count
+                    FROM app.event_event_types t
+                    WHERE t.event_id = e.id AND t.event_type_name = ANY(c.event_types)
+                ) shared
+                WHERE e.id <> $1 AND e.deleted_at IS NULL AND NOT e.pending
+                AND COALESCE(e.end_date, e.start_date + interval '1 day') >= $2
+            ),
+            -- Each signal gets its own `limit`, so a busy venue can't crowd
+            -- out the events of the same kind elsewhere.
+            ranked AS (
+                SELECT
+                    c.*,
+                    row_number() OVER (
+                        PARTITION BY c.same_venue
+                        ORDER BY
+                            CASE WHEN c.same_venue THEN 0 ELSE c.type_count END DESC,
+                            c.start_date ASC,
+                            c.id ASC
+                    ) AS rank
+                FROM candidates c
+                WHERE c.same_venue OR c.type_count > 0
+            )
+            SELECT
+                e.id,
+                e.updated_at,
+                e.name,
+                e.start_date,
+                e.end_date,
+                e.all_day,
+                e.original_location,
+                e.location_name,
+                e.lat,
+                e.lng,
+                COALESCE(array_agg(et.event_type_name ORDER BY et.event_type_name) FILTER (WHERE et.event_type_name IS NOT NULL), '{}') as "event_types!: Vec<EventType>",
+                e.confidence,
+                e.status as "status: EventStatus",
+                r.same_venue as "same_venue!",
+                r.first_type as "shared_type: EventType"
+            FROM ranked r
+            JOIN app.events e ON e.id = r.id
+            LEFT JOIN app.event_event_types et ON e.id = et.event_id
+            WHERE r.rank <= $3
+            GROUP BY e.id, r.same_venue, r.first_type, r.rank
+            ORDER BY r.same_venue DESC, r.rank ASC
+            "#,
+            event_id,
+            now,
+            limit
+        )
+        .fetch_all(self)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| RelatedEvent {
+                event: SimpleEvent {
+                    id: row.id,
+                    updated_at: row.updated_at,
+                    name: row.name,
+                    start_date: row.start_date,
+                    end_date: row.end_date,
+                    all_day: row.all_day,
+                    original_location: row.original_location,
+                    location_name: row.location_name,
+                    lat: row.lat,
+                    lng: row.lng,
+                    event_types: row.event_types,
+                    confidence: row.confidence,
+                    status: row.status,
+                },
+                same_venue: row.same_venue,
+                shared_type: row.shared_type,
+            })
+            .collect())
+    }
+
+    async fn set_featured(&self, id: i64, featured: bool) -> Result<()> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE app.events SET featured = $2
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            id,
+            featured
+        )
+        .execute(self)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow!("Event with id {} not found", id));
+        }
+
+        Ok(())
+    }
+
+    async fn set_status(&self, id: i64, status: EventStatus) -> Result<()> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE app.events SET status = $2
+            WHERE id = $1 AND deleted_at IS NULL
+            "#,
+            id,
+            status.as_ref()
+        )
+        .execute(self)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(anyhow!("Event with id {} not found", id));
+        }
+
+        Ok(())
+    }
+
+    async fn insert_report(&self, report: &NewUserReport) -> Result<i64> {
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO app.user_reports (event_id, reason, email, reporter_ip)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id
+            "#,
+            report.event_id,
+            report.reason,
+            report.email,
+            report.reporter_ip,
+        )
+        .fetch_one(self)
+        .await?;
+
+        Ok(id)
+    }
+
+    async fn count_reports_since(&self, reporter_ip: &str, since: DateTime<Utc>) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM app.user_reports
+            WHERE reporter_ip = $1 AND created_at >= $2
+            "#,
+            reporter_ip,
+            since,
+        )
+        .fetch_one(self)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn insert_contact_message(&self, message: &NewContactMessage) -> Result<i64> {
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO app.contact_messages (event_id, sender_email, message, sender_ip)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id
+            "#,
+            message.event_id,
+            message.sender_email,
+            message.message,
+            message.sender_ip,
+        )
+        .fetch_one(self)
+        .await?;
+
+        Ok(id)
+    }
+
+    async fn count_contact_messages_since(
+        &self,
+        sender_ip: &str,
+        since: DateTime<Utc>,
+    ) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM app.contact_messages
+            WHERE sender_ip = $1 AND created_at >= $2
+            "#,
+            sender_ip,
+            since,
+        )
+        .fetch_one(self)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn count_contact_messages_to_event_since(
+        &self,
+        event_id: i64,
+        sender_ip: &str,
+        since: DateTime<Utc>,
+    ) -> Result<i64> {
+        let count = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM app.contact_messages
+            WHERE event_id = $1 AND sender_ip = $2 AND created_at >= $3
+            "#,
+            event_id,
+            sender_ip,
+            since,
+        )
+        .fetch_one(self)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn list_reports(&self) -> Result<Vec<UserReport>> {
+        let reports = sqlx::query_as!(
+            UserReport,
+            r#"
+            SELECT r.id, r.event_id, e.name as event_name, r.reason, r.email, r.created_at
+            FROM app.user_reports r
+            JOIN app.events e ON e.id = r.event_id
+            WHERE e.deleted_at IS NULL
+            ORDER BY r.created_at DESC, r.id DESC
+            "#,
+        )
+        .fetch_all(self)
+        .await?;
+
+        Ok(reports)
+    }
+
+    async fn delete_report(&self, id: i64) -> Result<()> {
+        sqlx::query!("DELETE FROM app.user_reports WHERE id = $1", id)
+            .execute(self)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn find_processed_image(
+        &self,
+        dhash: u64,
+        max_distance: u32,
+    ) -> Result<Option<Vec<i64>>> {
+        // BIGINT is signed, so the hash is stored with its bits as-is.
+        let event_ids = sqlx::query_scalar!(
+            r#"
+            SELECT ARRAY(
+                SELECT e.id FROM app.events e
+                WHERE e.id = ANY(p.event_ids) AND NOT e.pending AND e.deleted_at IS NULL
+                ORDER BY e.id
+            ) as "event_ids!"
+            FROM app.processed_images p
+            WHERE bit_count((p.dhash # $1)::bit(64)) <= $2
+              AND (
+                cardinality(p.event_ids) = 0
+                OR EXISTS (
+                    SELECT 1 FROM app.events e
+                    WHERE e.id = ANY(p.event_ids) AND NOT e.pending AND e.deleted_at IS NULL
+                )
+              )
+            ORDER BY bit_count((p.dhash # $1)::bit(64)), p.created_at DESC
+            LIMIT 1
+            "#,
+            dhash as i64,
+            i64::from(max_distance),
+        )
+        .fetch_optional(self)
+        .await?;
+
+        Ok(event_ids)
+    }
+
+    async fn record_processed_image(&self, dhash: u64, event_ids: &[i64]) -> Result<()> {
+        sqlx::query!(
+            "INSERT INTO app.processed_images (dhash, event_ids) VALUES ($1, $2)",
+            dhash as i64,
+            event_ids,
+        )
+        .execute(self)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn stats(
+        &self,
+        now: DateTime<Utc>,
+        added_since: DateTime<Utc>,
+        tz: Tz,
+        top_venues: i64,
+    ) -> Result<SiteStats> {
+        let totals = sqlx::query!(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE NOT pending) as "total!",
+                COUNT(*) FILTER (
+                    WHERE NOT pending AND COALESCE(end_date, start_date) >= $1
+                ) as "upcoming!",
+                COUNT(*) FILTER (WHERE pending) as "pending!"
+            FROM app.events
+            WHERE deleted_at IS NULL
+            "#,
+            now
+        )
+        .fetch_one(self)
+        .await?;
+
+        let open_reports = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) as "count!"
+            FROM app.user_reports r
+            JOIN app.events e ON e.id = r.event_id
+            WHERE e.deleted_at IS NULL
+            "#
+        )
+        .fetch_one(self)
+        .await?;
+
+        let by_source = sqlx::query!(
+            r#"
+            SELECT source as "source!: EventSource", COUNT(*) as "count!"
+            FROM app.events
+            WHERE deleted_at IS NULL AND NOT pending
+            GROUP BY source
+            ORDER BY 2 DESC, 1
+            "#
+        )
+        .fetch_all(self)
+        .await?
+        .into_iter()
+        .map(|row| (row.source, row.count))
+        .collect();
+
+        let by_type = sqlx::query!(
+            r#"
+            SELECT et.event_type_name as "event_type!: EventType", COUNT(*) as "count!"
+            FROM app.event_event_types et
+            JOIN app.events e ON e.id = et.event_id
+            WHERE e.deleted_at IS NULL AND NOT e.pending
+            GROUP BY et.event_type_name
+            ORDER BY 2 DESC, 1
+            "#
+        )
+        .fetch_all(self)
+        .await?
+        .into_iter()
+        .map(|row| (row.event_type, row.count))
+        .collect();
+
+        let added_per_week = sqlx::query!(
+            r#"
+            SELECT
+                date_trunc('week', created_at AT TIME ZONE $2)::date as "week!",
+                COUNT(*) as "count!"
+            FROM app.events
+            WHERE deleted_at IS NULL AND NOT pending AND created_at >= $1
+            GROUP BY 1
+            ORDER BY 1
+            "#,
+            added_since,
+            tz.name()
+        )
+        .fetch_all(self)
+        .await?
+        .into_iter()
+        .map(|row| (row.week, row.count))
+        .collect();
+
+        let top_venues = sqlx::query!(
+            r#"
+            SELECT v.google_place_id, v.name, COUNT(*) as "count!"
+            FROM app.events e
+            JOIN app.venues v ON v.google_place_id = e.google_place_id
+            WHERE e.deleted_at IS NULL AND NOT e.pending
+            GROUP BY v.google_place_id, v.name
+            ORDER BY 3 DESC, 2
+            LIMIT $1
+            "#,
+            top_venues
+        )
+        .fetch_all(self)
+        .await?
+        .into_iter()
+        .map(|row| (row.google_place_id, row.name, row.count))
+        .collect();
+
+        Ok(SiteStats {
+            total: totals.total,
+            upcoming: totals.upcoming,
+            pending: totals.pending,
+            open_reports,
+            by_source,
+            by_type,
+            added_per_week,
+            top_venues,
+        })
+    }
+}
+
+pub async fn save_event_to_db(
+    executor: &sqlx::Pool<sqlx::Postgres>,
+    event: &NewEvent,
+) -> Result<i64> {
+    save_event_with_outcome(executor, event)
+        .await
+        .map(|outcome| outcome.id())
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SaveOutcome {
+    Inserted(i64),
+    /// An existing event matched, so nothing was written.
+    Duplicate(i64),
+}
+
+impl SaveOutcome {
+    pub fn id(&self) -> i64 {
+        match self {
+            SaveOutcome::Inserted(id) | SaveOutcome::Duplicate(id) => *id,
+        }
+    }
+}
+
+/// Adds the event's place to `app.venues` the first time it's seen, so
+/// the foreign key holds. An existing venue is left alone: later events
+/// don't get to rename it.
+async fn ensure_venue(tx: &mut sqlx::PgConnection, event: &NewEvent) -> Result<()> {
+    let Some(place_id) = &event.google_place_id else {
+        return Ok(());
+    };
+    let name = event
+        .location_name
+        .as_ref()
+        .or(event.address.as_ref())
+        .unwrap_or(place_id);
+    insert_venue(
+        tx,
+        place_id,
+        name,
+        event.address.as_deref(),
+        event.lat,
+        event.lng,
+    )
+    .await
+}
+
+async fn insert_venue(
+    tx: &mut sqlx::PgConnection,
+    place_id: &str,
+    name: &str,
+    address: Option<&str>,
+    lat: Option<f64>,
+    lng: Option<f64>,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+            INSERT INTO app.venues (google_place_id, name, address, lat, lng)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (google_place_id) DO NOTHING
+            "#,
+        place_id,
+        name,
+        address,
+        lat,
+        lng
+    )
+    .execute(tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Like `save_event_to_db`, for callers that report how many events were
+/// actually new.
+pub async fn save_event_with_outcome(
+    executor: &sqlx::Pool<sqlx::Postgres>,
+    event: &NewEvent,
+) -> Result<SaveOutcome> {
+    save_submitted_event(executor, event, &Submitter::default(), false).await
+}
+
+/// See `EventsRepo::insert_submitted`. A submission that duplicates an
+/// event we already have, pending or not, saves nothing.
+async fn save_submitted_event(
+    executor: &sqlx::Pool<sqlx::Postgres>,
+    event: &NewEvent,
+    submitter: &Submitter,
+    pending: bool,
+) -> Result<SaveOutcome> {
+    // If the event already exists, instead of saving a new one just
+    // return the ID for the existing one.
+    if let Some(duplicate_id) = find_duplicate(executor, event)
+        .await
+        .map_err(|e| anyhow!("Database lookup failed: {e}"))?
+    {
+        return Ok(SaveOutcome::Duplicate(duplicate_id));
+    }
+
+    let mut tx = executor.begin().await?;
+
+    ensure_venue(&mut tx, event).await?;
+
+    let id = sqlx::query_scalar!(
+        r#"
+            INSERT INTO app.events (
+                name,
+                description,
+                full_text,
+                start_date,
+                end_date,
+                all_day,
+                address,
+                original_location,
+                google_place_id,
+                location_name,
+                url,
+                confidence,
+                age_restrictions,
+                price,
+                source,
+                external_id,
+                lat,
+                lng,
+                contact_email,
+                contact_phone,
+                registration_required,
+                tags,
+                submitter_name,
+                submitter_email,
+                pending,
+                status
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26)
+            RETURNING id
+            "#,
+        event.name,
+        event.description,
+        event.full_text,
+        event.start_date,
+        event.end_date,
+        event.all_day,
+        event.address,
+        event.original_location,
+        event.google_place_id,
+        event.location_name,
+        event.url,
+        event.confidence,
+        event.age_restrictions,
+        event.price,
+        event.source.as_ref(),
+        event.external_id,
+        event.lat,
+        event.lng,
+        event.contact_email,
+        event.contact_phone,
+        event.registration_required,
+        &event.tags,
+        submitter.name,
+        submitter.email,
+        pending,
+        event.status.as_ref()
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| anyhow!("Database insert failed: {e}"))?;
+
+    for et in &event.event_types {
+        let et_str = et.as_ref();
+        sqlx::query!(
+            r#"
+                INSERT INTO app.event_event_types (event_id, event_type_name)
+                VALUES ($1, $2)
+                ON CONFLICT DO NOTHING
+                "#,
+            id,
+            et_str
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(SaveOutcome::Inserted(id))
+}
+
+#[derive(Debug, PartialEq)]
+pub enum UpsertOutcome {
+    Inserted(i64),
+    Updated(i64),
+    /// Updated, and the feed now says it's cancelled, postponed or back
+    /// on, which is worth telling webhook receivers about.
+    StatusChanged(i64),
+    Unchanged(i64),
+}
+
+/// Saves an event from an external feed, keyed on `(source, external_id)`.
+/// If we already have it, it's only rewritten when the feed says it changed
+/// since we last saw it, so reschedules and venue changes make it to the
+/// site without re-writing every event on every run. A `None`
+/// `source_updated_at` marks this copy as incomplete (its venue couldn't
+/// be looked up, say), so the next run rewrites it whatever the feed says.
+pub async fn upsert_external_event(
+    executor: &sqlx::Pool<sqlx::Postgres>,
+    event: &NewEvent,
+    source_updated_at: Option<DateTime<Utc>>,
+) -> Result<UpsertOutcome> {
+    let existing = sqlx::query!(
+        r#"
+            SELECT id, source_updated_at, status as "status: EventStatus"
+            FROM app.events
+            WHERE source = $1 AND external_id = $2
+            "#,
+        event.source.as_ref(),
+        event.external_id
+    )
+    .fetch_optional(executor)
+    .await?;
+
+    let Some(existing) = existing else {
+        let id = save_event_to_db(executor, event).await?;
+        sqlx::query!(
+            "UPDATE app.events SET source_updated_at = $2 WHERE id = $1",
+            id,
+            source_updated_at
+        )
+        .execute(executor)
+        .await?;
+        return Ok(UpsertOutcome::Inserted(id));
+    };
+
+    if let (Some(seen), Some(updated)) = (existing.source_updated_at, source_updated_at) {
+        if seen >= updated {
+            return Ok(UpsertOutcome::Unchanged(existing.id));
+        }
+    }
+
+    let mut tx = executor.begin().await?;
+
+    ensure_venue(&mut tx, event).await?;
+
+    sqlx::query!(
+        r#"
+            UPDATE app.events SET
+                name = $2,
+                description = $3,
+                full_text = $4,
+                start_date = $5,
+                end_date = $6,
+                all_day = $7,
+                address = $8,
+                original_location = $9,
+                google_place_id = $10,
+                location_name = $11,
+                url = $12,
+                age_restrictions = $13,
+                price = $14,
+                source_updated_at = $15,
+                lat = $16,
+                lng = $17,
+                contact_email = $18,
+                contact_phone = $19,
+                registration_required = $20,
+                tags = $21,
+                status = $22
+            WHERE id = $1
+            "#,
+        existing.id,
+        event.name,
+        event.description,
+        event.full_text,
+        event.start_date,
+        event.end_date,
+        event.all_day,
+        event.address,
+        event.original_location,
+        event.google_place_id,
+        event.location_name,
+        event.url,
+        event.age_restrictions,
+        event.price,
+        source_updated_at,
+        event.lat,
+        event.lng,
+        event.contact_email,
+        event.contact_phone,
+        event.registration_required,
+        &event.tags,
+        event.status.as_ref()
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| anyhow!("Database update failed: {e}"))?;
+
+    sqlx::query!(
+        "DELETE FROM app.event_event_types WHERE event_id = $1",
+        existing.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    for et in &event.event_types {
+        sqlx::query!(
+            r#"
+                INSERT INTO app.event_event_types (event_id, event_type_name)
+                VALUES ($1, $2)
+                ON CONFLICT DO NOTHING
+                "#,
+            existing.id,
+            et.as_ref()
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    if existing.status != event.status {
+        return Ok(UpsertOutcome::StatusChanged(existing.id));
+    }
+    Ok(UpsertOutcome::Updated(existing.id))
+}
+
+/// Moves upcoming events from `source` whose external ID wasn't seen in
+/// the latest fetch to the trash, i.e. the venue cancelled or unlisted
+/// them. It's the trash rather than gone for good so an admin can restore
+/// one a source dropped by mistake. Past events are left alone since
+/// sources routinely drop those from their feeds, and so are ones already
+/// marked cancelled or postponed, which stay listed so people who saw them
+/// find out they're off. Returns how many events were trashed.
+pub async fn prune_stale_events(
+    executor: &sqlx::Pool<sqlx::Postgres>,
+    source: &EventSource,
+    seen_ids: &[String],
+) -> Result<u64> {
+    let result = sqlx::query!(
+        r#"
+            UPDATE app.events SET deleted_at = now()
+            WHERE source = $1
+              AND external_id IS NOT NULL
+              AND NOT (external_id = ANY($2))
+              AND start_date > now()
+              AND status = 'scheduled'
+              AND deleted_at IS NULL
+            "#,
+        source.as_ref(),
+        seen_ids
+    )
+    .execute(executor)
+    .await
+    .map_err(|e| anyhow!("Failed to prune stale events: {e}"))?;
+
+    Ok(result.rows_affected())
+}
+
+/// Every source in `app.source_names`, see `SourceInfo`. Rows whose name
+/// isn't an `EventSource` are left out, since no event can carry them.
+pub async fn list_sources(executor: &sqlx::Pool<sqlx::Postgres>) -> Result<Vec<SourceInfo>> {
+    let rows = sqlx::query!(
+        r#"
+            SELECT name, display_name, url, feed_name, default_event_type, default_confidence
+            FROM app.source_names
+            ORDER BY name
+            "#
+    )
+    .fetch_all(executor)
+    .await
+    .map_err(|e| anyhow!("Failed to fetch sources: {e}"))?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            let source = EventSource::from_str(&row.name).ok()?;
+            Some(SourceInfo {
+                display_name: row.display_name.unwrap_or_else(|| source.to_string()),
+                homepage_url: row.url,
+                feed_name: row.feed_name,
+                default_event_type: row
+                    .default_event_type
+                    .and_then(|t| EventType::from_str(&t).ok()),
+                default_confidence: row.default_confidence,
+                source,
+            })
+        })
+        .collect())
+}
+
+/// How long a claimed upload can go unfinished before its key can be
+/// claimed again, on the view that its task died with a crash or restart.
+/// Well past the longest an upload waits its turn and is read.
+pub const IDEMPOTENCY_CLAIM_TIMEOUT: chrono::Duration = chrono::Duration::minutes(30);
+
+/// How long a deleted event stays in the trash before
+/// `purge_deleted_events` removes it.
+pub const TRASH_RETENTION: chrono::Duration = chrono::Duration::days(30);
+
+/// Permanently removes events that were deleted before `deleted_before`,
+/// returning how many. Their reports go with them.
+pub async fn purge_deleted_events(
+    executor: &sqlx::Pool<sqlx::Postgres>,
+    deleted_before: DateTime<Utc>,
+) -> Result<u64> {
+    let result = sqlx::query!(
+        "DELETE FROM app.events WHERE deleted_at < $1",
+        deleted_before
+    )
+    .execute(executor)
+    .await
+    .map_err(|e| anyhow!("Failed to purge deleted events: {e}"))?;
+
+    Ok(result.rows_affected())
+}
+
+/// How long "Google had no match" is believed before the address is
+/// looked up again. New venues do get added.
+pub const GEOCODE_NOT_FOUND_TTL: chrono::Duration = chrono::Duration::days(30);
+
+/// What the cache knows about `addresses`. An address that's missing was
+/// never looked up, only ever failed, or has a not-found answer older
+/// than `GEOCODE_NOT_FOUND_TTL`.
+pub async fn cached_geocodes(
+    executor: &sqlx::Pool<sqlx::Postgres>,
+    addresses: &[String],
+    now: DateTime<Utc>,
+) -> Result<HashMap<String, Geocoded>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT address, place_id, name, formatted_address, lat, lng
+        FROM app.geocode_cache
+        WHERE address = ANY($1) AND (place_id IS NOT NULL OR looked_up_at >= $2)
+        "#,
+        addresses,
+        now - GEOCODE_NOT_FOUND_TTL
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let result = match (
+                row.place_id,
+                row.name,
+                row.formatted_address,
+                row.lat,
+                row.lng,
+            ) {
+                (Some(place_id), Some(name), Some(formatted_address), Some(lat), Some(lng)) => {
+                    Geocoded::Found(GeocodedLocation {
+                        formatted_address,
+                        place_id,
+                        name,
+                        lat,
+                        lng,
+                    })
+                }
+                _ => Geocoded::NotFound,
+            };
+            (row.address, result)
+        })
+        .collect())
+}
+
+/// Remembers what Google said, replacing anything older. `Failed` lookups
+/// are skipped so the next run tries them again.
+pub async fn cache_geocodes(
+    executor: &sqlx::Pool<sqlx::Postgres>,
+    results: &HashMap<String, Geocoded>,
+) -> Result<()> {
+    for (address, result) in results {
+        let location = match result {
+            Geocoded::Found(location) => Some(location),
+            Geocoded::NotFound => None,
+            Geocoded::Failed => continue,
+        };
+        sqlx::query!(
+            r#"
+            INSERT INTO app.geocode_cache (address, place_id, name, formatted_address, lat, lng)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (address) DO UPDATE SET
+                place_id = EXCLUDED.place_id,
+                name = EXCLUDED.name,
+                formatted_address = EXCLUDED.formatted_address,
+                lat = EXCLUDED.lat,
+                lng = EXCLUDED.lng,
+                looked_up_at = now()
+            "#,
+            address,
+            location.map(|l| &l.place_id),
+            location.map(|l| &l.name),
+            location.map(|l| &l.formatted_address),
+            location.map(|l| l.lat),
+            location.map(|l| l.lng)
+        )
+        .execute(executor)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Events with a written-down location that never got matched to a place,
+/// mostly from before geocoding was reliable. Trashed events are included so
+/// they come back whole if restored.
+pub async fn events_missing_place_ids(
+    executor: &sqlx::Pool<sqlx::Postgres>,
+) -> Result<Vec<(i64, String)>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT id, original_location AS "original_location!"
+        FROM app.events
+        WHERE google_place_id IS NULL AND original_location IS NOT NULL
+        ORDER BY id
+        "#
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.id, row.original_location))
+        .collect())
+}
+
+/// Fills in the place for events that don't have one yet, adding the venue
+/// if it's new. Events that got a place some other way in the meantime are
+/// left alone. Returns how many events were updated.
+pub async fn set_event_place(
+    executor: &sqlx::Pool<sqlx::Postgres>,
+    ids: &[i64],
+    location: &GeocodedLocation,
+) -> Result<u64> {
+    let mut tx = executor.begin().await?;
+    insert_venue(
+        &mut tx,
+        &location.place_id,
+        &location.name,
+        Some(&location.formatted_address),
+        Some(location.lat),
+        Some(location.lng),
+    )
+    .await?;
+    let result = sqlx::query!(
+        r#"
+        UPDATE app.events SET
+            address = $2,
+            google_place_id = $3,
+            location_name = $4,
+            lat = $5,
+            lng = $6,
+            updated_at = now()
+        WHERE id = ANY($1) AND google_place_id IS NULL
+        "#,
+        ids,
+        location.formatted_address,
+        location.place_id,
+        location.name,
+        location.lat,
+        location.lng
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Deleted events still count as duplicates, so a feed or a re-uploaded
+/// flyer doesn't bring back something an admin threw away. Restoring it
+/// from the trash is the way back.
+pub async fn find_duplicate(
+    executor: &sqlx::Pool<sqlx::Postgres>,
+    event: &NewEvent,
+) -> Result<Option<i64>> {
+    // We map directly to Event struct for cleaner code
+    let potential_duplicates = sqlx::query_as!(
+            Event,
+            r#"
+            SELECT 
+                e.id,
+                e.created_at,
+                e.updated_at,
+                e.name,
+                e.description,
+                e.full_text,
+                e.start_date,
+                e.end_date,
+                e.all_day,
+                e.address,
+                e.original_location,
+                e.google_place_id,
+                e.lat,
+                e.lng,
+                e.location_name,
+                COALESCE(array_agg(et.event_type_name ORDER BY et.event_type_name) FILTER (WHERE et.event_type_name IS NOT NULL), '{}') as "event_types!: Vec<EventType>",
+                e.tags,
+                e.url,
+                e.confidence,
+                e.age_restrictions,
+                e.price,
+                e.contact_email,
+                e.contact_phone,
+                e.registration_required,
+                e.featured,
+                e.status as "status: EventStatus",
+                e.source as "source: EventSource",
+                e.external_id
+            FROM app.events e
+            LEFT JOIN app.event_event_types et ON e.id = et.event_id
+            WHERE e.start_date = $1
+            AND e.end_date IS NOT DISTINCT FROM $2
+            AND e.address IS NOT DISTINCT FROM $3
+            GROUP BY e.id
+            "#,
+            event.start_date,
+            event.end_date,
+            event.address
+        )
+        .fetch_all(executor)
+        .await?;
+
+    for row in potential_duplicates {
+        if is_duplicate(&row, event) {
+            log::info!("Found duplicate {row:?}. Using it instead of {event:?}");
+            return Ok(Some(row.id));
+        }
+    }
+
+    // The same link at overlapping times is the same event, however
+    // differently the name or venue were written down.
+    if let Some(url) = event.url.as_deref().and_then(normalize_url) {
+        let overlapping = sqlx::query!(
+            r#"
+            SELECT id, url as "url!"
+            FROM app.events
+            WHERE url IS NOT NULL
+            AND start_date <= $2
+            AND COALESCE(end_date, start_date) >= $1
+            ORDER BY id
+            "#,
+            event.start_date,
+            event.end_date.unwrap_or(event.start_date)
+        )
+        .fetch_all(executor)
+        .await?;
+
+        if let Some(row) = overlapping
+            .into_iter()
+            .find(|row| normalize_url(&row.url).as_deref() == Some(url.as_str()))
+        {
+            log::info!(
+                "Found event {} with the same link as {event:?}. Using it instead",
+                row.id
+            );
+            return Ok(Some(row.id));
+        }
+    }
+
+    Ok(None)
+}
+
+fn is_duplicate(a: &Event, b: &NewEvent) -> bool {
+    // start_date, end_date, and address are equal because of a
+    // previous database query.
+
+    // High threshold for name to avoid false positives (Workshop A vs B)
+    // 0.98 matches "Workshop A" vs "Workshop B", so we need > 0.98.
+    let name_match = jaro_winkler(&a.name, &b.name) > 0.985;
+    let desc_match = jaro_winkler(&a.description, &b.description) > 0.95;
+    name_match && desc_match
+}
+
+/// Pairs of events that look like the same thing listed twice, for an
+/// admin to review. Much looser than `is_duplicate`, which has to be sure
+/// enough to drop an insert on its own. Different sources word times and
+/// venues differently, so a similar name on the same day is enough here.
+pub fn find_likely_duplicates(events: &[SimpleEvent], tz: Tz) -> Vec<(&SimpleEvent, &SimpleEvent)> {
+    let mut by_day: BTreeMap<NaiveDate, Vec<&SimpleEvent>> = BTreeMap::new();
+    for event in events {
+        let day = event.start_date.with_timezone(&tz).date_naive();
+        by_day.entry(day).or_default().push(event);
+    }
+
+    let mut pairs = Vec::new();
+    for day_events in by_day.values() {
+        for (i, a) in day_events.iter().enumerate() {
+            for b in &day_events[i + 1..] {
+                let similarity = jaro_winkler(&a.name.to_lowercase(), &b.name.to_lowercase());
+                if similarity > 0.92 {
+                    pairs.push((*a, *b));
+                }
+            }
+        }
+    }
+    pairs
+}
+
+/// The files in `migrations/`, compiled in so the server and the `migrate`
+/// command can bring a database up to date without sqlx-cli or a checkout.
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Applies whatever migrations `pool`'s database hasn't seen yet and
+/// returns their versions. Already-applied ones are skipped, and sqlx holds
+/// an advisory lock while it runs, so this is safe on every start even with
+/// several instances starting at once. `pool` must connect as the schema
+/// owner (`Config::get_migrator_db_url`).
+pub async fn run_migrations(pool: &sqlx::PgPool) -> Result<Vec<i64>> {
+    let applied = applied_migration_versions(pool).await?;
+    let pending: Vec<&Migration> = MIGRATOR
+        .iter()
+        .filter(|m| !m.migration_type.is_down_migration() && !applied.contains(&m.version))
+        .collect();
+
+    MIGRATOR
+        .run(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to run migrations: {e}"))?;
+
+    for migration in &pending {
+        log::info!(
+            "Applied migration {} ({})",
+            migration.version,
+            migration.description
+        );
+    }
+    if pending.is_empty() {
+        log::info!("Database schema is up to date");
+    }
+    Ok(pending.iter().map(|m| m.version).collect())
+}
+
+/// Versions recorded in sqlx's bookkeeping table, which is created if this
+/// database has never been migrated.
+pub async fn applied_migration_versions(pool: &sqlx::PgPool) -> Result<HashSet<i64>> {
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    Ok(conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|m| m.version)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn create_event(name: &str, description: &str, address: Option<&str>) -> NewEvent {
+        NewEvent {
+            name: name.to_string(),
+            description: description.to_string(),
+            full_text: description.to_string(),
+            start_date: Utc.timestamp_opt(1672531200, 0).unwrap(), // 2023-01-01
+            end_date: None,
+            all_day: false,
+            address: address.map(|s| s.to_string()),
+            original_location: address.map(|s| s.to_string()),
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            status: EventStatus::Scheduled,
+        }
+    }
+
+    fn create_event_row(event: &NewEvent) -> Event {
+        Event {
+            id: 1,
+            created_at: Utc.timestamp_opt(1672531200, 0).unwrap(),
+            updated_at: Utc.timestamp_opt(1672531200, 0).unwrap(),
+            name: event.name.clone(),
+            description: event.description.clone(),
+            full_text: event.full_text.clone(),
+            start_date: event.start_date,
+            end_date: event.end_date,
+            all_day: event.all_day,
+            address: event.address.clone(),
+            original_location: event.original_location.clone(),
+            google_place_id: event.google_place_id.clone(),
+            lat: event.lat,
+            lng: event.lng,
+            location_name: event.location_name.clone(),
+            event_types: event.event_types.clone(),
+            tags: event.tags.clone(),
+            url: event.url.clone(),
+            confidence: event.confidence,
+            age_restrictions: event.age_restrictions.clone(),
+            price: event.price,
+            source: event.source.clone(),
+            external_id: event.external_id.clone(),
+            contact_email: event.contact_email.clone(),
+            contact_phone: event.contact_phone.clone(),
+            registration_required: event.registration_required,
+            featured: false,
+            status: EventStatus::Scheduled,
+        }
+    }
+
+    #[test]
+    fn test_duplicate_detection_boundaries() {
+        // Case 1: Name typo/extraction noise. "Somerville City Council" vs "Somerville City Councl"
+        // Should be duplicate
+        let e1 = create_event(
+            "Somerville City Council",
+            "Regular meeting of the council.",
+            Some("City Hall"),
+        );
+        let e2 = create_event(
+            "Somerville City Councl",
+            "Regular meeting of the council.",
+            Some("City Hall"),
+        );
+        assert!(
+            is_duplicate(&create_event_row(&e1), &e2),
+            "Typo in name should match"
+        );
+
+        // Case 2: Series events. "Workshop A" vs "Workshop B"
+        // Should NOT be duplicate
+        let e5 = create_event(
+            "Community Workshop A",
+            "Discussion on topic A.",
+            Some("Community Center"),
+        );
+        let e6 = create_event(
+            "Community Workshop B",
+            "Discussion on topic B.",
+            Some("Community Center"),
+        );
+        assert!(
+            !is_duplicate(&create_event_row(&e5), &e6),
+            "Workshop A vs B should NOT match"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_detection_strictness_lateral() {
+        // 1. Different Levels of same activity
+        let e1 = create_event("Salsa Level 1", "Learn the basics.", Some("Dance Studio"));
+        let e2 = create_event("Salsa Level 2", "Intermediate moves.", Some("Dance Studio"));
+        assert!(
+            !is_duplicate(&create_event_row(&e1), &e2),
+            "Level 1 vs Level 2 should NOT match"
+        );
+
+        // 2. Different Committees at City Hall
+        let e3 = create_event(
+            "School Committee Meeting",
+            "Weekly meeting.",
+            Some("City Hall"),
+        );
+        let e4 = create_event(
+            "Finance Committee Meeting",
+            "Weekly meeting.",
+            Some("City Hall"),
+        );
+        assert!(
+            !is_duplicate(&create_event_row(&e3), &e4),
+            "Different committees should NOT match"
+        );
+
+        // 3. Age Groups
+        let e5 = create_event("Youth Soccer (U8)", "Saturday game.", Some("Trum Field"));
+        let e6 = create_event("Youth Soccer (U10)", "Saturday game.", Some("Trum Field"));
+        assert!(
+            !is_duplicate(&create_event_row(&e5), &e6),
+            "Different age groups should NOT match"
+        );
+
+        // 4. Festival Acts (Same location, slightly different description/name)
+        let e7 = create_event("Porchfest: Band A", "Live music.", Some("123 Summer St"));
+        let e8 = create_event("Porchfest: Band B", "Live music.", Some("123 Summer St"));
+        assert!(
+            !is_duplicate(&create_event_row(&e7), &e8),
+            "Different bands at same festival venue should NOT match"
+        );
+
+        // 5. Language variations
+        let e9 = create_event("Storytime (English)", "Read aloud.", Some("Library"));
+        let e10 = create_event("Storytime (Spanish)", "Read aloud.", Some("Library"));
+        assert!(
+            !is_duplicate(&create_event_row(&e9), &e10),
+            "Different languages should NOT match"
+        );
+
+        // 6. Sports Opponents
+        let e11 = create_event(
+            "Somerville vs Medford",
+            "Varsity Game",
+            Some("Dilboy Stadium"),
+        );
+        let e12 = create_event(
+            "Somerville vs Everett",
+            "Varsity Game",
+            Some("Dilboy Stadium"),
+        );
+        assert!(
+            !is_duplicate(&create_event_row(&e11), &e12),
+            "Different opponents should NOT match"
+        );
+
+        // 7. Ward Meetings
+        let e13 = create_event("Ward 1 Meeting", "Community update", Some("Zoom"));
+        let e14 = create_event("Ward 2 Meeting", "Community update", Some("Zoom"));
+        assert!(
+            !is_duplicate(&create_event_row(&e13), &e14),
+            "Different wards should NOT match"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_detection_complex_scenarios() {
+        // Case 2: Generic Titles, Different Descriptions
+        let e3 = create_event(
+            "Weekly Meeting",
+            "Discussing zoning laws for the new park.",
+            Some("City Hall"),
+        );
+        let e4 = create_event(
+            "Weekly Meeting",
+            "Discussing school budget and teacher salaries.",
+            Some("City Hall"),
+        );
+        // Same name/location, but descriptions are totally different topics
+        assert!(
+            !is_duplicate(&create_event_row(&e3), &e4),
+            "Same title but different topics (descriptions) should NOT match"
+        );
+
+        // Case 3: Cut-off text leading to ambiguity (Extraction Artifacts)
+        let e5 = create_event("Ward Meeting", "Community update.", Some("Library"));
+        let e6 = create_event("Ward 2 Meeting", "Community update.", Some("Library"));
+        assert!(
+            !is_duplicate(&create_event_row(&e5), &e6),
+            "Generic/Cut-off name should NOT match specific name"
+        );
+
+        // Case 4: Truncated names matching unrelated events
+        let e7 = create_event("Somerville Art", "Local event.", Some("Armory"));
+        let e8 = create_event("Somerville Art Class", "Local event.", Some("Armory"));
+        assert!(
+            !is_duplicate(&create_event_row(&e7), &e8),
+            "Prefix match on different event types should NOT match"
+        );
+
+        // Case 5: Long Description overlaps but key differences
+        let e9 = create_event(
+            "Evening Social",
+            "Join us for a wonderful night of music, dancing, and light refreshments at the club.",
+            Some("The Club"),
+        );
+        let e10 = create_event(
+            "Evening Social",
+            "Join us for a wonderful night of painting, wine, and light refreshments at the club.",
+            Some("The Club"),
+        );
+        assert!(
+            !is_duplicate(&create_event_row(&e9), &e10),
+            "Descriptions with key activity differences should NOT match"
+        );
+    }
+
+    #[test]
+    fn test_find_likely_duplicates() {
+        let simple = |id, name: &str, hour| SimpleEvent {
+            id,
             updated_at: Utc.timestamp_opt(1672531200, 0).unwrap(),
-            name: event.name.clone(),
-            description: event.description.clone(),
-            full_text: event.full_text.clone(),
-            start_date: event.start_date,
-            end_date: event.end_date,
-            address: event.address.clone(),
-            original_location: event.original_location.clone(),
-            google_place_id: event.google_place_id.clone(),
-            location_name: event.location_name.clone(),
-            event_types: event.event_types.clone(),
-            url: event.url.clone(),
-            confidence: event.confidence,
-            age_restrictions: event.age_restrictions.clone(),
-            price: event.price,
-            source: event.source.clone(),
-            external_id: event.external_id.clone(),
+            name: name.to_string(),
+            // 2023-06-01, mid-afternoon in Somerville
+            start_date: Utc.with_ymd_and_hms(2023, 6, 1, hour, 0, 0).unwrap(),
+            end_date: None,
+            all_day: false,
+            original_location: None,
+            location_name: None,
+            lat: None,
+            lng: None,
+            event_types: vec![],
+            confidence: 1.0,
+            status: EventStatus::Scheduled,
+        };
+
+        let events = vec![
+            simple(1, "Porchfest 2023", 18),
+            // Same event from another source, different wording and time
+            simple(2, "PorchFest 2023!", 19),
+            simple(3, "Trivia Night", 18),
+            // Similar name, but the next day in Somerville
+            SimpleEvent {
+                start_date: Utc.with_ymd_and_hms(2023, 6, 2, 18, 0, 0).unwrap(),
+                ..simple(4, "Porchfest 2023", 18)
+            },
+        ];
+
+        let pairs: Vec<(i64, i64)> = find_likely_duplicates(&events, chrono_tz::America::New_York)
+            .into_iter()
+            .map(|(a, b)| (a.id, b.id))
+            .collect();
+        assert_eq!(pairs, vec![(1, 2)]);
+    }
+
+    #[sqlx::test(migrations = false)]
+    async fn test_run_migrations_applies_each_once(pool: sqlx::PgPool) -> Result<()> {
+        let applied = run_migrations(&pool).await?;
+        let expected: Vec<i64> = MIGRATOR.iter().map(|m| m.version).collect();
+        assert_eq!(applied, expected);
+        assert_eq!(
+            applied_migration_versions(&pool).await?,
+            expected.iter().copied().collect()
+        );
+
+        // A restart finds nothing left to do.
+        assert!(run_migrations(&pool).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_merge_keeps_richer_fields(pool: sqlx::PgPool) -> Result<()> {
+        let mut winner = create_event("Porchfest", "Music on porches.", Some("Somerville"));
+        winner.event_types = vec![EventType::Music];
+        let winner_id = save_event_to_db(&pool, &winner).await?;
+
+        let mut loser = create_event("PorchFest 2023!", "Bands everywhere", Some("Davis Sq"));
+        loser.google_place_id = Some("place-davis".to_string());
+        loser.location_name = Some("Davis Square".to_string());
+        loser.url = Some("https://example.com/porchfest".to_string());
+        loser.price = Some(0.0);
+        loser.event_types = vec![EventType::Music, EventType::Social];
+        let loser_id = save_event_to_db(&pool, &loser).await?;
+        assert_ne!(winner_id, loser_id);
+
+        pool.merge(loser_id, winner_id).await?;
+
+        assert!(pool.get(loser_id).await?.is_none());
+        let merged = pool.get(winner_id).await?.expect("Winner should remain");
+        assert_eq!(merged.name, "Porchfest");
+        assert_eq!(merged.description, "Music on porches.");
+        assert_eq!(merged.google_place_id.as_deref(), Some("place-davis"));
+        assert_eq!(merged.address.as_deref(), Some("Davis Sq"));
+        assert_eq!(merged.location_name.as_deref(), Some("Davis Square"));
+        assert_eq!(merged.url.as_deref(), Some("https://example.com/porchfest"));
+        assert_eq!(merged.price, Some(0.0));
+        assert_eq!(
+            merged.event_types,
+            vec![EventType::Music, EventType::Social]
+        );
+
+        assert!(pool.merge(loser_id, winner_id).await.is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_same_link_at_overlapping_times_is_a_duplicate(pool: sqlx::PgPool) -> Result<()> {
+        let mut flyer = create_event("Porchfest", "Bands on porches.", Some("Somerville"));
+        flyer.end_date = Some(flyer.start_date + chrono::Duration::hours(6));
+        flyer.url = Some("https://somervilleartscouncil.org/porchfest".to_string());
+        let id = save_event_to_db(&pool, &flyer).await?;
+
+        // A feed's listing of the same thing, starting partway through.
+        let mut listing = create_event("PorchFest 2023", "Music all over town", None);
+        listing.start_date = flyer.start_date + chrono::Duration::hours(1);
+        listing.url =
+            Some("https://SomervilleArtsCouncil.org/porchfest/?utm_source=feed".to_string());
+        assert_eq!(
+            save_event_with_outcome(&pool, &listing).await?,
+            SaveOutcome::Duplicate(id)
+        );
+
+        // Same link, but the next day.
+        listing.start_date = flyer.start_date + chrono::Duration::days(1);
+        assert!(matches!(
+            save_event_with_outcome(&pool, &listing).await?,
+            SaveOutcome::Inserted(_)
+        ));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_deleted_events_go_to_the_trash(pool: sqlx::PgPool) -> Result<()> {
+        let id = save_event_to_db(&pool, &create_event("Porchfest", "Bands", None)).await?;
+
+        pool.delete(id).await?;
+        assert!(pool.get(id).await?.is_none());
+        assert!(pool
+            .list(IndexQuery::default(), None, None)
+            .await?
+            .iter()
+            .all(|e| e.id != id));
+        let trash = pool.list_deleted().await?;
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].id, id);
+        assert_eq!(trash[0].name, "Porchfest");
+
+        pool.restore(id).await?;
+        assert!(pool.get(id).await?.is_some());
+        assert!(pool.list_deleted().await?.is_empty());
+
+        // Only events deleted before the cutoff are purged.
+        pool.delete(id).await?;
+        assert_eq!(
+            purge_deleted_events(&pool, Utc::now() - TRASH_RETENTION).await?,
+            0
+        );
+        assert_eq!(
+            purge_deleted_events(&pool, Utc::now() + chrono::Duration::minutes(1)).await?,
+            1
+        );
+        assert!(pool.list_deleted().await?.is_empty());
+        assert!(pool.restore(id).await.is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_featured_events_until_they_end(pool: sqlx::PgPool) -> Result<()> {
+        let start = Utc.timestamp_opt(1672531200, 0).unwrap();
+        let porchfest = save_event_to_db(&pool, &create_event("Porchfest", "Bands", None)).await?;
+        let mut fair = create_event("Craft Fair", "Crafts", None);
+        fair.end_date = Some(start + chrono::Duration::days(3));
+        let fair = save_event_to_db(&pool, &fair).await?;
+        save_event_to_db(&pool, &create_event("Open Mic", "Poems", None)).await?;
+
+        pool.set_featured(fair, true).await?;
+        pool.set_featured(porchfest, true).await?;
+        assert!(pool.get(fair).await?.unwrap().featured);
+        let ids = |events: Vec<SimpleEvent>| events.iter().map(|e| e.id).collect::<Vec<_>>();
+        assert_eq!(ids(pool.list_featured(start).await?), vec![porchfest, fair]);
+
+        // Without an end date it gets the day, like on the index.
+        let next_day = start + chrono::Duration::hours(30);
+        assert_eq!(ids(pool.list_featured(next_day).await?), vec![fair]);
+
+        pool.set_featured(fair, false).await?;
+        assert!(pool.list_featured(next_day).await?.is_empty());
+
+        // Deleted events drop out, and can't be featured while in the trash.
+        pool.delete(porchfest).await?;
+        assert!(pool.list_featured(start).await?.is_empty());
+        assert!(pool.set_featured(porchfest, true).await.is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_bulk_changes(pool: sqlx::PgPool) -> Result<()> {
+        let mut porchfest = create_event("Porchfest", "Bands", None);
+        porchfest.event_types = vec![EventType::Music];
+        let porchfest = save_event_to_db(&pool, &porchfest).await?;
+        let fair = save_event_to_db(&pool, &create_event("Zine Fair", "Zines", None)).await?;
+        let trashed = save_event_to_db(&pool, &create_event("Open Mic", "Poems", None)).await?;
+        pool.delete(trashed).await?;
+
+        let before = pool.get(porchfest).await?.unwrap().updated_at;
+        let updated = pool
+            .set_event_types(
+                &[porchfest, fair, trashed],
+                &[EventType::Art, EventType::Social],
+            )
+            .await?;
+        assert_eq!(updated, 2);
+        for id in [porchfest, fair] {
+            let event = pool.get(id).await?.unwrap();
+            assert_eq!(event.event_types, vec![EventType::Art, EventType::Social]);
+        }
+        assert!(pool.get(porchfest).await?.unwrap().updated_at > before);
+
+        assert_eq!(pool.delete_many(&[porchfest, trashed, 9999]).await?, 1);
+        assert!(pool.get(porchfest).await?.is_none());
+        assert!(pool.get(fair).await?.is_some());
+        assert_eq!(pool.list_deleted().await?.len(), 2);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_pending_submissions_stay_hidden_until_approved(pool: sqlx::PgPool) -> Result<()> {
+        let submitter = Submitter {
+            name: Some("Pat".to_string()),
+            email: None,
+        };
+        let id = pool
+            .insert_submitted(
+                &create_event("Stoop Sale", "Everything must go", None),
+                &submitter,
+                true,
+            )
+            .await?
+            .id();
+
+        assert!(pool.get(id).await?.is_none());
+        assert!(pool
+            .list(IndexQuery::default(), None, None)
+            .await?
+            .is_empty());
+        assert_eq!(pool.count(IndexQuery::default(), None, None).await?, 0);
+        let pending = pool.list_pending().await?;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, id);
+        assert_eq!(pending[0].submitter_name.as_deref(), Some("Pat"));
+
+        assert_eq!(pool.approve(&[id, id + 1]).await?, vec![id]);
+        assert!(pool.get(id).await?.is_some());
+        assert!(pool.list_pending().await?.is_empty());
+        assert_eq!(pool.get_submitter(id).await?, Some(submitter));
+        // Approving twice doesn't announce it twice.
+        assert!(pool.approve(&[id]).await?.is_empty());
+
+        let admin_id = pool
+            .insert(&create_event("Porchfest", "Bands", None))
+            .await?
+            .id();
+        assert_eq!(pool.get_submitter(admin_id).await?, None);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_events_share_one_venue_per_place(pool: sqlx::PgPool) -> Result<()> {
+        let mut porchfest = create_event("Porchfest", "Bands", Some("1 Davis Sq"));
+        porchfest.google_place_id = Some("place-davis".to_string());
+        porchfest.location_name = Some("Davis Square".to_string());
+        (porchfest.lat, porchfest.lng) = (Some(42.3967), Some(-71.1226));
+        save_event_to_db(&pool, &porchfest).await?;
+        let mut fair = create_event("Zine Fair", "Zines", Some("Davis Sq, Somerville"));
+        fair.google_place_id = Some("place-davis".to_string());
+        fair.location_name = Some("Davis Sq.".to_string());
+        save_event_to_db(&pool, &fair).await?;
+
+        // The first spelling sticks.
+        let venue = pool.get_venue("place-davis").await?.unwrap();
+        assert_eq!(venue.name, "Davis Square");
+        assert_eq!(venue.address.as_deref(), Some("1 Davis Sq"));
+        assert_eq!(venue.lat, Some(42.3967));
+        assert!(pool.get_venue("place-nowhere").await?.is_none());
+
+        let locations = pool.get_distinct_locations().await?;
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].name, "Davis Square");
+
+        // A place id has to name a venue.
+        let orphan = sqlx::query("UPDATE app.events SET google_place_id = 'place-nowhere'")
+            .execute(&pool)
+            .await;
+        assert!(orphan.is_err());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_count_agrees_with_list(pool: sqlx::PgPool) -> Result<()> {
+        let mut porchfest = create_event("Porchfest", "Bands on porches", Some("Davis"));
+        porchfest.event_types = vec![EventType::Music, EventType::Social];
+        porchfest.price = Some(0.0);
+        (porchfest.lat, porchfest.lng) = (Some(42.3967), Some(-71.1226));
+        porchfest.google_place_id = Some("place-davis".to_string());
+        let mut workshop = create_event("Zine Workshop", "Bring scissors", Some("Library"));
+        workshop.event_types = vec![EventType::Art];
+        workshop.price = Some(10.0);
+        workshop.tags = vec!["zines".to_string()];
+        workshop.start_date += chrono::Duration::days(2);
+        let mut feed = create_event("City Meeting", "Budget hearing", Some("City Hall"));
+        feed.source = EventSource::CityOfSomerville;
+        feed.start_date += chrono::Duration::days(5);
+        let mut trashed = create_event("Cancelled Show", "Nope", Some("Union"));
+        trashed.event_types = vec![EventType::Music];
+        for event in [&porchfest, &workshop, &feed] {
+            save_event_to_db(&pool, event).await?;
+        }
+        let trashed_id = save_event_to_db(&pool, &trashed).await?;
+        pool.delete(trashed_id).await?;
+
+        let start = porchfest.start_date;
+        let cases = [
+            (IndexQuery::default(), None, None),
+            (
+                IndexQuery {
+                    event_types: vec![EventType::Music, EventType::Art],
+                    ..Default::default()
+                },
+                None,
+                None,
+            ),
+            (
+                IndexQuery {
+                    source: vec![EventSource::CityOfSomerville],
+                    ..Default::default()
+                },
+                None,
+                None,
+            ),
+            (
+                IndexQuery {
+                    free: Some(true),
+                    q: Some("o".to_string()),
+                    ..Default::default()
+                },
+                None,
+                None,
+            ),
+            (
+                IndexQuery {
+                    location: vec!["place-davis".to_string()],
+                    lat: Some(42.3884),
+                    lng: Some(-71.1191),
+                    ..Default::default()
+                },
+                None,
+                None,
+            ),
+            (
+                IndexQuery {
+                    tag: Some("Zines".to_string()),
+                    ..Default::default()
+                },
+                None,
+                None,
+            ),
+            (
+                IndexQuery::default(),
+                Some(start + chrono::Duration::days(1)),
+                Some(start + chrono::Duration::days(3)),
+            ),
+        ];
+        for (case, (query, since, until)) in cases.into_iter().enumerate() {
+            let listed = pool.list(query.clone(), since, until).await?.len() as i64;
+            assert_eq!(
+                pool.count(query, since, until).await?,
+                listed,
+                "case {case}"
+            );
+        }
+        assert_eq!(pool.count(IndexQuery::default(), None, None).await?, 3);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_list_near_point(pool: sqlx::PgPool) -> Result<()> {
+        let mut davis = create_event("Davis Show", "Near", Some("Davis"));
+        (davis.lat, davis.lng) = (Some(42.3967), Some(-71.1226));
+        let mut downtown = create_event("Downtown Show", "Far", Some("Downtown"));
+        (downtown.lat, downtown.lng) = (Some(42.3555), Some(-71.0605));
+        let unplaced = create_event("Somewhere Show", "Unknown", Some("Somewhere"));
+        for event in [&davis, &downtown, &unplaced] {
+            save_event_to_db(&pool, event).await?;
+        }
+
+        // Porter Square, with Davis about a kilometer away and downtown ~7km.
+        let query = IndexQuery {
+            lat: Some(42.3884),
+            lng: Some(-71.1191),
+            radius_km: Some(2.0),
+            ..Default::default()
+        };
+        let names: Vec<String> = pool
+            .list(query.clone(), None, None)
+            .await?
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(names, vec!["Davis Show"]);
+        let full = pool.list_full(query, None, None).await?;
+        assert_eq!(full.len(), 1);
+        assert_eq!(full[0].name, "Davis Show");
+
+        // No point means no distance filter, even for unplaced events.
+        let all = pool.list(IndexQuery::default(), None, None).await?;
+        assert_eq!(all.len(), 3);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_list_by_tag(pool: sqlx::PgPool) -> Result<()> {
+        let mut brunch = create_event("Vegan Brunch", "Tofu scramble", Some("Cafe"));
+        brunch.tags = vec!["vegan".to_string(), "pop-up".to_string()];
+        let mut bbq = create_event("BBQ", "Brisket", Some("Yard"));
+        bbq.tags = vec!["pop-up".to_string()];
+        for event in [&brunch, &bbq] {
+            save_event_to_db(&pool, event).await?;
+        }
+
+        // The filter is normalized the same way the stored tags are.
+        let query = IndexQuery {
+            tag: Some(" #Vegan".to_string()),
+            ..Default::default()
+        };
+        let names: Vec<String> = pool
+            .list(query.clone(), None, None)
+            .await?
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(names, vec!["Vegan Brunch"]);
+        let full = pool.list_full(query, None, None).await?;
+        assert_eq!(full.len(), 1);
+        assert_eq!(full[0].tags, vec!["vegan", "pop-up"]);
+
+        let query = IndexQuery {
+            tag: Some("pop-up".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(pool.list(query, None, None).await?.len(), 2);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_idempotency_keys_block_only_finished_uploads(pool: sqlx::PgPool) -> Result<()> {
+        let submitter = Submitter::default();
+        let key = uuid::Uuid::new_v4();
+
+        assert!(pool.claim_idempotency_key(key, &submitter).await?);
+        // Still being read.
+        assert!(!pool.claim_idempotency_key(key, &submitter).await?);
+
+        pool.finish_idempotency_key(key, false).await?;
+        assert!(pool.claim_idempotency_key(key, &submitter).await?);
+
+        pool.finish_idempotency_key(key, true).await?;
+        assert!(!pool.claim_idempotency_key(key, &submitter).await?);
+
+        // Claimed by a task that died before finishing.
+        let abandoned = uuid::Uuid::new_v4();
+        assert!(pool.claim_idempotency_key(abandoned, &submitter).await?);
+        sqlx::query!(
+            "UPDATE app.idempotency_keys SET claimed_at = $2 WHERE idempotency_key = $1",
+            abandoned,
+            Utc::now() - IDEMPOTENCY_CLAIM_TIMEOUT - Duration::minutes(1)
+        )
+        .execute(&pool)
+        .await?;
+        assert!(pool.claim_idempotency_key(abandoned, &submitter).await?);
+        assert!(!pool.claim_idempotency_key(abandoned, &submitter).await?);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_sources_come_from_the_table(pool: sqlx::PgPool) -> Result<()> {
+        use strum::IntoEnumIterator;
+
+        // A row added ahead of its enum variant is skipped, not an error.
+        sqlx::query!(
+            "INSERT INTO app.source_names (name, feed_name) VALUES ('NewFeed', 'New Feed')"
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query!(
+            r#"
+            UPDATE app.source_names
+            SET display_name = NULL, default_event_type = 'Music', default_confidence = 0.8
+            WHERE name = 'TheMiddleEast'
+            "#
+        )
+        .execute(&pool)
+        .await?;
+
+        let sources = list_sources(&pool).await?;
+        assert_eq!(sources.len(), EventSource::iter().count());
+        assert_eq!(
+            pool.get_source(&EventSource::TheMiddleEast).await?,
+            Some(SourceInfo {
+                source: EventSource::TheMiddleEast,
+                display_name: "The Middle East".to_string(),
+                homepage_url: Some("https://mideastoffers.com".to_string()),
+                feed_name: Some("The Middle East".to_string()),
+                default_event_type: Some(EventType::Music),
+                default_confidence: Some(0.8),
+            })
+        );
+        let upload = pool.get_source(&EventSource::ImageUpload).await?.unwrap();
+        assert_eq!(upload.homepage_url, None);
+        assert_eq!(upload.feed_name, None);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_list_all_after_pages_by_id(pool: sqlx::PgPool) -> Result<()> {
+        let mut ids = Vec::new();
+        for (name, description) in [("Quiz", "Trivia"), ("Swap", "Clothes"), ("Jam", "Fiddles")] {
+            let mut event = create_event(name, description, None);
+            event.event_types = vec![EventType::Music];
+            ids.push(save_event_to_db(&pool, &event).await?);
         }
+
+        let first = pool.list_all_after(0, 2).await?;
+        assert_eq!(first.iter().map(|e| e.id).collect::<Vec<_>>(), ids[..2]);
+        assert_eq!(first[0].event_types, vec![EventType::Music]);
+        let rest = pool.list_all_after(first[1].id, 2).await?;
+        assert_eq!(rest.iter().map(|e| e.id).collect::<Vec<_>>(), ids[2..]);
+        assert!(pool.list_all_after(ids[2], 2).await?.is_empty());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_user_reports(pool: sqlx::PgPool) -> Result<()> {
+        let event_id = save_event_to_db(&pool, &create_event("Quiz", "Trivia", None)).await?;
+        let report = |reason: &str, ip: &str| NewUserReport {
+            event_id,
+            reason: reason.to_string(),
+            email: None,
+            reporter_ip: ip.to_string(),
+        };
+        pool.insert_report(&report("Wrong date", "203.0.113.5"))
+            .await?;
+        let newest = pool.insert_report(&report("Spam", "203.0.113.5")).await?;
+        pool.insert_report(&report("Cancelled", "192.0.2.1"))
+            .await?;
+
+        let hour_ago = Utc::now() - chrono::Duration::hours(1);
+        assert_eq!(pool.count_reports_since("203.0.113.5", hour_ago).await?, 2);
+        assert_eq!(
+            pool.count_reports_since("203.0.113.5", Utc::now()).await?,
+            0
+        );
+
+        let reports = pool.list_reports().await?;
+        assert_eq!(reports.len(), 3);
+        assert_eq!(reports[0].reason, "Cancelled");
+        assert_eq!(reports[0].event_name, "Quiz");
+
+        pool.delete_report(newest).await?;
+        assert_eq!(pool.list_reports().await?.len(), 2);
+
+        // Reports go with their event.
+        pool.delete(event_id).await?;
+        assert!(pool.list_reports().await?.is_empty());
+
+        Ok(())
     }
 
-    #[test]
-    fn test_duplicate_detection_boundaries() {
-        // Case 1: Name typo/extraction noise. "Somerville City Council" vs "Somerville City Councl"
-        // Should be duplicate
-        let e1 = create_event(
-            "Somerville City Council",
-            "Regular meeting of the council.",
-            Some("City Hall"),
-        );
-        let e2 = create_event(
-            "Somerville City Councl",
-            "Regular meeting of the council.",
-            Some("City Hall"),
-        );
-        assert!(
-            is_duplicate(&create_event_row(&e1), &e2),
-            "Typo in name should match"
+    #[sqlx::test]
+    async fn test_stats(pool: sqlx::PgPool) -> Result<()> {
+        let now = Utc::now();
+        let at_place = |name: &str, start: DateTime<Utc>, types: Vec<EventType>| NewEvent {
+            start_date: start,
+            google_place_id: Some("place-union".to_string()),
+            location_name: Some("Union Square".to_string()),
+            event_types: types,
+            ..create_event(name, name, Some("Union Sq"))
+        };
+        let porchfest = save_event_to_db(
+            &pool,
+            &at_place(
+                "Porchfest",
+                now + chrono::Duration::days(1),
+                vec![EventType::Music],
+            ),
+        )
+        .await?;
+        save_event_to_db(
+            &pool,
+            &at_place(
+                "Gig",
+                now - chrono::Duration::days(3),
+                vec![EventType::Music, EventType::Art],
+            ),
+        )
+        .await?;
+        save_event_to_db(
+            &pool,
+            &NewEvent {
+                start_date: now + chrono::Duration::days(2),
+                source: EventSource::SomervilleTheatre,
+                ..create_event("Matinee", "Film", None)
+            },
+        )
+        .await?;
+        // Neither of these is listed.
+        pool.insert_submitted(
+            &create_event("Stoop Sale", "Everything must go", None),
+            &Submitter::default(),
+            true,
+        )
+        .await?;
+        let trashed = save_event_to_db(&pool, &create_event("Gone", "Deleted", None)).await?;
+        pool.delete(trashed).await?;
+        pool.insert_report(&NewUserReport {
+            event_id: porchfest,
+            reason: "Moved indoors".to_string(),
+            email: None,
+            reporter_ip: "192.0.2.1".to_string(),
+        })
+        .await?;
+
+        let tz = chrono_tz::America::New_York;
+        let stats = pool
+            .stats(now, now - chrono::Duration::weeks(1), tz, 5)
+            .await?;
+
+        let today = now.with_timezone(&tz).date_naive();
+        let monday =
+            today - chrono::Duration::days(i64::from(today.weekday().num_days_from_monday()));
+        assert_eq!(
+            stats,
+            SiteStats {
+                total: 3,
+                upcoming: 2,
+                pending: 1,
+                open_reports: 1,
+                by_source: vec![
+                    (EventSource::ImageUpload, 2),
+                    (EventSource::SomervilleTheatre, 1)
+                ],
+                by_type: vec![(EventType::Music, 2), (EventType::Art, 1)],
+                added_per_week: vec![(monday, 3)],
+                top_venues: vec![("place-union".to_string(), "Union Square".to_string(), 2)],
+            }
         );
 
-        // Case 2: Series events. "Workshop A" vs "Workshop B"
-        // Should NOT be duplicate
-        let e5 = create_event(
-            "Community Workshop A",
-            "Discussion on topic A.",
-            Some("Community Center"),
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_find_processed_image_by_distance(pool: sqlx::PgPool) -> Result<()> {
+        // High bit set, which is negative once stored as BIGINT.
+        let flyer = 0xF0F0_0000_FFFF_1234_u64;
+        let first = save_event_to_db(&pool, &create_event("Contra Dance", "", None)).await?;
+        let second = save_event_to_db(&pool, &create_event("Dance Lesson", "", None)).await?;
+        pool.record_processed_image(flyer, &[first, second]).await?;
+        pool.record_processed_image(!flyer, &[]).await?;
+
+        assert_eq!(
+            pool.find_processed_image(flyer, 0).await?,
+            Some(vec![first, second])
         );
-        let e6 = create_event(
-            "Community Workshop B",
-            "Discussion on topic B.",
-            Some("Community Center"),
+        assert_eq!(
+            pool.find_processed_image(flyer ^ 0b111, 3).await?,
+            Some(vec![first, second])
         );
-        assert!(
-            !is_duplicate(&create_event_row(&e5), &e6),
-            "Workshop A vs B should NOT match"
+        assert_eq!(pool.find_processed_image(flyer ^ 0b111, 2).await?, None);
+        assert_eq!(
+            pool.find_processed_image(!flyer ^ 1, 1).await?,
+            Some(vec![])
         );
+
+        Ok(())
     }
 
-    #[test]
-    fn test_duplicate_detection_strictness_lateral() {
-        // 1. Different Levels of same activity
-        let e1 = create_event("Salsa Level 1", "Learn the basics.", Some("Dance Studio"));
-        let e2 = create_event("Salsa Level 2", "Intermediate moves.", Some("Dance Studio"));
-        assert!(
-            !is_duplicate(&create_event_row(&e1), &e2),
-            "Level 1 vs Level 2 should NOT match"
-        );
+    #[sqlx::test]
+    async fn test_processed_image_only_counts_listed_events(pool: sqlx::PgPool) -> Result<()> {
+        let flyer = 0x0123_4567_89AB_CDEF_u64;
+        let submitted = pool
+            .insert_submitted(
+                &create_event("Porch Concert", "", None),
+                &Submitter::default(),
+                true,
+            )
+            .await?
+            .id();
+        pool.record_processed_image(flyer, &[submitted]).await?;
 
-        // 2. Different Committees at City Hall
-        let e3 = create_event(
-            "School Committee Meeting",
-            "Weekly meeting.",
-            Some("City Hall"),
-        );
-        let e4 = create_event(
-            "Finance Committee Meeting",
-            "Weekly meeting.",
-            Some("City Hall"),
+        // Waiting for review, so an admin uploading it again isn't turned
+        // away with nothing published...
+        assert_eq!(pool.find_processed_image(flyer, 0).await?, None);
+
+        // ...until it's approved.
+        pool.approve(&[submitted]).await?;
+        assert_eq!(
+            pool.find_processed_image(flyer, 0).await?,
+            Some(vec![submitted])
         );
-        assert!(
-            !is_duplicate(&create_event_row(&e3), &e4),
-            "Different committees should NOT match"
+
+        pool.delete(submitted).await?;
+        assert_eq!(pool.find_processed_image(flyer, 0).await?, None);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_search_matches_flyer_text(pool: sqlx::PgPool) -> Result<()> {
+        let mut jazz = create_event("Friday Jazz", "Live music", None);
+        jazz.full_text = "FRIDAY JAZZ featuring the Mystic River Trio".to_string();
+        save_event_to_db(&pool, &jazz).await?;
+        save_event_to_db(&pool, &create_event("Trivia", "Bar trivia", None)).await?;
+
+        let query = IndexQuery {
+            q: Some("mystic river".to_string()),
+            ..Default::default()
+        };
+        let names: Vec<String> = pool
+            .list(query, None, None)
+            .await?
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(names, vec!["Friday Jazz"]);
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_event_types_deterministic_order(pool: sqlx::PgPool) -> Result<()> {
+        let mut event = create_event("Sorted Types", "Desc", Some("Loc"));
+        // Insert in mixed order
+        event.event_types = vec![EventType::Social, EventType::Art, EventType::ChildFriendly];
+        event.source = EventSource::ImageUpload;
+
+        let id = save_event_to_db(&pool, &event).await?;
+
+        let fetched = pool.get(id).await?.expect("Event not found");
+
+        // Should be sorted alphabetically by the string representation
+        // Art, ChildFriendly, Social
+        assert_eq!(
+            fetched.event_types,
+            vec![EventType::Art, EventType::ChildFriendly, EventType::Social]
         );
 
-        // 3. Age Groups
-        let e5 = create_event("Youth Soccer (U8)", "Saturday game.", Some("Trum Field"));
-        let e6 = create_event("Youth Soccer (U10)", "Saturday game.", Some("Trum Field"));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_prune_stale_events(pool: sqlx::PgPool) -> Result<()> {
+        let future = Utc::now() + chrono::Duration::days(7);
+        let past = Utc::now() - chrono::Duration::days(7);
+
+        let mut kept = create_event("Still Listed", "Desc", Some("Loc"));
+        kept.source = EventSource::AeronautBrewing;
+        kept.external_id = Some("kept".to_string());
+        kept.start_date = future;
+        let kept_id = save_event_to_db(&pool, &kept).await?;
+
+        let mut cancelled = create_event("Cancelled Show", "Other desc", Some("Loc"));
+        cancelled.source = EventSource::AeronautBrewing;
+        cancelled.external_id = Some("cancelled".to_string());
+        cancelled.start_date = future;
+        let cancelled_id = save_event_to_db(&pool, &cancelled).await?;
+
+        let mut called_off = create_event("Called Off", "Called off desc", Some("Loc"));
+        called_off.source = EventSource::AeronautBrewing;
+        called_off.external_id = Some("called-off".to_string());
+        called_off.start_date = future;
+        called_off.status = EventStatus::Cancelled;
+        let called_off_id = save_event_to_db(&pool, &called_off).await?;
+
+        let mut old = create_event("Last Week", "Old desc", Some("Loc"));
+        old.source = EventSource::AeronautBrewing;
+        old.external_id = Some("old".to_string());
+        old.start_date = past;
+        let old_id = save_event_to_db(&pool, &old).await?;
+
+        let mut other_source = create_event("Other Venue", "Another desc", Some("Loc"));
+        other_source.source = EventSource::BrattleTheatre;
+        other_source.external_id = Some("other".to_string());
+        other_source.start_date = future;
+        let other_id = save_event_to_db(&pool, &other_source).await?;
+
+        let pruned =
+            prune_stale_events(&pool, &EventSource::AeronautBrewing, &["kept".to_string()]).await?;
+
+        assert_eq!(pruned, 1);
+        assert!(pool.get(kept_id).await?.is_some());
+        assert!(pool.get(cancelled_id).await?.is_none());
         assert!(
-            !is_duplicate(&create_event_row(&e5), &e6),
-            "Different age groups should NOT match"
+            pool.get(old_id).await?.is_some(),
+            "Past events should never be pruned"
         );
-
-        // 4. Festival Acts (Same location, slightly different description/name)
-        let e7 = create_event("Porchfest: Band A", "Live music.", Some("123 Summer St"));
-        let e8 = create_event("Porchfest: Band B", "Live music.", Some("123 Summer St"));
         assert!(
-            !is_duplicate(&create_event_row(&e7), &e8),
-            "Different bands at same festival venue should NOT match"
+            pool.get(called_off_id).await?.is_some(),
+            "Cancelled events stay listed as cancelled"
         );
-
-        // 5. Language variations
-        let e9 = create_event("Storytime (English)", "Read aloud.", Some("Library"));
-        let e10 = create_event("Storytime (Spanish)", "Read aloud.", Some("Library"));
         assert!(
-            !is_duplicate(&create_event_row(&e9), &e10),
-            "Different languages should NOT match"
+            pool.get(other_id).await?.is_some(),
+            "Other sources should be untouched"
         );
 
-        // 6. Sports Opponents
-        let e11 = create_event(
-            "Somerville vs Medford",
-            "Varsity Game",
-            Some("Dilboy Stadium"),
+        // In the trash, not gone, so a mistaken prune can be undone.
+        let trashed = pool.list_deleted().await?;
+        assert_eq!(
+            trashed.iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![cancelled_id]
         );
-        let e12 = create_event(
-            "Somerville vs Everett",
-            "Varsity Game",
-            Some("Dilboy Stadium"),
+        pool.restore(cancelled_id).await?;
+        assert!(pool.get(cancelled_id).await?.is_some());
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_upsert_external_event_updates_when_source_changed(
+        pool: sqlx::PgPool,
+    ) -> Result<()> {
+        let first_seen = Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        let mut event = create_event("Trivia Night", "Desc", Some("Aeronaut"));
+        event.source = EventSource::AeronautBrewing;
+        event.external_id = Some("trivia-1".to_string());
+        event.event_types = vec![EventType::Trivia];
+
+        let UpsertOutcome::Inserted(id) =
+            upsert_external_event(&pool, &event, Some(first_seen)).await?
+        else {
+            panic!("New event should be inserted");
+        };
+
+        // Same last_updated as before: nothing to do, even if our copy differs.
+        event.name = "Ignored Rename".to_string();
+        assert_eq!(
+            upsert_external_event(&pool, &event, Some(first_seen)).await?,
+            UpsertOutcome::Unchanged(id)
         );
-        assert!(
-            !is_duplicate(&create_event_row(&e11), &e12),
-            "Different opponents should NOT match"
+        assert_eq!(pool.get(id).await?.unwrap().name, "Trivia Night");
+
+        // The venue rescheduled and the feed bumped last_updated.
+        let updated = first_seen + chrono::Duration::hours(1);
+        let rescheduled = event.start_date + chrono::Duration::days(1);
+        event.name = "Trivia Night (Rescheduled)".to_string();
+        event.start_date = rescheduled;
+        event.event_types = vec![EventType::Trivia, EventType::Social];
+        assert_eq!(
+            upsert_external_event(&pool, &event, Some(updated)).await?,
+            UpsertOutcome::Updated(id)
         );
 
-        // 7. Ward Meetings
-        let e13 = create_event("Ward 1 Meeting", "Community update", Some("Zoom"));
-        let e14 = create_event("Ward 2 Meeting", "Community update", Some("Zoom"));
-        assert!(
-            !is_duplicate(&create_event_row(&e13), &e14),
-            "Different wards should NOT match"
+        let fetched = pool.get(id).await?.unwrap();
+        assert_eq!(fetched.name, "Trivia Night (Rescheduled)");
+        assert_eq!(fetched.start_date, rescheduled);
+        assert_eq!(
+            fetched.event_types,
+            vec![EventType::Social, EventType::Trivia]
         );
-    }
 
-    #[test]
-    fn test_duplicate_detection_complex_scenarios() {
-        // Case 2: Generic Titles, Different Descriptions
-        let e3 = create_event(
-            "Weekly Meeting",
-            "Discussing zoning laws for the new park.",
-            Some("City Hall"),
+        // Saved while geocoding was down, so the next run has to redo it
+        // even though the feed hasn't changed.
+        assert_eq!(
+            upsert_external_event(&pool, &event, None).await?,
+            UpsertOutcome::Updated(id)
         );
-        let e4 = create_event(
-            "Weekly Meeting",
-            "Discussing school budget and teacher salaries.",
-            Some("City Hall"),
+        assert_eq!(
+            upsert_external_event(&pool, &event, Some(updated)).await?,
+            UpsertOutcome::Updated(id)
         );
-        // Same name/location, but descriptions are totally different topics
-        assert!(
-            !is_duplicate(&create_event_row(&e3), &e4),
-            "Same title but different topics (descriptions) should NOT match"
+        assert_eq!(
+            upsert_external_event(&pool, &event, Some(updated)).await?,
+            UpsertOutcome::Unchanged(id)
         );
 
-        // Case 3: Cut-off text leading to ambiguity (Extraction Artifacts)
-        let e5 = create_event("Ward Meeting", "Community update.", Some("Library"));
-        let e6 = create_event("Ward 2 Meeting", "Community update.", Some("Library"));
-        assert!(
-            !is_duplicate(&create_event_row(&e5), &e6),
-            "Generic/Cut-off name should NOT match specific name"
+        // The venue called it off and the feed says so.
+        event.status = EventStatus::Cancelled;
+        let called_off = updated + chrono::Duration::hours(1);
+        assert_eq!(
+            upsert_external_event(&pool, &event, Some(called_off)).await?,
+            UpsertOutcome::StatusChanged(id)
         );
+        assert_eq!(pool.get(id).await?.unwrap().status, EventStatus::Cancelled);
 
-        // Case 4: Truncated names matching unrelated events
-        let e7 = create_event("Somerville Art", "Local event.", Some("Armory"));
-        let e8 = create_event("Somerville Art Class", "Local event.", Some("Armory"));
-        assert!(
-            !is_duplicate(&create_event_row(&e7), &e8),
-            "Prefix match on different event types should NOT match"
-        );
+        Ok(())
+    }
 
-        // Case 5: Long Description overlaps but key differences
-        let e9 = create_event(
-            "Evening Social",
-            "Join us for a wonderful night of music, dancing, and light refreshments at the club.",
-            Some("The Club"),
+    #[sqlx::test]
+    async fn test_geocode_cache_forgets_failures_and_old_misses(pool: sqlx::PgPool) -> Result<()> {
+        let aeronaut = GeocodedLocation {
+            formatted_address: "14 Tyler St, Somerville, MA 02143, USA".to_string(),
+            place_id: "place-aeronaut".to_string(),
+            name: "Aeronaut Brewing Co.".to_string(),
+            lat: 42.3815,
+            lng: -71.1053,
+        };
+        let results = HashMap::from([
+            ("Aeronaut".to_string(), Geocoded::Found(aeronaut.clone())),
+            ("Nowhere".to_string(), Geocoded::NotFound),
+            ("Outage".to_string(), Geocoded::Failed),
+        ]);
+        cache_geocodes(&pool, &results).await?;
+
+        let addresses: Vec<String> = ["Aeronaut", "Nowhere", "Outage", "Unseen"]
+            .map(str::to_string)
+            .into();
+        let now = Utc::now();
+        let cached = cached_geocodes(&pool, &addresses, now).await?;
+        assert_eq!(
+            cached,
+            HashMap::from([
+                ("Aeronaut".to_string(), Geocoded::Found(aeronaut.clone())),
+                ("Nowhere".to_string(), Geocoded::NotFound),
+            ])
         );
-        let e10 = create_event(
-            "Evening Social",
-            "Join us for a wonderful night of painting, wine, and light refreshments at the club.",
-            Some("The Club"),
+
+        // A month on, Google gets another chance at the miss.
+        let later = now + GEOCODE_NOT_FOUND_TTL + chrono::Duration::days(1);
+        let cached = cached_geocodes(&pool, &addresses, later).await?;
+        assert_eq!(
+            cached,
+            HashMap::from([("Aeronaut".to_string(), Geocoded::Found(aeronaut))])
         );
-        assert!(
-            !is_duplicate(&create_event_row(&e9), &e10),
-            "Descriptions with key activity differences should NOT match"
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_list_ordered_by_confidence(pool: sqlx::PgPool) -> Result<()> {
+        let mut ids = Vec::new();
+        for (name, confidence) in [("Sure", 1.0), ("Shaky", 0.4), ("Fairly sure", 0.8)] {
+            let mut event = create_event(name, "Desc", None);
+            event.confidence = confidence;
+            ids.push(save_event_to_db(&pool, &event).await?);
+        }
+        let mut deleted = create_event("Deleted", "Desc", None);
+        deleted.confidence = 0.1;
+        let deleted_id = save_event_to_db(&pool, &deleted).await?;
+        pool.delete(deleted_id).await?;
+
+        let events = pool.list_ordered_by_confidence().await?;
+        let order: Vec<(&str, f64)> = events
+            .iter()
+            .map(|e| (e.name.as_str(), e.confidence))
+            .collect();
+        assert_eq!(
+            order,
+            vec![("Shaky", 0.4), ("Fairly sure", 0.8), ("Sure", 1.0)]
         );
+
+        Ok(())
     }
 
     #[sqlx::test]
-    async fn test_event_types_deterministic_order(pool: sqlx::PgPool) -> Result<()> {
-        let mut event = create_event("Sorted Types", "Desc", Some("Loc"));
-        // Insert in mixed order
-        event.event_types = vec![EventType::Social, EventType::Art, EventType::ChildFriendly];
-        event.source = EventSource::ImageUpload;
+    async fn test_list_related(pool: sqlx::PgPool) -> Result<()> {
+        let now = Utc.timestamp_opt(1672531200, 0).unwrap(); // 2023-01-01
+        let save = async |name: &str, place: Option<&str>, types: Vec<EventType>, days: i64| {
+            let mut event = create_event(name, "Desc", place);
+            event.google_place_id = place.map(|p| format!("place-{p}"));
+            event.event_types = types;
+            event.start_date = now + chrono::Duration::days(days);
+            save_event_to_db(&pool, &event).await
+        };
+        let jazz = save(
+            "Jazz Night",
+            Some("lilypad"),
+            vec![EventType::Music, EventType::Dance],
+            0,
+        )
+        .await?;
+        save("Open Mic", Some("lilypad"), vec![EventType::Comedy], 2).await?;
+        save("Brunch Set", Some("lilypad"), vec![EventType::Music], 3).await?;
+        save("Late Show", Some("lilypad"), vec![], 4).await?;
+        save("Last Week", Some("lilypad"), vec![EventType::Music], -7).await?;
+        save("Choir", Some("church"), vec![EventType::Music], 1).await?;
+        save(
+            "Swing Dance",
+            None,
+            vec![EventType::Dance, EventType::Music],
+            5,
+        )
+        .await?;
+        save("Yard Sale", Some("porch"), vec![EventType::YardSale], 1).await?;
+        let deleted = save("Cancelled", None, vec![EventType::Music], 1).await?;
+        pool.delete(deleted).await?;
 
-        let id = save_event_to_db(&pool, &event).await?;
+        let related = pool.list_related(jazz, now, 2).await?;
+        let found: Vec<(&str, bool, Option<EventType>)> = related
+            .iter()
+            .map(|r| (r.event.name.as_str(), r.same_venue, r.shared_type.clone()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                // The venue's next two, whatever they are.
+                ("Open Mic", true, None),
+                ("Brunch Set", true, Some(EventType::Music)),
+                // Elsewhere, sharing both types beats sharing one sooner.
+                ("Swing Dance", false, Some(EventType::Dance)),
+                ("Choir", false, Some(EventType::Music)),
+            ]
+        );
 
-        let fetched = pool.get(id).await?.expect("Event not found");
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_backfilling_event_places(pool: sqlx::PgPool) -> Result<()> {
+        let unplaced =
+            save_event_to_db(&pool, &create_event("Yard Sale", "Desc", Some("Aeronaut"))).await?;
+        save_event_to_db(&pool, &create_event("Mystery", "Desc", None)).await?;
+        let mut placed = create_event("Trivia", "Desc", Some("Davis"));
+        placed.google_place_id = Some("place-davis".to_string());
+        save_event_to_db(&pool, &placed).await?;
 
-        // Should be sorted alphabetically by the string representation
-        // Art, ChildFriendly, Social
         assert_eq!(
-            fetched.event_types,
-            vec![EventType::Art, EventType::ChildFriendly, EventType::Social]
+            events_missing_place_ids(&pool).await?,
+            vec![(unplaced, "Aeronaut".to_string())]
         );
 
+        let aeronaut = GeocodedLocation {
+            formatted_address: "14 Tyler St, Somerville, MA 02143, USA".to_string(),
+            place_id: "place-aeronaut".to_string(),
+            name: "Aeronaut Brewing Co.".to_string(),
+            lat: 42.3815,
+            lng: -71.1053,
+        };
+        assert_eq!(set_event_place(&pool, &[unplaced], &aeronaut).await?, 1);
+
+        let event = pool.get(unplaced).await?.unwrap();
+        assert_eq!(event.google_place_id.as_deref(), Some("place-aeronaut"));
+        assert_eq!(event.location_name.as_deref(), Some("Aeronaut Brewing Co."));
+        assert_eq!(event.lat, Some(42.3815));
+        // What was written down is kept for the next lookup.
+        assert_eq!(event.original_location.as_deref(), Some("Aeronaut"));
+        assert!(pool.get_venue("place-aeronaut").await?.is_some());
+
+        assert!(events_missing_place_ids(&pool).await?.is_empty());
+        assert_eq!(set_event_place(&pool, &[unplaced], &aeronaut).await?, 0);
+
         Ok(())
     }
 
@@ -960,6 +3786,19 @@ mod tests {
                 .await?;
         }
 
+        // Every google_place_id below has to be a venue first.
+        sqlx::query(
+            r#"
+            INSERT INTO app.venues (google_place_id, name)
+            SELECT 'place_id_' || i, 'Location ' || i
+            FROM generate_series(1, 100000) AS i
+            UNION ALL SELECT 'pid', 'Loc'
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .execute(pool)
+        .await?;
+
         // Bulk insert 100,000 rows using generate_series
         // This is much faster than inserting one by one from Rust
         sqlx::query(
@@ -1112,11 +3951,12 @@ mod tests {
                 SELECT DISTINCT e.id
                 FROM app.events e
                 LEFT JOIN app.event_event_types et ON e.id = et.event_id
-                WHERE (cardinality($1::text[]) = 0 OR et.event_type_name = ANY($1::text[]))
+                WHERE e.deleted_at IS NULL AND NOT e.pending
+                AND (cardinality($1::text[]) = 0 OR et.event_type_name = ANY($1::text[]))
                 AND (cardinality($2::text[]) = 0 OR e.source = ANY($2::text[]))
                 AND (cardinality($3::text[]) = 0 OR e.google_place_id = ANY($3::text[]))
                 AND ($4::boolean = false OR e.price = 0 OR e.price IS NULL)
-                AND ($5::text IS NULL OR e.name ILIKE ('%' || $5::text || '%'))
+                AND ($5::text IS NULL OR e.name ILIKE ('%' || $5::text || '%') OR e.full_text ILIKE ('%' || $5::text || '%'))
                 AND ($6::timestamptz IS NULL OR e.start_date >= $6)
                 AND ($7::timestamptz IS NULL OR e.start_date <= $7)
             )
@@ -1125,6 +3965,7 @@ mod tests {
                 e.name,
                 e.start_date,
                 e.end_date,
+                e.all_day,
                 e.original_location,
                 e.location_name,
                 COALESCE(array_agg(et.event_type_name ORDER BY et.event_type_name) FILTER (WHERE et.event_type_name IS NOT NULL), '{}') as "event_types!: Vec<EventType>"
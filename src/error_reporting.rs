@@ -0,0 +1,84 @@
+//! Sends errors from work nobody is watching, like reading an uploaded
+//! flyer, delivering a webhook or a nightly scrape, to Sentry, where
+//! they're harder to miss than in the logs. It needs both the `sentry`
+//! feature and `SENTRY_DSN`; otherwise every call here does nothing, and
+//! the log line the caller writes anyway is all there is.
+
+use std::fmt::Display;
+
+/// Keeps the reporter running. Reports still queued are sent when it's
+/// dropped, so `main` holds it until it returns.
+#[must_use]
+pub struct Guard {
+    #[cfg(feature = "sentry")]
+    _client: Option<sentry::ClientInitGuard>,
+}
+
+pub fn init(dsn: Option<&str>) -> Guard {
+    #[cfg(feature = "sentry")]
+    {
+        let client = dsn.map(|dsn| {
+            sentry::init((
+                dsn,
+                sentry::ClientOptions {
+                    release: sentry::release_name!(),
+                    ..Default::default()
+                },
+            ))
+        });
+        Guard { _client: client }
+    }
+    #[cfg(not(feature = "sentry"))]
+    {
+        if dsn.is_some() {
+            log::warn!(
+                "SENTRY_DSN is set, but this build doesn't have the sentry feature, so errors are only logged"
+            );
+        }
+        Guard {}
+    }
+}
+
+/// Reports `error`, with `context` such as the event id or webhook URL
+/// attached so it can be followed up without digging through logs.
+pub fn capture(error: &anyhow::Error, context: &[(&str, &dyn Display)]) {
+    #[cfg(feature = "sentry")]
+    sentry::with_scope(
+        |scope| {
+            for (key, value) in context {
+                scope.set_extra(key, value.to_string().into());
+            }
+        },
+        || sentry::integrations::anyhow::capture_anyhow(error),
+    );
+    #[cfg(not(feature = "sentry"))]
+    let _ = (error, context);
+}
+
+#[cfg(all(test, feature = "sentry"))]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_capture_attaches_context() {
+        let events = sentry::test::with_captured_events(|| {
+            capture(
+                &anyhow!("connection reset").context("Gave up sending event 7"),
+                &[
+                    ("event_id", &7),
+                    ("webhook_url", &"https://example.org/hook"),
+                ],
+            );
+        });
+
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.extra["event_id"], "7");
+        assert_eq!(event.extra["webhook_url"], "https://example.org/hook");
+        assert!(event.exception.values[0]
+            .value
+            .as_deref()
+            .is_some_and(|v| v.contains("connection reset")));
+    }
+}
@@ -0,0 +1,257 @@
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use image::{ImageFormat, Rgba, RgbaImage};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::{LazyLock, Mutex};
+
+/// The size Facebook, Mastodon and friends crop link previews to.
+pub const CARD_WIDTH: u32 = 1200;
+pub const CARD_HEIGHT: u32 = 630;
+
+const MARGIN: f32 = 80.0;
+const TITLE_SIZE: f32 = 72.0;
+const TITLE_LINE_HEIGHT: f32 = 84.0;
+const TITLE_MAX_LINES: usize = 3;
+const DETAIL_SIZE: f32 = 40.0;
+const DETAIL_LINE_HEIGHT: f32 = 52.0;
+
+// The site's --primary-bg, with white text on it.
+const BACKGROUND: Rgba<u8> = Rgba([0xd1, 0x3a, 0x26, 0xff]);
+const TEXT: Rgba<u8> = Rgba([0xff, 0xff, 0xff, 0xff]);
+const MUTED_TEXT: Rgba<u8> = Rgba([0xff, 0xdc, 0xd6, 0xff]);
+
+/// Bundled so cards look the same wherever the server runs; see
+/// assets/fonts/LICENSE-DejaVu.txt.
+static FONT: LazyLock<FontRef<'static>> = LazyLock::new(|| {
+    FontRef::try_from_slice(include_bytes!("../assets/fonts/DejaVuSans-Bold.ttf"))
+        .expect("bundled font is valid")
+});
+
+/// Most cards kept in memory. Each is a few dozen KB.
+const CACHE_CAPACITY: usize = 200;
+
+/// Rendered cards by event id, with the `updated_at` they were drawn from,
+/// so an edited event never gets its old card. When it fills up it's
+/// simply cleared, which is crude next to an LRU but keeps memory bounded
+/// and the busy cards come straight back.
+static CACHE: LazyLock<Mutex<HashMap<i64, CachedCard>>> = LazyLock::new(Mutex::default);
+
+type CachedCard = (DateTime<Utc>, Vec<u8>);
+
+/// The cached card for this version of the event, if there is one.
+pub fn cached_card(id: i64, updated_at: DateTime<Utc>) -> Option<Vec<u8>> {
+    let cache = CACHE.lock().unwrap();
+    cache
+        .get(&id)
+        .filter(|(drawn_from, _)| *drawn_from == updated_at)
+        .map(|(_, png)| png.clone())
+}
+
+pub fn cache_card(id: i64, updated_at: DateTime<Utc>, png: Vec<u8>) {
+    let mut cache = CACHE.lock().unwrap();
+    if cache.len() >= CACHE_CAPACITY && !cache.contains_key(&id) {
+        cache.clear();
+    }
+    cache.insert(id, (updated_at, png));
+}
+
+/// Draws a link preview for an event: its name, when, and where. Ingested
+/// events never had a flyer and uploaded ones aren't kept, so without this
+/// a shared link shows nothing. The same text always gives the same PNG.
+pub fn render_event_card(
+    name: &str,
+    when: &str,
+    venue: Option<&str>,
+    site: &str,
+) -> Result<Vec<u8>> {
+    let font = &*FONT;
+    let mut card = RgbaImage::from_pixel(CARD_WIDTH, CARD_HEIGHT, BACKGROUND);
+    let max_width = CARD_WIDTH as f32 - 2.0 * MARGIN;
+
+    let title = PxScale::from(TITLE_SIZE);
+    let mut y = MARGIN;
+    for line in wrap(font, title, name, max_width, TITLE_MAX_LINES) {
+        draw_text(&mut card, font, title, MARGIN, y, &line, TEXT);
+        y += TITLE_LINE_HEIGHT;
+    }
+
+    let detail = PxScale::from(DETAIL_SIZE);
+    y += DETAIL_LINE_HEIGHT / 2.0;
+    for text in [Some(when), venue].into_iter().flatten() {
+        for line in wrap(font, detail, text, max_width, 1) {
+            draw_text(&mut card, font, detail, MARGIN, y, &line, MUTED_TEXT);
+            y += DETAIL_LINE_HEIGHT;
+        }
+    }
+
+    let footer_y = CARD_HEIGHT as f32 - MARGIN - DETAIL_SIZE;
+    draw_text(&mut card, font, detail, MARGIN, footer_y, site, TEXT);
+
+    let mut png = Vec::new();
+    card.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| anyhow!("Failed to encode event card: {e}"))?;
+    Ok(png)
+}
+
+fn text_width(font: &FontRef, scale: PxScale, text: &str) -> f32 {
+    let font = font.as_scaled(scale);
+    let mut width = 0.0;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            width += font.kern(previous, id);
+        }
+        width += font.h_advance(id);
+        previous = Some(id);
+    }
+    width
+}
+
+/// Breaks `text` into lines no wider than `max_width`, ending the last one
+/// with an ellipsis if it doesn't all fit in `max_lines`. Words too long
+/// for a line on their own are split wherever they run out of room.
+fn wrap(
+    font: &FontRef,
+    scale: PxScale,
+    text: &str,
+    max_width: f32,
+    max_lines: usize,
+) -> Vec<String> {
+    let fits = |line: &str| text_width(font, scale, line) <= max_width;
+    let mut lines: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        let candidate = if current.is_empty() {
+            word.to_string()
+        } else {
+            format!("{current} {word}")
+        };
+        if fits(&candidate) {
+            current = candidate;
+            continue;
+        }
+        if !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+        }
+        for c in word.chars() {
+            current.push(c);
+            if !fits(&current) {
+                current.pop();
+                lines.push(std::mem::take(&mut current));
+                current.push(c);
+            }
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    if lines.len() > max_lines {
+        lines.truncate(max_lines);
+        let last = lines.last_mut().expect("max_lines is at least 1");
+        while !last.is_empty() && !fits(&format!("{last}…")) {
+            last.pop();
+        }
+        *last = format!("{}…", last.trim_end());
+    }
+    lines
+}
+
+/// Draws one line with its top at `y`, blending each glyph's coverage into
+/// the background.
+fn draw_text(
+    image: &mut RgbaImage,
+    font: &FontRef,
+    scale: PxScale,
+    x: f32,
+    y: f32,
+    text: &str,
+    color: Rgba<u8>,
+) {
+    let scaled = font.as_scaled(scale);
+    let baseline = y + scaled.ascent();
+    let mut caret = x;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = scaled.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += scaled.kern(previous, id);
+        }
+        let glyph = id.with_scale_and_position(scale, point(caret, baseline));
+        caret += scaled.h_advance(id);
+        previous = Some(id);
+
+        let Some(outlined) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outlined.px_bounds();
+        outlined.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i64 + i64::from(gx);
+            let py = bounds.min.y as i64 + i64::from(gy);
+            let (Ok(px), Ok(py)) = (u32::try_from(px), u32::try_from(py)) else {
+                return;
+            };
+            if px >= image.width() || py >= image.height() {
+                return;
+            }
+            let pixel = image.get_pixel_mut(px, py);
+            for channel in 0..3 {
+                let blended = f32::from(pixel[channel]) * (1.0 - coverage)
+                    + f32::from(color[channel]) * coverage;
+                pixel[channel] = blended.round() as u8;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_event_card() -> Result<()> {
+        let png = render_event_card(
+            "Porchfest",
+            "Sat, May 10, 2025 • 12:00 PM",
+            Some("Davis Square"),
+            "somerville.events",
+        )?;
+        let card = image::load_from_memory(&png)?;
+        assert_eq!((card.width(), card.height()), (CARD_WIDTH, CARD_HEIGHT));
+        // Cached by updated_at, so the same event must draw the same card.
+        assert_eq!(
+            png,
+            render_event_card(
+                "Porchfest",
+                "Sat, May 10, 2025 • 12:00 PM",
+                Some("Davis Square"),
+                "somerville.events",
+            )?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrap_long_names() {
+        let scale = PxScale::from(TITLE_SIZE);
+        let name = "The Annual Somerville Community Growing Center Spring \
+                    Plant Sale, Seed Swap and Pollinator Garden Volunteer Day";
+        let lines = wrap(&FONT, scale, name, 1040.0, TITLE_MAX_LINES);
+        assert_eq!(lines.len(), TITLE_MAX_LINES);
+        assert!(lines.last().unwrap().ends_with('…'));
+        assert!(lines
+            .iter()
+            .all(|line| text_width(&FONT, scale, line) <= 1040.0));
+
+        // A single unbroken word still fits, split across lines.
+        let lines = wrap(&FONT, scale, &"W".repeat(40), 1040.0, 5);
+        assert!(lines.len() > 1);
+        assert!(lines
+            .iter()
+            .all(|line| text_width(&FONT, scale, line) <= 1040.0));
+    }
+}
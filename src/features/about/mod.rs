@@ -0,0 +1,104 @@
+use crate::config::{Config, FooterLink};
+use crate::i18n::{Lang, Msg};
+use actix_web::{http::header::ContentType, HttpResponse, Responder};
+use askama::Template;
+use pulldown_cmark::{html, Options, Parser};
+use std::sync::LazyLock;
+
+/// Used when `ABOUT_FILE` isn't set.
+const DEFAULT_ABOUT: &str = include_str!("default.md");
+
+/// Ends the part of the about text that the index page shows.
+const MORE_MARKER: &str = "<!-- more -->";
+
+/// What the site is and how to add to it, rendered from `ABOUT_FILE`.
+pub struct About {
+    /// The start of the text, for the index page.
+    pub intro_html: String,
+    pub body_html: String,
+    pub links: Vec<FooterLink>,
+}
+
+impl About {
+    /// Without a `<!-- more -->` line, the intro is the first paragraph.
+    pub fn new(markdown: &str, links: Vec<FooterLink>) -> Self {
+        let intro = match markdown.split_once(MORE_MARKER) {
+            Some((intro, _)) => intro,
+            None => markdown.trim_start().split("\n\n").next().unwrap_or(""),
+        };
+        Self {
+            intro_html: render_markdown(intro),
+            body_html: render_markdown(markdown),
+            links,
+        }
+    }
+}
+
+/// Read once from the config, as the file doesn't change while running.
+pub fn about() -> &'static About {
+    static ABOUT: LazyLock<About> = LazyLock::new(|| {
+        let config = Config::from_env();
+        About::new(
+            config.about_markdown.as_deref().unwrap_or(DEFAULT_ABOUT),
+            config.footer_links.clone(),
+        )
+    });
+    &ABOUT
+}
+
+/// Markdown to HTML, cleaned of scripts, styles and event handlers. The
+/// text comes from whoever runs the site, but a snippet pasted into it
+/// still shouldn't be able to run script or get around the CSP.
+pub fn render_markdown(markdown: &str) -> String {
+    let parser = Parser::new_ext(markdown, Options::ENABLE_STRIKETHROUGH);
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, parser);
+    ammonia::clean(&unsafe_html)
+}
+
+#[derive(Template)]
+#[template(path = "about/about.html")]
+pub struct AboutTemplate {
+    pub about: &'static About,
+    /// The text is the site's own, untranslated, so the page around it
+    /// stays in English too.
+    pub lang: Lang,
+}
+
+pub async fn index() -> impl Responder {
+    let template = AboutTemplate {
+        about: about(),
+        lang: Lang::En,
+    };
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(template.render().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intro_and_sanitizing() {
+        let about = About::new(
+            "Local events.\n\n<!-- more -->\n\n## How it works\n\n\
+             Upload a [flyer](/upload).<script>alert(1)</script>\n\n\
+             <p style=\"color: red\" onclick=\"alert(1)\">Hi</p>",
+            vec![],
+        );
+        assert_eq!(about.intro_html.trim(), "<p>Local events.</p>");
+        assert!(about.body_html.contains("<h2>How it works</h2>"));
+        assert!(about
+            .body_html
+            .contains(r#"<a href="/upload" rel="noopener noreferrer">flyer</a>"#));
+        assert!(about.body_html.contains("<p>Hi</p>"));
+        for banned in ["<script", "style=", "onclick", "<!--"] {
+            assert!(!about.body_html.contains(banned), "{banned}");
+        }
+
+        // No marker: the first paragraph.
+        let about = About::new("\nFirst.\n\nSecond.", vec![]);
+        assert_eq!(about.intro_html.trim(), "<p>First.</p>");
+    }
+}
@@ -1,8 +1,260 @@
-use crate::models::{Event, EventType, SimpleEvent};
-use chrono_tz::America::New_York;
+use crate::config::EventDurations;
+use crate::contact_relay::ContactRelay;
+use crate::models::{Event, EventStatus, EventType, SimpleEvent};
+use actix_web::http::header::{
+    CacheControl, CacheDirective, ContentType, ETag, EntityTag, HeaderValue, HttpDate,
+    IfModifiedSince, IfNoneMatch, LastModified, RETRY_AFTER,
+};
+use actix_web::http::StatusCode;
+use actix_web::{HttpMessage, HttpRequest, HttpResponse, HttpResponseBuilder, ResponseError};
+use actix_web_lab::extract::QueryDeserializeError;
+use askama::Template;
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use std::fmt;
+use std::net::IpAddr;
+use std::sync::LazyLock;
+use std::time::SystemTime;
+use strum::IntoEnumIterator;
+
+#[derive(Template)]
+#[template(path = "common/error.html")]
+struct ErrorTemplate<'a> {
+    title: &'a str,
+    message: &'a str,
+}
+
+/// A styled error page with a link back to the index, for anything people
+/// might hit in a browser instead of a bare line of text.
+pub fn error_page(status: StatusCode, message: &str) -> HttpResponse {
+    let template = ErrorTemplate {
+        title: status.canonical_reason().unwrap_or("Error"),
+        message,
+    };
+    match template.render() {
+        Ok(body) => HttpResponse::build(status)
+            .content_type(ContentType::html())
+            .body(body),
+        Err(e) => {
+            log::error!("Failed to render error page: {e}");
+            HttpResponse::build(status).body(message.to_string())
+        }
+    }
+}
+
+/// Only same-site paths, so a form's "send me back to" field can't be used
+/// to bounce someone to another domain. `//host` is protocol-relative, and
+/// browsers read `/\host` the same way, so both count as offsite.
+pub fn is_local_path(path: &str) -> bool {
+    path.starts_with('/') && !path.starts_with("//") && !path.starts_with("/\\")
+}
+
+/// The address a request came from, for rate limits. `X-Forwarded-For` is
+/// only read when the connection is from one of `trusted_proxies`, since
+/// anyone else can put whatever they like in it. Each proxy appends the
+/// address it was connected from, so the last entry that isn't one of
+/// ours is the client's; anything left of it the client wrote itself.
+pub fn client_ip(req: &HttpRequest, trusted_proxies: &[IpAddr]) -> String {
+    let Some(peer) = req.peer_addr().map(|addr| addr.ip()) else {
+        return "unknown".to_string();
+    };
+    if !trusted_proxies.contains(&peer) {
+        return peer.to_string();
+    }
+    let forwarded: Vec<&str> = req
+        .headers()
+        .get_all("X-Forwarded-For")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+    for entry in forwarded.into_iter().rev() {
+        match entry.parse::<IpAddr>() {
+            Ok(ip) if trusted_proxies.contains(&ip) => continue,
+            Ok(ip) => return ip.to_string(),
+            // Not something one of our proxies would write, so there's no
+            // telling who did.
+            Err(_) => break,
+        }
+    }
+    peer.to_string()
+}
+
+pub fn not_found(message: &str) -> HttpResponse {
+    error_page(StatusCode::NOT_FOUND, message)
+}
+
+/// Fallback for any route we don't have.
+pub async fn default_not_found() -> HttpResponse {
+    not_found("There's no page here. It may have moved, or the event may have been removed.")
+}
+
+/// Response for a failed database read. Running out of pooled connections
+/// is temporary, so that gets a 503 telling clients to retry instead of a
+/// 500 that looks like a bug.
+pub fn database_error(e: &anyhow::Error, message: &'static str) -> HttpResponse {
+    if is_pool_exhausted(e) {
+        let mut response = error_page(StatusCode::SERVICE_UNAVAILABLE, message);
+        response
+            .headers_mut()
+            .insert(RETRY_AFTER, HeaderValue::from_static("5"));
+        response
+    } else {
+        error_page(StatusCode::INTERNAL_SERVER_ERROR, message)
+    }
+}
+
+fn is_pool_exhausted(e: &anyhow::Error) -> bool {
+    e.chain()
+        .any(|cause| matches!(cause.downcast_ref(), Some(sqlx::Error::PoolTimedOut)))
+}
+
+/// An error from one of the `/api` routes. Those get a JSON body of the
+/// form `{"error": {"code": ..., "message": ...}}` instead of the HTML
+/// error page, so clients can branch on `code` without parsing markup.
+#[derive(Debug)]
+pub enum ApiError {
+    BadRequest(String),
+    NotFound(String),
+    /// A failed database call. The cause is logged, not sent.
+    Database(anyhow::Error),
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::BadRequest(message) | ApiError::NotFound(message) => f.write_str(message),
+            ApiError::Database(e) => write!(f, "Database error: {e:#}"),
+        }
+    }
+}
+
+impl ApiError {
+    fn code(&self) -> &'static str {
+        match self {
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Database(e) if is_pool_exhausted(e) => "unavailable",
+            ApiError::Database(_) => "internal_error",
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            // Same split as `database_error`.
+            ApiError::Database(e) if is_pool_exhausted(e) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let message = match self {
+            ApiError::Database(e) => {
+                log::error!("API database error: {e:#}");
+                "Something went wrong on our end. Please try again later.".to_string()
+            }
+            other => other.to_string(),
+        };
+        let mut builder = HttpResponse::build(self.status_code());
+        if self.status_code() == StatusCode::SERVICE_UNAVAILABLE {
+            builder.insert_header((RETRY_AFTER, "5"));
+        }
+        builder.json(serde_json::json!({
+            "error": { "code": self.code(), "message": message }
+        }))
+    }
+}
+
+impl From<QueryDeserializeError> for ApiError {
+    fn from(e: QueryDeserializeError) -> Self {
+        ApiError::BadRequest(e.to_string())
+    }
+}
+
+/// Fallback for anything under `/api` that isn't a route.
+pub async fn api_not_found() -> Result<HttpResponse, ApiError> {
+    Err(ApiError::NotFound("No such API endpoint.".to_string()))
+}
+
+/// How long browsers and feed readers may reuse a public page before
+/// revalidating it.
+const PUBLIC_PAGE_MAX_AGE_SECS: u32 = 60;
+
+/// Cache validators for a page rendered from a set of events. The tag
+/// covers the newest `updated_at` and the number of events, so an edit, an
+/// addition or an event dropping out of view all produce a new tag.
+#[derive(Clone)]
+pub struct PageValidators {
+    etag: EntityTag,
+    last_modified: Option<DateTime<Utc>>,
+}
+
+impl PageValidators {
+    pub fn new(last_modified: Option<DateTime<Utc>>, event_count: usize) -> Self {
+        let stamp = last_modified.map_or(0, |t| t.timestamp_micros());
+        Self {
+            etag: EntityTag::new_weak(format!("{stamp:x}-{event_count:x}")),
+            last_modified,
+        }
+    }
+
+    /// Distinguishes representations served from the same URL, e.g. the
+    /// HTML and JSON forms of an event.
+    pub fn with_variant(mut self, variant: &str) -> Self {
+        self.etag = EntityTag::new_weak(format!("{}-{variant}", self.etag.tag()));
+        self
+    }
+
+    /// Whether the client already has this version. `If-None-Match` wins
+    /// over `If-Modified-Since` when both are sent, as RFC 9110 requires.
+    pub fn is_fresh(&self, req: &HttpRequest) -> bool {
+        if let Some(if_none_match) = req.get_header::<IfNoneMatch>() {
+            return match if_none_match {
+                IfNoneMatch::Any => true,
+                IfNoneMatch::Items(tags) => tags.iter().any(|tag| tag.weak_eq(&self.etag)),
+            };
+        }
+        match (req.get_header::<IfModifiedSince>(), self.last_modified) {
+            // HTTP dates only have second precision.
+            (Some(IfModifiedSince(since)), Some(modified)) => {
+                modified.timestamp() <= DateTime::<Utc>::from(SystemTime::from(since)).timestamp()
+            }
+            _ => false,
+        }
+    }
+
+    /// A bodyless 304 for a client whose copy is current.
+    pub fn not_modified(&self) -> HttpResponse {
+        self.apply(HttpResponse::NotModified()).finish()
+    }
+
+    /// Adds the validators and public caching headers to a response.
+    pub fn apply(&self, mut builder: HttpResponseBuilder) -> HttpResponseBuilder {
+        builder
+            .insert_header(ETag(self.etag.clone()))
+            .insert_header(CacheControl(vec![
+                CacheDirective::Public,
+                CacheDirective::MaxAge(PUBLIC_PAGE_MAX_AGE_SECS),
+            ]));
+        if let Some(modified) = self.last_modified {
+            builder.insert_header(LastModified(HttpDate::from(SystemTime::from(modified))));
+        }
+        builder
+    }
+}
 
 pub fn get_color_for_type(t: &EventType) -> String {
-    let (light_mode, dark_mode) = match t {
+    let (light_mode, dark_mode) = type_colors(t);
+    format!("light-dark({}, {})", light_mode, dark_mode)
+}
+
+/// An event type's color in light mode and in dark mode.
+pub fn type_colors(t: &EventType) -> (&'static str, &'static str) {
+    match t {
         EventType::Art
         | EventType::Exhibition
         | EventType::Film
@@ -25,8 +277,25 @@ pub fn get_color_for_type(t: &EventType) -> String {
         EventType::Sports | EventType::Fitness | EventType::Bikes => ("#1976d2", "#90caf9"), // Blue 700 / 200
         EventType::Religious => ("#5d4037", "#bcaaa4"), // Brown 700 / 200
         EventType::Other => ("#616161", "#eeeeee"),     // Grey 700 / 200
-    };
-    format!("light-dark({}, {})", light_mode, dark_mode)
+    }
+}
+
+/// Sets `--type-color` on anything marked with an event type's
+/// `data-type`. Kept in the stylesheet rather than in `style` attributes,
+/// which the Content-Security-Policy doesn't allow.
+pub fn event_type_css() -> &'static str {
+    static CSS: LazyLock<String> = LazyLock::new(|| {
+        EventType::iter()
+            .map(|t| {
+                format!(
+                    "[data-type=\"{}\"] {{ --type-color: {}; }}\n",
+                    t.value(),
+                    get_color_for_type(&t)
+                )
+            })
+            .collect()
+    });
+    &CSS
 }
 
 pub fn get_icon_for_type(t: &EventType) -> &'static str {
@@ -61,6 +330,8 @@ pub enum EventLocation {
         name: String,
         address: String,
         google_maps_link: String,
+        /// The venue's own page, with everything else on there.
+        venue_url: String,
     },
     Unstructured(String),
     Unknown,
@@ -71,7 +342,14 @@ pub struct EventTypeLink {
     pub url: String,
     pub label: String,
     pub icon: String,
-    pub color: String,
+    /// `EventType::value`, which picks the color from `event_type_css`.
+    pub value: String,
+}
+
+#[derive(Clone)]
+pub struct TagLink {
+    pub url: String,
+    pub label: String,
 }
 
 #[derive(Clone)]
@@ -86,44 +364,191 @@ pub struct EventViewModel {
     pub description: String,
     pub full_text_paragraphs: Vec<String>,
     pub event_types: Vec<EventTypeLink>,
+    pub tags: Vec<TagLink>,
     pub website_link: Option<String>,
     pub google_calendar_url: String,
     pub age_restrictions: Option<String>,
     pub price: Option<f64>,
+    pub contact_email: Option<String>,
+    /// The form for writing to the organizer, set by `relay_contact` in
+    /// place of `contact_email`.
+    pub contact_url: Option<String>,
+    pub contact_phone: Option<String>,
+    pub contact_phone_link: String,
+    pub registration_required: bool,
+    pub status: EventStatus,
 }
 
 #[derive(Clone)]
 pub struct SimpleEventViewModel {
     pub name: String,
+    /// For `<time datetime>`, in the same form as `EventViewModel::start_iso`.
+    pub start_iso: String,
     pub start_formatted: String,
     pub end_formatted: Option<String>,
     pub location: EventLocation,
     pub icon: String,
     pub detail_url: String,
+    pub status: EventStatus,
 }
 
 pub enum DateFormat {
-    TimeOnly,
-    FullDate,
+    TimeOnly(Clock),
+    FullDate(Clock),
 }
 
-impl EventViewModel {
-    pub fn from_event(event: &Event, format: DateFormat, is_past_view: bool) -> Self {
-        let start_ny = event.start_date.with_timezone(&New_York);
-        let start_iso = start_ny.to_rfc3339();
+/// 12 or 24-hour times. Readers pick with `?clock=24` on any page, or
+/// keep a choice in the `clock` cookie through `POST /clock`. Feeds,
+/// link previews and admin pages have no one reader to ask, and stay on
+/// the 12-hour default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Clock {
+    #[default]
+    TwelveHour,
+    TwentyFourHour,
+}
+
+impl Clock {
+    pub const COOKIE: &'static str = "clock";
 
-        let start_formatted = match format {
-            DateFormat::TimeOnly => start_ny.format("%-I:%M %p").to_string(),
-            DateFormat::FullDate => start_ny.format("%a, %b %-d, %Y • %-I:%M %p").to_string(),
+    /// `"12"` or `"24"`, as in the query param and cookie.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "12" => Some(Self::TwelveHour),
+            "24" => Some(Self::TwentyFourHour),
+            _ => None,
+        }
+    }
+
+    pub fn value(self) -> &'static str {
+        match self {
+            Self::TwelveHour => "12",
+            Self::TwentyFourHour => "24",
+        }
+    }
+
+    /// The `?clock=` param wins over the cookie, so a shared link shows
+    /// what its sender saw.
+    pub fn from_request(req: &HttpRequest) -> Self {
+        url::form_urlencoded::parse(req.query_string().as_bytes())
+            .find(|(key, _)| key == "clock")
+            .and_then(|(_, value)| Self::parse(&value))
+            .or_else(|| {
+                req.cookie(Self::COOKIE)
+                    .and_then(|c| Self::parse(c.value()))
+            })
+            .unwrap_or_default()
+    }
+
+    fn time_format(self) -> &'static str {
+        match self {
+            Self::TwelveHour => "%-I:%M %p",
+            Self::TwentyFourHour => "%H:%M",
+        }
+    }
+}
+
+/// The local date an all-day event's timestamp stands for. Feeds sometimes
+/// send bare dates as UTC midnight, which is the previous evening in the
+/// Americas, so those are taken at their UTC date instead of being shifted
+/// back a day.
+pub fn all_day_date(t: DateTime<Utc>, tz: Tz) -> NaiveDate {
+    if t.time() == NaiveTime::MIN {
+        t.date_naive()
+    } else {
+        t.with_timezone(&tz).date_naive()
+    }
+}
+
+/// First and last local day of an all-day event, both inclusive. Anything
+/// that lists, exports or hides all-day events by date goes through this so
+/// they agree on which days a festival covers.
+pub fn all_day_span(
+    start: DateTime<Utc>,
+    end: Option<DateTime<Utc>>,
+    tz: Tz,
+) -> (NaiveDate, NaiveDate) {
+    let first_day = all_day_date(start, tz);
+    let last_day = end
+        .map(|end| all_day_date(end, tz))
+        .unwrap_or(first_day)
+        .max(first_day);
+    (first_day, last_day)
+}
+
+/// The first instant of `day` in `tz`. That's usually midnight, but zones
+/// that spring forward at midnight (e.g. America/Santiago) skip it, and the
+/// day then starts at 1am.
+pub fn local_midnight(day: NaiveDate, tz: Tz) -> DateTime<Tz> {
+    (0..24)
+        .filter_map(|hour| {
+            tz.from_local_datetime(&day.and_hms_opt(hour, 0, 0)?)
+                .earliest()
+        })
+        .next()
+        .expect("every day has a start")
+}
+
+pub fn format_start(start_local: DateTime<Tz>, format: &DateFormat, all_day: bool) -> String {
+    match (format, all_day) {
+        (DateFormat::TimeOnly(clock), false) => start_local.format(clock.time_format()).to_string(),
+        (DateFormat::TimeOnly(_), true) => "All day".to_string(),
+        (DateFormat::FullDate(clock), false) => start_local
+            .format(&format!("%a, %b %-d, %Y • {}", clock.time_format()))
+            .to_string(),
+        (DateFormat::FullDate(_), true) => {
+            start_local.format("%a, %b %-d, %Y • All day").to_string()
+        }
+    }
+}
+
+pub fn format_end(end_local: DateTime<Tz>, format: &DateFormat, all_day: bool) -> Option<String> {
+    match (format, all_day) {
+        (DateFormat::TimeOnly(clock), false) => {
+            Some(end_local.format(clock.time_format()).to_string())
+        }
+        // The index already lists multi-day events under every day they span,
+        // so repeating "All day" as an end time is just noise.
+        (DateFormat::TimeOnly(_), true) => None,
+        (DateFormat::FullDate(clock), false) => Some(
+            end_local
+                .format(&format!("%a, %b %-d, %Y • {}", clock.time_format()))
+                .to_string(),
+        ),
+        (DateFormat::FullDate(_), true) => Some(end_local.format("%a, %b %-d, %Y").to_string()),
+    }
+}
+
+impl EventViewModel {
+    pub fn from_event(
+        event: &Event,
+        format: DateFormat,
+        is_past_view: bool,
+        tz: Tz,
+        durations: &EventDurations,
+    ) -> Self {
+        let (first_day, last_day) = all_day_span(event.start_date, event.end_date, tz);
+        let start_local = if event.all_day {
+            local_midnight(first_day, tz)
+        } else {
+            event.start_date.with_timezone(&tz)
+        };
+        let start_iso = if event.all_day {
+            first_day.to_string()
+        } else {
+            start_local.to_rfc3339()
         };
 
+        let start_formatted = format_start(start_local, &format, event.all_day);
+
         let (end_iso, end_formatted) = if let Some(end) = event.end_date {
-            let end_ny = end.with_timezone(&New_York);
-            let end_str = match format {
-                DateFormat::TimeOnly => end_ny.format("%-I:%M %p").to_string(),
-                DateFormat::FullDate => end_ny.format("%a, %b %-d, %Y • %-I:%M %p").to_string(),
+            let (end_local, end_iso) = if event.all_day {
+                (local_midnight(last_day, tz), last_day.to_string())
+            } else {
+                let end_local = end.with_timezone(&tz);
+                (end_local, end_local.to_rfc3339())
             };
-            (end_ny.to_rfc3339(), Some(end_str))
+            (end_iso, format_end(end_local, &format, event.all_day))
         } else {
             (String::new(), None)
         };
@@ -135,7 +560,23 @@ impl EventViewModel {
                 url: c.get_url_with_past(is_past_view),
                 label: c.to_string(),
                 icon: get_icon_for_type(c).to_string(),
-                color: get_color_for_type(c).to_string(),
+                value: c.value(),
+            })
+            .collect();
+
+        let tags = event
+            .tags
+            .iter()
+            .map(|tag| {
+                let mut params = url::form_urlencoded::Serializer::new(String::new());
+                params.append_pair("tag", tag);
+                if is_past_view {
+                    params.append_pair("past", "true");
+                }
+                TagLink {
+                    url: format!("/?{}", params.finish()),
+                    label: tag.clone(),
+                }
             })
             .collect();
 
@@ -147,7 +588,11 @@ impl EventViewModel {
             EventLocation::Structured {
                 name: name.clone(),
                 address: addr.clone(),
-                google_maps_link: format!("https://www.google.com/maps/search/?api=1&query={encoded_addr}&query_place_id={google_place_id}")
+                google_maps_link: format!("https://www.google.com/maps/search/?api=1&query={encoded_addr}&query_place_id={google_place_id}"),
+                venue_url: format!(
+                    "/venue/{}",
+                    url::form_urlencoded::byte_serialize(google_place_id.as_bytes()).collect::<String>()
+                ),
             }
         } else if let Some(orig) = &event.original_location {
             EventLocation::Unstructured(orig.clone())
@@ -155,15 +600,23 @@ impl EventViewModel {
             EventLocation::Unknown
         };
 
-        let start_utc = event.start_date.format("%Y%m%dT%H%M%SZ").to_string();
-        let end_utc = if let Some(end) = event.end_date {
-            end.format("%Y%m%dT%H%M%SZ").to_string()
+        let dates = if event.all_day {
+            // Google Calendar wants all-day ranges as bare dates with an
+            // exclusive end, same as iCal.
+            format!(
+                "{}/{}",
+                first_day.format("%Y%m%d"),
+                (last_day + chrono::Duration::days(1)).format("%Y%m%d")
+            )
         } else {
-            (event.start_date + chrono::Duration::hours(1))
+            let start_utc = event.start_date.format("%Y%m%dT%H%M%SZ").to_string();
+            // Ends where the calendar download does.
+            let end_utc = durations
+                .end(event.start_date, event.end_date, &event.event_types)
                 .format("%Y%m%dT%H%M%SZ")
-                .to_string()
+                .to_string();
+            format!("{}/{}", start_utc, end_utc)
         };
-        let dates = format!("{}/{}", start_utc, end_utc);
 
         let location_str = if let (Some(name), Some(addr)) = (&event.location_name, &event.address)
         {
@@ -207,33 +660,62 @@ impl EventViewModel {
                 .filter(|s| !s.is_empty())
                 .collect(),
             event_types,
+            tags,
             website_link: crate::models::sanitize_url(event.url.clone()),
             google_calendar_url,
             age_restrictions: event.age_restrictions.clone(),
             price: event.price,
+            contact_email: event.contact_email.clone(),
+            contact_url: None,
+            contact_phone: event.contact_phone.clone(),
+            contact_phone_link: event
+                .contact_phone
+                .as_deref()
+                .map(crate::models::tel_link)
+                .unwrap_or_default(),
+            registration_required: event.registration_required,
+            status: event.status,
         }
     }
+
+    /// For public pages when the contact relay is on: links to the contact
+    /// form instead of showing the organizer's address.
+    pub fn relay_contact(mut self, relay: &ContactRelay) -> Self {
+        if relay.is_enabled() && self.contact_email.take().is_some() {
+            self.contact_url = Some(format!("/event/{}/contact", self.id));
+        }
+        self
+    }
 }
 
 impl SimpleEventViewModel {
-    pub fn from_event(event: &SimpleEvent, format: DateFormat, detail_url_prefix: &str) -> Self {
-        let start_ny = event.start_date.with_timezone(&New_York);
-
-        let start_formatted = match format {
-            DateFormat::TimeOnly => start_ny.format("%-I:%M %p").to_string(),
-            DateFormat::FullDate => start_ny.format("%a, %b %-d, %Y • %-I:%M %p").to_string(),
+    pub fn from_event(
+        event: &SimpleEvent,
+        format: DateFormat,
+        detail_url_prefix: &str,
+        tz: Tz,
+    ) -> Self {
+        let (first_day, last_day) = all_day_span(event.start_date, event.end_date, tz);
+        let (start_local, end_local) = if event.all_day {
+            (
+                local_midnight(first_day, tz),
+                event.end_date.map(|_| local_midnight(last_day, tz)),
+            )
+        } else {
+            (
+                event.start_date.with_timezone(&tz),
+                event.end_date.map(|end| end.with_timezone(&tz)),
+            )
         };
 
-        let end_formatted = if let Some(end) = event.end_date {
-            let end_ny = end.with_timezone(&New_York);
-            let end_str = match format {
-                DateFormat::TimeOnly => end_ny.format("%-I:%M %p").to_string(),
-                DateFormat::FullDate => end_ny.format("%a, %b %-d, %Y • %-I:%M %p").to_string(),
-            };
-            Some(end_str)
+        let start_iso = if event.all_day {
+            first_day.to_string()
         } else {
-            None
+            start_local.to_rfc3339()
         };
+        let start_formatted = format_start(start_local, &format, event.all_day);
+
+        let end_formatted = end_local.and_then(|end| format_end(end, &format, event.all_day));
 
         // For simple event, we don't have address or google_place_id, so we can't build structured location link
         // We fallback to location_name or original_location
@@ -253,11 +735,13 @@ impl SimpleEventViewModel {
 
         Self {
             name: event.name.clone(),
+            start_iso,
             start_formatted,
             end_formatted,
             location,
             icon,
             detail_url,
+            status: event.status,
         }
     }
 }
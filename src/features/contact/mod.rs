@@ -0,0 +1,190 @@
+use crate::config::Config;
+use crate::features::common::{client_ip, database_error, error_page, not_found};
+use crate::i18n::{Lang, Msg};
+use crate::models::{sanitize_email, Event, NewContactMessage};
+use crate::AppState;
+use actix_web::http::{header, StatusCode};
+use actix_web::{http::header::ContentType, web, HttpRequest, HttpResponse, Responder};
+use askama::Template;
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+
+/// Room for a real question, not for a newsletter.
+pub const MAX_MESSAGE_LEN: usize = 2000;
+
+/// Messages one address may send organizers per hour. Someone with a
+/// question about a couple of events stays well under it.
+const MAX_MESSAGES_PER_HOUR: i64 = 3;
+
+/// Messages one address may send one event's organizer per day. The
+/// organizer never asked to be on here, so nobody gets to keep writing to
+/// them. It's per sender rather than for everyone together, which would
+/// let one person use up the day's allowance and shut everyone else out.
+const MAX_MESSAGES_PER_EVENT_PER_DAY: i64 = 5;
+
+#[derive(Template)]
+#[template(path = "contact/form.html")]
+pub struct FormTemplate {
+    pub event_id: i64,
+    pub event_name: String,
+    pub lang: Lang,
+}
+
+#[derive(Template)]
+#[template(path = "contact/sent.html")]
+pub struct SentTemplate;
+
+#[derive(Debug, Deserialize)]
+pub struct ContactForm {
+    pub message: String,
+    pub email: String,
+}
+
+/// The event, if readers can write to its organizer: the relay is on and
+/// the event has an address to relay to.
+async fn contactable_event(state: &AppState, id: i64) -> Result<Event, HttpResponse> {
+    let not_contactable = || not_found("There's no way to contact this event's organizer here.");
+    if !state.contact_relay.is_enabled() {
+        return Err(not_contactable());
+    }
+    match state.events_repo.get(id).await {
+        Ok(Some(event)) if event.contact_email.is_some() => Ok(event),
+        Ok(Some(_)) => Err(not_contactable()),
+        Ok(None) => Err(not_found(
+            "We couldn't find that event. It may have been removed.",
+        )),
+        Err(e) => {
+            log::error!("Failed to fetch event: {e:#}");
+            Err(database_error(&e, "Failed to fetch event"))
+        }
+    }
+}
+
+pub async fn form(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let event = match contactable_event(&state, path.into_inner()).await {
+        Ok(event) => event,
+        Err(response) => return response,
+    };
+    let lang = Lang::from_request(&req);
+    let template = FormTemplate {
+        event_id: event.id,
+        event_name: event.name,
+        lang,
+    };
+    HttpResponse::Ok()
+        .insert_header((header::CONTENT_LANGUAGE, lang.code()))
+        .content_type(ContentType::html())
+        .body(template.render().unwrap())
+}
+
+/// Anyone can write, so there's no login here; limits on the sender's
+/// address and on the event are what stop the relay from being used to
+/// spam organizers.
+pub async fn submit(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<i64>,
+    web::Form(form): web::Form<ContactForm>,
+) -> impl Responder {
+    let event_id = path.into_inner();
+
+    let message = form.message.trim();
+    if message.is_empty() {
+        return error_page(StatusCode::BAD_REQUEST, "Please write a message.");
+    }
+    if message.chars().count() > MAX_MESSAGE_LEN {
+        return error_page(
+            StatusCode::BAD_REQUEST,
+            &format!("Please keep it under {MAX_MESSAGE_LEN} characters."),
+        );
+    }
+    // Required, since a question the organizer can't answer is no use.
+    let Some(sender_email) = sanitize_email(Some(form.email)) else {
+        return error_page(
+            StatusCode::BAD_REQUEST,
+            "Please give an email address the organizer can reply to.",
+        );
+    };
+
+    let event = match contactable_event(&state, event_id).await {
+        Ok(event) => event,
+        Err(response) => return response,
+    };
+
+    let sender_ip = client_ip(&req, &state.trusted_proxies);
+
+    let now = Utc::now();
+    let counts = futures_util::try_join!(
+        state
+            .events_repo
+            .count_contact_messages_since(&sender_ip, now - Duration::hours(1)),
+        state.events_repo.count_contact_messages_to_event_since(
+            event_id,
+            &sender_ip,
+            now - Duration::days(1)
+        ),
+    );
+    match counts {
+        Ok((sent, _)) if sent >= MAX_MESSAGES_PER_HOUR => {
+            return error_page(
+                StatusCode::TOO_MANY_REQUESTS,
+                "You've sent a lot of messages recently. Please try again later.",
+            );
+        }
+        Ok((_, received)) if received >= MAX_MESSAGES_PER_EVENT_PER_DAY => {
+            return error_page(
+                StatusCode::TOO_MANY_REQUESTS,
+                "You've written to this organizer a lot today. Please try again tomorrow.",
+            );
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Failed to count contact messages: {e:#}");
+            return database_error(&e, "Failed to send message");
+        }
+    }
+
+    let new_message = NewContactMessage {
+        event_id,
+        sender_email,
+        message: message.to_string(),
+        sender_ip,
+    };
+    let id = match state.events_repo.insert_contact_message(&new_message).await {
+        Ok(id) => id,
+        Err(e) => {
+            log::error!("Failed to save contact message for event {event_id}: {e:#}");
+            return database_error(&e, "Failed to send message");
+        }
+    };
+
+    let base_url = Config::from_env().public_url.trim_end_matches('/');
+    let event_url = format!("{base_url}/event/{event_id}");
+    let organizer_email = event.contact_email.as_deref().unwrap_or_default();
+    if let Err(e) = state.contact_relay.send(
+        id,
+        organizer_email,
+        &new_message.sender_email,
+        &event.name,
+        &event_url,
+        &new_message.message,
+    ) {
+        log::error!("Failed to relay contact message {id}: {e:#}");
+        return error_page(StatusCode::INTERNAL_SERVER_ERROR, "Failed to send message");
+    }
+
+    log::info!("Relaying contact message {id} to the organizer of event {event_id}");
+    HttpResponse::SeeOther()
+        .insert_header((header::LOCATION, "/contact-sent"))
+        .finish()
+}
+
+pub async fn sent() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(SentTemplate.render().unwrap())
+}
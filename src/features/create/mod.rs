@@ -0,0 +1,183 @@
+use crate::database::SaveOutcome;
+use crate::features::common::error_page;
+use crate::features::upload::hydrate_event_locations;
+use crate::image_processing::datetime_from_naive;
+use crate::models::{sanitize_url, EventSource, EventStatus, EventType, NewEvent};
+use crate::AppState;
+use actix_web::http::StatusCode;
+use actix_web::{http::header::ContentType, web, HttpResponse, Responder};
+use askama::Template;
+use awc::Client;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use strum::IntoEnumIterator;
+
+#[derive(Template)]
+#[template(path = "create/create.html")]
+pub struct CreateTemplate {
+    pub all_event_types: Vec<EventTypeOption>,
+    /// What was sent, so a rejected form comes back filled in.
+    pub form: CreateForm,
+    pub error: Option<&'static str>,
+}
+
+pub struct EventTypeOption {
+    pub value: String,
+    pub label: String,
+    pub selected: bool,
+}
+
+/// Plain form fields. HTML forms send empty strings for blank inputs,
+/// so optional fields are Strings here and turned into Options below.
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateForm {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub start: String,
+    #[serde(default)]
+    pub end: String,
+    #[serde(default)]
+    pub location: String,
+    pub event_type: Option<EventType>,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub price: String,
+}
+
+/// The choices for the category select in `common/event_fields.html`.
+pub fn event_type_options(selected: Option<&EventType>) -> Vec<EventTypeOption> {
+    EventType::iter()
+        .map(|t| EventTypeOption {
+            value: t.value(),
+            label: t.to_string(),
+            selected: selected == Some(&t),
+        })
+        .collect()
+}
+
+fn render_form(status: StatusCode, form: CreateForm, error: Option<&'static str>) -> HttpResponse {
+    let template = CreateTemplate {
+        all_event_types: event_type_options(form.event_type.as_ref()),
+        form,
+        error,
+    };
+    HttpResponse::build(status)
+        .content_type(ContentType::html())
+        .body(template.render().unwrap())
+}
+
+pub async fn index() -> impl Responder {
+    render_form(StatusCode::OK, CreateForm::default(), None)
+}
+
+pub async fn save(
+    state: web::Data<AppState>,
+    client: web::Data<Client>,
+    web::Form(form): web::Form<CreateForm>,
+) -> impl Responder {
+    let mut event = match new_event_from_form(&form, state.timezone) {
+        Ok(event) => event,
+        Err(message) => return render_form(StatusCode::BAD_REQUEST, form, Some(message)),
+    };
+
+    hydrate_event_locations(
+        std::slice::from_mut(&mut event),
+        &client,
+        state.geocoding_key(),
+        state.api_timeouts.geocoding,
+        state.geocoding_concurrency,
+    )
+    .await;
+
+    match state.events_repo.insert(&event).await {
+        Ok(outcome) => {
+            let id = outcome.id();
+            if let SaveOutcome::Inserted(_) = outcome {
+                state.index_cache.invalidate();
+                state.webhooks.notify_new_event(id, &event);
+            }
+            log::info!("Manually added event '{}' with id: {}", event.name, id);
+            HttpResponse::SeeOther()
+                .insert_header((actix_web::http::header::LOCATION, format!("/event/{id}")))
+                .finish()
+        }
+        Err(e) => {
+            log::error!("Failed to save event '{}' to database: {e:#}", event.name);
+            error_page(StatusCode::INTERNAL_SERVER_ERROR, "Failed to save event")
+        }
+    }
+}
+
+fn parse_local_datetime(value: &str, tz: Tz) -> Option<DateTime<Utc>> {
+    // <input type="datetime-local"> omits seconds unless the user sets them.
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S"))
+        .ok()
+        .and_then(|naive| datetime_from_naive(naive, tz))
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+fn new_event_from_form(form: &CreateForm, tz: Tz) -> Result<NewEvent, &'static str> {
+    let name = non_empty(&form.name).ok_or("Name is required")?;
+    let start_date = parse_local_datetime(form.start.trim(), tz).ok_or("Invalid start date")?;
+    let end_date = match non_empty(&form.end) {
+        Some(end) => Some(parse_local_datetime(&end, tz).ok_or("Invalid end date")?),
+        None => None,
+    };
+    if end_date.is_some_and(|end| end < start_date) {
+        return Err("The event can't end before it starts");
+    }
+    let event_type = form.event_type.clone().ok_or("Category is required")?;
+    // f64 parsing takes "NaN" and "inf", which the number input would
+    // never send but a hand-made POST could.
+    let price = match non_empty(&form.price) {
+        Some(price) => Some(
+            price
+                .parse::<f64>()
+                .ok()
+                .filter(|price| price.is_finite() && *price >= 0.0)
+                .ok_or("Invalid price")?,
+        ),
+        None => None,
+    };
+    let description = form.description.trim().to_string();
+
+    Ok(NewEvent {
+        name,
+        full_text: description.clone(),
+        description,
+        start_date,
+        end_date,
+        all_day: false,
+        address: None,
+        original_location: non_empty(&form.location),
+        google_place_id: None,
+        lat: None,
+        lng: None,
+        location_name: None,
+        event_types: vec![event_type],
+        tags: vec![],
+        url: sanitize_url(non_empty(&form.url)),
+        // A human typed this in, so there's nothing to be unsure about.
+        confidence: 1.0,
+        age_restrictions: None,
+        price,
+        source: EventSource::UserSubmitted,
+        external_id: None,
+        contact_email: None,
+        contact_phone: None,
+        registration_required: false,
+        status: EventStatus::Scheduled,
+    })
+}
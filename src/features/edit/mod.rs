@@ -1,42 +1,226 @@
-use crate::features::common::{DateFormat, EventLocation, EventViewModel, SimpleEventViewModel};
+use crate::backup::write_ndjson;
+use crate::database::{find_likely_duplicates, TRASH_RETENTION};
+use crate::features::common::{
+    database_error, error_page, local_midnight, not_found, Clock, DateFormat, EventLocation,
+    EventViewModel, SimpleEventViewModel,
+};
+use crate::i18n::{Lang, Msg};
 use crate::AppState;
-use actix_web::http::header::ContentType;
+use actix_web::http::header::{self, ContentType};
+use actix_web::http::StatusCode;
 use actix_web::{web, HttpResponse, Responder};
 use askama::Template;
+use chrono::{DateTime, Datelike, Duration, Utc};
+use serde::Deserialize;
 
-use crate::features::view::IndexQuery;
+use crate::features::view::{IndexQuery, LabeledValue};
+use crate::models::{EventStatus, EventType, NewEvent, SiteStats, Submitter};
+use actix_web_lab::extract::UrlEncodedForm;
+use strum::IntoEnumIterator;
 
 #[derive(Template)]
 #[template(path = "edit/index.html")]
 struct EditListTemplate {
-    events: Vec<SimpleEventViewModel>,
+    pending: Vec<PendingViewModel>,
+    reports: Vec<ReportViewModel>,
+    duplicates: Vec<DuplicatePair>,
+    events: Vec<EditListRow>,
+    event_types: Vec<LabeledValue>,
+    /// How the last bulk action went, see `bulk`.
+    bulk_result: Option<String>,
+    by_confidence: bool,
+    /// Always English, like the rest of the admin pages.
+    lang: Lang,
+}
+
+/// An event in the list, with the id its bulk-action checkbox submits.
+struct EditListRow {
+    id: i64,
+    event: SimpleEventViewModel,
+    /// 0 to 1, shown as a meter so the shaky ones stand out while scrolling.
+    confidence: f64,
+    confidence_percent: i64,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum EditSort {
+    /// Soonest first, the default.
+    Date,
+    /// Least confident first, for working through the ones most likely to
+    /// have been misread.
+    Confidence,
+}
+
+#[derive(Deserialize)]
+pub struct EditListQuery {
+    sort: Option<EditSort>,
+    /// Set by the redirect after a bulk action: how many of the `selected`
+    /// events it changed.
+    updated: Option<u64>,
+    selected: Option<u64>,
+}
+
+/// A submitted event waiting for review. It isn't on the site yet, so
+/// there's no page to link to; this is all the admin gets to go on.
+struct PendingViewModel {
+    id: i64,
+    name: String,
+    when: String,
+    location: Option<String>,
+    description: String,
+    submitter_name: Option<String>,
+    submitter_email: Option<String>,
+    received: String,
+}
+
+/// A visitor's report, with where to go to fix the event and how to clear
+/// the report once it's dealt with.
+struct ReportViewModel {
+    event_name: String,
+    event_url: String,
+    reason: String,
+    email: Option<String>,
+    received: String,
+    dismiss_action: String,
+}
+
+/// Two events that look like the same thing, with a form action for
+/// keeping each one.
+struct DuplicatePair {
+    first: SimpleEventViewModel,
+    second: SimpleEventViewModel,
+    keep_first_action: String,
+    keep_second_action: String,
 }
 
 #[derive(Template)]
 #[template(path = "edit/show.html")]
 pub struct EditShowTemplate {
     pub event: EventViewModel,
+    /// Everything read off the flyer, for working out why an extraction
+    /// went wrong.
+    pub full_text: String,
+    pub featured: bool,
+    /// Who sent it in through the upload form, if they said.
+    pub submitter: Option<Submitter>,
+    /// Always English, like the rest of the admin pages.
+    pub lang: Lang,
 }
 
-pub async fn index(state: web::Data<AppState>) -> impl Responder {
-    match state
-        .events_repo
-        .list(IndexQuery::default(), None, None)
-        .await
-    {
+pub async fn index(state: web::Data<AppState>, query: web::Query<EditListQuery>) -> impl Responder {
+    let bulk_result = match (query.updated, query.selected) {
+        (Some(updated), Some(selected)) if updated < selected => Some(format!(
+            "Updated {updated} of {selected} selected events. The other {} may already have been deleted.",
+            selected - updated
+        )),
+        (Some(updated), Some(_)) => Some(format!("Updated {updated} events.")),
+        _ => None,
+    };
+
+    let format =
+        |t: DateTime<Utc>, pattern| t.with_timezone(&state.timezone).format(pattern).to_string();
+    let pending = match state.events_repo.list_pending().await {
+        Ok(events) => events
+            .into_iter()
+            .map(|event| PendingViewModel {
+                id: event.id,
+                when: if event.all_day {
+                    format(event.start_date, "%a, %b %-d, %Y")
+                } else {
+                    format(event.start_date, "%a, %b %-d, %Y • %-I:%M %p")
+                },
+                location: event.location_name.or(event.original_location),
+                name: event.name,
+                description: event.description,
+                submitter_name: event.submitter_name,
+                submitter_email: event.submitter_email,
+                received: format(event.created_at, "%a, %b %-d • %-I:%M %p"),
+            })
+            .collect(),
+        Err(e) => {
+            log::error!("Failed to fetch pending events: {e}");
+            return database_error(&e, "Failed to fetch pending events");
+        }
+    };
+
+    let reports = match state.events_repo.list_reports().await {
+        Ok(reports) => reports
+            .into_iter()
+            .map(|report| ReportViewModel {
+                event_url: format!("/edit/event/{}", report.event_id),
+                event_name: report.event_name,
+                reason: report.reason,
+                email: report.email,
+                received: format(report.created_at, "%a, %b %-d • %-I:%M %p"),
+                dismiss_action: format!("/edit/report/{}/dismiss", report.id),
+            })
+            .collect(),
+        Err(e) => {
+            log::error!("Failed to fetch reports: {e}");
+            return database_error(&e, "Failed to fetch reports");
+        }
+    };
+
+    let by_confidence = query.sort == Some(EditSort::Confidence);
+    let events = if by_confidence {
+        state.events_repo.list_ordered_by_confidence().await
+    } else {
+        state
+            .events_repo
+            .list(IndexQuery::default(), None, None)
+            .await
+    };
+    match events {
         Ok(events) => {
-            let vms: Vec<SimpleEventViewModel> = events
+            let to_vm = |e| {
+                SimpleEventViewModel::from_event(
+                    e,
+                    DateFormat::FullDate(Clock::TwelveHour),
+                    "/edit/event",
+                    state.timezone,
+                )
+            };
+            let duplicates = find_likely_duplicates(&events, state.timezone)
+                .into_iter()
+                .map(|(first, second)| DuplicatePair {
+                    first: to_vm(first),
+                    second: to_vm(second),
+                    keep_first_action: format!("/event/{}/merge?into={}", second.id, first.id),
+                    keep_second_action: format!("/event/{}/merge?into={}", first.id, second.id),
+                })
+                .collect();
+            let rows: Vec<EditListRow> = events
                 .iter()
-                .map(|e| SimpleEventViewModel::from_event(e, DateFormat::FullDate, "/edit/event"))
+                .map(|e| EditListRow {
+                    id: e.id,
+                    event: to_vm(e),
+                    confidence: e.confidence,
+                    confidence_percent: (e.confidence * 100.0).round() as i64,
+                })
                 .collect();
-            let template = EditListTemplate { events: vms };
+            let template = EditListTemplate {
+                pending,
+                reports,
+                duplicates,
+                events: rows,
+                event_types: EventType::iter()
+                    .map(|t| LabeledValue {
+                        value: t.value(),
+                        label: t.to_string(),
+                    })
+                    .collect(),
+                bulk_result,
+                by_confidence,
+                lang: Lang::En,
+            };
             HttpResponse::Ok()
                 .content_type(ContentType::html())
                 .body(template.render().unwrap())
         }
         Err(e) => {
             log::error!("Failed to fetch events: {e}");
-            HttpResponse::InternalServerError().body("Failed to fetch events")
+            database_error(&e, "Failed to fetch events")
         }
     }
 }
@@ -45,28 +229,495 @@ pub async fn show(state: web::Data<AppState>, path: web::Path<i64>) -> impl Resp
     let id = path.into_inner();
     match state.events_repo.get(id).await {
         Ok(Some(event)) => {
+            let submitter = match state.events_repo.get_submitter(id).await {
+                Ok(submitter) => submitter,
+                Err(e) => {
+                    log::error!("Failed to fetch submitter: {e}");
+                    return database_error(&e, "Failed to fetch event");
+                }
+            };
             let template = EditShowTemplate {
-                event: EventViewModel::from_event(&event, DateFormat::FullDate, false),
+                event: EventViewModel::from_event(
+                    &event,
+                    DateFormat::FullDate(Clock::TwelveHour),
+                    false,
+                    state.timezone,
+                    &state.event_durations,
+                ),
+                full_text: event.full_text,
+                featured: event.featured,
+                submitter,
+                lang: Lang::En,
             };
             HttpResponse::Ok()
                 .content_type(ContentType::html())
                 .body(template.render().unwrap())
         }
-        Ok(None) => HttpResponse::NotFound().body("Event not found"),
+        Ok(None) => not_found("We couldn't find that event. It may have been removed."),
+        Err(e) => {
+            log::error!("Failed to fetch event: {e}");
+            database_error(&e, "Failed to fetch event")
+        }
+    }
+}
+
+/// The webhook payload for the event, as `notify_new_event` builds it,
+/// for working out why a receiver shows an event oddly. Nothing is sent.
+pub async fn webhook_preview(state: web::Data<AppState>, path: web::Path<i64>) -> impl Responder {
+    let id = path.into_inner();
+    match state.events_repo.get(id).await {
+        Ok(Some(event)) => match state.webhooks.preview(id, &NewEvent::from(event)) {
+            Ok(body) => HttpResponse::Ok()
+                .content_type(ContentType::json())
+                .body(body),
+            Err(e) => {
+                log::error!("Failed to serialize webhook payload for event {id}: {e}");
+                error_page(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to build the webhook payload",
+                )
+            }
+        },
+        Ok(None) => not_found("We couldn't find that event. It may have been removed."),
         Err(e) => {
             log::error!("Failed to fetch event: {e}");
-            HttpResponse::InternalServerError().body("Failed to fetch event")
+            database_error(&e, "Failed to fetch event")
         }
     }
 }
 
+/// Sends the admin to the trash afterwards, where the event is first in
+/// line with a Restore button in case the click was a mistake.
 pub async fn delete(state: web::Data<AppState>, path: web::Path<i64>) -> impl Responder {
     match state.events_repo.delete(path.into_inner()).await {
+        Ok(_) => {
+            state.index_cache.invalidate();
+            HttpResponse::SeeOther()
+                .insert_header(("Location", "/edit/trash"))
+                .finish()
+        }
+        Err(e) => error_page(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Failed to delete event: {}", e),
+        ),
+    }
+}
+
+pub async fn restore(state: web::Data<AppState>, path: web::Path<i64>) -> impl Responder {
+    let id = path.into_inner();
+    match state.events_repo.restore(id).await {
+        Ok(_) => {
+            state.index_cache.invalidate();
+            HttpResponse::SeeOther()
+                .insert_header(("Location", format!("/edit/event/{id}")))
+                .finish()
+        }
+        Err(e) => error_page(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Failed to restore event: {}", e),
+        ),
+    }
+}
+
+/// A deleted event as listed in the trash.
+struct TrashedEventViewModel {
+    name: String,
+    start: String,
+    deleted: String,
+    purged: String,
+    restore_action: String,
+}
+
+#[derive(Template)]
+#[template(path = "edit/trash.html")]
+struct TrashTemplate {
+    events: Vec<TrashedEventViewModel>,
+}
+
+pub async fn trash(state: web::Data<AppState>) -> impl Responder {
+    match state.events_repo.list_deleted().await {
+        Ok(events) => {
+            let format = |t: DateTime<Utc>, pattern| {
+                t.with_timezone(&state.timezone).format(pattern).to_string()
+            };
+            let events = events
+                .into_iter()
+                .map(|event| TrashedEventViewModel {
+                    name: event.name,
+                    start: format(event.start_date, "%a, %b %-d, %Y • %-I:%M %p"),
+                    deleted: format(event.deleted_at, "%a, %b %-d • %-I:%M %p"),
+                    purged: format(event.deleted_at + TRASH_RETENTION, "%b %-d"),
+                    restore_action: format!("/edit/event/{}/restore", event.id),
+                })
+                .collect();
+            let template = TrashTemplate { events };
+            HttpResponse::Ok()
+                .content_type(ContentType::html())
+                .body(template.render().unwrap())
+        }
+        Err(e) => {
+            log::error!("Failed to fetch deleted events: {e}");
+            database_error(&e, "Failed to fetch deleted events")
+        }
+    }
+}
+
+/// How many weeks of additions the stats page charts.
+const STATS_WEEKS: i64 = 12;
+
+/// How many venues the stats page ranks.
+const STATS_TOP_VENUES: i64 = 10;
+
+/// One line of a breakdown, with its share of the largest for the bar.
+struct StatsRow {
+    label: String,
+    /// Where the label links to, if anywhere.
+    url: Option<String>,
+    count: i64,
+    max: i64,
+}
+
+impl StatsRow {
+    fn rows(counts: impl IntoIterator<Item = (String, Option<String>, i64)>) -> Vec<Self> {
+        let counts: Vec<_> = counts.into_iter().collect();
+        let max = counts.iter().map(|(_, _, count)| *count).max().unwrap_or(0);
+        counts
+            .into_iter()
+            .map(|(label, url, count)| Self {
+                label,
+                url,
+                count,
+                max,
+            })
+            .collect()
+    }
+}
+
+/// A breakdown on the stats page, as a table of bars.
+struct StatsSection {
+    id: &'static str,
+    heading: String,
+    rows: Vec<StatsRow>,
+}
+
+#[derive(Template)]
+#[template(path = "edit/stats.html")]
+struct StatsTemplate {
+    stats: SiteStats,
+    sections: Vec<StatsSection>,
+}
+
+pub async fn stats(state: web::Data<AppState>) -> impl Responder {
+    let now = Utc::now();
+    let today = now.with_timezone(&state.timezone).date_naive();
+    let this_monday = today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+    let first_monday = this_monday - Duration::weeks(STATS_WEEKS - 1);
+    let added_since = local_midnight(first_monday, state.timezone).with_timezone(&Utc);
+
+    match state
+        .events_repo
+        .stats(now, added_since, state.timezone, STATS_TOP_VENUES)
+        .await
+    {
+        Ok(stats) => {
+            // Quiet weeks are missing from the counts, but belong on the
+            // chart as much as the busy ones.
+            let added_per_week = StatsRow::rows((0..STATS_WEEKS).map(|i| {
+                let monday = first_monday + Duration::weeks(i);
+                let count = stats
+                    .added_per_week
+                    .iter()
+                    .find(|(week, _)| *week == monday)
+                    .map_or(0, |(_, count)| *count);
+                (format!("Week of {}", monday.format("%b %-d")), None, count)
+            }));
+            let by_source = StatsRow::rows(
+                stats
+                    .by_source
+                    .iter()
+                    .map(|(source, count)| (source.to_string(), None, *count)),
+            );
+            let by_type = StatsRow::rows(stats.by_type.iter().map(|(t, count)| {
+                let url = format!("/?type={}", t.value());
+                (t.to_string(), Some(url), *count)
+            }));
+            let top_venues = StatsRow::rows(stats.top_venues.iter().map(|(id, name, count)| {
+                let url = format!(
+                    "/venue/{}",
+                    url::form_urlencoded::byte_serialize(id.as_bytes()).collect::<String>()
+                );
+                (name.clone(), Some(url), *count)
+            }));
+            let sections = vec![
+                StatsSection {
+                    id: "added",
+                    heading: format!("Added in the last {STATS_WEEKS} weeks"),
+                    rows: added_per_week,
+                },
+                StatsSection {
+                    id: "sources",
+                    heading: "By source".to_string(),
+                    rows: by_source,
+                },
+                StatsSection {
+                    id: "types",
+                    heading: "By event type".to_string(),
+                    rows: by_type,
+                },
+                StatsSection {
+                    id: "venues",
+                    heading: "Top venues".to_string(),
+                    rows: top_venues,
+                },
+            ];
+            let template = StatsTemplate { stats, sections };
+            HttpResponse::Ok()
+                .content_type(ContentType::html())
+                .body(template.render().unwrap())
+        }
+        Err(e) => {
+            log::error!("Failed to compute stats: {e}");
+            database_error(&e, "Failed to compute stats")
+        }
+    }
+}
+
+pub async fn dismiss_report(state: web::Data<AppState>, path: web::Path<i64>) -> impl Responder {
+    match state.events_repo.delete_report(path.into_inner()).await {
         Ok(_) => HttpResponse::SeeOther()
             .insert_header(("Location", "/edit"))
             .finish(),
+        Err(e) => error_page(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Failed to dismiss report: {}", e),
+        ),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BulkAction {
+    /// Publishes submitted events from the review queue.
+    Approve,
+    Delete,
+    Recategorize,
+}
+
+#[derive(Deserialize)]
+pub struct BulkForm {
+    action: BulkAction,
+    #[serde(default)]
+    id: Vec<i64>,
+    /// The new categories, for `Recategorize`.
+    #[serde(default)]
+    event_type: Vec<EventType>,
+}
+
+/// Applies one action to every event checked in the list, for cleaning up
+/// after a bad scraper run. Goes back to the list with a count of how many
+/// it changed.
+pub async fn bulk(
+    state: web::Data<AppState>,
+    UrlEncodedForm(form): UrlEncodedForm<BulkForm>,
+) -> impl Responder {
+    if form.id.is_empty() {
+        return HttpResponse::BadRequest().body("Select at least one event");
+    }
+    let result = match form.action {
+        BulkAction::Approve => match state.events_repo.approve(&form.id).await {
+            Ok(approved) => {
+                announce_approved(&state, &approved).await;
+                Ok(approved.len() as u64)
+            }
+            Err(e) => Err(e),
+        },
+        BulkAction::Delete => state.events_repo.delete_many(&form.id).await,
+        BulkAction::Recategorize => {
+            if form.event_type.is_empty() {
+                return HttpResponse::BadRequest().body("Select at least one category");
+            }
+            state
+                .events_repo
+                .set_event_types(&form.id, &form.event_type)
+                .await
+        }
+    };
+
+    match result {
+        Ok(updated) => {
+            state.index_cache.invalidate();
+            HttpResponse::SeeOther()
+                .insert_header((
+                    "Location",
+                    format!("/edit?updated={updated}&selected={}", form.id.len()),
+                ))
+                .finish()
+        }
         Err(e) => {
-            HttpResponse::InternalServerError().body(format!("Failed to delete event: {}", e))
+            log::error!("Bulk action failed: {e}");
+            database_error(&e, "Failed to update events")
+        }
+    }
+}
+
+/// Sends newly approved events to the webhooks, which weren't told about
+/// them while they were pending. A failure here only costs the webhook.
+async fn announce_approved(state: &AppState, ids: &[i64]) {
+    for &id in ids {
+        match state.events_repo.get(id).await {
+            Ok(Some(event)) => state.webhooks.notify_new_event(id, &NewEvent::from(event)),
+            Ok(None) => {}
+            Err(e) => log::error!("Failed to fetch approved event {id} for webhooks: {e}"),
         }
     }
 }
+
+#[derive(Deserialize)]
+pub struct FeaturedForm {
+    featured: bool,
+}
+
+/// Pins the event to the top of the front page, or unpins it. It stays in
+/// its own day's list either way.
+pub async fn set_featured(
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+    form: web::Form<FeaturedForm>,
+) -> impl Responder {
+    let id = path.into_inner();
+    match state.events_repo.set_featured(id, form.featured).await {
+        Ok(_) => {
+            state.index_cache.invalidate();
+            HttpResponse::SeeOther()
+                .insert_header(("Location", format!("/edit/event/{id}")))
+                .finish()
+        }
+        Err(e) => error_page(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Failed to update event: {}", e),
+        ),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct StatusForm {
+    status: EventStatus,
+}
+
+/// Marks the event cancelled or postponed, or back on. Unlike deleting, it
+/// stays listed so people who saw it find out, and webhook receivers are
+/// told so they can update what they posted.
+pub async fn set_status(
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+    form: web::Form<StatusForm>,
+) -> impl Responder {
+    let id = path.into_inner();
+    if let Err(e) = state.events_repo.set_status(id, form.status).await {
+        return error_page(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Failed to update event: {}", e),
+        );
+    }
+    state.index_cache.invalidate();
+    // Pending events aren't returned, so receivers only hear about ones
+    // they were told about in the first place.
+    match state.events_repo.get(id).await {
+        Ok(Some(event)) => state
+            .webhooks
+            .notify_updated_event(id, &NewEvent::from(event)),
+        Ok(None) => {}
+        Err(e) => log::error!("Failed to fetch event {id} for webhooks: {e}"),
+    }
+    HttpResponse::SeeOther()
+        .insert_header(("Location", format!("/edit/event/{id}")))
+        .finish()
+}
+
+#[derive(Deserialize)]
+pub struct MergeQuery {
+    into: i64,
+}
+
+/// Merges the event in the path into `?into=`, for near-duplicates from
+/// different sources that `find_duplicate` didn't catch on insert.
+pub async fn merge(
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+    query: web::Query<MergeQuery>,
+) -> impl Responder {
+    let from_id = path.into_inner();
+    let into_id = query.into;
+    if from_id == into_id {
+        return HttpResponse::BadRequest().body("Can't merge an event into itself");
+    }
+
+    let from = match state.events_repo.get(from_id).await {
+        Ok(Some(event)) => event,
+        Ok(None) => return not_found("We couldn't find that event. It may have been removed."),
+        Err(e) => {
+            log::error!("Failed to fetch event: {e}");
+            return database_error(&e, "Failed to fetch event");
+        }
+    };
+
+    // The importer would just re-insert a deleted feed event on its next
+    // run, so the feed's copy has to be the one we keep.
+    if from.external_id.is_some() {
+        return HttpResponse::Conflict().body(
+            "This event comes from a feed and would be re-imported. Merge the other event into it instead.",
+        );
+    }
+
+    match state.events_repo.merge(from_id, into_id).await {
+        Ok(_) => {
+            state.index_cache.invalidate();
+            HttpResponse::SeeOther()
+                .insert_header(("Location", format!("/edit/event/{into_id}")))
+                .finish()
+        }
+        Err(e) => error_page(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &format!("Failed to merge events: {}", e),
+        ),
+    }
+}
+
+/// Events fetched per database round trip while exporting.
+const EXPORT_BATCH_SIZE: i64 = 500;
+
+/// Every event as newline-delimited JSON, one full `Event` per line, for
+/// backups and for seeding another instance with `import_events`. Streamed a batch at a time so
+/// the response never holds the whole table in memory.
+pub async fn export(state: web::Data<AppState>) -> impl Responder {
+    let body = futures_util::stream::try_unfold(Some(0), move |cursor| {
+        let state = state.clone();
+        async move {
+            let Some(after_id) = cursor else {
+                return Ok(None);
+            };
+            let events = state
+                .events_repo
+                .list_all_after(after_id, EXPORT_BATCH_SIZE)
+                .await
+                .inspect_err(|e| log::error!("Export failed after event {after_id}: {e:#}"))?;
+            let Some(last) = events.last() else {
+                return Ok(None);
+            };
+            // A short batch is the last one, which saves a final empty query.
+            let next = (events.len() as i64 == EXPORT_BATCH_SIZE).then_some(last.id);
+
+            let mut chunk = Vec::new();
+            write_ndjson(&events, &mut chunk)?;
+            Ok::<_, anyhow::Error>(Some((web::Bytes::from(chunk), next)))
+        }
+    });
+
+    HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            "attachment; filename=\"events.ndjson\"",
+        ))
+        .streaming(body)
+}
@@ -0,0 +1,238 @@
+use crate::auth::{verify_credentials, FAILED_LOGIN_WINDOW};
+use crate::features::common::{client_ip, is_local_path};
+use crate::AppState;
+use actix_session::{
+    config::CookieContentSecurity, storage::CookieSessionStore, Session, SessionExt,
+    SessionMiddleware,
+};
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    cookie::{Key, SameSite},
+    dev::{ServiceRequest, ServiceResponse},
+    http::{
+        header::{self, ContentType},
+        Method, StatusCode,
+    },
+    middleware::Next,
+    web, Error, FromRequest, HttpRequest, HttpResponse, Responder,
+};
+use actix_web_httpauth::extractors::basic::BasicAuth;
+use askama::Template;
+use serde::Deserialize;
+
+/// Session entry set once the admin has logged in. The cookie is signed, so
+/// its presence is enough; there's only the one admin account.
+const ADMIN_SESSION_KEY: &str = "admin";
+
+#[derive(Template)]
+#[template(path = "login/login.html")]
+pub struct LoginTemplate {
+    pub next: String,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginQuery {
+    pub next: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginForm {
+    pub username: String,
+    pub password: String,
+    #[serde(default)]
+    pub next: String,
+}
+
+/// Signed rather than encrypted: the cookie only says "logged in", so there
+/// is nothing to hide, but it mustn't be forgeable. Lax same-site keeps other
+/// sites from riding the session on the admin POST routes.
+pub fn session_middleware(key: Key, secure: bool) -> SessionMiddleware<CookieSessionStore> {
+    SessionMiddleware::builder(CookieSessionStore::default(), key)
+        .cookie_name("session".to_string())
+        .cookie_content_security(CookieContentSecurity::Signed)
+        .cookie_http_only(true)
+        .cookie_same_site(SameSite::Lax)
+        .cookie_secure(secure)
+        .build()
+}
+
+/// Where to go after logging in, if it's on this site.
+fn safe_next(next: Option<&str>) -> String {
+    match next {
+        Some(next) if is_local_path(next) => next.to_string(),
+        _ => "/edit".to_string(),
+    }
+}
+
+fn render_login(status: StatusCode, next: String, error: Option<String>) -> HttpResponse {
+    let template = LoginTemplate { next, error };
+    HttpResponse::build(status)
+        .content_type(ContentType::html())
+        .body(template.render().unwrap())
+}
+
+pub async fn index(query: web::Query<LoginQuery>) -> impl Responder {
+    render_login(StatusCode::OK, safe_next(query.next.as_deref()), None)
+}
+
+pub async fn login(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    session: Session,
+    web::Form(form): web::Form<LoginForm>,
+) -> impl Responder {
+    let next = safe_next(Some(&form.next));
+
+    let ip = client_ip(&req, &state.trusted_proxies);
+    if state.login_throttle.is_locked(&ip) {
+        return render_login(
+            StatusCode::TOO_MANY_REQUESTS,
+            next,
+            Some("Too many wrong passwords. Please try again later.".to_string()),
+        );
+    }
+    let ok = verify_credentials(
+        &state.username,
+        &state.password,
+        &form.username,
+        &form.password,
+    )
+    .await;
+    if !ok {
+        state.login_throttle.record_failure(&ip);
+        return render_login(
+            StatusCode::UNAUTHORIZED,
+            next,
+            Some("Wrong username or password".to_string()),
+        );
+    }
+    state.login_throttle.record_success(&ip);
+
+    // A fresh session on login, so a cookie planted before it can't be
+    // promoted to an admin one.
+    session.renew();
+    if let Err(e) = session.insert(ADMIN_SESSION_KEY, true) {
+        log::error!("Failed to store admin session: {e}");
+        return HttpResponse::InternalServerError().finish();
+    }
+
+    HttpResponse::SeeOther()
+        .insert_header((header::LOCATION, next))
+        .finish()
+}
+
+pub async fn logout(session: Session) -> impl Responder {
+    session.purge();
+    HttpResponse::SeeOther()
+        .insert_header((header::LOCATION, "/"))
+        .finish()
+}
+
+/// Whether the request is from the admin, by the same rules as
+/// `require_admin`. For pages anyone can use that behave differently for
+/// the admin.
+pub async fn is_admin(req: &HttpRequest) -> bool {
+    let logged_in = req
+        .get_session()
+        .get::<bool>(ADMIN_SESSION_KEY)
+        .ok()
+        .flatten()
+        .unwrap_or(false);
+    if logged_in {
+        return true;
+    }
+
+    if !req.headers().contains_key(header::AUTHORIZATION) {
+        return false;
+    }
+    let state = req
+        .app_data::<web::Data<AppState>>()
+        .expect("AppState missing; did you register .app_data(Data::new(AppState{...}))?");
+    let ip = client_ip(req, &state.trusted_proxies);
+    if state.login_throttle.is_locked(&ip) {
+        return false;
+    }
+    let Ok(credentials) = BasicAuth::extract(req).await else {
+        return false;
+    };
+    let ok = verify_credentials(
+        &state.username,
+        &state.password,
+        credentials.user_id(),
+        credentials.password().unwrap_or_default(),
+    )
+    .await;
+    if ok {
+        state.login_throttle.record_success(&ip);
+    } else {
+        state.login_throttle.record_failure(&ip);
+    }
+    ok
+}
+
+/// Whether the request's address has been turned away for too many wrong
+/// passwords.
+fn is_locked_out(req: &HttpRequest) -> bool {
+    req.app_data::<web::Data<AppState>>().is_some_and(|state| {
+        let ip = client_ip(req, &state.trusted_proxies);
+        state.login_throttle.is_locked(&ip)
+    })
+}
+
+/// Guards the admin routes. A logged-in session gets through, and so do
+/// basic-auth credentials, for scripts that can't do the login dance.
+/// Browsers without either are sent to the login form; anything else gets a
+/// 401 with a basic-auth challenge.
+pub async fn require_admin(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let has_credentials = req.headers().contains_key(header::AUTHORIZATION);
+    // Before this attempt is counted, so the try that uses up the limit
+    // still gets told the password was wrong.
+    let locked_out = has_credentials && is_locked_out(req.request());
+    if is_admin(req.request()).await {
+        return Ok(next.call(req).await?.map_into_left_body());
+    }
+
+    let response = if locked_out {
+        HttpResponse::TooManyRequests()
+            .insert_header((
+                header::RETRY_AFTER,
+                FAILED_LOGIN_WINDOW.as_secs().to_string(),
+            ))
+            .body("Too many wrong passwords. Please try again later.")
+    } else if req.method() == Method::GET && !has_credentials {
+        let target = req
+            .uri()
+            .path_and_query()
+            .map(|p| p.as_str())
+            .unwrap_or("/edit");
+        let next: String = url::form_urlencoded::byte_serialize(target.as_bytes()).collect();
+        HttpResponse::SeeOther()
+            .insert_header((header::LOCATION, format!("/login?next={next}")))
+            .finish()
+    } else {
+        HttpResponse::Unauthorized()
+            .insert_header((header::WWW_AUTHENTICATE, "Basic realm=\"admin\""))
+            .body("Invalid credentials")
+    };
+    Ok(req.into_response(response).map_into_right_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_safe_next() {
+        assert_eq!(safe_next(Some("/edit/event/1")), "/edit/event/1");
+        assert_eq!(safe_next(Some("/upload?x=1")), "/upload?x=1");
+        assert_eq!(safe_next(Some("//evil.example")), "/edit");
+        assert_eq!(safe_next(Some("/\\evil.example")), "/edit");
+        assert_eq!(safe_next(Some("https://evil.example")), "/edit");
+        assert_eq!(safe_next(Some("")), "/edit");
+        assert_eq!(safe_next(None), "/edit");
+    }
+}
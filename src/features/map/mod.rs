@@ -0,0 +1,137 @@
+use crate::features::common::{database_error, ApiError};
+use crate::features::view::{compute_time_range, IndexQuery, NearPoint};
+use crate::models::Event;
+use crate::AppState;
+use actix_web::http::header::ContentType;
+use actix_web::{web, HttpResponse, Responder};
+use actix_web_lab::extract::QueryDeserializeError;
+use askama::Template;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+#[derive(Template)]
+#[template(path = "map/index.html")]
+struct MapTemplate {
+    events: Vec<MapEvent>,
+    events_json: String,
+}
+
+/// Just enough about an event to put a pin on the map and link to it.
+#[derive(Serialize)]
+pub struct MapEvent {
+    pub id: i64,
+    pub name: String,
+    pub start: DateTime<Utc>,
+    pub location_name: Option<String>,
+    pub url: String,
+    pub lat: f64,
+    pub lng: f64,
+    /// Only set for "near me" searches.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance_km: Option<f64>,
+}
+
+impl MapEvent {
+    /// Events we couldn't place have nowhere to go on a map.
+    fn from_event(event: &Event) -> Option<Self> {
+        Some(Self {
+            id: event.id,
+            name: event.name.clone(),
+            start: event.start_date,
+            location_name: event
+                .location_name
+                .clone()
+                .or_else(|| event.original_location.clone()),
+            url: format!("/event/{}", event.id),
+            lat: event.lat?,
+            lng: event.lng?,
+            distance_km: None,
+        })
+    }
+}
+
+async fn load_map_events(
+    state: &AppState,
+    now_utc: DateTime<Utc>,
+    query: IndexQuery,
+    near: Option<NearPoint>,
+) -> anyhow::Result<Vec<MapEvent>> {
+    let (is_past, has_date_filter, since, until) =
+        compute_time_range(now_utc, &query, state.timezone);
+    let events = state.events_repo.list_full(query, since, until).await?;
+
+    let mut map_events: Vec<MapEvent> = events
+        .iter()
+        .filter(|event| {
+            // Same rule as the index: upcoming means it hasn't ended yet.
+            if has_date_filter || is_past {
+                return true;
+            }
+            let end =
+                state
+                    .event_durations
+                    .end(event.start_date, event.end_date, &event.event_types);
+            end >= now_utc
+        })
+        .filter_map(MapEvent::from_event)
+        .collect();
+
+    // Near me: closest first instead of chronological.
+    if let Some(near) = near {
+        map_events.sort_by(|a, b| {
+            near.distance_km(a.lat, a.lng)
+                .total_cmp(&near.distance_km(b.lat, b.lng))
+        });
+        for event in &mut map_events {
+            event.distance_km = Some(near.distance_km(event.lat, event.lng));
+        }
+    }
+
+    Ok(map_events)
+}
+
+pub async fn index(
+    state: web::Data<AppState>,
+    query: actix_web_lab::extract::Query<IndexQuery>,
+) -> impl Responder {
+    let query = query.into_inner();
+    let near = match query.near() {
+        Ok(near) => near,
+        Err(message) => return HttpResponse::BadRequest().body(message),
+    };
+    match load_map_events(&state, Utc::now(), query, near).await {
+        Ok(events) => {
+            // The JSON goes inside a <script> tag, so a name containing
+            // "</script>" must not be able to close it.
+            let events_json = serde_json::to_string(&events)
+                .unwrap_or_else(|_| "[]".to_string())
+                .replace("</", "<\\/");
+            let template = MapTemplate {
+                events,
+                events_json,
+            };
+            HttpResponse::Ok()
+                .content_type(ContentType::html())
+                .body(template.render().unwrap())
+        }
+        Err(e) => {
+            log::error!("Failed to fetch events for map: {e}");
+            database_error(&e, "Failed to fetch events")
+        }
+    }
+}
+
+/// The map's data as JSON, for anyone who wants to build on it.
+pub async fn api_events(
+    state: web::Data<AppState>,
+    query: Result<actix_web_lab::extract::Query<IndexQuery>, QueryDeserializeError>,
+) -> Result<HttpResponse, ApiError> {
+    let query = query?.into_inner();
+    let near = query
+        .near()
+        .map_err(|message| ApiError::BadRequest(message.to_string()))?;
+    let events = load_map_events(&state, Utc::now(), query, near)
+        .await
+        .map_err(ApiError::Database)?;
+    Ok(HttpResponse::Ok().json(events))
+}
@@ -1,4 +1,11 @@
+pub mod about;
 pub mod common;
+pub mod contact;
+pub mod create;
 pub mod edit;
+pub mod login;
+pub mod map;
+pub mod report;
 pub mod upload;
+pub mod venue;
 pub mod view;
@@ -0,0 +1,115 @@
+use crate::features::common::{client_ip, database_error, error_page, not_found};
+use crate::models::{sanitize_email, NewUserReport};
+use crate::AppState;
+use actix_web::http::StatusCode;
+use actix_web::{http::header::ContentType, web, HttpRequest, HttpResponse, Responder};
+use askama::Template;
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+
+/// Long enough for "the date is wrong, it's the 14th per their website",
+/// short enough that the form isn't a free pastebin.
+pub const MAX_REPORT_REASON_LEN: usize = 1000;
+
+/// Reports one address may send per hour. Real corrections come one or two
+/// at a time; anything past this is someone hammering the form.
+const MAX_REPORTS_PER_HOUR: i64 = 5;
+
+#[derive(Template)]
+#[template(path = "report/received.html")]
+pub struct ReceivedTemplate;
+
+#[derive(Debug, Deserialize)]
+pub struct ReportForm {
+    pub reason: String,
+    #[serde(default)]
+    pub email: String,
+}
+
+/// Anyone can report a problem with an event, so there's no login here;
+/// limiting by address is what stops the form from being flooded.
+pub async fn submit(
+    state: web::Data<AppState>,
+    req: HttpRequest,
+    path: web::Path<i64>,
+    web::Form(form): web::Form<ReportForm>,
+) -> impl Responder {
+    let event_id = path.into_inner();
+
+    let reason = form.reason.trim();
+    if reason.is_empty() {
+        return error_page(StatusCode::BAD_REQUEST, "Please say what's wrong.");
+    }
+    if reason.chars().count() > MAX_REPORT_REASON_LEN {
+        return error_page(
+            StatusCode::BAD_REQUEST,
+            &format!("Please keep it under {MAX_REPORT_REASON_LEN} characters."),
+        );
+    }
+    let email = match form.email.trim() {
+        "" => None,
+        email => match sanitize_email(Some(email.to_string())) {
+            Some(email) => Some(email),
+            None => {
+                return error_page(
+                    StatusCode::BAD_REQUEST,
+                    "That email address doesn't look right.",
+                )
+            }
+        },
+    };
+
+    let reporter_ip = client_ip(&req, &state.trusted_proxies);
+
+    match state
+        .events_repo
+        .count_reports_since(&reporter_ip, Utc::now() - Duration::hours(1))
+        .await
+    {
+        Ok(count) if count >= MAX_REPORTS_PER_HOUR => {
+            return error_page(
+                StatusCode::TOO_MANY_REQUESTS,
+                "You've sent a lot of reports recently. Please try again later.",
+            );
+        }
+        Ok(_) => {}
+        Err(e) => {
+            log::error!("Failed to count reports: {e:#}");
+            return database_error(&e, "Failed to save report");
+        }
+    }
+
+    match state.events_repo.get(event_id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return not_found("We couldn't find that event. It may have been removed."),
+        Err(e) => {
+            log::error!("Failed to fetch event: {e:#}");
+            return database_error(&e, "Failed to save report");
+        }
+    }
+
+    let report = NewUserReport {
+        event_id,
+        reason: reason.to_string(),
+        email,
+        reporter_ip,
+    };
+    match state.events_repo.insert_report(&report).await {
+        Ok(id) => {
+            log::info!("Received report {id} about event {event_id}");
+            HttpResponse::SeeOther()
+                .insert_header((actix_web::http::header::LOCATION, "/report-received"))
+                .finish()
+        }
+        Err(e) => {
+            log::error!("Failed to save report about event {event_id}: {e:#}");
+            error_page(StatusCode::INTERNAL_SERVER_ERROR, "Failed to save report")
+        }
+    }
+}
+
+pub async fn received() -> impl Responder {
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(ReceivedTemplate.render().unwrap())
+}
@@ -1,76 +1,236 @@
-use crate::image_processing::parse_image;
+use crate::background_tasks::BackgroundTasks;
+use crate::database::SaveOutcome;
+use crate::error_reporting;
+use crate::features::common::{
+    all_day_span, error_page, format_end, format_start, local_midnight, Clock, DateFormat,
+};
+use crate::features::login::is_admin;
+use crate::geocoding::Geocoded;
+use crate::image_processing::{
+    image_file_dhash, parse_image, parse_page_text, ExtractionOptions, FLYER_FORMATS,
+    SAME_IMAGE_MAX_DISTANCE,
+};
+use crate::models::{sanitize_email, Event, NewEvent, Submitter};
+use crate::web_page::{fetch_page, Page};
 use crate::AppState;
-use actix_multipart::form::{tempfile::TempFile, MultipartForm};
-use actix_web::{http::header::ContentType, web, HttpResponse, Responder};
+use actix_multipart::form::{tempfile::TempFile, MultipartForm, MultipartFormConfig};
+use actix_multipart::MultipartError;
+use actix_web::error::{InternalError, PayloadError};
+use actix_web::http::StatusCode;
+use actix_web::{
+    http::header::ContentType, web, HttpRequest, HttpResponse, Responder, ResponseError,
+};
+use actix_web_lab::extract::UrlEncodedForm;
 use askama::Template;
 use awc::Client;
-use futures_util::future;
-use std::collections::{HashMap, HashSet};
+use chrono::Utc;
+use chrono_tz::Tz;
+use image::{ImageFormat, ImageReader};
+use serde::Deserialize;
 use std::fs;
+use std::path::PathBuf;
+use url::Url;
 use uuid::Uuid;
 
 #[derive(Template)]
 #[template(path = "upload/upload.html")]
 pub struct UploadTemplate {
     pub idempotency_key: String,
+    /// The page link form's own, so sending a flyer and then a link
+    /// doesn't look like a repeat.
+    pub page_idempotency_key: String,
+    /// Admin uploads publish straight away and can be previewed; anyone
+    /// else's wait for review.
+    pub admin: bool,
 }
 
 #[derive(Template)]
 #[template(path = "upload/success.html")]
-pub struct SuccessTemplate;
+pub struct SuccessTemplate {
+    /// The upload is waiting for review rather than about to appear.
+    pub pending: bool,
+}
 
 #[derive(Debug, MultipartForm)]
 pub struct UploadForm {
     pub image: TempFile,
     pub idempotency_key: actix_multipart::form::text::Text<Uuid>,
+    /// Both optional. Browsers send them empty when left blank.
+    pub submitter_name: Option<actix_multipart::form::text::Text<String>>,
+    pub submitter_email: Option<actix_multipart::form::text::Text<String>>,
+}
+
+/// Enough for a name or an organization's, short enough that the field
+/// isn't somewhere to paste an essay.
+pub const MAX_SUBMITTER_NAME_LEN: usize = 100;
+
+/// Who the upload came from and whether it has to wait for an admin. The
+/// default is an anonymous upload that publishes straight away.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Submission {
+    pub submitter: Submitter,
+    pub pending: bool,
+}
+
+/// Reads the optional submitter fields, blank meaning not given.
+fn parse_submitter(name: &str, email: &str) -> Result<Submitter, String> {
+    let name = match name.trim() {
+        "" => None,
+        name if name.chars().count() > MAX_SUBMITTER_NAME_LEN => {
+            return Err(format!(
+                "Please keep your name under {MAX_SUBMITTER_NAME_LEN} characters."
+            ));
+        }
+        name => Some(name.to_string()),
+    };
+    let email = match email.trim() {
+        "" => None,
+        email => Some(
+            sanitize_email(Some(email.to_string()))
+                .ok_or_else(|| "That email address doesn't look right.".to_string())?,
+        ),
+    };
+    Ok(Submitter { name, email })
+}
+
+/// Deletes the uploaded image even if processing is cancelled partway,
+/// e.g. when a shutdown gives up waiting for it.
+struct TempFileGuard(Option<PathBuf>);
+
+impl TempFileGuard {
+    async fn remove(mut self) {
+        if let Some(path) = self.0.take() {
+            let path_to_remove = path.clone();
+            if let Err(e) = web::block(move || fs::remove_file(path_to_remove)).await {
+                log::warn!("Failed to remove temp file {:?}: {}", path, e);
+            }
+        }
+    }
 }
 
-pub async fn index() -> impl Responder {
+impl Drop for TempFileGuard {
+    fn drop(&mut self) {
+        if let Some(path) = self.0.take() {
+            if let Err(e) = fs::remove_file(&path) {
+                log::warn!("Failed to remove temp file {:?}: {}", path, e);
+            }
+        }
+    }
+}
+
+/// Caps the whole upload form at `max_bytes`. A bigger one gets a 413 as
+/// soon as it crosses the limit, before the rest is written to disk.
+pub fn multipart_config(max_bytes: usize) -> MultipartFormConfig {
+    MultipartFormConfig::default()
+        .total_limit(max_bytes)
+        .error_handler(move |err, _req| {
+            let response = match &err {
+                MultipartError::Payload(PayloadError::Overflow) => error_page(
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    &format!(
+                        "That image is too large. Uploads can be up to {} MB.",
+                        max_bytes / 1024 / 1024
+                    ),
+                ),
+                _ => error_page(err.status_code(), &err.to_string()),
+            };
+            InternalError::from_response(err, response).into()
+        })
+}
+
+const UNSUPPORTED_IMAGE: &str = "Please upload a JPEG, PNG, GIF or WebP image.";
+
+/// Reads enough of the file to tell what it really is, whatever its name
+/// or declared type says.
+async fn detect_flyer_format(path: PathBuf) -> Option<ImageFormat> {
+    let format = web::block(move || ImageReader::open(path)?.with_guessed_format())
+        .await
+        .ok()?
+        .ok()?
+        .format()?;
+    FLYER_FORMATS.contains(&format).then_some(format)
+}
+
+pub async fn index(req: HttpRequest) -> impl Responder {
     let idempotency_key = Uuid::new_v4().to_string();
-    let template = UploadTemplate { idempotency_key };
+    let template = UploadTemplate {
+        idempotency_key,
+        page_idempotency_key: Uuid::new_v4().to_string(),
+        admin: is_admin(&req).await,
+    };
     HttpResponse::Ok()
         .content_type(ContentType::html())
         .body(template.render().unwrap())
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UploadQuery {
+    /// `?preview=1` shows what was extracted and waits for confirmation
+    /// instead of publishing in the background.
+    pub preview: Option<u8>,
+}
+
+/// Open to everyone when `PUBLIC_UPLOADS` is on. Only the admin's uploads
+/// publish straight away; anyone else's wait in the review queue on
+/// `/edit`.
 pub async fn save(
     state: web::Data<AppState>,
     client: web::Data<Client>,
+    tasks: web::Data<BackgroundTasks>,
+    query: web::Query<UploadQuery>,
+    http_req: HttpRequest,
     MultipartForm(req): MultipartForm<UploadForm>,
 ) -> impl Responder {
-    let idempotency_key = req.idempotency_key.0;
+    // Checked before claiming the idempotency key so the retry isn't
+    // rejected as a duplicate.
+    if tasks.is_shutting_down() {
+        return HttpResponse::ServiceUnavailable()
+            .insert_header(("Retry-After", "30"))
+            .body("Server is restarting, please try again shortly.");
+    }
 
-    // Check for idempotency
-    match state
-        .events_repo
-        .claim_idempotency_key(idempotency_key)
-        .await
-    {
-        Ok(true) => {
-            // New request, proceed
-        }
-        Ok(false) => {
-            // Duplicate request
-            log::warn!(
-                "Duplicate upload attempt blocked for key: {}",
-                idempotency_key
-            );
-            return HttpResponse::Conflict().body("Upload already in progress or completed.");
-        }
-        Err(e) => {
-            log::error!("Database error checking idempotency: {e}");
-            return HttpResponse::InternalServerError().body("Database error");
-        }
+    // Checked before claiming the idempotency key, so the retry with the
+    // right file isn't rejected as a duplicate. Some clients label every
+    // file application/octet-stream, so that's left to the sniffing.
+    let declared_ok = req.image.content_type.as_ref().is_none_or(|mime| {
+        mime.essence_str() == "application/octet-stream"
+            || FLYER_FORMATS
+                .iter()
+                .any(|format| format.to_mime_type() == mime.essence_str())
+    });
+    if !declared_ok {
+        return error_page(StatusCode::UNSUPPORTED_MEDIA_TYPE, UNSUPPORTED_IMAGE);
+    }
+    let Some(format) = detect_flyer_format(req.image.file.path().to_path_buf()).await else {
+        return error_page(StatusCode::UNSUPPORTED_MEDIA_TYPE, UNSUPPORTED_IMAGE);
+    };
+
+    let submitter = match parse_submitter(
+        req.submitter_name.as_ref().map_or("", |name| name.as_str()),
+        req.submitter_email
+            .as_ref()
+            .map_or("", |email| email.as_str()),
+    ) {
+        Ok(submitter) => submitter,
+        Err(message) => return error_page(StatusCode::BAD_REQUEST, &message),
+    };
+    let admin = is_admin(&http_req).await;
+    // The preview's confirm form is admin-only, so a preview would be a
+    // dead end for anyone else.
+    if query.preview == Some(1) && !admin {
+        return error_page(
+            StatusCode::FORBIDDEN,
+            "Only admins can review a flyer before it's published.",
+        );
+    }
+
+    let idempotency_key = req.idempotency_key.0;
+    if let Err(response) = claim_upload(&state, idempotency_key, &submitter).await {
+        return response;
     }
 
     let temp_dir = std::env::temp_dir();
-    let extension = req
-        .image
-        .file_name
-        .as_ref()
-        .and_then(|name| std::path::Path::new(name).extension())
-        .and_then(|ext| ext.to_str())
-        .unwrap_or("jpg");
+    let extension = format.extensions_str().first().copied().unwrap_or("jpg");
     let file_name = format!("{}.{}", idempotency_key, extension);
     let dest_path = temp_dir.join(&file_name);
     let dest_path_clone = dest_path.clone();
@@ -82,96 +242,682 @@ pub async fn save(
         Ok(Ok(_)) => {} // Success
         Ok(Err(e)) => {
             log::error!("Failed to persist uploaded file: {e}");
-            return HttpResponse::InternalServerError().body("Failed to save uploaded file");
+            finish_upload(&state, idempotency_key, false).await;
+            return error_page(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to save uploaded file",
+            );
         }
         Err(e) => {
             log::error!("Blocking task failed: {e}");
-            return HttpResponse::InternalServerError().body("Internal Server Error");
+            finish_upload(&state, idempotency_key, false).await;
+            return error_page(StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error");
         }
     }
 
+    let temp_file = TempFileGuard(Some(dest_path.clone()));
+    if query.preview == Some(1) {
+        let response = preview_upload(&dest_path, &client, &state, submitter).await;
+        finish_upload(&state, idempotency_key, response.status().is_success()).await;
+        temp_file.remove().await;
+        return response;
+    }
+
     let state = state.into_inner();
+    let task_state = state.clone();
     let client = client.into_inner();
+    let submission = Submission {
+        submitter,
+        pending: !admin,
+    };
+    let success_url = if submission.pending {
+        "/upload-success?pending=1"
+    } else {
+        "/upload-success"
+    };
 
-    actix_web::rt::spawn(async move {
-        match parse_image(&dest_path, &client, &state.openai_api_key).await {
-            Ok(mut events) => {
-                if events.is_empty() {
+    let spawned = tasks.spawn(async move {
+        let state = task_state;
+        let succeeded = match process_upload(&dest_path, &client, &state, &submission).await {
+            Ok(UploadOutcome::AlreadyProcessed(event_ids)) => {
+                log::info!("Skipped an upload already processed as events {event_ids:?}");
+                true
+            }
+            Ok(UploadOutcome::Processed(event_ids)) => {
+                if event_ids.is_empty() {
                     log::info!("Image processed but no events found");
-                } else {
-                    hydrate_event_locations(&mut events, &client, &state.google_maps_api_key).await;
-
-                    for event in &mut events {
-                        match state.events_repo.insert(event).await {
-                            Ok(id) => {
-                                log::info!(
-                                    "Saved event '{}' to database with id: {}",
-                                    event.name,
-                                    id
-                                );
-                            }
-                            Err(e) => {
-                                log::error!(
-                                    "Failed to save event '{}' to database: {e:#}",
-                                    event.name
-                                );
-                            }
-                        }
-                    }
                 }
+                true
             }
             Err(e) => {
-                log::error!("parse_image failed: {e:#}");
+                log::error!("Processing upload failed: {e:#}");
+                error_reporting::capture(&e, &[("idempotency_key", &idempotency_key)]);
+                false
             }
+        };
+        // A failed upload can be sent again with the same key; nothing of
+        // it was saved.
+        finish_upload(&state, idempotency_key, succeeded).await;
+
+        temp_file.remove().await;
+    });
+
+    if !spawned {
+        // Shutdown began while the file was uploading.
+        finish_upload(&state, idempotency_key, false).await;
+        return HttpResponse::ServiceUnavailable()
+            .insert_header(("Retry-After", "30"))
+            .body("Server is restarting, please try again shortly.");
+    }
+
+    HttpResponse::SeeOther()
+        .insert_header((actix_web::http::header::LOCATION, success_url))
+        .finish()
+}
+
+/// Claims the idempotency key, or says why the upload can't go ahead.
+async fn claim_upload(
+    state: &AppState,
+    idempotency_key: Uuid,
+    submitter: &Submitter,
+) -> Result<(), HttpResponse> {
+    match state
+        .events_repo
+        .claim_idempotency_key(idempotency_key, submitter)
+        .await
+    {
+        Ok(true) => Ok(()),
+        Ok(false) => {
+            log::warn!(
+                "Duplicate upload attempt blocked for key: {}",
+                idempotency_key
+            );
+            Err(HttpResponse::Conflict().body("Upload already in progress or completed."))
         }
+        Err(e) => {
+            log::error!("Database error checking idempotency: {e}");
+            Err(error_page(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Database error",
+            ))
+        }
+    }
+}
 
-        let path_to_remove = dest_path.clone();
-        if let Err(e) = web::block(move || fs::remove_file(path_to_remove)).await {
-            log::warn!("Failed to remove temp file {:?}: {}", dest_path, e);
+#[derive(Debug, Deserialize)]
+pub struct PageForm {
+    pub url: String,
+    pub idempotency_key: Uuid,
+    #[serde(default)]
+    pub submitter_name: String,
+    #[serde(default)]
+    pub submitter_email: String,
+}
+
+/// Takes a link to an event's web page instead of a flyer. The page is
+/// fetched while the submitter waits, so a link we can't or may not read
+/// is turned away on the spot; reading the events off it happens in the
+/// background, like a flyer's. Open to the same people as `save`.
+pub async fn save_page(
+    state: web::Data<AppState>,
+    client: web::Data<Client>,
+    tasks: web::Data<BackgroundTasks>,
+    http_req: HttpRequest,
+    UrlEncodedForm(form): UrlEncodedForm<PageForm>,
+) -> impl Responder {
+    if tasks.is_shutting_down() {
+        return HttpResponse::ServiceUnavailable()
+            .insert_header(("Retry-After", "30"))
+            .body("Server is restarting, please try again shortly.");
+    }
+
+    let Ok(url) = Url::parse(form.url.trim()) else {
+        return error_page(
+            StatusCode::BAD_REQUEST,
+            "That doesn't look like a link to a web page.",
+        );
+    };
+    let submitter = match parse_submitter(&form.submitter_name, &form.submitter_email) {
+        Ok(submitter) => submitter,
+        Err(message) => return error_page(StatusCode::BAD_REQUEST, &message),
+    };
+    let idempotency_key = form.idempotency_key;
+    if let Err(response) = claim_upload(&state, idempotency_key, &submitter).await {
+        return response;
+    }
+
+    let page = match fetch_page(&url).await {
+        Ok(page) => page,
+        Err(e) => {
+            log::info!("Couldn't read submitted page {url}: {e:#}");
+            finish_upload(&state, idempotency_key, false).await;
+            return error_page(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                &format!("We couldn't read that page: {e}."),
+            );
         }
+    };
+
+    let state = state.into_inner();
+    let task_state = state.clone();
+    let client = client.into_inner();
+    let submission = Submission {
+        submitter,
+        pending: !is_admin(&http_req).await,
+    };
+    let success_url = if submission.pending {
+        "/upload-success?pending=1"
+    } else {
+        "/upload-success"
+    };
+
+    let spawned = tasks.spawn(async move {
+        let state = task_state;
+        let succeeded = match process_page(&page, &client, &state, &submission).await {
+            Ok(event_ids) => {
+                if event_ids.is_empty() {
+                    log::info!("Page {} read but no events found", page.url);
+                }
+                true
+            }
+            Err(e) => {
+                log::error!("Processing page {} failed: {e:#}", page.url);
+                error_reporting::capture(&e, &[("url", &page.url)]);
+                false
+            }
+        };
+        finish_upload(&state, idempotency_key, succeeded).await;
     });
 
+    if !spawned {
+        finish_upload(&state, idempotency_key, false).await;
+        return HttpResponse::ServiceUnavailable()
+            .insert_header(("Retry-After", "30"))
+            .body("Server is restarting, please try again shortly.");
+    }
+
+    HttpResponse::SeeOther()
+        .insert_header((actix_web::http::header::LOCATION, success_url))
+        .finish()
+}
+
+/// Reads, geocodes and saves the events on a fetched page, returning the
+/// ids saved. Events without a link of their own get the page's.
+async fn process_page(
+    page: &Page,
+    client: &Client,
+    state: &AppState,
+    submission: &Submission,
+) -> anyhow::Result<Vec<i64>> {
+    let mut events = {
+        let _slot = state.upload_slots.acquire().await;
+        parse_page_text(
+            &page.text,
+            client,
+            &state.openai_api_key,
+            state.timezone,
+            state.api_timeouts.openai,
+            ExtractionOptions {
+                structured_outputs: state.openai_structured_outputs,
+                past_event_window: state.past_event_window,
+            },
+        )
+        .await?
+    };
+    for event in &mut events {
+        if event.url.is_none() {
+            event.url = Some(page.url.to_string());
+        }
+    }
+    hydrate_event_locations(
+        &mut events,
+        client,
+        state.geocoding_key(),
+        state.api_timeouts.geocoding,
+        state.geocoding_concurrency,
+    )
+    .await;
+
+    Ok(save_events(state, &events, None, submission).await)
+}
+
+/// Records how the upload under `idempotency_key` ended. If that fails, a
+/// failed upload's retry just has to wait out
+/// `database::IDEMPOTENCY_CLAIM_TIMEOUT`.
+async fn finish_upload(state: &AppState, idempotency_key: Uuid, succeeded: bool) {
+    if let Err(e) = state
+        .events_repo
+        .finish_idempotency_key(idempotency_key, succeeded)
+        .await
+    {
+        log::error!("Failed to record how upload {idempotency_key} ended: {e:#}");
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum UploadOutcome {
+    /// A near-identical image was uploaded before, so the LLM wasn't asked
+    /// again. Holds the events that earlier upload produced.
+    AlreadyProcessed(Vec<i64>),
+    /// The ids of the events saved from this image.
+    Processed(Vec<i64>),
+}
+
+/// What an uploaded flyer turned into, before anything is saved.
+enum Extraction {
+    /// See `UploadOutcome::AlreadyProcessed`.
+    AlreadyProcessed(Vec<i64>),
+    Parsed {
+        events: Vec<NewEvent>,
+        /// `None` if the image couldn't be hashed.
+        dhash: Option<u64>,
+    },
+}
+
+/// Extracts and saves the events on an uploaded flyer. The same poster
+/// tends to get photographed and uploaded more than once, and the LLM call
+/// is the expensive part, so a perceptual hash of each processed image is
+/// kept and a repeat is skipped; its events are already in the database.
+pub async fn process_upload(
+    image_path: &std::path::Path,
+    client: &Client,
+    state: &AppState,
+    submission: &Submission,
+) -> anyhow::Result<UploadOutcome> {
+    match extract_events(image_path, client, state).await? {
+        Extraction::AlreadyProcessed(event_ids) => Ok(UploadOutcome::AlreadyProcessed(event_ids)),
+        Extraction::Parsed { events, dhash } => Ok(UploadOutcome::Processed(
+            save_events(state, &events, dhash, submission).await,
+        )),
+    }
+}
+
+async fn extract_events(
+    image_path: &std::path::Path,
+    client: &Client,
+    state: &AppState,
+) -> anyhow::Result<Extraction> {
+    // A hashing or lookup failure only costs the saving, not the upload.
+    let dhash = match image_file_dhash(image_path).await {
+        Ok(dhash) => Some(dhash),
+        Err(e) => {
+            log::warn!("Failed to hash uploaded image: {e:#}");
+            None
+        }
+    };
+    if let Some(dhash) = dhash {
+        match state
+            .events_repo
+            .find_processed_image(dhash, SAME_IMAGE_MAX_DISTANCE)
+            .await
+        {
+            Ok(Some(event_ids)) => return Ok(Extraction::AlreadyProcessed(event_ids)),
+            Ok(None) => {}
+            Err(e) => log::warn!("Failed to look up processed images: {e:#}"),
+        }
+    }
+
+    // Only the OpenAI call waits its turn. Repeat flyers above never make
+    // one, and geocoding has its own limit.
+    let mut events = {
+        let _slot = state.upload_slots.acquire().await;
+        parse_image(
+            image_path,
+            client,
+            &state.openai_api_key,
+            state.timezone,
+            state.api_timeouts.openai,
+            ExtractionOptions {
+                structured_outputs: state.openai_structured_outputs,
+                past_event_window: state.past_event_window,
+            },
+        )
+        .await?
+    };
+    hydrate_event_locations(
+        &mut events,
+        client,
+        state.geocoding_key(),
+        state.api_timeouts.geocoding,
+        state.geocoding_concurrency,
+    )
+    .await;
+
+    Ok(Extraction::Parsed { events, dhash })
+}
+
+/// Inserts the events and records the image they came from, returning the
+/// ids of the ones that saved. Pending ones are announced to the webhooks
+/// when they're approved instead.
+async fn save_events(
+    state: &AppState,
+    events: &[NewEvent],
+    dhash: Option<u64>,
+    submission: &Submission,
+) -> Vec<i64> {
+    let mut event_ids = Vec::new();
+    for event in events {
+        match state
+            .events_repo
+            .insert_submitted(event, &submission.submitter, submission.pending)
+            .await
+        {
+            Ok(SaveOutcome::Inserted(id)) => {
+                log::info!("Saved event '{}' to database with id: {}", event.name, id);
+                if !submission.pending {
+                    state.index_cache.invalidate();
+                    state.webhooks.notify_new_event(id, event);
+                }
+                event_ids.push(id);
+            }
+            Ok(SaveOutcome::Duplicate(id)) => {
+                log::info!("Event '{}' was already saved with id: {}", event.name, id);
+                event_ids.push(id);
+            }
+            Err(e) => {
+                log::error!("Failed to save event '{}' to database: {e:#}", event.name);
+                error_reporting::capture(&e, &[("event_name", &event.name)]);
+            }
+        }
+    }
+
+    // Recorded even when nothing was found, so the same selfie doesn't go
+    // to the LLM twice either. Pending ids only count once they're approved,
+    // so an admin can still re-upload a flyer that's waiting in the queue.
+    if let Some(dhash) = dhash {
+        if let Err(e) = state
+            .events_repo
+            .record_processed_image(dhash, &event_ids)
+            .await
+        {
+            log::warn!("Failed to record processed image: {e:#}");
+        }
+    }
+
+    event_ids
+}
+
+/// One extracted event as an admin reviews it before it's published.
+pub struct PreviewEvent {
+    pub name: String,
+    pub when: String,
+    pub location: String,
+    pub description: String,
+    pub event_types: Vec<String>,
+    pub full_text: String,
+    /// The event exactly as extracted, carried through the confirm form.
+    pub json: String,
+}
+
+impl PreviewEvent {
+    fn from_new_event(event: &NewEvent, tz: Tz) -> anyhow::Result<Self> {
+        let (first_day, last_day) = all_day_span(event.start_date, event.end_date, tz);
+        let start_local = if event.all_day {
+            local_midnight(first_day, tz)
+        } else {
+            event.start_date.with_timezone(&tz)
+        };
+        let mut when = format_start(
+            start_local,
+            &DateFormat::FullDate(Clock::TwelveHour),
+            event.all_day,
+        );
+        if let Some(end) = event.end_date {
+            let end_local = if event.all_day {
+                local_midnight(last_day, tz)
+            } else {
+                end.with_timezone(&tz)
+            };
+            if let Some(end) = format_end(
+                end_local,
+                &DateFormat::FullDate(Clock::TwelveHour),
+                event.all_day,
+            ) {
+                when = format!("{when} – {end}");
+            }
+        }
+
+        Ok(Self {
+            name: event.name.clone(),
+            when,
+            location: event
+                .address
+                .clone()
+                .or_else(|| event.original_location.clone())
+                .unwrap_or_else(|| "Unknown".to_string()),
+            description: event.description.clone(),
+            event_types: event.event_types.iter().map(|t| t.to_string()).collect(),
+            full_text: event.full_text.clone(),
+            json: serde_json::to_string(&unsaved_event(event.clone()))?,
+        })
+    }
+}
+
+/// `NewEvent`'s own serde shape is the schema the LLM fills in, which
+/// leaves out coordinates and contact details, so a previewed event goes
+/// through the confirm form as a full `Event` instead.
+pub fn unsaved_event(event: NewEvent) -> Event {
+    let now = Utc::now();
+    Event {
+        id: 0,
+        created_at: now,
+        updated_at: now,
+        name: event.name,
+        description: event.description,
+        full_text: event.full_text,
+        start_date: event.start_date,
+        end_date: event.end_date,
+        all_day: event.all_day,
+        address: event.address,
+        original_location: event.original_location,
+        google_place_id: event.google_place_id,
+        lat: event.lat,
+        lng: event.lng,
+        location_name: event.location_name,
+        event_types: event.event_types,
+        tags: event.tags,
+        url: event.url,
+        confidence: event.confidence,
+        age_restrictions: event.age_restrictions,
+        price: event.price,
+        source: event.source,
+        external_id: event.external_id,
+        contact_email: event.contact_email,
+        contact_phone: event.contact_phone,
+        registration_required: event.registration_required,
+        status: event.status,
+        featured: false,
+    }
+}
+
+#[derive(Template)]
+#[template(path = "upload/preview.html")]
+pub struct PreviewTemplate {
+    /// Set when this image was uploaded before, instead of `events`.
+    pub already_processed: Option<Vec<i64>>,
+    pub events: Vec<PreviewEvent>,
+    pub dhash: Option<u64>,
+    /// A fresh key for the confirm form, so publishing twice is caught.
+    pub idempotency_key: String,
+    /// Carried through the confirm form, so it's saved with the events.
+    pub submitter: Submitter,
+}
+
+/// Runs the extraction while the admin waits and shows the result, with
+/// nothing saved until they confirm it.
+async fn preview_upload(
+    image_path: &std::path::Path,
+    client: &Client,
+    state: &AppState,
+    submitter: Submitter,
+) -> HttpResponse {
+    let template = match extract_events(image_path, client, state).await {
+        Ok(Extraction::AlreadyProcessed(event_ids)) => PreviewTemplate {
+            already_processed: Some(event_ids),
+            events: vec![],
+            dhash: None,
+            idempotency_key: Uuid::new_v4().to_string(),
+            submitter,
+        },
+        Ok(Extraction::Parsed { events, dhash }) => {
+            let events: anyhow::Result<Vec<_>> = events
+                .iter()
+                .map(|event| PreviewEvent::from_new_event(event, state.timezone))
+                .collect();
+            match events {
+                Ok(events) => PreviewTemplate {
+                    already_processed: None,
+                    events,
+                    dhash,
+                    idempotency_key: Uuid::new_v4().to_string(),
+                    submitter,
+                },
+                Err(e) => {
+                    log::error!("Failed to prepare upload preview: {e:#}");
+                    return error_page(
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        "Failed to prepare preview",
+                    );
+                }
+            }
+        }
+        Err(e) => {
+            log::error!("Processing upload preview failed: {e:#}");
+            return error_page(
+                StatusCode::BAD_GATEWAY,
+                "We couldn't read any events from that image. Please try again.",
+            );
+        }
+    };
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(template.render().unwrap())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ConfirmForm {
+    pub idempotency_key: Uuid,
+    #[serde(default)]
+    pub dhash: Option<u64>,
+    /// Every previewed event, as `Event` JSON.
+    #[serde(default)]
+    pub event: Vec<String>,
+    /// Indexes into `event` of the ones the admin kept.
+    #[serde(default)]
+    pub publish: Vec<usize>,
+    #[serde(default)]
+    pub submitter_name: String,
+    #[serde(default)]
+    pub submitter_email: String,
+}
+
+/// Saves the previewed events the admin kept.
+pub async fn confirm(
+    state: web::Data<AppState>,
+    UrlEncodedForm(form): UrlEncodedForm<ConfirmForm>,
+) -> impl Responder {
+    let mut events = Vec::new();
+    for index in &form.publish {
+        let Some(json) = form.event.get(*index) else {
+            return HttpResponse::BadRequest().body("Unknown event selected");
+        };
+        match serde_json::from_str::<Event>(json) {
+            Ok(event) => events.push(NewEvent::from(event)),
+            Err(e) => {
+                log::warn!("Rejected a malformed previewed event: {e}");
+                return HttpResponse::BadRequest().body("Malformed event");
+            }
+        }
+    }
+
+    let submission = match parse_submitter(&form.submitter_name, &form.submitter_email) {
+        Ok(submitter) => Submission {
+            submitter,
+            pending: false,
+        },
+        Err(message) => return error_page(StatusCode::BAD_REQUEST, &message),
+    };
+
+    match state
+        .events_repo
+        .claim_idempotency_key(form.idempotency_key, &submission.submitter)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            return HttpResponse::Conflict().body("These events were already published.");
+        }
+        Err(e) => {
+            log::error!("Database error checking idempotency: {e}");
+            return error_page(StatusCode::INTERNAL_SERVER_ERROR, "Database error");
+        }
+    }
+
+    // Rejected events count as processed too, so a later upload of the
+    // same flyer doesn't bring them back.
+    let event_ids = save_events(&state, &events, form.dhash, &submission).await;
+    finish_upload(&state, form.idempotency_key, true).await;
+    let location = match event_ids.as_slice() {
+        [id] => format!("/event/{id}"),
+        _ => "/edit".to_string(),
+    };
     HttpResponse::SeeOther()
-        .insert_header((actix_web::http::header::LOCATION, "/upload-success"))
+        .insert_header((actix_web::http::header::LOCATION, location))
         .finish()
 }
 
-pub async fn success() -> impl Responder {
-    let template = SuccessTemplate;
+#[derive(Debug, Deserialize)]
+pub struct SuccessQuery {
+    pub pending: Option<u8>,
+}
+
+pub async fn success(query: web::Query<SuccessQuery>) -> impl Responder {
+    let template = SuccessTemplate {
+        pending: query.pending == Some(1),
+    };
     HttpResponse::Ok()
         .content_type(ContentType::html())
         .body(template.render().unwrap())
 }
 
+/// Fills in each event's place from its `original_location`. With no
+/// `api_key`, geocoding is off and the events are left as they are.
 pub async fn hydrate_event_locations(
-    events: &mut [crate::models::NewEvent],
+    events: &mut [NewEvent],
     client: &awc::Client,
-    api_key: &str,
+    api_key: Option<&str>,
+    timeout: std::time::Duration,
+    concurrency: usize,
 ) {
-    let unique_locations: HashSet<String> = events
-        .iter()
-        .filter_map(|e| e.original_location.clone())
-        .collect();
-
-    let geocoding_futures = unique_locations.iter().map(|loc| async move {
-        match crate::geocoding::canonicalize_address(client, loc, api_key).await {
-            Ok(Some(canon)) => Some((loc.clone(), canon)),
-            Ok(None) => None,
-            Err(e) => {
-                log::warn!("Geocoding failed for '{}': {}", loc, e);
-                None
-            }
-        }
-    });
+    let Some(api_key) = api_key else {
+        return;
+    };
 
-    let geocoded_results = future::join_all(geocoding_futures).await;
-    let location_map: HashMap<String, _> = geocoded_results.into_iter().flatten().collect();
+    // "The Burren" and "Burren Pub" on one flyer are one lookup and one
+    // place. Each event keeps its own spelling as `original_location`.
+    let queries = crate::geocoding::queries_by_venue_key(
+        events.iter().filter_map(|e| e.original_location.clone()),
+    );
+
+    let queries = &queries;
+    let lookups = crate::geocoding::canonicalize_addresses(
+        queries.keys().cloned(),
+        concurrency,
+        crate::geocoding::RETRY_DELAY,
+        |key| async move {
+            crate::geocoding::canonicalize_address(client, &queries[&key], api_key, timeout).await
+        },
+    )
+    .await;
 
     for event in events {
         if let Some(loc) = &event.original_location {
-            if let Some(canon) = location_map.get(loc) {
+            let key = crate::geocoding::venue_key(loc);
+            if let Some(canon) = lookups.results.get(&key).and_then(Geocoded::location) {
                 event.address = Some(canon.formatted_address.clone());
                 event.google_place_id = Some(canon.place_id.clone());
+                event.lat = Some(canon.lat);
+                event.lng = Some(canon.lng);
                 event.location_name = Some(canon.name.clone());
             }
         }
@@ -181,7 +927,8 @@ pub async fn hydrate_event_locations(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::models::{EventSource, NewEvent};
+    use crate::config::ApiTimeouts;
+    use crate::models::{EventSource, EventStatus};
     use chrono::Utc;
 
     #[actix_rt::test]
@@ -201,16 +948,24 @@ mod tests {
                 full_text: "".to_string(),
                 start_date: Utc::now(),
                 end_date: None,
+                all_day: false,
                 address: None,
                 google_place_id: None,
+                lat: None,
+                lng: None,
                 location_name: None,
                 event_types: vec![],
+                tags: vec![],
                 url: None,
                 confidence: 1.0,
                 age_restrictions: None,
                 price: None,
                 source: EventSource::ImageUpload,
                 external_id: None,
+                contact_email: None,
+                contact_phone: None,
+                registration_required: false,
+                status: EventStatus::Scheduled,
             },
             NewEvent {
                 name: "Somerville Theatre Event".to_string(),
@@ -219,16 +974,24 @@ mod tests {
                 full_text: "".to_string(),
                 start_date: Utc::now(),
                 end_date: None,
+                all_day: false,
                 address: None,
                 google_place_id: None,
+                lat: None,
+                lng: None,
                 location_name: None,
                 event_types: vec![],
+                tags: vec![],
                 url: None,
                 confidence: 1.0,
                 age_restrictions: None,
                 price: None,
                 source: EventSource::ImageUpload,
                 external_id: None,
+                contact_email: None,
+                contact_phone: None,
+                registration_required: false,
+                status: EventStatus::Scheduled,
             },
             NewEvent {
                 name: "Unknown Place Event".to_string(),
@@ -237,16 +1000,24 @@ mod tests {
                 full_text: "".to_string(),
                 start_date: Utc::now(),
                 end_date: None,
+                all_day: false,
                 address: None,
                 google_place_id: None,
+                lat: None,
+                lng: None,
                 location_name: None,
                 event_types: vec![],
+                tags: vec![],
                 url: None,
                 confidence: 1.0,
                 age_restrictions: None,
                 price: None,
                 source: EventSource::ImageUpload,
                 external_id: None,
+                contact_email: None,
+                contact_phone: None,
+                registration_required: false,
+                status: EventStatus::Scheduled,
             },
             NewEvent {
                 name: "Another Davis Square Event".to_string(),
@@ -255,20 +1026,35 @@ mod tests {
                 full_text: "".to_string(),
                 start_date: Utc::now(),
                 end_date: None,
+                all_day: false,
                 address: None,
                 google_place_id: None,
+                lat: None,
+                lng: None,
                 location_name: None,
                 event_types: vec![],
+                tags: vec![],
                 url: None,
                 confidence: 1.0,
                 age_restrictions: None,
                 price: None,
                 source: EventSource::ImageUpload,
                 external_id: None,
+                contact_email: None,
+                contact_phone: None,
+                registration_required: false,
+                status: EventStatus::Scheduled,
             },
         ];
 
-        hydrate_event_locations(&mut events, &client, &api_key).await;
+        hydrate_event_locations(
+            &mut events,
+            &client,
+            Some(&api_key),
+            ApiTimeouts::default().geocoding,
+            crate::config::DEFAULT_GEOCODING_CONCURRENCY,
+        )
+        .await;
 
         // Verify results
         assert_eq!(events[0].location_name.as_deref(), Some("Davis Square"));
@@ -279,4 +1065,52 @@ mod tests {
         assert!(events[2].address.is_none()); // Unknown place should not result in address
         assert_eq!(events[3].location_name.as_deref(), Some("Davis Square"));
     }
+
+    #[actix_rt::test]
+    async fn test_hydrate_skips_lookups_when_geocoding_is_off() {
+        let mut events = vec![NewEvent {
+            name: "Davis Square Event".to_string(),
+            original_location: Some("Davis Square".to_string()),
+            description: "".to_string(),
+            full_text: "".to_string(),
+            start_date: Utc::now(),
+            end_date: None,
+            all_day: false,
+            address: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            status: EventStatus::Scheduled,
+        }];
+
+        // A lookup that went out could only fail without a key, and a
+        // failed one waits RETRY_DELAY before trying again, so finishing
+        // sooner means nothing was sent.
+        let started = std::time::Instant::now();
+        hydrate_event_locations(
+            &mut events,
+            &awc::Client::default(),
+            None,
+            ApiTimeouts::default().geocoding,
+            crate::config::DEFAULT_GEOCODING_CONCURRENCY,
+        )
+        .await;
+        assert!(started.elapsed() < crate::geocoding::RETRY_DELAY);
+
+        assert_eq!(events[0].original_location.as_deref(), Some("Davis Square"));
+        assert!(events[0].address.is_none());
+        assert!(events[0].google_place_id.is_none());
+    }
 }
@@ -0,0 +1,84 @@
+use crate::features::common::{
+    database_error, local_midnight, not_found, Clock, DateFormat, EventLocation,
+    SimpleEventViewModel,
+};
+use crate::features::view::IndexQuery;
+use crate::i18n::{Lang, Msg};
+use crate::AppState;
+use actix_web::http::header::ContentType;
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use askama::Template;
+use chrono::Utc;
+
+#[derive(Template)]
+#[template(path = "venue/show.html")]
+pub struct VenueTemplate {
+    pub name: String,
+    pub address: Option<String>,
+    pub google_maps_link: String,
+    /// The index, filtered to this venue.
+    pub filter_url: String,
+    pub events: Vec<SimpleEventViewModel>,
+    /// For the event list's badges. The rest of the page isn't translated
+    /// yet, so this stays English to match.
+    pub lang: Lang,
+}
+
+/// A venue and what's on there from today on.
+pub async fn show(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let place_id = path.into_inner();
+    let venue = match state.events_repo.get_venue(&place_id).await {
+        Ok(Some(venue)) => venue,
+        Ok(None) => return not_found("We don't know that venue."),
+        Err(e) => {
+            log::error!("Failed to fetch venue: {e}");
+            return database_error(&e, "Failed to fetch venue");
+        }
+    };
+
+    let query = IndexQuery {
+        location: vec![place_id.clone()],
+        ..Default::default()
+    };
+    let filter_url = format!("/?{}", query.to_query_string());
+    let today = Utc::now().with_timezone(&state.timezone).date_naive();
+    let since = local_midnight(today, state.timezone).with_timezone(&Utc);
+    let events = match state.events_repo.list(query, Some(since), None).await {
+        Ok(events) => events,
+        Err(e) => {
+            log::error!("Failed to fetch events: {e}");
+            return database_error(&e, "Failed to fetch events");
+        }
+    };
+
+    let clock = Clock::from_request(&req);
+    let encoded_name: String =
+        url::form_urlencoded::byte_serialize(venue.name.as_bytes()).collect();
+    let template = VenueTemplate {
+        google_maps_link: format!(
+            "https://www.google.com/maps/search/?api=1&query={encoded_name}&query_place_id={place_id}"
+        ),
+        name: venue.name,
+        address: venue.address,
+        filter_url,
+        events: events
+            .iter()
+            .map(|e| {
+                SimpleEventViewModel::from_event(
+                    e,
+                    DateFormat::FullDate(clock),
+                    "/event",
+                    state.timezone,
+                )
+            })
+            .collect(),
+        lang: Lang::En,
+    };
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .body(template.render().unwrap())
+}
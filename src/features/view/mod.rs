@@ -1,42 +1,72 @@
-use crate::config::Config;
+use crate::config::{Config, EventDurations};
+use crate::contact_relay::ContactRelay;
+use crate::event_card;
+use crate::features::about::{about, About};
 use crate::features::common::{
-    get_color_for_type, get_icon_for_type, DateFormat, EventLocation, EventViewModel,
+    all_day_span, database_error, get_icon_for_type, is_local_path, local_midnight, not_found,
+    type_colors, ApiError, Clock, DateFormat, EventLocation, EventViewModel, PageValidators,
     SimpleEventViewModel,
 };
-use crate::models::{Event, EventSource, EventType, SimpleEvent};
+use crate::geocoding::venue_key;
+use crate::i18n::{Lang, Msg};
+use crate::ical_timezone;
+use crate::index_cache::CachedPage;
+use crate::models::{
+    Event, EventSource, EventStatus, EventType, RelatedEvent, SimpleEvent, SourceInfo,
+};
 use crate::AppState;
-use actix_web::http::header::ContentType;
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::cookie::{time::Duration as CookieDuration, Cookie, SameSite};
+use actix_web::http::header::{self, Accept, ContentType};
+use actix_web::{mime, web, HttpMessage, HttpRequest, HttpResponse, Responder};
 use askama::Template;
-use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
-use chrono_tz::America::New_York;
-use icalendar::{Calendar, CalendarDateTime, Component, Event as IcalEvent, EventLike};
-use serde::Deserialize;
-use std::collections::BTreeMap;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use futures_util::StreamExt;
+use icalendar::{
+    Alarm, Calendar, CalendarDateTime, Component, Event as IcalEvent, EventLike,
+    EventStatus as IcalStatus, Trigger,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use strum::IntoEnumIterator;
 
 #[derive(Template)]
 #[template(path = "view/index.html")]
 pub struct IndexTemplate {
+    /// Shown above `days` on the unfiltered front page. These events are
+    /// in their day's section as well.
+    pub featured: Vec<SimpleEventViewModel>,
     pub days: Vec<DaySection>,
     pub is_past_view: bool,
     pub all_event_types: Vec<EventTypeViewModel>,
     pub all_sources: Vec<LabeledValue>,
     pub all_locations: Vec<LabeledValue>,
     pub query: IndexQuery,
+    /// Set on the `/today` and `/this-weekend` shortcut pages.
+    pub heading: Option<Msg>,
     pub prev_day_link: Option<String>,
     pub next_day_link: Option<String>,
     pub atom_url: String,
     pub webcal_url: String,
     pub https_url: String,
+    /// A one-off `.ics` of the events listed. None on the shortcut pages,
+    /// whose time windows the download doesn't take.
+    pub download_url: Option<String>,
     pub google_cal_link: String,
+    pub clock: Clock,
+    pub lang: Lang,
+    /// This page without `?clock=` or `?lang=`, to come back to after
+    /// switching.
+    pub back_url: String,
+    /// Only on the page as a visitor first lands on it.
+    pub show_intro: bool,
+    pub about: &'static About,
 }
 
 pub struct EventTypeViewModel {
     pub value: String,
     pub label: String,
     pub icon: String,
-    pub color: String,
 }
 
 pub struct LabeledValue {
@@ -44,16 +74,89 @@ pub struct LabeledValue {
     pub label: String,
 }
 
+/// A bare version of the event page for printing and screen readers: one
+/// heading per level, no icons or buttons, and every detail spelled out.
+#[derive(Template)]
+#[template(path = "view/print.html")]
+pub struct PrintTemplate {
+    pub event: EventViewModel,
+    pub page_url: String,
+}
+
 #[derive(Template)]
 #[template(path = "view/show.html")]
 pub struct ShowTemplate {
     pub event: EventViewModel,
+    pub page_url: String,
+    /// The event's `card.png`, as the link preview image.
+    pub card_url: String,
+    /// What else is on at the venue or of the same kind, under the event.
+    pub related: Vec<RelatedSection>,
+    /// Where the event was listed, for sources with a homepage to send
+    /// readers to. Flyers and submissions have none.
+    pub source: Option<SourceInfo>,
+    pub lang: Lang,
+}
+
+/// How many suggestions of each kind an event page shows: at its venue,
+/// and sharing an event type elsewhere.
+const RELATED_EVENTS_LIMIT: i64 = 4;
+
+/// A list of suggestions under an event, see `EventsRepo::list_related`.
+pub struct RelatedSection {
+    pub id: String,
+    pub heading: String,
+    pub events: Vec<SimpleEventViewModel>,
+}
+
+/// One section for the venue, then one per shared event type, in the
+/// order their events were ranked.
+fn related_sections(
+    related: &[RelatedEvent],
+    tz: Tz,
+    clock: Clock,
+    lang: Lang,
+) -> Vec<RelatedSection> {
+    let mut sections: Vec<RelatedSection> = Vec::new();
+    for related in related {
+        let (id, heading) = match (related.same_venue, &related.shared_type) {
+            (true, _) => (
+                "more-at-venue".to_string(),
+                lang.t(Msg::MoreAtVenue).to_string(),
+            ),
+            (false, Some(EventType::Other)) => (
+                "more-other".to_string(),
+                lang.t(Msg::MoreLikeThis).to_string(),
+            ),
+            (false, Some(t)) => (
+                format!("more-{}", t.value()),
+                lang.t(Msg::OtherTypeEvents).replace("{}", t.as_ref()),
+            ),
+            (false, None) => continue,
+        };
+        let event = SimpleEventViewModel::from_event(
+            &related.event,
+            DateFormat::FullDate(clock),
+            "/event",
+            tz,
+        );
+        match sections.iter_mut().find(|section| section.id == id) {
+            Some(section) => section.events.push(event),
+            None => sections.push(RelatedSection {
+                id,
+                heading,
+                events: vec![event],
+            }),
+        }
+    }
+    sections
 }
 
 #[derive(Template)]
 #[template(path = "view/atom_entry.html", escape = "none")]
 struct AtomEntryTemplate {
     event: EventViewModel,
+    lang: Lang,
 }
 
 struct AtomEntry {
@@ -78,7 +181,80 @@ struct AtomFeedTemplate {
 pub struct DaySection {
     pub day_id: String,
     pub date_header: String,
-    pub events: Vec<SimpleEventViewModel>,
+    pub events: Vec<ListedEvent>,
+}
+
+pub struct ListedEvent {
+    pub event: SimpleEventViewModel,
+    /// Set on the first date of a collapsed weekly series.
+    pub series: Option<WeeklySeries>,
+}
+
+/// A weekly event listed once, see `IndexQuery::collapse_weekly`.
+pub struct WeeklySeries {
+    /// "Every Tuesday"
+    pub label: String,
+    /// Every date's own page, the first included, so each one can still
+    /// be linked to.
+    pub dates: Vec<LabeledValue>,
+}
+
+/// How many dates in a row make a weekly series. Two could be a workshop
+/// and its follow-up.
+const MIN_WEEKLY_SERIES: usize = 3;
+
+/// Events of the same name at the same venue, each starting exactly a week
+/// after the one before in local time, as runs of ids in date order.
+/// Events with no venue are left alone, since two "Open Mic"s somewhere in
+/// town needn't be the same one.
+pub(crate) fn weekly_series(events: &[SimpleEvent], tz: Tz) -> Vec<Vec<i64>> {
+    let local_start = |e: &SimpleEvent| -> NaiveDateTime {
+        if e.all_day {
+            all_day_span(e.start_date, e.end_date, tz)
+                .0
+                .and_time(NaiveTime::MIN)
+        } else {
+            e.start_date.with_timezone(&tz).naive_local()
+        }
+    };
+
+    let mut groups: HashMap<(String, String), Vec<&SimpleEvent>> = HashMap::new();
+    for event in events {
+        let Some(venue) = event
+            .location_name
+            .as_ref()
+            .or(event.original_location.as_ref())
+        else {
+            continue;
+        };
+        groups
+            .entry((event.name.trim().to_lowercase(), venue_key(venue)))
+            .or_default()
+            .push(event);
+    }
+
+    let mut series = Vec::new();
+    for mut group in groups.into_values() {
+        group.sort_by_key(|e| local_start(e));
+        let mut run: Vec<&SimpleEvent> = Vec::new();
+        for event in group {
+            let follows = run
+                .last()
+                .is_some_and(|prev| local_start(event) - local_start(prev) == Duration::weeks(1));
+            if !follows && run.len() >= MIN_WEEKLY_SERIES {
+                series.push(run.iter().map(|e| e.id).collect());
+            }
+            if !follows {
+                run.clear();
+            }
+            run.push(event);
+        }
+        if run.len() >= MIN_WEEKLY_SERIES {
+            series.push(run.iter().map(|e| e.id).collect());
+        }
+    }
+    series.sort();
+    series
 }
 
 #[derive(Deserialize, Default, Clone)]
@@ -95,9 +271,67 @@ pub struct IndexQuery {
     pub since: Option<NaiveDate>,
     pub until: Option<NaiveDate>,
     pub on: Option<NaiveDate>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    pub radius_km: Option<f64>,
+    pub tag: Option<String>,
+    /// List an event that repeats every week once, under its next date,
+    /// with the rest of its dates folded under it. A display choice, not
+    /// a filter: every event is still on the page.
+    pub collapse_weekly: Option<bool>,
+}
+
+/// Used when `lat`/`lng` are given without a radius: about a 30 minute walk.
+const DEFAULT_RADIUS_KM: f64 = 2.0;
+/// Anything wider than this is basically all of Greater Boston anyway, and
+/// capping it keeps the distance filter from being a no-op full scan.
+const MAX_RADIUS_KM: f64 = 50.0;
+
+/// A "near me" search: events within `radius_km` of a point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NearPoint {
+    pub lat: f64,
+    pub lng: f64,
+    pub radius_km: f64,
+}
+
+impl NearPoint {
+    /// Great-circle distance from this point, in km. The database filters
+    /// with the same formula, so the two agree on what's in range.
+    pub fn distance_km(&self, lat: f64, lng: f64) -> f64 {
+        const EARTH_RADIUS_KM: f64 = 6371.0;
+        let d_lat = (lat - self.lat).to_radians();
+        let d_lng = (lng - self.lng).to_radians();
+        let a = (d_lat / 2.0).sin().powi(2)
+            + self.lat.to_radians().cos() * lat.to_radians().cos() * (d_lng / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+    }
 }
 
 impl IndexQuery {
+    /// The "near me" point, if one was asked for. Radii beyond
+    /// `MAX_RADIUS_KM` are capped rather than rejected, since a client
+    /// asking for "everything within 100km" should still get results.
+    pub fn near(&self) -> Result<Option<NearPoint>, &'static str> {
+        let (lat, lng) = match (self.lat, self.lng) {
+            (None, None) if self.radius_km.is_none() => return Ok(None),
+            (Some(lat), Some(lng)) => (lat, lng),
+            _ => return Err("lat and lng must be given together"),
+        };
+        if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lng) {
+            return Err("lat or lng is out of range");
+        }
+        let radius_km = self.radius_km.unwrap_or(DEFAULT_RADIUS_KM);
+        if radius_km.is_nan() || radius_km <= 0.0 {
+            return Err("radius_km must be positive");
+        }
+        Ok(Some(NearPoint {
+            lat,
+            lng,
+            radius_km: radius_km.min(MAX_RADIUS_KM),
+        }))
+    }
+
     pub fn has_filters(&self) -> bool {
         !self.event_types.is_empty()
             || !self.source.is_empty()
@@ -107,6 +341,8 @@ impl IndexQuery {
             || self.since.is_some()
             || self.until.is_some()
             || self.on.is_some()
+            || self.lat.is_some()
+            || self.tag.as_deref().is_some_and(|t| !t.is_empty())
     }
 
     pub fn has_event_type(&self, type_val: &str) -> bool {
@@ -153,47 +389,50 @@ impl IndexQuery {
         if let Some(d) = self.on {
             params.append_pair("on", &d.to_string());
         }
+        if let Some(ref tag) = self.tag {
+            if !tag.is_empty() {
+                params.append_pair("tag", tag);
+            }
+        }
+        if let Some(true) = self.collapse_weekly {
+            params.append_pair("collapse_weekly", "true");
+        }
+        if let (Some(lat), Some(lng)) = (self.lat, self.lng) {
+            params.append_pair("lat", &lat.to_string());
+            params.append_pair("lng", &lng.to_string());
+            if let Some(radius_km) = self.radius_km {
+                params.append_pair("radius_km", &radius_km.to_string());
+            }
+        }
 
         params.finish()
     }
 }
 
-fn compute_time_range(
+pub(crate) fn compute_time_range(
     now_utc: DateTime<Utc>,
     index_query: &IndexQuery,
+    tz: Tz,
 ) -> (bool, bool, Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
     let is_past = index_query.past.unwrap_or(false);
     let has_date_filter =
         index_query.since.is_some() || index_query.until.is_some() || index_query.on.is_some();
 
+    // Day bounds come from local_midnight rather than fixed 00:00/23:59:59
+    // wall times, which don't exist on DST-change days in some zones.
+    let day_start = |d: NaiveDate| local_midnight(d, tz).with_timezone(&Utc);
+    let day_end = |d: NaiveDate| {
+        let next = d.succ_opt().expect("date overflow");
+        local_midnight(next, tz).with_timezone(&Utc) - Duration::seconds(1)
+    };
+
     let (since, until) = if let Some(on_date) = index_query.on {
-        let start = New_York
-            .from_local_datetime(&on_date.and_hms_opt(0, 0, 0).unwrap())
-            .single()
-            .unwrap()
-            .with_timezone(&Utc);
-        let end = New_York
-            .from_local_datetime(&on_date.and_hms_opt(23, 59, 59).unwrap())
-            .single()
-            .unwrap()
-            .with_timezone(&Utc);
-        (Some(start), Some(end))
+        (Some(day_start(on_date)), Some(day_end(on_date)))
     } else if has_date_filter {
-        let start = index_query.since.map(|d| {
-            New_York
-                .from_local_datetime(&d.and_hms_opt(0, 0, 0).unwrap())
-                .single()
-                .unwrap()
-                .with_timezone(&Utc)
-        });
-        let end = index_query.until.map(|d| {
-            New_York
-                .from_local_datetime(&d.and_hms_opt(23, 59, 59).unwrap())
-                .single()
-                .unwrap()
-                .with_timezone(&Utc)
-        });
-        (start, end)
+        (
+            index_query.since.map(day_start),
+            index_query.until.map(day_end),
+        )
     } else if is_past {
         // Past events
         (None, Some(now_utc))
@@ -207,74 +446,342 @@ fn compute_time_range(
     (is_past, has_date_filter, since, until)
 }
 
+/// What the index page shows for a given query: the range of events to
+/// fetch, and which of those it lists on which days. The one-off calendar
+/// download shares it, so the download holds what the reader was looking at.
+pub(crate) struct Listing {
+    is_past: bool,
+    has_date_filter: bool,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    /// What "already over" is measured against when hiding ended events.
+    visible_from: DateTime<Utc>,
+    window_to: Option<DateTime<Utc>>,
+    first_day: NaiveDate,
+    last_day: NaiveDate,
+    tz: Tz,
+    /// When events without an end date are over.
+    durations: EventDurations,
+}
+
+impl Listing {
+    pub(crate) fn new(
+        now_utc: DateTime<Utc>,
+        query: &IndexQuery,
+        window: Option<&Window>,
+        tz: Tz,
+        durations: &EventDurations,
+    ) -> Self {
+        let (is_past, has_date_filter, since, until) = match window {
+            // Same two-day buffer as the upcoming view, for multi-day events
+            // that started before the window and have no end date.
+            Some(w) => (false, false, Some(w.from - Duration::days(2)), Some(w.to)),
+            None => compute_time_range(now_utc, query, tz),
+        };
+        let first_day = if let Some(w) = window {
+            w.from.with_timezone(&tz).date_naive()
+        } else if is_past || has_date_filter {
+            NaiveDate::MIN
+        } else {
+            (now_utc - Duration::days(1))
+                .with_timezone(&tz)
+                .date_naive()
+        };
+        // Keeps a festival running into next week from listing its
+        // Monday under "this weekend".
+        let last_day = window.map_or(NaiveDate::MAX, |w| {
+            (w.to - Duration::seconds(1))
+                .with_timezone(&tz)
+                .date_naive()
+        });
+        Listing {
+            is_past,
+            has_date_filter,
+            since,
+            until,
+            visible_from: window.map_or(now_utc, |w| w.from),
+            window_to: window.map(|w| w.to),
+            first_day,
+            last_day,
+            tz,
+            durations: durations.clone(),
+        }
+    }
+
+    /// The days an event is listed under, in order, or none if the page
+    /// leaves it out.
+    pub(crate) fn days(
+        &self,
+        start: DateTime<Utc>,
+        end_date: Option<DateTime<Utc>>,
+        all_day: bool,
+        event_types: &[EventType],
+    ) -> Vec<NaiveDate> {
+        if self.window_to.is_some_and(|to| start >= to) {
+            return Vec::new();
+        }
+        let (start_day, end_day, visibility_end) = if all_day {
+            // Whole local days, so a Fri-Sun festival stays up until
+            // Sunday is over no matter what time the feed gave.
+            let (first_day, last_day) = all_day_span(start, end_date, self.tz);
+            let after_last_day = last_day.succ_opt().expect("date overflow");
+            (
+                first_day,
+                last_day,
+                local_midnight(after_last_day, self.tz).with_timezone(&Utc),
+            )
+        } else {
+            let start_day = start.with_timezone(&self.tz).date_naive();
+            let end_day =
+                end_date.map_or(start_day, |end| end.with_timezone(&self.tz).date_naive());
+            (
+                start_day,
+                end_day,
+                self.durations.end(start, end_date, event_types),
+            )
+        };
+
+        // Filter based on visibility relative to now. The past view shows
+        // only events that have ended, the upcoming view only those that
+        // haven't.
+        if !self.has_date_filter && (visibility_end >= self.visible_from) == self.is_past {
+            return Vec::new();
+        }
+
+        let (from, to) = if start_day <= end_day {
+            (start_day, end_day)
+        } else {
+            (end_day, start_day)
+        };
+        from.iter_days()
+            .take_while(|day| *day <= to)
+            .filter(|day| *day >= self.first_day && *day <= self.last_day)
+            .collect()
+    }
+}
+
+/// The hour Friday's events start counting as the weekend.
+const WEEKEND_STARTS_AT_HOUR: u32 = 17;
+
+/// A fixed stretch of time the shortcut pages cover. Unlike the `since` and
+/// `until` query parameters these don't have to fall on day boundaries, so
+/// "this weekend" can start Friday evening and "today" can start now.
+pub(crate) struct Window {
+    heading: Msg,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Shortcut {
+    Today,
+    ThisWeekend,
+}
+
+impl Shortcut {
+    pub(crate) fn window(self, now_utc: DateTime<Utc>, tz: Tz) -> Window {
+        let today = now_utc.with_timezone(&tz).date_naive();
+        let after = |d: NaiveDate| {
+            local_midnight(d.succ_opt().expect("date overflow"), tz).with_timezone(&Utc)
+        };
+        match self {
+            Shortcut::Today => Window {
+                heading: Msg::Today,
+                from: now_utc,
+                to: after(today),
+            },
+            Shortcut::ThisWeekend => {
+                // Monday-Thursday look ahead to the coming Friday; Saturday
+                // and Sunday look back to the one that started the weekend.
+                let days_from_monday = i64::from(today.weekday().num_days_from_monday());
+                let friday = today + Duration::days(4 - days_from_monday);
+                let sunday = friday + Duration::days(2);
+                let friday_evening = friday
+                    .and_hms_opt(WEEKEND_STARTS_AT_HOUR, 0, 0)
+                    .and_then(|t| t.and_local_timezone(tz).earliest())
+                    .unwrap_or_else(|| local_midnight(friday, tz))
+                    .with_timezone(&Utc);
+                Window {
+                    heading: Msg::ThisWeekend,
+                    // Once the weekend is under way, drop what's already over.
+                    from: friday_evening.max(now_utc),
+                    to: after(sunday),
+                }
+            }
+        }
+    }
+}
+
+pub async fn today(req: HttpRequest, state: web::Data<AppState>) -> impl Responder {
+    shortcut_with_now(req, state, Utc::now(), Shortcut::Today).await
+}
+
+pub async fn this_weekend(req: HttpRequest, state: web::Data<AppState>) -> impl Responder {
+    shortcut_with_now(req, state, Utc::now(), Shortcut::ThisWeekend).await
+}
+
+pub async fn shortcut_with_now(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    now_utc: DateTime<Utc>,
+    shortcut: Shortcut,
+) -> HttpResponse {
+    let window = shortcut.window(now_utc, state.timezone);
+    render_index(req, state, now_utc, IndexQuery::default(), Some(window)).await
+}
+
 pub async fn index(
+    req: HttpRequest,
     state: web::Data<AppState>,
     query: actix_web_lab::extract::Query<IndexQuery>,
 ) -> impl Responder {
-    index_with_now(state, Utc::now(), query.into_inner()).await
+    index_with_now(req, state, Utc::now(), query.into_inner()).await
 }
 
 pub async fn index_with_now(
+    req: HttpRequest,
     state: web::Data<AppState>,
     now_utc: DateTime<Utc>,
     query: IndexQuery,
 ) -> impl Responder {
-    let (is_past, has_date_filter, since, until) = compute_time_range(now_utc, &query);
+    render_index(req, state, now_utc, query, None).await
+}
+
+async fn render_index(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    now_utc: DateTime<Utc>,
+    query: IndexQuery,
+    window: Option<Window>,
+) -> HttpResponse {
+    let near = match query.near() {
+        Ok(near) => near,
+        Err(message) => return HttpResponse::BadRequest().body(message),
+    };
+    let clock = Clock::from_request(&req);
+    let lang = Lang::from_request(&req);
+
+    let today = now_utc.with_timezone(&state.timezone).date_naive();
+    let cache_key = format!(
+        "{}?{}|{}|{}",
+        req.path(),
+        query.to_query_string(),
+        clock.value(),
+        lang.code()
+    );
+    if let Some(page) = state.index_cache.get(&cache_key, today) {
+        if page.validators.is_fresh(&req) {
+            return page.validators.not_modified();
+        }
+        return index_response(&page.validators, lang, page.body);
+    }
+    let cache_version = state.index_cache.version();
+
+    let listing = Listing::new(
+        now_utc,
+        &query,
+        window.as_ref(),
+        state.timezone,
+        &state.event_durations,
+    );
+    let (is_past, since, until) = (listing.is_past, listing.since, listing.until);
 
     // Fetch events and distinct locations
     let events_result = state.events_repo.list(query.clone(), since, until).await;
     let locations_result = state.events_repo.get_distinct_locations().await;
+    let is_landing = window.is_none() && query.to_query_string().is_empty();
+    // Only on the page as a visitor first lands on it. Under a filter the
+    // banner would show events the filter just excluded.
+    let featured_result = if is_landing {
+        state.events_repo.list_featured(now_utc).await
+    } else {
+        Ok(Vec::new())
+    };
 
-    match (events_result, locations_result) {
-        (Ok(events), Ok(locations)) => {
-            let earliest_day_to_render: NaiveDate = if is_past || has_date_filter {
-                NaiveDate::MIN
+    match (events_result, locations_result, featured_result) {
+        (Ok(events), Ok(locations), Ok(mut featured)) => {
+            // The query keeps anything that started within the last day;
+            // whether an event without an end is over depends on its type.
+            featured.retain(|e| {
+                !listing
+                    .days(e.start_date, e.end_date, e.all_day, &e.event_types)
+                    .is_empty()
+            });
+            // The past view reads backwards, where a series "every Tuesday"
+            // from its last date would be more confusing than helpful.
+            let series = if query.collapse_weekly == Some(true) && !is_past {
+                let single_day: Vec<SimpleEvent> = events
+                    .iter()
+                    .filter(|e| {
+                        listing
+                            .days(e.start_date, e.end_date, e.all_day, &e.event_types)
+                            .len()
+                            == 1
+                    })
+                    .cloned()
+                    .collect();
+                weekly_series(&single_day, state.timezone)
             } else {
-                (now_utc - Duration::days(1))
-                    .with_timezone(&New_York)
-                    .date_naive()
+                Vec::new()
             };
+            let events_by_id: HashMap<i64, &SimpleEvent> =
+                events.iter().map(|e| (e.id, e)).collect();
+            // Each series is listed under its first date only.
+            let mut series_by_lead = HashMap::new();
+            let mut folded = HashSet::new();
+            for ids in &series {
+                folded.extend(ids[1..].iter().copied());
+                let dates = ids
+                    .iter()
+                    .map(|id| {
+                        let start = events_by_id[id].start_date.with_timezone(&state.timezone);
+                        LabeledValue {
+                            value: format!("/event/{id}"),
+                            label: start.format("%a, %b %-d").to_string(),
+                        }
+                    })
+                    .collect();
+                let weekday = events_by_id[&ids[0]]
+                    .start_date
+                    .with_timezone(&state.timezone)
+                    .format("%A")
+                    .to_string();
+                series_by_lead.insert(
+                    ids[0],
+                    WeeklySeries {
+                        label: lang.t(Msg::EveryWeekday).replace("{}", &weekday),
+                        dates,
+                    },
+                );
+            }
 
             let mut events_by_day: BTreeMap<NaiveDate, Vec<SimpleEvent>> = BTreeMap::new();
-
-            for event in events {
-                let start = event.start_date;
-                let start_day = start.with_timezone(&New_York).date_naive();
-                let (end_day, visibility_end) = match event.end_date {
-                    None => (start_day, start + Duration::days(1)),
-                    Some(end) => (end.with_timezone(&New_York).date_naive(), end),
-                };
-
-                // Filter based on visibility relative to now
-                if !has_date_filter {
-                    if is_past {
-                        // In past view, show only events that have ended
-                        if visibility_end >= now_utc {
-                            continue;
-                        }
-                    } else {
-                        // In upcoming view, show only events that haven't ended yet
-                        if visibility_end < now_utc {
-                            continue;
-                        }
-                    }
+            for event in events.iter().filter(|e| !folded.contains(&e.id)) {
+                for day in listing.days(
+                    event.start_date,
+                    event.end_date,
+                    event.all_day,
+                    &event.event_types,
+                ) {
+                    events_by_day.entry(day).or_default().push(event.clone());
                 }
+            }
 
-                let (mut day, last_day) = if start_day <= end_day {
-                    (start_day, end_day)
-                } else {
-                    (end_day, start_day)
-                };
-
-                while day <= last_day {
-                    if day >= earliest_day_to_render {
-                        events_by_day.entry(day).or_default().push(event.clone());
-                    }
-                    if day == last_day {
-                        break;
-                    }
-                    day = day.succ_opt().expect("date overflow");
-                }
+            // Only the events that actually made it onto the page count, so
+            // one ending and dropping out of view changes the tag too.
+            let rendered_events = events_by_day
+                .values()
+                .flatten()
+                .chain(&featured)
+                .chain(events.iter().filter(|e| folded.contains(&e.id)));
+            let validators = PageValidators::new(
+                rendered_events.clone().map(|e| e.updated_at).max(),
+                rendered_events.count(),
+            )
+            .with_variant(clock.value())
+            .with_variant(lang.code());
+            if validators.is_fresh(&req) {
+                return validators.not_modified();
             }
 
             let mut days = Vec::new();
@@ -292,10 +799,26 @@ pub async fn index_with_now(
                         .cmp(&b.start_date)
                         .then_with(|| a.name.cmp(&b.name))
                 });
+                // Near me: closest first within each day. The query only
+                // returns events with coordinates when a point is given.
+                if let Some(near) = near {
+                    let distance = |e: &SimpleEvent| {
+                        near.distance_km(e.lat.unwrap_or_default(), e.lng.unwrap_or_default())
+                    };
+                    day_events.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+                }
 
-                let vms: Vec<SimpleEventViewModel> = day_events
+                let vms: Vec<ListedEvent> = day_events
                     .iter()
-                    .map(|e| SimpleEventViewModel::from_event(e, DateFormat::TimeOnly, "/event"))
+                    .map(|e| ListedEvent {
+                        event: SimpleEventViewModel::from_event(
+                            e,
+                            DateFormat::TimeOnly(clock),
+                            "/event",
+                            state.timezone,
+                        ),
+                        series: series_by_lead.remove(&e.id),
+                    })
                     .collect();
 
                 days.push(DaySection {
@@ -341,15 +864,16 @@ pub async fn index_with_now(
                 )
             };
 
-            // For webcal, we replace http/https with webcal.
-            // If the public_url is just "somerville-events.com", we assume https (webcal).
-            // But config.public_url usually includes scheme.
-            let webcal_url = if https_url.starts_with("https://") {
-                https_url.replace("https://", "webcal://")
-            } else if https_url.starts_with("http://") {
-                https_url.replace("http://", "webcal://")
+            let webcal_url = to_webcal_url(&https_url);
+            let download_url = match (&window, query_str.is_empty()) {
+                (Some(_), _) => None,
+                (None, true) => Some("/events/download.ics".to_string()),
+                (None, false) => Some(format!("/events/download.ics?{}", query_str)),
+            };
+            let back_url = if query_str.is_empty() {
+                req.path().to_string()
             } else {
-                format!("webcal://{}", https_url)
+                format!("{}?{}", req.path(), query_str)
             };
 
             let google_cal_link = format!(
@@ -358,6 +882,17 @@ pub async fn index_with_now(
             );
 
             let template = IndexTemplate {
+                featured: featured
+                    .iter()
+                    .map(|e| {
+                        SimpleEventViewModel::from_event(
+                            e,
+                            DateFormat::FullDate(clock),
+                            "/event",
+                            state.timezone,
+                        )
+                    })
+                    .collect(),
                 days,
                 is_past_view: is_past,
                 all_event_types: EventType::iter()
@@ -365,7 +900,6 @@ pub async fn index_with_now(
                         value: t.value(),
                         label: t.to_string(),
                         icon: get_icon_for_type(&t).to_string(),
-                        color: get_color_for_type(&t),
                     })
                     .collect(),
                 all_sources: EventSource::iter()
@@ -382,44 +916,392 @@ pub async fn index_with_now(
                     })
                     .collect(),
                 query,
+                heading: window.map(|w| w.heading),
                 prev_day_link,
                 next_day_link,
                 atom_url,
                 webcal_url,
                 https_url,
+                download_url,
                 google_cal_link,
+                clock,
+                lang,
+                back_url,
+                show_intro: is_landing,
+                about: about(),
             };
 
-            HttpResponse::Ok()
-                .content_type(ContentType::html())
-                .body(template.render().unwrap())
+            let body = web::Bytes::from(template.render().unwrap());
+            state.index_cache.insert(
+                cache_key,
+                today,
+                cache_version,
+                CachedPage {
+                    validators: validators.clone(),
+                    body: body.clone(),
+                },
+            );
+            index_response(&validators, lang, body)
         }
-        (Err(e), _) | (_, Err(e)) => {
+        (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
             log::error!("Failed to fetch events or locations: {e}");
-            HttpResponse::InternalServerError().body("Failed to fetch events")
+            database_error(&e, "Failed to fetch events")
+        }
+    }
+}
+
+fn index_response(validators: &PageValidators, lang: Lang, body: web::Bytes) -> HttpResponse {
+    validators
+        .apply(HttpResponse::Ok())
+        .insert_header((header::VARY, "Cookie, Accept-Language"))
+        .insert_header((header::CONTENT_LANGUAGE, lang.code()))
+        .content_type(ContentType::html())
+        .body(body)
+}
+
+#[derive(Deserialize)]
+pub struct ClockForm {
+    pub clock: String,
+    /// The page the choice was made on.
+    #[serde(default)]
+    pub back: String,
+}
+
+/// Remembers a reader's 12 or 24-hour choice in a cookie and sends them
+/// back to the page they made it on.
+pub async fn set_clock(web::Form(form): web::Form<ClockForm>) -> impl Responder {
+    let Some(clock) = Clock::parse(&form.clock) else {
+        return HttpResponse::BadRequest().body("clock must be 12 or 24");
+    };
+    remember_choice(Clock::COOKIE, clock.value(), form.back)
+}
+
+#[derive(Deserialize)]
+pub struct LangForm {
+    pub lang: String,
+    /// The page the choice was made on.
+    #[serde(default)]
+    pub back: String,
+}
+
+/// Remembers a reader's language in a cookie and sends them back to the
+/// page they picked it on.
+pub async fn set_lang(web::Form(form): web::Form<LangForm>) -> impl Responder {
+    let Some(lang) = Lang::parse(&form.lang) else {
+        return HttpResponse::BadRequest().body("unsupported language");
+    };
+    remember_choice(Lang::COOKIE, lang.code(), form.back)
+}
+
+/// Sets a reader's preference cookie and redirects to `back`, if it's one
+/// of our own pages.
+fn remember_choice(name: &'static str, value: &'static str, back: String) -> HttpResponse {
+    let back = if is_local_path(&back) {
+        back
+    } else {
+        "/".to_string()
+    };
+    let cookie = Cookie::build(name, value)
+        .path("/")
+        .max_age(CookieDuration::days(365))
+        .same_site(SameSite::Lax)
+        .http_only(true)
+        .finish();
+    HttpResponse::SeeOther()
+        .cookie(cookie)
+        .insert_header((header::LOCATION, back))
+        .finish()
+}
+
+/// No Sitemap line: there's no sitemap to point at, and crawlers find the
+/// events by following links from the index anyway.
+pub async fn robots_txt() -> impl Responder {
+    // Admin pages sit behind basic auth, but keep crawlers away from them
+    // anyway in case that ever gets relaxed.
+    HttpResponse::Ok()
+        .content_type(ContentType::plaintext())
+        .body(
+            "User-agent: *\n\
+             Allow: /\n\
+             Disallow: /edit\n\
+             Disallow: /upload\n\
+             Disallow: /create\n\
+             Disallow: /login\n\
+             Disallow: /activitypub/\n",
+        )
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ShowQuery {
+    pub format: Option<String>,
+}
+
+/// The JSON form of `/event/{id}`. Kept separate from `Event` so the
+/// public shape doesn't change whenever a column is added, and so
+/// extraction internals like `full_text` and `confidence` stay out of it.
+#[derive(Debug, Serialize)]
+pub struct EventJson {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub start_date: DateTime<Utc>,
+    pub end_date: Option<DateTime<Utc>>,
+    pub all_day: bool,
+    pub location_name: Option<String>,
+    pub address: Option<String>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    pub event_types: Vec<String>,
+    pub tags: Vec<String>,
+    pub url: Option<String>,
+    pub price: Option<f64>,
+    pub age_restrictions: Option<String>,
+    pub registration_required: bool,
+    pub status: EventStatus,
+    /// Unset when the contact relay is on; see `contact_url`.
+    pub contact_email: Option<String>,
+    /// Where to write to the organizer when the contact relay is on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contact_url: Option<String>,
+    pub contact_phone: Option<String>,
+    pub source: String,
+    pub updated_at: DateTime<Utc>,
+    pub html_url: String,
+    pub ics_url: String,
+}
+
+impl EventJson {
+    fn from_event(event: Event, base_url: &str, relay: &ContactRelay) -> Self {
+        let (contact_email, contact_url) = match event.contact_email {
+            Some(_) if relay.is_enabled() => {
+                (None, Some(format!("{base_url}/event/{}/contact", event.id)))
+            }
+            email => (email, None),
+        };
+        Self {
+            html_url: format!("{base_url}/event/{}", event.id),
+            ics_url: format!("{base_url}/event/{}.ics", event.id),
+            id: event.id,
+            name: event.name,
+            description: event.description,
+            start_date: event.start_date,
+            end_date: event.end_date,
+            all_day: event.all_day,
+            location_name: event.location_name,
+            address: event.address.or(event.original_location),
+            lat: event.lat,
+            lng: event.lng,
+            event_types: event.event_types.iter().map(EventType::value).collect(),
+            tags: event.tags,
+            url: event.url,
+            price: event.price,
+            age_restrictions: event.age_restrictions,
+            registration_required: event.registration_required,
+            status: event.status,
+            contact_email,
+            contact_url,
+            contact_phone: event.contact_phone,
+            source: event.source.value(),
+            updated_at: event.updated_at,
         }
     }
 }
 
-pub async fn show(state: web::Data<AppState>, path: web::Path<i64>) -> impl Responder {
+/// Whether to answer with JSON rather than the HTML page. `?format=` wins
+/// so a link can force either; otherwise JSON only when the client ranks it
+/// above HTML, since browsers send `*/*` and must keep getting the page.
+fn wants_json(req: &HttpRequest, format: Option<&str>) -> Result<bool, &'static str> {
+    match format {
+        Some("json") => return Ok(true),
+        Some("html") => return Ok(false),
+        Some(_) => return Err("format must be json or html"),
+        None => {}
+    }
+    let Some(accept) = req.get_header::<Accept>() else {
+        return Ok(false);
+    };
+    for mime in accept.ranked() {
+        match (mime.type_(), mime.subtype()) {
+            (mime::APPLICATION, mime::JSON) => return Ok(true),
+            (mime::TEXT, mime::HTML) | (mime::TEXT, mime::STAR) | (mime::STAR, mime::STAR) => {
+                return Ok(false)
+            }
+            _ => {}
+        }
+    }
+    Ok(false)
+}
+
+pub async fn show(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+    query: web::Query<ShowQuery>,
+) -> impl Responder {
     let id = path.into_inner();
+    let json = match wants_json(&req, query.format.as_deref()) {
+        Ok(json) => json,
+        Err(message) => return HttpResponse::BadRequest().body(message),
+    };
     match state.events_repo.get(id).await {
         Ok(Some(event)) => {
+            // Only the page has suggestions, and it's still worth showing
+            // without them.
+            let related = if json {
+                Vec::new()
+            } else {
+                state
+                    .events_repo
+                    .list_related(id, Utc::now(), RELATED_EVENTS_LIMIT)
+                    .await
+                    .unwrap_or_else(|e| {
+                        log::error!("Failed to fetch related events: {e}");
+                        Vec::new()
+                    })
+            };
+            let source = if json {
+                None
+            } else {
+                state
+                    .events_repo
+                    .get_source(&event.source)
+                    .await
+                    .unwrap_or_else(|e| {
+                        log::error!("Failed to fetch source: {e}");
+                        None
+                    })
+                    .filter(|info| info.homepage_url.is_some())
+            };
+            let last_modified = related
+                .iter()
+                .map(|related| related.event.updated_at)
+                .chain([event.updated_at])
+                .max();
+
+            // The two representations share a URL, so they need distinct
+            // tags, and caches need to know the Accept header matters. The
+            // page's times and wording also depend on the reader's clock and
+            // language.
+            let clock = Clock::from_request(&req);
+            let lang = Lang::from_request(&req);
+            let validators = PageValidators::new(last_modified, 1 + related.len())
+                .with_variant(if json { "json" } else { "html" })
+                .with_variant(clock.value())
+                .with_variant(lang.code());
+            if validators.is_fresh(&req) {
+                return validators.not_modified();
+            }
+
+            let mut response = validators.apply(HttpResponse::Ok());
+            response.insert_header((header::VARY, "Accept, Cookie, Accept-Language"));
+            let base_url = Config::from_env().public_url.trim_end_matches('/');
+            if json {
+                return response.json(EventJson::from_event(event, base_url, &state.contact_relay));
+            }
+
             let template = ShowTemplate {
-                event: EventViewModel::from_event(&event, DateFormat::FullDate, false),
+                event: EventViewModel::from_event(
+                    &event,
+                    DateFormat::FullDate(clock),
+                    false,
+                    state.timezone,
+                    &state.event_durations,
+                )
+                .relay_contact(&state.contact_relay),
+                page_url: format!("{base_url}/event/{id}"),
+                card_url: format!("{base_url}/event/{id}/card.png"),
+                related: related_sections(&related, state.timezone, clock, lang),
+                source,
+                lang,
             };
-            HttpResponse::Ok()
+            response
+                .insert_header((header::CONTENT_LANGUAGE, lang.code()))
                 .content_type(ContentType::html())
                 .body(template.render().unwrap())
         }
-        Ok(None) => HttpResponse::NotFound().body("Event not found"),
+        Ok(None) => not_found("We couldn't find that event. It may have been removed."),
         Err(e) => {
             log::error!("Failed to fetch event: {e}");
-            HttpResponse::InternalServerError().body("Failed to fetch event")
+            database_error(&e, "Failed to fetch event")
         }
     }
 }
 
+/// One event as JSON, in the same shape as `/event/{id}` serves to
+/// clients asking for JSON, but with JSON errors too.
+pub async fn api_event(
+    state: web::Data<AppState>,
+    path: Result<web::Path<i64>, actix_web::Error>,
+) -> Result<HttpResponse, ApiError> {
+    // A non-numeric id can't name an event either.
+    let id = path
+        .map_err(|_| ApiError::NotFound("Event ids are numbers.".to_string()))?
+        .into_inner();
+    let event = state
+        .events_repo
+        .get(id)
+        .await
+        .map_err(ApiError::Database)?
+        .ok_or_else(|| ApiError::NotFound(format!("No event with id {id}.")))?;
+    let base_url = Config::from_env().public_url.trim_end_matches('/');
+    Ok(HttpResponse::Ok().json(EventJson::from_event(event, base_url, &state.contact_relay)))
+}
+
+/// An event type as `/api/categories` lists it, styled the way the site
+/// shows it so a client's matches.
+#[derive(Debug, Serialize)]
+pub struct CategoryJson {
+    /// What `event_types` holds in the events API, and what `type=`
+    /// filters on.
+    pub value: String,
+    pub label: String,
+    /// The id of the type's symbol in the site's icon sprite.
+    pub icon: &'static str,
+    pub color: ColorJson,
+    /// The index filtered to the type.
+    pub html_url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ColorJson {
+    pub light: &'static str,
+    pub dark: &'static str,
+}
+
+/// Every event type with its label, icon and color, from the same helpers
+/// the pages use.
+pub async fn api_categories() -> HttpResponse {
+    let base_url = Config::from_env()
+        .public_url
+        .trim_end_matches('/')
+        .to_string();
+    let categories: Vec<CategoryJson> = EventType::iter()
+        .map(|t| {
+            let (light, dark) = type_colors(&t);
+            CategoryJson {
+                value: t.value(),
+                label: t.to_string(),
+                icon: get_icon_for_type(&t),
+                color: ColorJson { light, dark },
+                html_url: format!("{base_url}{}", t.get_url()),
+            }
+        })
+        .collect();
+    HttpResponse::Ok().json(categories)
+}
+
+/// Swaps the scheme of a feed URL for `webcal://`, which makes Apple and
+/// Google Calendar open their subscribe dialog instead of downloading a
+/// one-off copy. A bare host (a `PUBLIC_URL` without a scheme) just gets
+/// the prefix.
+fn to_webcal_url(url: &str) -> String {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .unwrap_or(url);
+    format!("webcal://{rest}")
+}
+
 fn generate_calendar_metadata(
     index_query: &IndexQuery,
     location_map: &BTreeMap<String, String>,
@@ -442,6 +1324,10 @@ fn generate_calendar_metadata(
         name_parts.push(types.join(", "));
     }
 
+    if let Some(tag) = index_query.tag.as_deref().filter(|t| !t.is_empty()) {
+        name_parts.push(format!("#{tag}"));
+    }
+
     name_parts.push("Somerville Events".to_string());
 
     if !index_query.location.is_empty() {
@@ -498,55 +1384,176 @@ async fn load_location_map(
         .unwrap_or_default()
 }
 
+/// Events are serialized in pages of this size, so the feed's memory use
+/// doesn't grow with the number of events.
+const ICAL_FEED_PAGE_SIZE: i64 = 200;
+
+const ICAL_FOOTER: &str = "END:VCALENDAR\r\n";
+
+/// Everything before a calendar's events: `calendar` rendered without its
+/// footer, then the `VTIMEZONE` its events' `TZID` refers to, which
+/// icalendar doesn't write itself. Rendering it this way keeps the header
+/// exactly what icalendar would produce for the whole calendar.
+fn ical_header(calendar: &Calendar, tz: Tz) -> String {
+    let rendered = calendar.to_string();
+    let mut header = rendered
+        .strip_suffix(ICAL_FOOTER)
+        .unwrap_or(&rendered)
+        .to_string();
+    header.push_str(&ical_timezone::vtimezone(tz, Utc::now()));
+    header
+}
+
+/// Sends no ETag or Last-Modified, unlike the Atom feed: the body is
+/// streamed a page at a time, so the validators aren't known until the
+/// response has already started.
 pub async fn ical_feed(
     state: web::Data<AppState>,
     query: actix_web_lab::extract::Query<IndexQuery>,
 ) -> impl Responder {
     let index_query = query.into_inner();
     let now_utc = Utc::now();
-    let (_is_past, _has_date_filter, since, until) = compute_time_range(now_utc, &index_query);
+    let (_is_past, _has_date_filter, since, until) =
+        compute_time_range(now_utc, &index_query, state.timezone);
 
     // Fetch location names if we have location filters
     let location_map = load_location_map(&state, &index_query).await;
 
-    match state
+    // Fetch the first page before we start responding, so a database outage
+    // is still reported with a proper status rather than a truncated 200.
+    let first_page = match state
         .events_repo
-        .list_full(index_query.clone(), since, until)
+        .list_full_page(index_query.clone(), since, until, None, ICAL_FEED_PAGE_SIZE)
         .await
     {
-        Ok(events) => {
-            let mut calendar = Calendar::new();
-
-            let config = Config::from_env();
-            let (name, description) =
-                generate_calendar_metadata(&index_query, &location_map, &config.public_url);
+        Ok(events) => events,
+        Err(e) => {
+            log::error!("Failed to fetch events for ical feed: {e}");
+            return database_error(&e, "Failed to fetch events");
+        }
+    };
 
-            calendar.name(&name);
-            calendar.description(&description);
+    let config = Config::from_env();
+    let (name, description) =
+        generate_calendar_metadata(&index_query, &location_map, &config.public_url);
+
+    let header = ical_header(
+        Calendar::new().name(&name).description(&description),
+        state.timezone,
+    );
+
+    let pages = futures_util::stream::unfold(Some(first_page), move |page| {
+        let state = state.clone();
+        let index_query = index_query.clone();
+        async move {
+            let page = page?;
+            let chunk: String = page
+                .iter()
+                .map(|event| ical_event(event, state.timezone, &state.event_durations).to_string())
+                .collect();
 
-            for event in events {
-                calendar.push(IcalEvent::from(&event));
-            }
+            let next_page = if page.len() < ICAL_FEED_PAGE_SIZE as usize {
+                None
+            } else {
+                let cursor = page.last().map(|event| (event.start_date, event.id));
+                match state
+                    .events_repo
+                    .list_full_page(index_query, since, until, cursor, ICAL_FEED_PAGE_SIZE)
+                    .await
+                {
+                    Ok(events) => Some(events),
+                    Err(e) => {
+                        // Headers are long gone, so all we can do is cut the
+                        // response short and let the client retry.
+                        log::error!("Failed to fetch ical feed page: {e}");
+                        return Some((Err(std::io::Error::other(e.to_string())), None));
+                    }
+                }
+            };
 
-            HttpResponse::Ok()
-                .content_type("text/calendar")
-                .insert_header(("Content-Disposition", "inline; filename=\"events.ics\""))
-                .body(calendar.to_string())
+            Some((Ok(web::Bytes::from(chunk)), next_page))
         }
+    });
+
+    let body = futures_util::stream::once(async move { Ok(web::Bytes::from(header)) })
+        .chain(pages)
+        .chain(futures_util::stream::once(async {
+            Ok(web::Bytes::from_static(ICAL_FOOTER.as_bytes()))
+        }));
+
+    HttpResponse::Ok()
+        .content_type("text/calendar")
+        .insert_header(("Content-Disposition", "inline; filename=\"events.ics\""))
+        .streaming(body)
+}
+
+/// A one-off copy of what the index page lists for the same filters, for
+/// readers who want those events in their calendar without subscribing.
+/// Unlike `ical_feed` it keeps the page's own cut-offs, so an event that
+/// has already ended isn't in the file.
+pub async fn ical_download(
+    state: web::Data<AppState>,
+    query: actix_web_lab::extract::Query<IndexQuery>,
+) -> impl Responder {
+    let index_query = query.into_inner();
+    if let Err(message) = index_query.near() {
+        return HttpResponse::BadRequest().body(message);
+    }
+    let listing = Listing::new(
+        Utc::now(),
+        &index_query,
+        None,
+        state.timezone,
+        &state.event_durations,
+    );
+    let events = match state
+        .events_repo
+        .list_full(index_query.clone(), listing.since, listing.until)
+        .await
+    {
+        Ok(events) => events,
         Err(e) => {
-            log::error!("Failed to fetch events for ical feed: {e}");
-            HttpResponse::InternalServerError().body("Failed to fetch events")
+            log::error!("Failed to fetch events for ical download: {e}");
+            return database_error(&e, "Failed to fetch events");
         }
+    };
+
+    let location_map = load_location_map(&state, &index_query).await;
+    let config = Config::from_env();
+    let (name, description) =
+        generate_calendar_metadata(&index_query, &location_map, &config.public_url);
+
+    let mut body = ical_header(
+        Calendar::new().name(&name).description(&description),
+        state.timezone,
+    );
+    for event in events.iter().filter(|e| {
+        !listing
+            .days(e.start_date, e.end_date, e.all_day, &e.event_types)
+            .is_empty()
+    }) {
+        body.push_str(&ical_event(event, state.timezone, &state.event_durations).to_string());
     }
+    body.push_str(ICAL_FOOTER);
+
+    HttpResponse::Ok()
+        .content_type("text/calendar")
+        .insert_header((
+            "Content-Disposition",
+            "attachment; filename=\"somerville-events.ics\"",
+        ))
+        .body(body)
 }
 
 pub async fn atom_feed(
+    req: HttpRequest,
     state: web::Data<AppState>,
     query: actix_web_lab::extract::Query<IndexQuery>,
 ) -> impl Responder {
     let index_query = query.into_inner();
     let now_utc = Utc::now();
-    let (is_past, _has_date_filter, since, until) = compute_time_range(now_utc, &index_query);
+    let (is_past, _has_date_filter, since, until) =
+        compute_time_range(now_utc, &index_query, state.timezone);
 
     let location_map = load_location_map(&state, &index_query).await;
 
@@ -556,6 +1563,13 @@ pub async fn atom_feed(
         .await
     {
         Ok(events) => {
+            // Feed readers poll on a timer; most polls find nothing new.
+            let validators =
+                PageValidators::new(events.iter().map(|e| e.updated_at).max(), events.len());
+            if validators.is_fresh(&req) {
+                return validators.not_modified();
+            }
+
             let config = Config::from_env();
             let base_url = config.public_url.trim_end_matches('/');
             let query_str = index_query.to_query_string();
@@ -580,13 +1594,21 @@ pub async fn atom_feed(
                     let link = format!("{}/event/{}", base_url, id);
                     let updated = event.updated_at.to_rfc3339();
                     let content = AtomEntryTemplate {
-                        event: EventViewModel::from_event(event, DateFormat::FullDate, is_past),
+                        event: EventViewModel::from_event(
+                            event,
+                            DateFormat::FullDate(Clock::TwelveHour),
+                            is_past,
+                            state.timezone,
+                            &state.event_durations,
+                        )
+                        .relay_contact(&state.contact_relay),
+                        lang: Lang::En,
                     }
                     .render()?;
 
                     Ok(AtomEntry {
                         id: link.clone(),
-                        title: event.name.clone(),
+                        title: title_with_status(event),
                         link,
                         updated,
                         content,
@@ -612,7 +1634,8 @@ pub async fn atom_feed(
                         entries,
                     };
 
-                    HttpResponse::Ok()
+                    validators
+                        .apply(HttpResponse::Ok())
                         .content_type("application/atom+xml; charset=utf-8")
                         .body(template.render().unwrap())
                 }
@@ -624,17 +1647,168 @@ pub async fn atom_feed(
         }
         Err(e) => {
             log::error!("Failed to fetch events for Atom feed: {e}");
-            HttpResponse::InternalServerError().body("Failed to fetch events")
+            database_error(&e, "Failed to fetch events")
+        }
+    }
+}
+
+pub async fn print(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let event = match state.events_repo.get(id).await {
+        Ok(Some(event)) => event,
+        Ok(None) => return not_found("We couldn't find that event. It may have been removed."),
+        Err(e) => {
+            log::error!("Failed to fetch event: {e}");
+            return database_error(&e, "Failed to fetch event");
+        }
+    };
+
+    let clock = Clock::from_request(&req);
+    let validators = PageValidators::new(Some(event.updated_at), 1)
+        .with_variant("print")
+        .with_variant(clock.value());
+    if validators.is_fresh(&req) {
+        return validators.not_modified();
+    }
+
+    let base_url = Config::from_env().public_url.trim_end_matches('/');
+    let template = PrintTemplate {
+        event: EventViewModel::from_event(
+            &event,
+            DateFormat::FullDate(clock),
+            false,
+            state.timezone,
+            &state.event_durations,
+        )
+        .relay_contact(&state.contact_relay),
+        page_url: format!("{base_url}/event/{id}"),
+    };
+    validators
+        .apply(HttpResponse::Ok())
+        .insert_header((header::VARY, "Cookie"))
+        .content_type(ContentType::html())
+        .body(template.render().unwrap())
+}
+
+/// The event's link preview image. It only depends on the event, so it's
+/// cached against `updated_at` both here and by whoever fetches it.
+pub async fn card(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let event = match state.events_repo.get(id).await {
+        Ok(Some(event)) => event,
+        Ok(None) => return not_found("We couldn't find that event. It may have been removed."),
+        Err(e) => {
+            log::error!("Failed to fetch event: {e}");
+            return database_error(&e, "Failed to fetch event");
         }
+    };
+
+    let validators = PageValidators::new(Some(event.updated_at), 1).with_variant("card");
+    if validators.is_fresh(&req) {
+        return validators.not_modified();
     }
+
+    let png = match event_card::cached_card(id, event.updated_at) {
+        Some(png) => png,
+        None => {
+            let view = EventViewModel::from_event(
+                &event,
+                DateFormat::FullDate(Clock::TwelveHour),
+                false,
+                state.timezone,
+                &state.event_durations,
+            );
+            let when = match &view.end_formatted {
+                Some(end) => format!("{} – {}", view.start_formatted, end),
+                None => view.start_formatted,
+            };
+            let venue = event
+                .location_name
+                .or(event.original_location)
+                .or(event.address);
+            let public_url = &Config::from_env().public_url;
+            let site = url::Url::parse(public_url)
+                .ok()
+                .and_then(|url| url.host_str().map(str::to_string))
+                .unwrap_or_else(|| public_url.clone());
+            let rendered = web::block(move || {
+                event_card::render_event_card(&event.name, &when, venue.as_deref(), &site)
+            })
+            .await;
+            match rendered {
+                Ok(Ok(png)) => {
+                    event_card::cache_card(id, event.updated_at, png.clone());
+                    png
+                }
+                Ok(Err(e)) => {
+                    log::error!("Failed to render card for event {id}: {e}");
+                    return HttpResponse::InternalServerError().finish();
+                }
+                Err(e) => {
+                    log::error!("Blocking task failed: {e}");
+                    return HttpResponse::InternalServerError().finish();
+                }
+            }
+        }
+    };
+
+    validators
+        .apply(HttpResponse::Ok())
+        .content_type(ContentType::png())
+        .body(png)
+}
+
+#[derive(Deserialize)]
+pub struct IcalQuery {
+    /// A reminder this long before the start, like `30m`, `2h` or `1d`.
+    pub alarm: Option<String>,
+}
+
+/// Reads a reminder offset like `30m`, `2h` or `1d`. Anything over a week
+/// is more likely a typo than a reminder anyone wants.
+fn parse_alarm(value: &str) -> Option<Duration> {
+    let unit = value.chars().last()?;
+    let amount: u16 = value[..value.len() - unit.len_utf8()].parse().ok()?;
+    let before = match unit {
+        'm' => Duration::minutes(amount.into()),
+        'h' => Duration::hours(amount.into()),
+        'd' => Duration::days(amount.into()),
+        _ => return None,
+    };
+    (before <= Duration::weeks(1)).then_some(before)
 }
 
-pub async fn ical(state: web::Data<AppState>, path: web::Path<i64>) -> impl Responder {
+pub async fn ical(
+    state: web::Data<AppState>,
+    path: web::Path<i64>,
+    query: web::Query<IcalQuery>,
+) -> impl Responder {
+    let alarm = match query.alarm.as_deref().map(parse_alarm) {
+        None => None,
+        Some(Some(before)) => Some(before),
+        Some(None) => {
+            return HttpResponse::BadRequest()
+                .body("alarm must be like 30m, 2h or 1d, and at most a week")
+        }
+    };
     let id = path.into_inner();
     match state.events_repo.get(id).await {
         Ok(Some(event)) => {
-            let ical_event = IcalEvent::from(&event);
-            let calendar = Calendar::new().push(ical_event).done();
+            let mut ical_event = ical_event(&event, state.timezone, &state.event_durations);
+            if let Some(before) = alarm {
+                ical_event.alarm(Alarm::display(&event.name, Trigger::before_start(before)));
+            }
+            let mut body = ical_header(&Calendar::new(), state.timezone);
+            body.push_str(&ical_event.to_string());
+            body.push_str(ICAL_FOOTER);
 
             HttpResponse::Ok()
                 .content_type("text/calendar")
@@ -642,65 +1816,239 @@ pub async fn ical(state: web::Data<AppState>, path: web::Path<i64>) -> impl Resp
                     "Content-Disposition",
                     format!("inline; filename=\"event-{}.ics\"", id),
                 ))
-                .body(calendar.to_string())
+                .body(body)
         }
-        Ok(None) => HttpResponse::NotFound().body("Event not found"),
+        Ok(None) => not_found("We couldn't find that event. It may have been removed."),
         Err(e) => {
             log::error!("Failed to fetch event: {e}");
-            HttpResponse::InternalServerError().body("Failed to fetch event")
+            database_error(&e, "Failed to fetch event")
         }
     }
 }
 
-impl From<&Event> for IcalEvent {
-    fn from(event: &Event) -> Self {
-        let mut ical_event = IcalEvent::new();
-        ical_event
-            .summary(&event.name)
-            .description(&event.full_text);
-
-        if let Some(url) = &event.url {
-            ical_event.url(url);
-        }
+/// The name, with "Cancelled: " or "Postponed: " in front when it's off,
+/// for feeds and calendars that only show a title.
+fn title_with_status(event: &Event) -> String {
+    match Msg::for_status(event.status) {
+        Some(badge) => format!("{}: {}", Lang::En.t(badge), event.name),
+        None => event.name.clone(),
+    }
+}
 
-        let location = if let (Some(name), Some(addr)) = (&event.location_name, &event.address) {
-            format!("{}, {}", name, addr)
-        } else {
-            event
-                .address
-                .clone()
-                .or(event.original_location.clone())
-                .unwrap_or_default()
-        };
+fn ical_event(event: &Event, tz: Tz, durations: &EventDurations) -> IcalEvent {
+    let mut ical_event = IcalEvent::new();
+    ical_event.description(&event.full_text);
 
-        if !location.is_empty() {
-            ical_event.location(&location);
+    // Few calendar apps show STATUS, so the title says it too.
+    ical_event.summary(&title_with_status(event));
+    match event.status {
+        EventStatus::Scheduled => {}
+        EventStatus::Cancelled => {
+            ical_event.status(IcalStatus::Cancelled);
         }
-
-        let start = event.start_date;
-        let start_et = start.with_timezone(&New_York);
-        ical_event.starts(CalendarDateTime::from_date_time(start_et));
-        if let Some(end) = event.end_date {
-            ical_event.ends(CalendarDateTime::from_date_time(
-                end.with_timezone(&New_York),
-            ));
-        } else {
-            ical_event.ends(CalendarDateTime::from_date_time(
-                start_et + chrono::Duration::hours(1),
-            ));
+        // iCalendar has no "postponed"; tentative is the nearest, since
+        // the date as given isn't happening.
+        EventStatus::Postponed => {
+            ical_event.status(IcalStatus::Tentative);
         }
+    }
 
-        // Use event ID for UID to ensure updates are tracked correctly
-        ical_event.uid(&format!("somerville-events-{}", event.id));
+    if let Some(url) = &event.url {
+        ical_event.url(url);
+    }
 
-        ical_event
+    let location = if let (Some(name), Some(addr)) = (&event.location_name, &event.address) {
+        format!("{}, {}", name, addr)
+    } else {
+        event
+            .address
+            .clone()
+            .or(event.original_location.clone())
+            .unwrap_or_default()
+    };
+
+    if !location.is_empty() {
+        ical_event.location(&location);
     }
+
+    let start = event.start_date;
+    let start_local = start.with_timezone(&tz);
+    if event.all_day {
+        // All-day events get a DATE-valued DTSTART so calendar apps put them
+        // in the all-day row instead of at midnight. DTEND is exclusive.
+        let (first_day, last_day) = all_day_span(start, event.end_date, tz);
+        ical_event.starts(first_day);
+        ical_event.ends(last_day + chrono::Duration::days(1));
+    } else {
+        ical_event.starts(CalendarDateTime::from_date_time(start_local));
+        let end = durations.end(start, event.end_date, &event.event_types);
+        ical_event.ends(CalendarDateTime::from_date_time(end.with_timezone(&tz)));
+    }
+
+    // Use event ID for UID to ensure updates are tracked correctly
+    ical_event.uid(&format!("somerville-events-{}", event.id));
+    // Without a METHOD, DTSTAMP is when the event last changed (RFC 5545
+    // 3.8.7.2). Left out, the crate stamps the time of the download, and
+    // every refresh looks like an edit.
+    ical_event.timestamp(event.updated_at);
+    ical_event.last_modified(event.updated_at);
+    // We don't count revisions, so seconds between creation and the last
+    // edit stand in: that only goes up, which is all calendars need to
+    // take the newer copy over the one they have.
+    let revision = (event.updated_at - event.created_at).num_seconds();
+    ical_event.sequence(u32::try_from(revision).unwrap_or(0));
+
+    ical_event
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_near_point_validation() {
+        let query = |lat, lng, radius_km| IndexQuery {
+            lat,
+            lng,
+            radius_km,
+            ..Default::default()
+        };
+
+        assert_eq!(query(None, None, None).near(), Ok(None));
+        assert_eq!(
+            query(Some(42.39), Some(-71.12), None).near(),
+            Ok(Some(NearPoint {
+                lat: 42.39,
+                lng: -71.12,
+                radius_km: DEFAULT_RADIUS_KM
+            }))
+        );
+        assert_eq!(
+            query(Some(42.39), Some(-71.12), Some(500.0))
+                .near()
+                .map(|p| p.map(|p| p.radius_km)),
+            Ok(Some(MAX_RADIUS_KM))
+        );
+        assert!(query(Some(42.39), None, None).near().is_err());
+        assert!(query(None, None, Some(1.0)).near().is_err());
+        assert!(query(Some(91.0), Some(-71.12), None).near().is_err());
+        assert!(query(Some(42.39), Some(-71.12), Some(0.0)).near().is_err());
+        assert!(query(Some(42.39), Some(-71.12), Some(f64::NAN))
+            .near()
+            .is_err());
+    }
+
+    #[test]
+    fn test_near_point_distance() {
+        let davis = NearPoint {
+            lat: 42.3967,
+            lng: -71.1226,
+            radius_km: 1.0,
+        };
+        // Davis to Porter Square is a bit under a kilometer.
+        let porter = davis.distance_km(42.3884, -71.1191);
+        assert!((0.9..1.0).contains(&porter), "got {porter}");
+        assert_eq!(davis.distance_km(davis.lat, davis.lng), 0.0);
+    }
+
+    #[test]
+    fn test_weekly_series() {
+        use chrono::TimeZone;
+        use chrono_tz::America::New_York;
+        let event = |id, name: &str, venue: &str, month, day, hour| SimpleEvent {
+            id,
+            updated_at: Utc::now(),
+            name: name.to_string(),
+            start_date: New_York
+                .with_ymd_and_hms(2025, month, day, hour, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc),
+            end_date: None,
+            all_day: false,
+            original_location: Some(venue.to_string()),
+            location_name: None,
+            lat: None,
+            lng: None,
+            event_types: vec![],
+            confidence: 1.0,
+            status: EventStatus::Scheduled,
+        };
+
+        let events = vec![
+            // Tuesdays across the change to daylight time on March 9th,
+            // with the venue spelled two ways.
+            event(1, "Trivia Night", "The Burren", 3, 4, 19),
+            event(2, "Trivia night", "Burren Pub", 3, 11, 19),
+            event(3, "Trivia Night", "The Burren", 3, 18, 19),
+            event(4, "Trivia Night", "The Burren", 3, 25, 19),
+            // Same name elsewhere.
+            event(5, "Trivia Night", "Aeronaut", 3, 6, 19),
+            event(6, "Trivia Night", "Aeronaut", 3, 13, 19),
+            event(7, "Trivia Night", "Aeronaut", 3, 20, 19),
+            // A week apart only twice before the time changes.
+            event(8, "Open Mic", "Davis Square", 3, 2, 20),
+            event(9, "Open Mic", "Davis Square", 3, 9, 20),
+            event(10, "Open Mic", "Davis Square", 3, 16, 21),
+            event(11, "Open Mic", "Davis Square", 3, 23, 21),
+            // Every other week.
+            event(12, "Book Club", "Library", 3, 1, 10),
+            event(13, "Book Club", "Library", 3, 15, 10),
+            event(14, "Book Club", "Library", 3, 29, 10),
+        ];
+
+        assert_eq!(
+            weekly_series(&events, New_York),
+            vec![vec![1, 2, 3, 4], vec![5, 6, 7]]
+        );
+    }
+
+    #[test]
+    fn test_weekend_window() {
+        use chrono::TimeZone;
+        use chrono_tz::America::New_York;
+        let local = |day, hour| {
+            New_York
+                .with_ymd_and_hms(2025, 1, day, hour, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+
+        // From a Wednesday, the coming Friday evening.
+        let window = Shortcut::ThisWeekend.window(local(15, 12), New_York);
+        assert_eq!(window.from, local(17, WEEKEND_STARTS_AT_HOUR));
+        assert_eq!(window.to, local(20, 0));
+
+        // Saturday afternoon: the same weekend, minus what's already over.
+        let window = Shortcut::ThisWeekend.window(local(18, 15), New_York);
+        assert_eq!(window.from, local(18, 15));
+        assert_eq!(window.to, local(20, 0));
+
+        // Sunday night still belongs to the weekend that's ending.
+        let window = Shortcut::ThisWeekend.window(local(19, 22), New_York);
+        assert_eq!(window.to, local(20, 0));
+    }
+
+    #[test]
+    fn test_to_webcal_url() {
+        assert_eq!(
+            to_webcal_url("https://example.com/events.ics?type=art"),
+            "webcal://example.com/events.ics?type=art"
+        );
+        assert_eq!(
+            to_webcal_url("http://localhost:8080/events.ics"),
+            "webcal://localhost:8080/events.ics"
+        );
+        assert_eq!(
+            to_webcal_url("example.com/events.ics"),
+            "webcal://example.com/events.ics"
+        );
+        // Only the scheme is rewritten, never a URL further along.
+        assert_eq!(
+            to_webcal_url("https://example.com/events.ics?q=https://x"),
+            "webcal://example.com/events.ics?q=https://x"
+        );
+    }
+
     #[test]
     fn test_generate_calendar_metadata_default() {
         let query = IndexQuery::default();
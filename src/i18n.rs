@@ -0,0 +1,387 @@
+use crate::models::EventStatus;
+use actix_web::http::header::{AcceptLanguage, Preference};
+use actix_web::{HttpMessage, HttpRequest};
+
+/// The languages the public pages are translated into. Somerville has
+/// large Portuguese- and Spanish-speaking communities. Readers pick with
+/// `?lang=pt` on any page, keep a choice in the `lang` cookie through
+/// `POST /lang`, or get their browser's preference. Feeds and admin pages
+/// have no one reader to ask, and stay in English.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    En,
+    Pt,
+    Es,
+}
+
+impl Lang {
+    pub const COOKIE: &'static str = "lang";
+
+    pub const ALL: [Self; 3] = [Self::En, Self::Pt, Self::Es];
+
+    /// A language tag such as `pt` or `pt-BR`. Regional variants share a
+    /// translation.
+    pub fn parse(value: &str) -> Option<Self> {
+        let primary = value.split(['-', '_']).next()?;
+        Self::ALL
+            .into_iter()
+            .find(|lang| lang.code().eq_ignore_ascii_case(primary))
+    }
+
+    /// The language's tag, as in the query param, cookie and `lang`
+    /// attribute.
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::En => "en",
+            Self::Pt => "pt",
+            Self::Es => "es",
+        }
+    }
+
+    /// What the language calls itself, for the switcher.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::En => "English",
+            Self::Pt => "Português",
+            Self::Es => "Español",
+        }
+    }
+
+    /// The `?lang=` param wins over the cookie, so a shared link shows what
+    /// its sender saw, and the cookie over `Accept-Language`, so a choice
+    /// made on the site sticks.
+    pub fn from_request(req: &HttpRequest) -> Self {
+        url::form_urlencoded::parse(req.query_string().as_bytes())
+            .find(|(key, _)| key == "lang")
+            .and_then(|(_, value)| Self::parse(&value))
+            .or_else(|| {
+                req.cookie(Self::COOKIE)
+                    .and_then(|c| Self::parse(c.value()))
+            })
+            .or_else(|| {
+                req.get_header::<AcceptLanguage>()?
+                    .ranked()
+                    .into_iter()
+                    .find_map(|preference| match preference {
+                        Preference::Specific(tag) => Self::parse(tag.primary_language()),
+                        Preference::Any => None,
+                    })
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn t(self, msg: Msg) -> &'static str {
+        msg.translations()[self as usize]
+    }
+}
+
+/// Every string the translated pages show, keyed by what it's for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    Search,
+    Shortcuts,
+    Today,
+    ThisWeekend,
+    Map,
+    UploadFlyer,
+    AboutThisSite,
+    MoreAboutThisSite,
+    About,
+    FilterEvents,
+    FreeOnly,
+    Tagged,
+    Day,
+    EventType,
+    Source,
+    Location,
+    Subscribe,
+    SubscribeHelp,
+    SubscribeInCalendar,
+    AddToGoogleCalendar,
+    CopyUrl,
+    PasteInto,
+    GoogleCalendarSettings,
+    OrYourCalendarApp,
+    PreferRss,
+    SubscribeToFeed,
+    ApplyFilters,
+    Clear,
+    ShowUpcoming,
+    Featured,
+    PreviousDay,
+    NextDay,
+    ViewPast,
+    AddTheseToCalendar,
+    Show12Hour,
+    Show24Hour,
+    Language,
+    Unknown,
+    Ages,
+    Price,
+    RegistrationRequired,
+    Contact,
+    Phone,
+    GoogleCalendar,
+    OtherCalendar,
+    OtherCalendarWithReminder,
+    AllDetails,
+    PrintVersion,
+    MoreAtVenue,
+    MoreLikeThis,
+    /// `{}` is the event type.
+    OtherTypeEvents,
+    ReportProblem,
+    WhatsWrong,
+    ReportEmail,
+    SendReport,
+    MessageOrganizer,
+    MessageOrganizerHelp,
+    YourMessage,
+    YourEmailToReply,
+    SendMessage,
+    ShowWeeklyOnce,
+    /// `{}` is the day of the week.
+    EveryWeekday,
+    Cancelled,
+    Postponed,
+}
+
+impl Msg {
+    /// English, Portuguese and Spanish, in `Lang` order.
+    fn translations(self) -> [&'static str; 3] {
+        match self {
+            Self::Search => ["Search", "Pesquisar", "Buscar"],
+            Self::Shortcuts => ["Shortcuts", "Atalhos", "Atajos"],
+            Self::Today => ["Today", "Hoje", "Hoy"],
+            Self::ThisWeekend => ["This weekend", "Este fim de semana", "Este fin de semana"],
+            Self::Map => ["Map", "Mapa", "Mapa"],
+            Self::UploadFlyer => [
+                "Upload an event flyer",
+                "Envie o cartaz de um evento",
+                "Sube el cartel de un evento",
+            ],
+            Self::AboutThisSite => ["About this site", "Sobre este site", "Acerca de este sitio"],
+            Self::MoreAboutThisSite => [
+                "More about this site",
+                "Mais sobre este site",
+                "Más sobre este sitio",
+            ],
+            Self::About => ["About", "Sobre", "Acerca de"],
+            Self::FilterEvents => ["Filter Events", "Filtrar eventos", "Filtrar eventos"],
+            Self::FreeOnly => [
+                "Free events only",
+                "Somente eventos gratuitos",
+                "Solo eventos gratuitos",
+            ],
+            Self::Tagged => ["Tagged", "Com a etiqueta", "Con la etiqueta"],
+            Self::Day => ["Day", "Dia", "Día"],
+            Self::EventType => ["Event Type", "Tipo de evento", "Tipo de evento"],
+            Self::Source => ["Source", "Fonte", "Fuente"],
+            Self::Location => ["Location", "Local", "Lugar"],
+            Self::Subscribe => ["Subscribe", "Assinar", "Suscribirse"],
+            Self::SubscribeHelp => [
+                "Get these events on your phone or computer. Updates automatically.",
+                "Receba estes eventos no seu celular ou computador. Atualiza automaticamente.",
+                "Recibe estos eventos en tu teléfono o computadora. Se actualiza automáticamente.",
+            ],
+            Self::SubscribeInCalendar => [
+                "Subscribe in your calendar",
+                "Assine no seu calendário",
+                "Suscríbete en tu calendario",
+            ],
+            Self::AddToGoogleCalendar => [
+                "Add to Google Calendar",
+                "Adicionar ao Google Agenda",
+                "Añadir a Google Calendar",
+            ],
+            Self::CopyUrl => [
+                "Or copy this URL:",
+                "Ou copie este endereço:",
+                "O copia esta dirección:",
+            ],
+            Self::PasteInto => ["Paste it into", "Cole-o nas", "Pégala en la"],
+            Self::GoogleCalendarSettings => [
+                "Google Calendar Settings",
+                "configurações do Google Agenda",
+                "configuración de Google Calendar",
+            ],
+            Self::OrYourCalendarApp => [
+                "or your calendar app.",
+                "ou no seu app de calendário.",
+                "o en tu aplicación de calendario.",
+            ],
+            Self::PreferRss => ["Prefer RSS?", "Prefere RSS?", "¿Prefieres RSS?"],
+            Self::SubscribeToFeed => [
+                "Subscribe to the feed",
+                "Assine o feed",
+                "Suscríbete al feed",
+            ],
+            Self::ApplyFilters => ["Apply Filters", "Aplicar filtros", "Aplicar filtros"],
+            Self::Clear => ["Clear", "Limpar", "Borrar"],
+            Self::ShowUpcoming => [
+                "Show upcoming events",
+                "Mostrar próximos eventos",
+                "Mostrar próximos eventos",
+            ],
+            Self::Featured => ["Featured", "Em destaque", "Destacados"],
+            Self::PreviousDay => ["Previous Day", "Dia anterior", "Día anterior"],
+            Self::NextDay => ["Next Day", "Próximo dia", "Día siguiente"],
+            Self::ViewPast => [
+                "View past events",
+                "Ver eventos passados",
+                "Ver eventos pasados",
+            ],
+            Self::AddTheseToCalendar => [
+                "Add these to your calendar",
+                "Adicione estes ao seu calendário",
+                "Añade estos a tu calendario",
+            ],
+            Self::Show12Hour => [
+                "Show 12-hour times",
+                "Mostrar horário de 12 horas",
+                "Mostrar horas en formato de 12 horas",
+            ],
+            Self::Show24Hour => [
+                "Show 24-hour times",
+                "Mostrar horário de 24 horas",
+                "Mostrar horas en formato de 24 horas",
+            ],
+            Self::Language => ["Language", "Idioma", "Idioma"],
+            Self::Unknown => ["Unknown", "Desconhecido", "Desconocido"],
+            Self::Ages => ["Ages:", "Idades:", "Edades:"],
+            Self::Price => ["Price:", "Preço:", "Precio:"],
+            Self::RegistrationRequired => [
+                "Registration required",
+                "Inscrição obrigatória",
+                "Inscripción obligatoria",
+            ],
+            Self::Contact => ["Contact:", "Contato:", "Contacto:"],
+            Self::Phone => ["Phone:", "Telefone:", "Teléfono:"],
+            Self::GoogleCalendar => ["Google Calendar", "Google Agenda", "Google Calendar"],
+            Self::OtherCalendar => ["Other Calendar", "Outro calendário", "Otro calendario"],
+            Self::OtherCalendarWithReminder => [
+                "Other Calendar, with a reminder",
+                "Outro calendário, com lembrete",
+                "Otro calendario, con recordatorio",
+            ],
+            Self::AllDetails => ["All details", "Todos os detalhes", "Todos los detalles"],
+            Self::PrintVersion => [
+                "Plain version for printing",
+                "Versão simples para imprimir",
+                "Versión sencilla para imprimir",
+            ],
+            Self::MoreAtVenue => [
+                "More at this venue",
+                "Mais neste local",
+                "Más en este lugar",
+            ],
+            Self::MoreLikeThis => [
+                "More events like this",
+                "Mais eventos como este",
+                "Más eventos como este",
+            ],
+            Self::OtherTypeEvents => [
+                "Other {} events",
+                "Outros eventos de {}",
+                "Otros eventos de {}",
+            ],
+            Self::ReportProblem => [
+                "Report a problem with this event",
+                "Informe um problema com este evento",
+                "Informa de un problema con este evento",
+            ],
+            Self::WhatsWrong => ["What's wrong?", "O que está errado?", "¿Qué está mal?"],
+            Self::ReportEmail => [
+                "Your email (optional, in case we have questions)",
+                "Seu e-mail (opcional, caso tenhamos dúvidas)",
+                "Tu correo electrónico (opcional, por si tenemos preguntas)",
+            ],
+            Self::SendReport => ["Send report", "Enviar relatório", "Enviar informe"],
+            Self::MessageOrganizer => [
+                "Message the organizer",
+                "Enviar mensagem ao organizador",
+                "Enviar un mensaje al organizador",
+            ],
+            Self::MessageOrganizerHelp => [
+                "We'll email your message to the organizer. They'll see your email address so they can reply, but you won't see theirs.",
+                "Enviaremos sua mensagem por e-mail ao organizador. Ele verá seu e-mail para poder responder, mas você não verá o dele.",
+                "Enviaremos tu mensaje por correo al organizador. Verá tu correo para poder responder, pero tú no verás el suyo.",
+            ],
+            Self::YourMessage => ["Your message", "Sua mensagem", "Tu mensaje"],
+            Self::YourEmailToReply => [
+                "Your email, for their reply",
+                "Seu e-mail, para a resposta",
+                "Tu correo electrónico, para la respuesta",
+            ],
+            Self::SendMessage => ["Send message", "Enviar mensagem", "Enviar mensaje"],
+            Self::ShowWeeklyOnce => [
+                "Show weekly events once",
+                "Mostrar eventos semanais uma vez",
+                "Mostrar eventos semanales una vez",
+            ],
+            // Day names aren't translated anywhere on the site yet, so these
+            // are worded to read with an English one.
+            Self::EveryWeekday => ["Every {}", "Toda semana: {}", "Cada semana: {}"],
+            Self::Cancelled => ["Cancelled", "Cancelado", "Cancelado"],
+            Self::Postponed => ["Postponed", "Adiado", "Aplazado"],
+        }
+    }
+
+    /// The badge for an event that's off, if it is.
+    pub fn for_status(status: EventStatus) -> Option<Self> {
+        match status {
+            EventStatus::Scheduled => None,
+            EventStatus::Cancelled => Some(Self::Cancelled),
+            EventStatus::Postponed => Some(Self::Postponed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::cookie::Cookie;
+    use actix_web::test::TestRequest;
+
+    #[test]
+    fn test_lang_from_request() {
+        let req = TestRequest::default().to_http_request();
+        assert_eq!(Lang::from_request(&req), Lang::En);
+
+        // The first supported language the browser asks for.
+        let req = TestRequest::default()
+            .insert_header(("Accept-Language", "fr;q=0.9, es-MX;q=0.8, en;q=0.5"))
+            .to_http_request();
+        assert_eq!(Lang::from_request(&req), Lang::Es);
+
+        let req = TestRequest::default()
+            .insert_header(("Accept-Language", "pt-BR"))
+            .cookie(Cookie::new(Lang::COOKIE, "es"))
+            .to_http_request();
+        assert_eq!(Lang::from_request(&req), Lang::Es);
+
+        let req = TestRequest::with_uri("/?lang=pt")
+            .cookie(Cookie::new(Lang::COOKIE, "es"))
+            .to_http_request();
+        assert_eq!(Lang::from_request(&req), Lang::Pt);
+
+        // Nothing we have: English.
+        let req = TestRequest::with_uri("/?lang=fr")
+            .insert_header(("Accept-Language", "fr, de"))
+            .to_http_request();
+        assert_eq!(Lang::from_request(&req), Lang::En);
+    }
+
+    #[test]
+    fn test_translations() {
+        assert_eq!(Lang::En.t(Msg::Today), "Today");
+        assert_eq!(Lang::Pt.t(Msg::Today), "Hoje");
+        assert_eq!(Lang::Es.t(Msg::Today), "Hoy");
+        for lang in Lang::ALL {
+            assert_eq!(Lang::parse(lang.code()), Some(lang));
+            assert!(lang.t(Msg::OtherTypeEvents).contains("{}"));
+            assert!(lang.t(Msg::EveryWeekday).contains("{}"));
+        }
+    }
+}
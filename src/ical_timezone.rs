@@ -0,0 +1,307 @@
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Offset, TimeZone, Utc, Weekday,
+};
+use chrono_tz::{OffsetComponents, OffsetName, Tz};
+use std::fmt::Write;
+
+/// How many years ahead a recurring rule has to hold before we trust it.
+/// Zones whose changes don't follow one get their changes listed instead.
+const RULE_CHECK_YEARS: i32 = 10;
+
+/// Years either side of now that listed changes cover.
+const LISTED_YEARS: i32 = 10;
+
+/// The earliest year a rule is traced back to. Offsets before then aren't
+/// worth describing for an events calendar.
+const EARLIEST_YEAR: i32 = 1970;
+
+/// A change of offset, such as the start or end of daylight saving time.
+struct Transition {
+    at: DateTime<Utc>,
+    from: i32,
+    to: i32,
+}
+
+impl Transition {
+    /// The wall time it happens at, on the clock in use before it.
+    fn onset(&self) -> NaiveDateTime {
+        self.at.naive_utc() + Duration::seconds(i64::from(self.from))
+    }
+}
+
+/// Which weekday of the month a yearly change falls on, e.g. the second
+/// Sunday in March, or the last one.
+#[derive(Clone, Copy)]
+struct Rule {
+    month: u32,
+    weekday: Weekday,
+    /// 1 to 5 counts from the start of the month; -1 is the last.
+    nth: i8,
+}
+
+impl Rule {
+    fn date(self, year: i32) -> Option<NaiveDate> {
+        if self.nth > 0 {
+            NaiveDate::from_weekday_of_month_opt(year, self.month, self.weekday, self.nth as u8)
+        } else {
+            let first_of_next = if self.month == 12 {
+                NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+            } else {
+                NaiveDate::from_ymd_opt(year, self.month + 1, 1)?
+            };
+            let last = first_of_next.pred_opt()?;
+            let back = (7 + last.weekday().num_days_from_monday()
+                - self.weekday.num_days_from_monday())
+                % 7;
+            Some(last - Duration::days(i64::from(back)))
+        }
+    }
+
+    fn byday(self) -> String {
+        let day = match self.weekday {
+            Weekday::Mon => "MO",
+            Weekday::Tue => "TU",
+            Weekday::Wed => "WE",
+            Weekday::Thu => "TH",
+            Weekday::Fri => "FR",
+            Weekday::Sat => "SA",
+            Weekday::Sun => "SU",
+        };
+        format!("{}{day}", self.nth)
+    }
+}
+
+/// The `VTIMEZONE` that `TZID={tz}` times refer to. RFC 5545 requires one
+/// in the calendar, and without it some apps read the times as the
+/// reader's own wall time, moving events for anyone outside the zone.
+/// `now` picks the rules in force.
+pub fn vtimezone(tz: Tz, now: DateTime<Utc>) -> String {
+    let year = now.year();
+    let mut out = format!("BEGIN:VTIMEZONE\r\nTZID:{}\r\n", tz.name());
+
+    let this_year = transitions_in_year(tz, year);
+    if this_year.is_empty() {
+        let offset = offset_at(tz, now);
+        let start = NaiveDate::from_ymd_opt(EARLIEST_YEAR, 1, 1)
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .expect("valid date");
+        write_observance(&mut out, tz, now, start, offset, offset, None);
+    } else {
+        let rules: Option<Vec<_>> = this_year
+            .iter()
+            .map(|t| rule_for(tz, t).map(|rule| (t, rule)))
+            .collect();
+        match rules {
+            Some(rules) => {
+                for (transition, rule) in rules {
+                    let first_year = (EARLIEST_YEAR..year)
+                        .rev()
+                        .take_while(|&y| rule_holds(tz, rule, transition, y))
+                        .last()
+                        .unwrap_or(year);
+                    let onset = rule
+                        .date(first_year)
+                        .expect("the rule held that year")
+                        .and_time(transition.onset().time());
+                    write_observance(
+                        &mut out,
+                        tz,
+                        transition.at,
+                        onset,
+                        transition.from,
+                        transition.to,
+                        Some(rule),
+                    );
+                }
+            }
+            None => {
+                for y in year - LISTED_YEARS..=year + LISTED_YEARS {
+                    for t in transitions_in_year(tz, y) {
+                        write_observance(&mut out, tz, t.at, t.onset(), t.from, t.to, None);
+                    }
+                }
+            }
+        }
+    }
+
+    out.push_str("END:VTIMEZONE\r\n");
+    out
+}
+
+/// Writes a `STANDARD` or `DAYLIGHT` block for the offset in force from
+/// `onset`, named after how it's known at `at`.
+fn write_observance(
+    out: &mut String,
+    tz: Tz,
+    at: DateTime<Utc>,
+    onset: NaiveDateTime,
+    from: i32,
+    to: i32,
+    rule: Option<Rule>,
+) {
+    let offset = tz.offset_from_utc_datetime(&at.naive_utc());
+    let kind = if offset.dst_offset().is_zero() {
+        "STANDARD"
+    } else {
+        "DAYLIGHT"
+    };
+    // Writing to a String can't fail.
+    let _ = write!(
+        out,
+        "BEGIN:{kind}\r\nDTSTART:{}\r\nTZOFFSETFROM:{}\r\nTZOFFSETTO:{}\r\n",
+        onset.format("%Y%m%dT%H%M%S"),
+        format_offset(from),
+        format_offset(to)
+    );
+    if let Some(name) = offset.abbreviation() {
+        let _ = write!(out, "TZNAME:{name}\r\n");
+    }
+    if let Some(rule) = rule {
+        let _ = write!(
+            out,
+            "RRULE:FREQ=YEARLY;BYMONTH={};BYDAY={}\r\n",
+            rule.month,
+            rule.byday()
+        );
+    }
+    let _ = write!(out, "END:{kind}\r\n");
+}
+
+/// A yearly rule that puts this change on the right day for years to
+/// come, if there is one. A date late in the month could be the fourth
+/// Sunday or the last, so both are tried.
+fn rule_for(tz: Tz, transition: &Transition) -> Option<Rule> {
+    let onset = transition.onset();
+    let nth = ((onset.day() - 1) / 7 + 1) as i8;
+    [nth, -1]
+        .into_iter()
+        .map(|nth| Rule {
+            month: onset.month(),
+            weekday: onset.weekday(),
+            nth,
+        })
+        .find(|&rule| {
+            (onset.year()..=onset.year() + RULE_CHECK_YEARS)
+                .all(|y| rule_holds(tz, rule, transition, y))
+        })
+}
+
+/// Whether the zone changed offset the same way, at the same wall time, on
+/// the day the rule gives for `year`.
+fn rule_holds(tz: Tz, rule: Rule, transition: &Transition, year: i32) -> bool {
+    let Some(date) = rule.date(year) else {
+        return false;
+    };
+    let at =
+        date.and_time(transition.onset().time()) - Duration::seconds(i64::from(transition.from));
+    let at = Utc.from_utc_datetime(&at);
+    offset_at(tz, at - Duration::minutes(1)) == transition.from
+        && offset_at(tz, at) == transition.to
+}
+
+/// Every change of offset in the (UTC) year, found a day at a time and
+/// then narrowed down to the minute.
+fn transitions_in_year(tz: Tz, year: i32) -> Vec<Transition> {
+    let Some(start) = Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).single() else {
+        return Vec::new();
+    };
+    let mut transitions = Vec::new();
+    let mut before = start;
+    let mut offset = offset_at(tz, start);
+    while before.year() == year {
+        let after = before + Duration::days(1);
+        let next_offset = offset_at(tz, after);
+        if next_offset != offset {
+            let (mut lo, mut hi) = (before, after);
+            while hi - lo > Duration::minutes(1) {
+                let mid = lo + Duration::minutes((hi - lo).num_minutes() / 2);
+                if offset_at(tz, mid) == offset {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            transitions.push(Transition {
+                at: hi,
+                from: offset,
+                to: next_offset,
+            });
+        }
+        before = after;
+        offset = next_offset;
+    }
+    transitions
+}
+
+/// Seconds east of UTC.
+fn offset_at(tz: Tz, at: DateTime<Utc>) -> i32 {
+    tz.offset_from_utc_datetime(&at.naive_utc())
+        .fix()
+        .local_minus_utc()
+}
+
+/// `-0500`, or `+053328` for the odd offset with seconds.
+fn format_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+    let (hours, minutes, secs) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if secs == 0 {
+        format!("{sign}{hours:02}{minutes:02}")
+    } else {
+        format!("{sign}{hours:02}{minutes:02}{secs:02}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 6, 1, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_new_york() {
+        assert_eq!(
+            vtimezone(chrono_tz::America::New_York, now()),
+            "BEGIN:VTIMEZONE\r\n\
+             TZID:America/New_York\r\n\
+             BEGIN:DAYLIGHT\r\n\
+             DTSTART:20070311T020000\r\n\
+             TZOFFSETFROM:-0500\r\n\
+             TZOFFSETTO:-0400\r\n\
+             TZNAME:EDT\r\n\
+             RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=2SU\r\n\
+             END:DAYLIGHT\r\n\
+             BEGIN:STANDARD\r\n\
+             DTSTART:20071104T020000\r\n\
+             TZOFFSETFROM:-0400\r\n\
+             TZOFFSETTO:-0500\r\n\
+             TZNAME:EST\r\n\
+             RRULE:FREQ=YEARLY;BYMONTH=11;BYDAY=1SU\r\n\
+             END:STANDARD\r\n\
+             END:VTIMEZONE\r\n"
+        );
+    }
+
+    #[test]
+    fn test_last_sunday_rules_and_zones_without_dst() {
+        let london = vtimezone(chrono_tz::Europe::London, now());
+        assert!(london.contains("RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU\r\n"));
+        assert!(london.contains("RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU\r\n"));
+        assert!(london.contains("TZNAME:BST\r\n"));
+
+        assert_eq!(
+            vtimezone(chrono_tz::Asia::Kolkata, now()),
+            "BEGIN:VTIMEZONE\r\n\
+             TZID:Asia/Kolkata\r\n\
+             BEGIN:STANDARD\r\n\
+             DTSTART:19700101T000000\r\n\
+             TZOFFSETFROM:+0530\r\n\
+             TZOFFSETTO:+0530\r\n\
+             TZNAME:IST\r\n\
+             END:STANDARD\r\n\
+             END:VTIMEZONE\r\n"
+        );
+    }
+}
@@ -1,25 +1,36 @@
-use crate::models::{EventSource, EventType, NewEvent};
+use crate::models::{normalize_tags, EventSource, EventStatus, EventType, NewEvent};
 use actix_web::web;
 use anyhow::{anyhow, Result};
 use awc::Client;
 use base64::{engine::general_purpose::STANDARD as b64, Engine as _};
-use chrono::{DateTime, LocalResult, NaiveDateTime, TimeZone, Utc};
-use chrono_tz::America::New_York;
+use chrono::{DateTime, LocalResult, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc};
+use chrono_tz::Tz;
 use futures_util::future;
-use image::{DynamicImage, ImageFormat, ImageReader};
+use image::codecs::{gif::GifDecoder, png::PngDecoder, webp::WebPDecoder};
+use image::{AnimationDecoder, DynamicImage, ImageFormat, ImageReader};
 use rxing::{
     common::HybridBinarizer, qrcode::QRCodeReader, BinaryBitmap, BufferedImageLuminanceSource,
     DecodeHintValue, DecodeHints, ImmutableReader,
 };
-use schemars::schema_for;
+use schemars::{gen::SchemaSettings, schema_for};
 use serde_json::json;
 use std::{
     io::Cursor,
     path::Path,
     sync::{Arc, LazyLock},
+    time::Duration,
 };
+use strsim::jaro_winkler;
 use url::Url;
 
+/// The image formats a flyer can be read from.
+pub const FLYER_FORMATS: &[ImageFormat] = &[
+    ImageFormat::Jpeg,
+    ImageFormat::Png,
+    ImageFormat::Gif,
+    ImageFormat::WebP,
+];
+
 static QR_READER: LazyLock<QRCodeReader> = LazyLock::new(QRCodeReader::default);
 
 static SCHEMA_STR: LazyLock<String> = LazyLock::new(|| {
@@ -27,6 +38,51 @@ static SCHEMA_STR: LazyLock<String> = LazyLock::new(|| {
     serde_json::to_string_pretty(&schema).unwrap()
 });
 
+/// The extraction schema in the subset structured outputs accepts, for
+/// OpenAI to hold the reply to.
+static STRICT_SCHEMA: LazyLock<serde_json::Value> = LazyLock::new(|| {
+    // Inlined, so there are no `definitions` refs for OpenAI to resolve.
+    let schema = SchemaSettings::draft07()
+        .with(|settings| settings.inline_subschemas = true)
+        .into_generator()
+        .into_root_schema_for::<ImageEventExtraction>();
+    let mut schema = serde_json::to_value(schema).unwrap();
+    make_strict(&mut schema);
+    schema
+});
+
+/// Rewrites a schemars schema into what strict structured outputs allows:
+/// every property required (optional ones are already nullable) and no
+/// others, and no `format` hints, which it rejects for `double` and
+/// `partial-date-time`.
+fn make_strict(schema: &mut serde_json::Value) {
+    let Some(object) = schema.as_object_mut() else {
+        return;
+    };
+    object.remove("$schema");
+    object.remove("definitions");
+    object.remove("format");
+    if let Some(properties) = object.get("properties").and_then(|p| p.as_object()) {
+        let required: Vec<_> = properties.keys().cloned().collect();
+        object.insert("required".to_string(), json!(required));
+        object.insert("additionalProperties".to_string(), json!(false));
+    }
+    for value in object.values_mut() {
+        match value {
+            serde_json::Value::Object(map) => {
+                if map.contains_key("type") {
+                    make_strict(value);
+                } else {
+                    // `properties`: names mapped to schemas.
+                    map.values_mut().for_each(make_strict);
+                }
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(make_strict),
+            _ => {}
+        }
+    }
+}
+
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
 pub struct SingleEventExtraction {
     pub name: Option<String>,
@@ -38,9 +94,13 @@ pub struct SingleEventExtraction {
     pub location: Option<String>,
     /// "YardSale" | "Art" | "Music" | "Dance" | "Performance" | "Food" | "PersonalService" | "Meeting" | "Government" | "Volunteer" | "Fundraiser" | "Film" | "Theater" | "Comedy" | "Literature" | "Exhibition" | "Workshop" | "Fitness" | "Market" | "Sports" | "Social" | "Trivia" | "BoardGames" | "Bikes" | "Holiday" | "Religious" | "ChildFriendly" | "Other"
     pub event_types: Option<Vec<String>>,
+    /// Short lowercase keywords, e.g. "vegan", "queer", "pop-up"
+    pub tags: Option<Vec<String>>,
     pub url: Option<String>,
     pub age_restrictions: Option<String>,
     pub price: Option<f64>,
+    /// True only if the image says the event has already happened
+    pub already_happened: Option<bool>,
     /// Confidence level of the extraction (0.0 to 1.0)
     pub confidence: f64,
 }
@@ -52,12 +112,66 @@ pub struct ImageEventExtraction {
     pub events: Vec<SingleEventExtraction>,
 }
 
+/// Confidence given to an extracted event that seems to be over already,
+/// so it heads the admin's lowest-confidence-first list for a second look.
+pub const PAST_EVENT_CONFIDENCE: f64 = 0.1;
+
+/// Flyer text shorter than this can't hold a name and a date, so whatever
+/// the model found in it is a guess: a selfie, a logo, a blurry photo.
+const MIN_FULL_TEXT_CHARS: usize = 10;
+
+/// Names the model puts in when it couldn't read one, compared after
+/// `normalize_name`.
+const PLACEHOLDER_NAMES: &[&str] = &[
+    "event",
+    "event name",
+    "event title",
+    "untitled",
+    "untitled event",
+    "unknown",
+    "unknown event",
+    "name",
+    "title",
+    "tbd",
+    "tba",
+    "n a",
+    "none",
+    "null",
+    "sample event",
+];
+
+/// How alike two names from the same flyer, at the same start, must be to
+/// count as the model listing one event twice. As strict as
+/// `database::is_duplicate`, so "Workshop A" and "Workshop B" stay apart.
+const SAME_FLYER_NAME_SIMILARITY: f64 = 0.985;
+
+/// How a flyer's extraction is asked for and checked.
+#[derive(Debug, Clone, Copy)]
+pub struct ExtractionOptions {
+    /// See `Config::openai_structured_outputs`.
+    pub structured_outputs: bool,
+    /// See `Config::past_event_window`.
+    pub past_event_window: TimeDelta,
+}
+
 pub async fn parse_image(
     image_path: &Path,
     client: &Client,
     api_key: &str,
+    tz: Tz,
+    timeout: Duration,
+    options: ExtractionOptions,
 ) -> Result<Vec<NewEvent>> {
-    parse_image_with_now(image_path, Utc::now(), client, api_key).await
+    parse_image_with_now(
+        image_path,
+        Utc::now(),
+        client,
+        api_key,
+        tz,
+        timeout,
+        options,
+    )
+    .await
 }
 
 async fn parse_image_with_now(
@@ -65,6 +179,9 @@ async fn parse_image_with_now(
     now: DateTime<Utc>,
     client: &Client,
     api_key: &str,
+    tz: Tz,
+    timeout: Duration,
+    options: ExtractionOptions,
 ) -> Result<Vec<NewEvent>> {
     let path = image_path.to_path_buf();
 
@@ -83,83 +200,353 @@ async fn parse_image_with_now(
         .format()
         .ok_or_else(|| anyhow!("Unknown image format"))?;
 
-    match format {
-        ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::Gif | ImageFormat::WebP => {}
-        _ => return Err(anyhow!("Image format must be jpg, png, gif, or webp")),
-    };
+    if !FLYER_FORMATS.contains(&format) {
+        return Err(anyhow!("Image format must be jpg, png, gif, or webp"));
+    }
+
+    // An animation's first frame is often blank or mid-transition, so the
+    // LLM gets whichever frame shows the most and the QR search covers all
+    // of them.
+    let bytes_for_frames = bytes.clone();
+    let (frames, representative_png) = web::block(move || {
+        let frames = decode_frames(&bytes_for_frames, format)?;
+        let representative_png = if frames.len() > 1 {
+            let index = representative_frame(&frames);
+            log::info!(
+                "Using frame {} of {} from an animated image",
+                index + 1,
+                frames.len()
+            );
+            let mut png = Vec::new();
+            frames[index].write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+            Some(png)
+        } else {
+            None
+        };
+        Ok::<_, anyhow::Error>((frames, representative_png))
+    })
+    .await
+    .map_err(|e| anyhow!("Blocking task failed: {}", e))??;
 
     // Concurrently process image with
     //   A) QR Code extraction (CPU intensive)
     //   B) LLM (Network intensive)
 
     // Task A: QR Code Extraction (CPU intensive)
-    let bytes_for_qr = bytes.clone();
-    let qr_future = web::block(move || {
-        let reader =
-            ImageReader::new(Cursor::new(bytes_for_qr.as_slice())).with_guessed_format()?;
-        let img = reader.decode()?;
-        Ok::<Option<Url>, anyhow::Error>(extract_qr_url(img))
-    });
+    let qr_future = web::block(move || frames.into_iter().find_map(extract_qr_url));
 
     // Task B: LLM Extraction (Network intensive)
-    let now_str = now.to_rfc3339();
-    let mime_type = format.to_mime_type();
-    let b64_data = b64.encode(bytes.as_slice());
+    let (mime_type, b64_data) = match &representative_png {
+        Some(png) => (ImageFormat::Png.to_mime_type(), b64.encode(png)),
+        None => (format.to_mime_type(), b64.encode(bytes.as_slice())),
+    };
     let data_url = format!("data:{mime_type};base64,{b64_data}");
-    let payload = json!({
+    let payload = extraction_payload(
+        ExtractionInput::Image {
+            data_url: &data_url,
+            ocr_text: None,
+        },
+        now,
+        tz,
+        options.structured_outputs,
+    );
+    let llm_future = request_extraction(client, OPENAI_CHAT_URL, api_key, payload, timeout);
+
+    // Save some time by doing QR Parsing and making
+    // a network request to the LLM at the same time
+    let (qr_result, llm_result) = future::join(qr_future, llm_future).await;
+    let content = llm_result?;
+
+    log::debug!("Extracted content: {}", content);
+
+    let mut events = parse_and_validate_response(&content, tz, now - options.past_event_window)?;
+
+    // Hand-lettered and heavily stylized posters can defeat the vision
+    // model yet still be legible to plain OCR, whose text gives the model
+    // something to check its reading against.
+    #[cfg(feature = "ocr")]
+    if best_confidence(&events) < LOW_CONFIDENCE {
+        let image_bytes = representative_png.clone().unwrap_or_else(|| bytes.to_vec());
+        match ocr_text(image_bytes).await {
+            Ok(text) if text.trim().chars().count() >= MIN_FULL_TEXT_CHARS => {
+                let payload = extraction_payload(
+                    ExtractionInput::Image {
+                        data_url: &data_url,
+                        ocr_text: Some(&text),
+                    },
+                    now,
+                    tz,
+                    options.structured_outputs,
+                );
+                let content =
+                    request_extraction(client, OPENAI_CHAT_URL, api_key, payload, timeout).await?;
+                let retried =
+                    parse_and_validate_response(&content, tz, now - options.past_event_window)?;
+                log::info!(
+                    "Asked again with OCR text: best confidence {:.2} before, {:.2} after",
+                    best_confidence(&events),
+                    best_confidence(&retried)
+                );
+                if best_confidence(&retried) > best_confidence(&events) {
+                    events = retried;
+                }
+            }
+            Ok(_) => log::info!("OCR found no text on the flyer either"),
+            Err(e) => log::warn!("OCR failed: {e:#}"),
+        }
+    }
+
+    let qr_url = qr_result.map_err(|e| anyhow!("QR task failed: {}", e))?;
+
+    if let Some(qr_url) = qr_url {
+        log::info!("QR code URL detected: {qr_url}");
+        for event in &mut events {
+            event.url = Some(qr_url.to_string());
+        }
+    }
+
+    Ok(events)
+}
+
+/// Below this, every event in an extraction looking shaky, the flyer was
+/// probably misread. Events already over are flagged far lower, so they
+/// count too, which costs an OCR pass but does no harm.
+#[cfg(feature = "ocr")]
+const LOW_CONFIDENCE: f64 = 0.5;
+
+/// The most confident event's confidence, or 0 with none.
+#[cfg(feature = "ocr")]
+fn best_confidence(events: &[NewEvent]) -> f64 {
+    events
+        .iter()
+        .map(|event| event.confidence)
+        .fold(0.0, f64::max)
+}
+
+/// Reads a flyer's text with Tesseract, for when the vision model
+/// struggled with it.
+#[cfg(feature = "ocr")]
+async fn ocr_text(image_bytes: Vec<u8>) -> Result<String> {
+    web::block(move || {
+        let image = image::load_from_memory(&image_bytes)?.to_rgb8();
+        let (width, height) = image.dimensions();
+        let text = tesseract::ocr_from_frame(
+            image.as_raw(),
+            width as i32,
+            height as i32,
+            3,
+            width as i32 * 3,
+            "eng",
+        )?;
+        Ok(text)
+    })
+    .await
+    .map_err(|e| anyhow!("Blocking task failed: {}", e))?
+}
+
+/// Reads the events off an event's web page instead of a flyer, given the
+/// page's text from `web_page::page_text`. The events come back as
+/// `EventSource::UserSubmitted`, since no image was uploaded.
+pub async fn parse_page_text(
+    page_text: &str,
+    client: &Client,
+    api_key: &str,
+    tz: Tz,
+    timeout: Duration,
+    options: ExtractionOptions,
+) -> Result<Vec<NewEvent>> {
+    let now = Utc::now();
+    let payload = extraction_payload(
+        ExtractionInput::Page(page_text),
+        now,
+        tz,
+        options.structured_outputs,
+    );
+    let content = request_extraction(client, OPENAI_CHAT_URL, api_key, payload, timeout).await?;
+
+    log::debug!("Extracted content: {}", content);
+
+    let mut events = parse_and_validate_response(&content, tz, now - options.past_event_window)?;
+    for event in &mut events {
+        event.source = EventSource::UserSubmitted;
+    }
+    Ok(events)
+}
+
+/// What the events are read from.
+#[derive(Debug, Clone, Copy)]
+enum ExtractionInput<'a> {
+    Image {
+        /// The flyer, as a `data:` URL.
+        data_url: &'a str,
+        /// What OCR read on it, if it was needed.
+        ocr_text: Option<&'a str>,
+    },
+    /// A web page's text.
+    Page(&'a str),
+}
+
+/// The chat completion request for reading a flyer. With structured
+/// outputs OpenAI holds the reply to the schema itself; older models only
+/// know JSON mode, so they get the schema spelled out in the prompt and
+/// the drift that comes with it.
+fn extraction_payload(
+    input: ExtractionInput,
+    now: DateTime<Utc>,
+    tz: Tz,
+    structured_outputs: bool,
+) -> serde_json::Value {
+    let now_str = now.to_rfc3339();
+    let tz_name = tz.name();
+    let (response_format, schema_instructions) = if structured_outputs {
+        let response_format = json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": "image_event_extraction",
+                "strict": true,
+                "schema": *STRICT_SCHEMA
+            }
+        });
+        (response_format, String::new())
+    } else {
+        let schema_instructions = format!(
+            "You must respond with a JSON object that matches this exact schema:\n{}\n\
+             Do not return the schema in your response.\n",
+            *SCHEMA_STR
+        );
+        (json!({ "type": "json_object" }), schema_instructions)
+    };
+    // A page has menus and footers around the event, and the model has no
+    // QR codes to be tempted by.
+    let (noun, full_text_instruction, url_instruction, user_content) = match input {
+        ExtractionInput::Image { data_url, ocr_text } => {
+            let mut content = vec![
+                json!({ "type": "text", "text": "Extract all text and events from this image and return it in the specified JSON format." }),
+                json!({ "type": "image_url", "image_url": { "url": data_url } }),
+            ];
+            if let Some(ocr_text) = ocr_text {
+                content.push(json!({
+                    "type": "text",
+                    "text": format!("OCR read this text on the image. It may have mistakes, so trust the image where they disagree:\n\n{ocr_text}")
+                }));
+            }
+            (
+                "image",
+                "The full_text field should contain all readable text from the image.",
+                "Do not attempt to decode QR codes. Only extract URLs that are visible as text.",
+                json!(content),
+            )
+        }
+        ExtractionInput::Page(text) => (
+            "web page",
+            "The full_text field should contain the page's text about the events, leaving out menus, footers and other site boilerplate.",
+            "The page's own address is already known; only include a URL if the page gives a different one for the event.",
+            json!(format!(
+                "Extract all events from this web page and return them in the specified JSON format.\n\n{text}"
+            )),
+        ),
+    };
+    json!({
         "model": "gpt-4o-mini",
         "temperature": 0,
-        "response_format": { "type": "json_object" },
+        "max_tokens": MAX_TOKENS,
+        "response_format": response_format,
         "messages": [
             {
                 "role": "system",
                 "content": format!(
-                    r#"You are an expert at extracting event information from images.
-                        You must respond with a JSON object that matches this exact schema:
-                        {schema_str}
-                        
+                    r#"You are an expert at extracting event information from {noun}s.
+                        {schema_instructions}
                         Instructions:
-                        - Extract all distinct events found in the image.
+                        - Extract all distinct events found in the {noun}.
                         - If a poster lists multiple dates for the same event (e.g. a series), treat each date as a separate event in the `events` list.
                         - If you are uncertain about any fields, set them to null.
-                        - The full_text field should contain all readable text from the image.
+                        - {full_text_instruction}
                         - The description field should be the description of the event.
                         - The confidence should be a number between 0.0 and 1.0 indicating how confident you are in the extraction.
                         - Focus on extracting event-related information like the name, date, time, location, url, description, age restrictions, and price.
                         - Try to always extract at least one event type in event_types.
+                        - Use tags for a few short keywords that capture what's specific about the event and that event_types can't express (e.g. "vegan", "queer", "pop-up", "jazz"). Don't repeat the event type as a tag.
                         - Today's date is {now_str}.
                         - The start_date and end_date must be formatted as ISO 8601 strings without timezone offset (e.g., "YYYY-MM-DDTHH:MM:SS").
-                        - All events are in the Somerville/Cambridge/Boston area (America/New_York timezone).
-                        - Assume the event is in the future unless the text clearly indicates it is in the past.
+                        - All events are in the Somerville/Cambridge/Boston area ({tz_name} timezone).
+                        - Assume the event is in the future unless the text clearly indicates it is in the past. If it does, set already_happened to true.
                         - If the date is ambiguous (e.g. "Friday"), assume it is the next occurrence after today's date ({now_str}).
                         - DO NOT default the date to {now_str} if no date is found; return null instead.
-                        - Do not make up a URL. Only include a URL if it is explicitly written in the image.
-                        - Do not attempt to decode QR codes. Only extract URLs that are visible as text.
+                        - Do not make up a URL. Only include a URL if it is explicitly written in the {noun}.
+                        - {url_instruction}
                         - Be thorough but accurate. Return only valid JSON.
-                        - Do not return the schema in your response.
                         "#
-                , schema_str = *SCHEMA_STR)
+                )
             },
             {
                 "role": "user",
-                "content": [
-                    { "type": "text", "text": "Extract all text and events from this image and return it in the specified JSON format." },
-                    { "type": "image_url", "image_url": { "url": data_url } }
-                ]
+                "content": user_content
             }
         ]
-    });
-    let llm_future = client
-        .post("https://api.openai.com/v1/chat/completions")
-        .insert_header(("Authorization", format!("Bearer {api_key}")))
-        .insert_header(("Content-Type", "application/json"))
-        .send_json(&payload);
+    })
+}
 
-    // Save some time by doing QR Parsing and making
-    // a network request to the LLM at the same time
-    let (qr_result, llm_result) = future::join(qr_future, llm_future).await;
+const OPENAI_CHAT_URL: &str = "https://api.openai.com/v1/chat/completions";
+
+/// Room for a flyer's full text and a dozen or so events.
+const MAX_TOKENS: u32 = 4096;
+/// For the one retry after a reply was cut off. gpt-4o-mini can't write
+/// more than this in one go anyway.
+const RETRY_MAX_TOKENS: u32 = 16384;
+
+/// A chat completion's reply text.
+#[derive(Debug)]
+struct Completion {
+    content: String,
+    /// The model hit `max_tokens` before it finished, so the JSON stops
+    /// partway through.
+    truncated: bool,
+}
+
+/// Asks for the extraction, and asks once more with a bigger token limit if
+/// the reply was cut off. Flyers with lots of dates (a season's schedule)
+/// are the usual culprits. A reply that's still cut off is returned as is,
+/// for `parse_and_validate_response` to salvage what it can.
+async fn request_extraction(
+    client: &Client,
+    url: &str,
+    api_key: &str,
+    mut payload: serde_json::Value,
+    timeout: Duration,
+) -> Result<String> {
+    let completion = request_completion(client, url, api_key, &payload, timeout).await?;
+    if !completion.truncated {
+        return Ok(completion.content);
+    }
+
+    log::warn!("Reply was cut off at {MAX_TOKENS} tokens, asking again with {RETRY_MAX_TOKENS}");
+    payload["max_tokens"] = json!(RETRY_MAX_TOKENS);
+    let completion = request_completion(client, url, api_key, &payload, timeout).await?;
+    if completion.truncated {
+        log::warn!("Reply was cut off again at {RETRY_MAX_TOKENS} tokens");
+    }
+    Ok(completion.content)
+}
 
-    let mut resp = llm_result.map_err(|e| anyhow!("HTTP request failed: {e}"))?;
+/// Sends a chat completion and returns the reply. The timeout is set
+/// on this request alone; reading a busy flyer can take longer than the
+/// shared client's default allows, or should be cut shorter.
+async fn request_completion(
+    client: &Client,
+    url: &str,
+    api_key: &str,
+    payload: &serde_json::Value,
+    timeout: Duration,
+) -> Result<Completion> {
+    let mut resp = client
+        .post(url)
+        .timeout(timeout)
+        .insert_header(("Authorization", format!("Bearer {api_key}")))
+        .insert_header(("Content-Type", "application/json"))
+        .send_json(payload)
+        .await
+        .map_err(|e| anyhow!("HTTP request failed: {e}"))?;
 
     let body = resp
         .body()
@@ -177,109 +564,374 @@ async fn parse_image_with_now(
     let json: serde_json::Value = serde_json::from_slice(&body)
         .map_err(|e| anyhow!("Failed to parse JSON response: {}", e))?;
 
-    let content = json["choices"][0]["message"]["content"]
-        .as_str()
-        .unwrap_or("")
-        .trim()
-        .to_string();
+    let choice = &json["choices"][0];
+    Ok(Completion {
+        content: choice["message"]["content"]
+            .as_str()
+            .unwrap_or("")
+            .trim()
+            .to_string(),
+        truncated: choice["finish_reason"] == "length",
+    })
+}
 
-    log::debug!("Extracted content: {}", content);
+pub fn datetime_from_naive(naive_local: NaiveDateTime, tz: Tz) -> Option<DateTime<Utc>> {
+    // Interpret `naive_local` as a *local wall-clock time* in the site's
+    // timezone. Posters never print an offset, and they're all for events
+    // in the area the site covers.
+    match tz.from_local_datetime(&naive_local) {
+        LocalResult::Single(datetime) => Some(datetime.with_timezone(&Utc)),
+        LocalResult::Ambiguous(earlier, _later) => Some(earlier.with_timezone(&Utc)),
+        LocalResult::None => None,
+    }
+}
+
+/// `ImageEventExtraction` with each event left as raw JSON, so one
+/// malformed event can be reported and dropped without losing the rest.
+#[derive(serde::Deserialize)]
+struct RawExtraction {
+    full_text: Option<String>,
+    events: Vec<serde_json::Value>,
+}
 
-    let mut events = parse_and_validate_response(&content)?;
+/// Checks the response against the schema we asked for, event by event.
+/// Events that don't fit it, or that lack the name and start time we need,
+/// are dropped; every problem is logged alongside the raw response so the
+/// prompt can be improved. Events over before `stale_before` are kept, but
+/// flagged with `PAST_EVENT_CONFIDENCE` unless the flyer said they'd
+/// already happened.
+fn parse_and_validate_response(
+    content: &str,
+    tz: Tz,
+    stale_before: DateTime<Utc>,
+) -> Result<Vec<NewEvent>> {
+    let extraction = parse_extraction(content).inspect_err(|e| {
+        log::error!("Couldn't read the extraction: {e:#}\nResponse: {content}");
+    })?;
 
-    let qr_url = qr_result.map_err(|e| anyhow!("QR task failed: {}", e))??;
+    let full_text = extraction.full_text.unwrap_or_default();
+    let extracted_count = extraction.events.len();
+    let mut valid_events = Vec::new();
+    let mut problems = Vec::new();
 
-    if let Some(qr_url) = qr_url {
-        log::info!("QR code URL detected: {qr_url}");
-        for event in &mut events {
-            event.url = Some(qr_url.to_string());
+    let text_len = full_text.trim().chars().count();
+    if text_len < MIN_FULL_TEXT_CHARS && extracted_count > 0 {
+        problems.push(format!(
+            "all events: the flyer's text is only {text_len} characters"
+        ));
+    } else {
+        for (index, raw_event) in extraction.events.into_iter().enumerate() {
+            match validate_event(raw_event, &full_text, tz, stale_before)
+                .and_then(|event| check_against_batch(event, &mut valid_events))
+            {
+                Ok(()) => {}
+                Err(problem) => problems.push(format!("event {}: {}", index + 1, problem)),
+            }
         }
     }
 
-    Ok(events)
+    if !problems.is_empty() {
+        log::warn!(
+            "Dropped {} of {} extracted events:\n  - {}\nResponse: {}",
+            problems.len(),
+            extracted_count,
+            problems.join("\n  - "),
+            content
+        );
+    }
+
+    Ok(valid_events)
 }
 
-fn datetime_from_naive(naive_local: NaiveDateTime) -> Option<DateTime<Utc>> {
-    // Interpret `naive_local` as a *local wall-clock time* in the America/NewYork timezone.
-    // All our event posters should be from the Camberville area so this is a
-    // safe assumption for now.
-    match New_York.from_local_datetime(&naive_local) {
-        LocalResult::Single(datetime) => Some(datetime.with_timezone(&Utc)),
-        LocalResult::Ambiguous(earlier, _later) => Some(earlier.with_timezone(&Utc)),
-        LocalResult::None => None,
+/// Adds an event to those already read off the same flyer, unless its name
+/// is a placeholder or it repeats one of them. Models sometimes list an
+/// event twice, slightly reworded; of the two, the more confident is kept.
+/// This runs before `database::find_duplicate`, which only sees what's
+/// already stored.
+fn check_against_batch(
+    event: NewEvent,
+    batch: &mut Vec<NewEvent>,
+) -> std::result::Result<(), String> {
+    let name = normalize_name(&event.name);
+    if PLACEHOLDER_NAMES.contains(&name.as_str()) {
+        return Err(format!("'{}' is a placeholder name", event.name));
+    }
+
+    let repeated = batch.iter_mut().find(|other| {
+        other.start_date == event.start_date
+            && jaro_winkler(&normalize_name(&other.name), &name) > SAME_FLYER_NAME_SIMILARITY
+    });
+    match repeated {
+        Some(other) => {
+            let problem = format!("'{}' repeats '{}'", event.name, other.name);
+            if event.confidence > other.confidence {
+                *other = event;
+            }
+            Err(problem)
+        }
+        None => {
+            batch.push(event);
+            Ok(())
+        }
     }
 }
 
-fn parse_and_validate_response(content: &str) -> Result<Vec<NewEvent>> {
-    // Strip markdown code blocks if present.
-    // LLMs like to surround code in them.
-    let clean_content = if content.trim().starts_with("```") {
-        content
-            .lines()
-            .skip(1) // Skip ```json
-            .take_while(|l| !l.trim().starts_with("```"))
-            .collect::<Vec<_>>()
-            .join("\n")
-    } else {
-        content.to_string()
-    };
+/// Lowercase words without punctuation, so "Porchfest!" and "porchfest"
+/// compare equal.
+fn normalize_name(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
 
-    let extraction: ImageEventExtraction = serde_json::from_str(&clean_content)
-        .map_err(|e| anyhow!("Failed to parse JSON: {} (Content: {})", e, clean_content))?;
+/// Reads the JSON out of a reply, however it came wrapped. LLMs like to put
+/// it in a markdown fence (sometimes only a closing one) or add a sentence
+/// before or after it, despite being told not to.
+fn parse_extraction(content: &str) -> Result<RawExtraction> {
+    let start = content
+        .find('{')
+        .ok_or_else(|| anyhow!("No JSON object in the response"))?;
+    let json = match content.rfind('}') {
+        Some(end) if end > start => &content[start..=end],
+        _ => &content[start..],
+    };
 
-    let full_text = extraction.full_text.unwrap_or_default();
-    let mut valid_events = Vec::new();
+    match serde_json::from_str(json) {
+        Ok(extraction) => Ok(extraction),
+        // The reply stopped partway through, even after the retry with more
+        // tokens. Keep the events that came through whole.
+        Err(e) if e.is_eof() => {
+            let closed = close_truncated_json(&content[start..])
+                .ok_or_else(|| anyhow!("Response was cut off before any event: {e}"))?;
+            log::warn!("Response was cut off; keeping the events that came through whole");
+            serde_json::from_str(&closed)
+                .map_err(|e| anyhow!("Response was cut off and couldn't be repaired: {e}"))
+        }
+        Err(e) => Err(anyhow!("Failed to parse JSON: {e}")),
+    }
+}
 
-    for extracted_event in extraction.events {
-        let Some(name) = extracted_event.name.filter(|n| !n.trim().is_empty()) else {
-            log::info!("Skipping event extraction missing name");
+/// Cuts JSON that stops partway through back to the end of its last
+/// complete object, then closes whatever arrays and objects were still
+/// open. For an extraction that's the last whole event. `None` if nothing
+/// was complete, or if nothing needed closing.
+fn close_truncated_json(json: &str) -> Option<String> {
+    let mut open = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut last_complete = None;
+
+    for (i, c) in json.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
             continue;
-        };
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => open.push('}'),
+            '[' => open.push(']'),
+            '}' | ']' => {
+                open.pop();
+                if c == '}' {
+                    last_complete = Some((i + 1, open.clone()));
+                }
+            }
+            _ => {}
+        }
+    }
 
-        let Some(naive_start) = extracted_event.start_date else {
-            log::info!("Skipping event extraction missing start_date");
-            continue;
-        };
+    if open.is_empty() {
+        return None;
+    }
+    let (end, still_open) = last_complete?;
+    Some(
+        json[..end]
+            .chars()
+            .chain(still_open.into_iter().rev())
+            .collect(),
+    )
+}
 
-        let Some(start_date) = datetime_from_naive(naive_start) else {
-            log::warn!("Invalid local time for start_date: {:?}", naive_start);
-            continue;
-        };
+fn validate_event(
+    raw_event: serde_json::Value,
+    full_text: &str,
+    tz: Tz,
+    stale_before: DateTime<Utc>,
+) -> std::result::Result<NewEvent, String> {
+    let extracted_event: SingleEventExtraction =
+        serde_json::from_value(raw_event).map_err(|e| e.to_string())?;
+
+    let name = extracted_event
+        .name
+        .filter(|n| !n.trim().is_empty())
+        .ok_or("missing name")?;
+
+    let naive_start = extracted_event.start_date.ok_or("missing start_date")?;
+
+    let start_date = datetime_from_naive(naive_start, tz)
+        .ok_or_else(|| format!("start_date {naive_start} doesn't exist in {tz}"))?;
+
+    let end_date = extracted_event.end_date.and_then(|naive| {
+        let dt = datetime_from_naive(naive, tz);
+        if dt.is_none() {
+            log::warn!("Invalid local time for end_date: {:?}", naive);
+        }
+        dt
+    });
+
+    // Flyers often give a date with no time ("Saturday, Nov 8"), which the
+    // model reports as midnight. Showing that as "12:00 AM" is misleading,
+    // so treat it as an all-day event instead.
+    let all_day = end_date.is_none() && naive_start.time() == NaiveTime::MIN;
+
+    // The prompt says to assume upcoming dates, yet a flyer for last
+    // month's show with no year on it still comes back in the past now and
+    // then. Rather than list that as settled, leave it for a person to
+    // check, unless the flyer itself said it was over.
+    let mut confidence = extracted_event.confidence;
+    if end_date.unwrap_or(start_date) < stale_before
+        && extracted_event.already_happened != Some(true)
+    {
+        log::warn!("'{name}' on {start_date} is already over; flagging it for review");
+        confidence = confidence.min(PAST_EVENT_CONFIDENCE);
+    }
+
+    Ok(NewEvent {
+        name,
+        start_date,
+        description: extracted_event.description.unwrap_or_default(),
+        full_text: full_text.to_string(),
+        end_date,
+        all_day,
+        address: None,
+        original_location: extracted_event.location,
+        google_place_id: None,
+        lat: None,
+        lng: None,
+        location_name: None,
+        event_types: extracted_event
+            .event_types
+            .unwrap_or_default()
+            .into_iter()
+            .map(EventType::from)
+            .collect(),
+        tags: normalize_tags(extracted_event.tags.unwrap_or_default()),
+        url: crate::models::sanitize_url(extracted_event.url),
+        confidence,
+        age_restrictions: extracted_event.age_restrictions,
+        price: extracted_event.price,
+        source: EventSource::ImageUpload,
+        external_id: None,
+        contact_email: None,
+        contact_phone: None,
+        registration_required: false,
+        status: EventStatus::Scheduled,
+    })
+}
+
+/// How many of the 64 hash bits can differ before two images stop counting
+/// as the same flyer. Re-saving, resizing or recompressing a photo moves a
+/// handful of bits; a different poster moves around half of them.
+pub const SAME_IMAGE_MAX_DISTANCE: u32 = 10;
+
+/// Difference hash: shrink to 9x8 grayscale and record whether each pixel
+/// is brighter than its right-hand neighbour. It survives resizing and
+/// recompression, so the same flyer uploaded twice hashes (nearly) the same.
+pub fn dhash(image: &DynamicImage) -> u64 {
+    let small = image
+        .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+        .to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let brighter = small.get_pixel(x, y)[0] > small.get_pixel(x + 1, y)[0];
+            hash = (hash << 1) | u64::from(brighter);
+        }
+    }
+    hash
+}
+
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// `dhash` of an image file, decoded off the async runtime.
+pub async fn image_file_dhash(image_path: &Path) -> Result<u64> {
+    let path = image_path.to_path_buf();
+    web::block(move || {
+        let image = ImageReader::open(&path)?.with_guessed_format()?.decode()?;
+        Ok::<u64, anyhow::Error>(dhash(&image))
+    })
+    .await
+    .map_err(|e| anyhow!("Blocking task failed: {}", e))?
+}
 
-        let end_date = extracted_event.end_date.and_then(|naive| {
-            let dt = datetime_from_naive(naive);
-            if dt.is_none() {
-                log::warn!("Invalid local time for end_date: {:?}", naive);
+/// Every frame of an animated GIF, WebP or PNG, composited to full size,
+/// or just the one image for anything else.
+fn decode_frames(bytes: &[u8], format: ImageFormat) -> Result<Vec<DynamicImage>> {
+    let frames = match format {
+        ImageFormat::Gif => GifDecoder::new(Cursor::new(bytes))?.into_frames(),
+        ImageFormat::WebP => {
+            let decoder = WebPDecoder::new(Cursor::new(bytes))?;
+            if !decoder.has_animation() {
+                return Ok(vec![image::load_from_memory_with_format(bytes, format)?]);
             }
-            dt
-        });
+            decoder.into_frames()
+        }
+        ImageFormat::Png => {
+            let decoder = PngDecoder::new(Cursor::new(bytes))?;
+            if !decoder.is_apng()? {
+                return Ok(vec![image::load_from_memory_with_format(bytes, format)?]);
+            }
+            decoder.apng()?.into_frames()
+        }
+        _ => return Ok(vec![image::load_from_memory_with_format(bytes, format)?]),
+    };
+    let frames: Vec<DynamicImage> = frames
+        .map(|frame| frame.map(|frame| DynamicImage::ImageRgba8(frame.into_buffer())))
+        .collect::<Result<_, _>>()?;
+    if frames.is_empty() {
+        return Err(anyhow!("Image has no frames"));
+    }
+    Ok(frames)
+}
 
-        valid_events.push(NewEvent {
-            name,
-            start_date,
-            description: extracted_event.description.unwrap_or_default(),
-            full_text: full_text.clone(),
-            end_date,
-            address: None,
-            original_location: extracted_event.location,
-            google_place_id: None,
-            location_name: None,
-            event_types: extracted_event
-                .event_types
-                .unwrap_or_default()
-                .into_iter()
-                .map(EventType::from)
-                .collect(),
-            url: crate::models::sanitize_url(extracted_event.url),
-            confidence: extracted_event.confidence,
-            age_restrictions: extracted_event.age_restrictions,
-            price: extracted_event.price,
-            source: EventSource::ImageUpload,
-            external_id: None,
-        });
+/// How much there is to read in a frame: the summed brightness change
+/// between neighbouring pixels of a small grayscale copy. Text and QR
+/// codes are nearly all edges, while blank and faded frames have few.
+fn detail_score(image: &DynamicImage) -> u64 {
+    let small = image
+        .resize(256, 256, image::imageops::FilterType::Triangle)
+        .to_luma8();
+    let mut score = 0u64;
+    for y in 0..small.height() {
+        for x in 1..small.width() {
+            score += u64::from(small.get_pixel(x, y)[0].abs_diff(small.get_pixel(x - 1, y)[0]));
+        }
     }
+    for y in 1..small.height() {
+        for x in 0..small.width() {
+            score += u64::from(small.get_pixel(x, y)[0].abs_diff(small.get_pixel(x, y - 1)[0]));
+        }
+    }
+    score
+}
 
-    Ok(valid_events)
+/// Index of the frame with the most detail; the first one on a tie.
+fn representative_frame(frames: &[DynamicImage]) -> usize {
+    frames
+        .iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|(_, frame)| detail_score(frame))
+        .map_or(0, |(index, _)| index)
 }
 
 fn extract_qr_url(image: DynamicImage) -> Option<Url> {
@@ -296,14 +948,27 @@ fn extract_qr_url(image: DynamicImage) -> Option<Url> {
 
 #[cfg(test)]
 mod tests {
-    use crate::config::Config;
+    use crate::config::{Config, DEFAULT_TIMEZONE};
 
     use super::*;
     use chrono::TimeZone;
 
+    fn extraction_options(config: &Config) -> ExtractionOptions {
+        ExtractionOptions {
+            structured_outputs: config.openai_structured_outputs,
+            past_event_window: config.past_event_window,
+        }
+    }
+
+    /// Before any of the sample replies' dates, for tests that aren't
+    /// about events already being over.
+    fn long_ago() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap()
+    }
+
     fn get_test_client() -> Client {
         awc::ClientBuilder::new()
-            .timeout(std::time::Duration::from_secs(120))
+            .timeout(Duration::from_secs(120))
             .finish()
     }
 
@@ -318,6 +983,9 @@ mod tests {
             fixed_now_utc,
             &client,
             &config.openai_api_key,
+            config.timezone,
+            config.api_timeouts.openai,
+            extraction_options(config),
         )
         .await?;
 
@@ -353,6 +1021,9 @@ mod tests {
             fixed_now_utc,
             &client,
             &config.openai_api_key,
+            config.timezone,
+            config.api_timeouts.openai,
+            extraction_options(config),
         )
         .await?;
 
@@ -378,6 +1049,9 @@ mod tests {
             fixed_now_utc,
             &client,
             &config.openai_api_key,
+            config.timezone,
+            config.api_timeouts.openai,
+            extraction_options(config),
         )
         .await?;
 
@@ -390,6 +1064,53 @@ mod tests {
         Ok(())
     }
 
+    /// The Fuzzstival poster's blocky display type is the sort the vision
+    /// model tends to misread.
+    #[cfg(feature = "ocr")]
+    #[actix_web::test]
+    async fn test_ocr_text_on_a_hard_flyer() -> Result<()> {
+        let config = Config::from_env();
+        let client = get_test_client();
+        let now = Utc.with_ymd_and_hms(2025, 9, 1, 12, 0, 0).unwrap();
+        let bytes = std::fs::read("examples/fuzz.jpeg")?;
+        let data_url = format!("data:image/jpeg;base64,{}", b64.encode(&bytes));
+        let extract = |ocr_text: Option<String>| {
+            let payload = extraction_payload(
+                ExtractionInput::Image {
+                    data_url: &data_url,
+                    ocr_text: ocr_text.as_deref(),
+                },
+                now,
+                config.timezone,
+                config.openai_structured_outputs,
+            );
+            let client = &client;
+            async move {
+                let content = request_extraction(
+                    client,
+                    OPENAI_CHAT_URL,
+                    &config.openai_api_key,
+                    payload,
+                    config.api_timeouts.openai,
+                )
+                .await?;
+                parse_and_validate_response(&content, config.timezone, long_ago())
+            }
+        };
+
+        let text = ocr_text(bytes.clone()).await?;
+        assert!(text.to_lowercase().contains("armory"), "{text}");
+
+        let before = best_confidence(&extract(None).await?);
+        let after = best_confidence(&extract(Some(text)).await?);
+        assert!(
+            after >= before,
+            "OCR text lowered the confidence from {before:.2} to {after:.2}"
+        );
+
+        Ok(())
+    }
+
     #[actix_web::test]
     async fn test_flyer_with_qr_code() -> Result<()> {
         let config = Config::from_env();
@@ -402,6 +1123,9 @@ mod tests {
             fixed_now_utc,
             &client,
             &config.openai_api_key,
+            config.timezone,
+            config.api_timeouts.openai,
+            extraction_options(config),
         )
         .await?;
 
@@ -433,6 +1157,392 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_midnight_start_without_end_is_all_day() -> Result<()> {
+        let content = r#"{
+            "full_text": "Yard sale Saturday. Bake sale Sunday 9am-noon.",
+            "events": [
+                {"name": "Yard Sale", "start_date": "2025-06-21T00:00:00", "confidence": 0.9},
+                {"name": "Bake Sale", "start_date": "2025-06-22T09:00:00", "end_date": "2025-06-22T12:00:00", "confidence": 0.9},
+                {"name": "Overnight", "start_date": "2025-06-22T00:00:00", "end_date": "2025-06-22T06:00:00", "confidence": 0.9}
+            ]
+        }"#;
+
+        let events = parse_and_validate_response(content, DEFAULT_TIMEZONE, long_ago())?;
+
+        assert_eq!(events.len(), 3);
+        assert!(events[0].all_day, "Midnight start with no end is all day");
+        assert!(!events[1].all_day, "Timed event is not all day");
+        assert!(
+            !events[2].all_day,
+            "Midnight start with an explicit end is a real time"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed_events_are_dropped_and_the_rest_kept() -> Result<()> {
+        let content = r#"{
+            "full_text": "Porchfest Saturday noon. Swap meet Sunday.",
+            "events": [
+                {"name": "Porchfest", "start_date": "2025-05-10T12:00:00", "confidence": 0.9},
+                {"name": "", "start_date": "2025-05-11T10:00:00", "confidence": 0.4},
+                {"name": "Swap Meet", "start_date": "Sunday", "confidence": 0.5}
+            ]
+        }"#;
+
+        let events = parse_and_validate_response(content, DEFAULT_TIMEZONE, long_ago())?;
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].name, "Porchfest");
+        assert_eq!(
+            events[0].full_text,
+            "Porchfest Saturday noon. Swap meet Sunday."
+        );
+
+        // Without an events list there's nothing to salvage.
+        assert!(parse_and_validate_response(
+            r#"{"full_text": "hi"}"#,
+            DEFAULT_TIMEZONE,
+            long_ago()
+        )
+        .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_spurious_events_in_a_batch_are_dropped() -> Result<()> {
+        let content = r#"{
+            "full_text": "PORCHFEST! Sat May 10 noon. Workshops A and B at 2pm. Story time 10am and 11am.",
+            "events": [
+                {"name": "Porchfest", "start_date": "2025-05-10T12:00:00", "confidence": 0.7},
+                {"name": "PORCHFEST!", "start_date": "2025-05-10T12:00:00", "confidence": 0.9},
+                {"name": "Workshop A", "start_date": "2025-05-10T14:00:00", "confidence": 0.9},
+                {"name": "Workshop B", "start_date": "2025-05-10T14:00:00", "confidence": 0.9},
+                {"name": "Story Time", "start_date": "2025-05-10T10:00:00", "confidence": 0.9},
+                {"name": "Story Time", "start_date": "2025-05-10T11:00:00", "confidence": 0.9},
+                {"name": "Untitled Event", "start_date": "2025-05-10T09:00:00", "confidence": 0.3}
+            ]
+        }"#;
+
+        let events = parse_and_validate_response(content, DEFAULT_TIMEZONE, long_ago())?;
+
+        let names: Vec<_> = events
+            .iter()
+            .map(|e| (e.name.as_str(), e.confidence))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                // The more confident of the two, in the first one's place.
+                ("PORCHFEST!", 0.9),
+                ("Workshop A", 0.9),
+                ("Workshop B", 0.9),
+                // The same name at different times is two sessions.
+                ("Story Time", 0.9),
+                ("Story Time", 0.9),
+            ]
+        );
+
+        // Barely any text: whatever was "found" is a guess.
+        let content = r#"{
+            "full_text": "COLA",
+            "events": [{"name": "Cola Tasting", "start_date": "2025-05-10T12:00:00", "confidence": 0.4}]
+        }"#;
+        assert!(parse_and_validate_response(content, DEFAULT_TIMEZONE, long_ago())?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_events_already_over_are_flagged() -> Result<()> {
+        let content = r#"{
+            "full_text": "Porchfest May 10. Art walk recap. Festival May 9-13. Swap meet May 11.",
+            "events": [
+                {"name": "Porchfest", "start_date": "2025-05-10T12:00:00", "confidence": 0.9},
+                {"name": "Art Walk", "start_date": "2025-05-10T12:00:00", "already_happened": true, "confidence": 0.9},
+                {"name": "Festival", "start_date": "2025-05-09T10:00:00", "end_date": "2025-05-13T18:00:00", "confidence": 0.9},
+                {"name": "Swap Meet", "start_date": "2025-05-11T15:00:00", "confidence": 0.9}
+            ]
+        }"#;
+        let now = Utc.with_ymd_and_hms(2025, 5, 12, 16, 0, 0).unwrap();
+
+        let events =
+            parse_and_validate_response(content, DEFAULT_TIMEZONE, now - TimeDelta::hours(24))?;
+
+        let confidences: Vec<_> = events
+            .iter()
+            .map(|e| (e.name.as_str(), e.confidence))
+            .collect();
+        assert_eq!(
+            confidences,
+            vec![
+                // Two days gone and the flyer didn't say so: a misread date.
+                ("Porchfest", PAST_EVENT_CONFIDENCE),
+                ("Art Walk", 0.9),
+                // Started days ago, but still on.
+                ("Festival", 0.9),
+                // Within the window, e.g. uploaded the morning after.
+                ("Swap Meet", 0.9),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wrapped_and_truncated_responses() -> Result<()> {
+        let json = r#"{"full_text": "Porchfest Saturday noon.", "events": [{"name": "Porchfest", "start_date": "2025-05-10T12:00:00", "confidence": 0.9}]}"#;
+        for content in [
+            format!("```json\n{json}\n```"),
+            format!("```\n{json}\n```"),
+            format!("{json}\n```"),
+            format!("Here are the events I found:\n\n{json}\n\nLet me know if you need more."),
+        ] {
+            let events = parse_and_validate_response(&content, DEFAULT_TIMEZONE, long_ago())?;
+            assert_eq!(events.len(), 1, "{content}");
+            assert_eq!(events[0].name, "Porchfest");
+        }
+
+        // Cut off partway through the second event, braces in strings and all.
+        let truncated = r#"```json
+{"full_text": "Swap {meet} \"Sunday\"", "events": [
+  {"name": "Swap {Meet}", "start_date": "2025-05-11T10:00:00", "confidence": 0.8},
+  {"name": "Porchfest", "start_date": "2025-05-1"#;
+        let events = parse_and_validate_response(truncated, DEFAULT_TIMEZONE, long_ago())?;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].name, "Swap {Meet}");
+        assert_eq!(events[0].full_text, r#"Swap {meet} "Sunday""#);
+
+        // Cut off before any event was complete.
+        assert!(parse_and_validate_response(
+            r#"{"full_text": "Porchfest", "events": [{"name": "Porch"#,
+            DEFAULT_TIMEZONE,
+            long_ago()
+        )
+        .is_err());
+        // Not JSON at all.
+        assert!(parse_and_validate_response(
+            "I can't read this flyer.",
+            DEFAULT_TIMEZONE,
+            long_ago()
+        )
+        .is_err());
+        // Complete, but broken in the middle.
+        assert!(parse_and_validate_response(
+            r#"{"full_text": "Porchfest", "events": [{"name": "Porchfest",, }]}"#,
+            DEFAULT_TIMEZONE,
+            long_ago()
+        )
+        .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dhash_matches_resized_copy_but_not_other_flyers() -> Result<()> {
+        let flyer = image::open("examples/dance_flyer.jpg")?;
+        let hash = dhash(&flyer);
+
+        // A smaller re-save of the same photo, as a phone share would do.
+        let mut jpeg = Vec::new();
+        flyer
+            .resize(
+                flyer.width() / 3,
+                flyer.height() / 3,
+                image::imageops::FilterType::Triangle,
+            )
+            .write_to(&mut Cursor::new(&mut jpeg), ImageFormat::Jpeg)?;
+        let copy = image::load_from_memory(&jpeg)?;
+        assert!(hamming_distance(hash, dhash(&copy)) <= SAME_IMAGE_MAX_DISTANCE);
+
+        for other in ["examples/yardsale_flyer.jpg", "examples/selfie.jpg"] {
+            let distance = hamming_distance(hash, dhash(&image::open(other)?));
+            assert!(distance > SAME_IMAGE_MAX_DISTANCE, "{other}: {distance}");
+        }
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_openai_request_times_out_per_call() -> Result<()> {
+        use actix_web::{App, HttpResponse, HttpServer};
+
+        // Stands in for an OpenAI that's taking its time.
+        let server = HttpServer::new(|| {
+            App::new().default_service(web::to(|| async {
+                actix_web::rt::time::sleep(Duration::from_secs(5)).await;
+                HttpResponse::Ok().json(json!({
+                    "choices": [{ "message": { "content": "{}" } }]
+                }))
+            }))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))?;
+        let url = format!("http://{}/v1/chat/completions", server.addrs()[0]);
+        let handle = server.run();
+        let server_handle = handle.handle();
+        actix_web::rt::spawn(handle);
+
+        // The client itself would wait far longer.
+        let client = get_test_client();
+        let started = std::time::Instant::now();
+        let result = request_completion(
+            &client,
+            &url,
+            "dummy",
+            &json!({}),
+            Duration::from_millis(200),
+        )
+        .await;
+        server_handle.stop(false).await;
+
+        let err = result.expect_err("the slow response should time out");
+        assert!(err.to_string().contains("Timeout"), "{err:#}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_request_holds_the_reply_to_the_schema() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 17, 0, 0).unwrap();
+        let payload = extraction_payload(
+            ExtractionInput::Image {
+                data_url: "data:image/png;base64,",
+                ocr_text: None,
+            },
+            now,
+            DEFAULT_TIMEZONE,
+            true,
+        );
+
+        assert_eq!(payload["max_tokens"], MAX_TOKENS);
+        let format = &payload["response_format"];
+        assert_eq!(format["type"], "json_schema");
+        assert_eq!(format["json_schema"]["strict"], true);
+        let schema = &format["json_schema"]["schema"];
+        assert_eq!(schema["required"], json!(["events", "full_text"]));
+        assert_eq!(schema["additionalProperties"], false);
+        let event = &schema["properties"]["events"]["items"];
+        assert_eq!(event["additionalProperties"], false);
+        assert_eq!(
+            event["required"].as_array().map(Vec::len),
+            event["properties"].as_object().map(|p| p.len())
+        );
+        assert_eq!(event["properties"]["start_date"]["format"], json!(null));
+        // The schema is sent once, not again in the prompt.
+        let prompt = payload["messages"][0]["content"].as_str().unwrap();
+        assert!(!prompt.contains("\"properties\""), "{prompt}");
+
+        // Models with only JSON mode get the schema in the prompt instead.
+        let payload = extraction_payload(
+            ExtractionInput::Image {
+                data_url: "data:image/png;base64,",
+                ocr_text: None,
+            },
+            now,
+            DEFAULT_TIMEZONE,
+            false,
+        );
+        assert_eq!(payload["response_format"], json!({ "type": "json_object" }));
+        let prompt = payload["messages"][0]["content"].as_str().unwrap();
+        assert!(prompt.contains("\"properties\""), "{prompt}");
+    }
+
+    #[test]
+    fn test_page_text_goes_in_the_prompt() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 17, 0, 0).unwrap();
+        let payload = extraction_payload(
+            ExtractionInput::Page("Title: Fall Contra Dance"),
+            now,
+            DEFAULT_TIMEZONE,
+            true,
+        );
+
+        let prompt = payload["messages"][0]["content"].as_str().unwrap();
+        assert!(prompt.contains("from web pages"), "{prompt}");
+        assert!(!prompt.contains("QR"), "{prompt}");
+        let user = payload["messages"][1]["content"].as_str().unwrap();
+        assert!(user.ends_with("\n\nTitle: Fall Contra Dance"), "{user}");
+    }
+
+    #[actix_web::test]
+    async fn test_truncated_reply_is_retried_with_more_tokens() -> Result<()> {
+        use actix_web::{App, HttpResponse, HttpServer};
+        use std::sync::{Arc, Mutex};
+
+        // Stands in for an OpenAI that runs out of tokens unless given plenty.
+        let limits = Arc::new(Mutex::new(Vec::new()));
+        let seen = limits.clone();
+        let server = HttpServer::new(move || {
+            let seen = seen.clone();
+            App::new().default_service(web::to(move |body: web::Json<serde_json::Value>| {
+                let max_tokens = body["max_tokens"].as_u64().unwrap_or(0);
+                seen.lock().unwrap().push(max_tokens);
+                let (content, finish_reason) = if max_tokens > u64::from(MAX_TOKENS) {
+                    (r#"{"events": []}"#, "stop")
+                } else {
+                    (r#"{"events": ["#, "length")
+                };
+                async move {
+                    HttpResponse::Ok().json(json!({
+                        "choices": [{
+                            "message": { "content": content },
+                            "finish_reason": finish_reason
+                        }]
+                    }))
+                }
+            }))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))?;
+        let url = format!("http://{}/v1/chat/completions", server.addrs()[0]);
+        let handle = server.run();
+        let server_handle = handle.handle();
+        actix_web::rt::spawn(handle);
+
+        let client = get_test_client();
+        let result = request_extraction(
+            &client,
+            &url,
+            "dummy",
+            json!({ "max_tokens": MAX_TOKENS }),
+            Duration::from_secs(5),
+        )
+        .await;
+        server_handle.stop(false).await;
+
+        assert_eq!(result?, r#"{"events": []}"#);
+        assert_eq!(
+            *limits.lock().unwrap(),
+            vec![u64::from(MAX_TOKENS), u64::from(RETRY_MAX_TOKENS)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_animated_gif_uses_the_frame_with_content() -> Result<()> {
+        // A blank first frame, then a QR code.
+        let bytes = std::fs::read("examples/two_frame_qr.gif")?;
+        let frames = decode_frames(&bytes, ImageFormat::Gif)?;
+        assert_eq!(frames.len(), 2);
+        assert_eq!(representative_frame(&frames), 1);
+        assert!(extract_qr_url(frames[0].clone()).is_none());
+
+        let url = frames.into_iter().find_map(extract_qr_url);
+        assert_eq!(url, Some(Url::parse("https://cypressf.com/")?));
+
+        // A still image is its own only frame.
+        let still = std::fs::read("examples/qrcode.png")?;
+        assert_eq!(decode_frames(&still, ImageFormat::Png)?.len(), 1);
+
+        Ok(())
+    }
+
     #[test]
     fn test_qr_decode_poster() -> Result<()> {
         let img = image::open("examples/large_qr_code_poster.jpg")?;
@@ -455,6 +1565,9 @@ mod tests {
             fixed_now_utc,
             &client,
             &config.openai_api_key,
+            config.timezone,
+            config.api_timeouts.openai,
+            extraction_options(config),
         )
         .await?;
 
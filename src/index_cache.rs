@@ -0,0 +1,174 @@
+//! Rendered index pages, kept for a short while. The index is the busiest
+//! page, and every hit otherwise costs three queries and a render for
+//! events that change a few times a day.
+//!
+//! An entry is dropped when it outlives the TTL, when an event is added,
+//! changed or removed through the app, or when the local day turns over,
+//! since which days are listed and what "today" covers depend on it. The
+//! ingestor and scrapers write from other processes, so their events show
+//! up once the TTL runs out.
+
+use crate::features::common::PageValidators;
+use actix_web::web::Bytes;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a rendered page is served, unless `INDEX_CACHE_SECS` says
+/// otherwise. Matches the `max-age` browsers already get.
+pub const DEFAULT_INDEX_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Searches and map points make for endless distinct URLs. Past this many
+/// pages the expired ones are swept, and if that isn't enough the cache
+/// starts over.
+const MAX_ENTRIES: usize = 1000;
+
+#[derive(Clone)]
+pub struct CachedPage {
+    pub validators: PageValidators,
+    pub body: Bytes,
+}
+
+struct Entry {
+    page: CachedPage,
+    day: NaiveDate,
+    version: u64,
+    stored_at: Instant,
+}
+
+pub struct IndexCache {
+    ttl: Duration,
+    version: AtomicU64,
+    entries: Mutex<HashMap<String, Entry>>,
+}
+
+impl Default for IndexCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_INDEX_CACHE_TTL)
+    }
+}
+
+impl IndexCache {
+    /// A zero `ttl` turns the cache off.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            version: AtomicU64::new(0),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Read before fetching the events for a page, and handed back to
+    /// `insert`, so a page rendered from data an edit has since replaced
+    /// isn't stored as current.
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::SeqCst)
+    }
+
+    /// Call after any change to the events the index lists.
+    pub fn invalidate(&self) {
+        self.version.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// The page stored under `key`, if it's still good on `today`.
+    pub fn get(&self, key: &str, today: NaiveDate) -> Option<CachedPage> {
+        self.get_at(key, today, Instant::now())
+    }
+
+    pub fn insert(&self, key: String, today: NaiveDate, version: u64, page: CachedPage) {
+        self.insert_at(key, today, version, page, Instant::now())
+    }
+
+    fn get_at(&self, key: &str, today: NaiveDate, now: Instant) -> Option<CachedPage> {
+        if self.ttl.is_zero() {
+            return None;
+        }
+        let entries = self.entries.lock().expect("index cache lock poisoned");
+        let entry = entries.get(key)?;
+        self.is_current(entry, today, now)
+            .then(|| entry.page.clone())
+    }
+
+    fn insert_at(
+        &self,
+        key: String,
+        today: NaiveDate,
+        version: u64,
+        page: CachedPage,
+        now: Instant,
+    ) {
+        if self.ttl.is_zero() || version != self.version() {
+            return;
+        }
+        let mut entries = self.entries.lock().expect("index cache lock poisoned");
+        if entries.len() >= MAX_ENTRIES && !entries.contains_key(&key) {
+            entries.retain(|_, entry| self.is_current(entry, today, now));
+            if entries.len() >= MAX_ENTRIES {
+                entries.clear();
+            }
+        }
+        entries.insert(
+            key,
+            Entry {
+                page,
+                day: today,
+                version,
+                stored_at: now,
+            },
+        );
+    }
+
+    fn is_current(&self, entry: &Entry, today: NaiveDate, now: Instant) -> bool {
+        entry.version == self.version()
+            && entry.day == today
+            && now.duration_since(entry.stored_at) < self.ttl
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(body: &'static str) -> CachedPage {
+        CachedPage {
+            validators: PageValidators::new(None, 0),
+            body: Bytes::from_static(body.as_bytes()),
+        }
+    }
+
+    #[test]
+    fn test_entries_expire_with_the_ttl_the_day_and_edits() {
+        let cache = IndexCache::new(Duration::from_secs(60));
+        let day = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let next_day = day.succ_opt().unwrap();
+        let t0 = Instant::now();
+        let body = |page: Option<CachedPage>| page.map(|p| p.body);
+
+        cache.insert_at("/".to_string(), day, cache.version(), page("a"), t0);
+        assert_eq!(body(cache.get_at("/", day, t0)), Some(Bytes::from("a")));
+        assert!(cache.get_at("/?free=true", day, t0).is_none());
+        assert!(cache.get_at("/", next_day, t0).is_none());
+        assert!(cache
+            .get_at("/", day, t0 + Duration::from_secs(60))
+            .is_none());
+
+        cache.invalidate();
+        assert!(cache.get_at("/", day, t0).is_none());
+
+        // Rendered before the edit, stored after it.
+        let stale_version = cache.version();
+        cache.invalidate();
+        cache.insert_at("/".to_string(), day, stale_version, page("b"), t0);
+        assert!(cache.get_at("/", day, t0).is_none());
+    }
+
+    #[test]
+    fn test_zero_ttl_turns_the_cache_off() {
+        let cache = IndexCache::new(Duration::ZERO);
+        let day = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        cache.insert("/".to_string(), day, cache.version(), page("a"));
+        assert!(cache.get("/", day).is_none());
+    }
+}
@@ -1,16 +1,72 @@
+pub mod auth;
+pub mod background_tasks;
+pub mod backup;
 pub mod config;
+pub mod contact_relay;
 pub mod database;
+pub mod error_reporting;
+pub mod event_card;
 pub mod features;
 pub mod geocoding;
+pub mod i18n;
+pub mod ical_timezone;
 pub mod image_processing;
+pub mod index_cache;
 pub mod models;
+pub mod scraper;
+pub mod security;
+pub mod web_page;
+pub mod webhooks;
 
+use auth::LoginThrottle;
+use background_tasks::ConcurrencyLimit;
+use chrono::TimeDelta;
+use chrono_tz::Tz;
+use config::{ApiTimeouts, EventDurations};
+use contact_relay::ContactRelay;
 use database::EventsRepo;
+use index_cache::IndexCache;
+use std::net::IpAddr;
+use webhooks::Webhooks;
 
 pub struct AppState {
     pub openai_api_key: String,
     pub google_maps_api_key: String,
+    /// See `Config::geocoding_enabled`.
+    pub geocoding_enabled: bool,
     pub username: String,
     pub password: String,
+    /// See `Config::timezone`.
+    pub timezone: Tz,
+    pub api_timeouts: ApiTimeouts,
+    /// See `Config::geocoding_concurrency`.
+    pub geocoding_concurrency: usize,
+    /// Flyers being read wait on this. See `Config::upload_concurrency`.
+    pub upload_slots: ConcurrencyLimit,
+    /// See `Config::openai_structured_outputs`.
+    pub openai_structured_outputs: bool,
+    /// See `Config::past_event_window`.
+    pub past_event_window: TimeDelta,
+    /// See `Config::trusted_proxies`.
+    pub trusted_proxies: Vec<IpAddr>,
+    /// See `Config::event_durations`.
+    pub event_durations: EventDurations,
+    /// Told about each event an upload or the create form adds.
+    pub webhooks: Webhooks,
+    /// Sends readers' messages on to organizers. Off unless `SMTP_URL` is
+    /// set.
+    pub contact_relay: ContactRelay,
+    /// Addresses that have been getting the admin password wrong.
+    pub login_throttle: LoginThrottle,
+    /// Rendered index pages. Handlers that change events invalidate it.
+    pub index_cache: IndexCache,
     pub events_repo: Box<dyn EventsRepo>,
 }
+
+impl AppState {
+    /// The key to geocode with, or `None` when geocoding is off.
+    pub fn geocoding_key(&self) -> Option<&str> {
+        self.geocoding_enabled
+            .then_some(self.google_maps_api_key.as_str())
+    }
+}
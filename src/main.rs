@@ -1,63 +1,111 @@
 use actix_web::{
-    dev::ServiceRequest,
-    error::ErrorUnauthorized,
-    middleware,
+    cookie::Key,
+    middleware::{self, from_fn},
     web::{self, Data},
-    App, Error, HttpServer,
+    App, HttpServer,
 };
-use actix_web_httpauth::{extractors::basic::BasicAuth, middleware::HttpAuthentication};
 use actix_web_query_method_middleware::QueryMethod;
 use anyhow::Result;
-use somerville_events::{config::Config, features, AppState};
-use sqlx::postgres::PgPoolOptions;
-
-async fn basic_auth_validator(
-    req: ServiceRequest,
-    credentials: BasicAuth,
-) -> Result<ServiceRequest, (Error, ServiceRequest)> {
-    let state = req
-        .app_data::<Data<AppState>>()
-        .expect("AppState missing; did you register .app_data(Data::new(AppState{...}))?");
-
-    let username = credentials.user_id();
-    let password = credentials.password().unwrap_or_default();
+use somerville_events::{
+    auth::LoginThrottle,
+    background_tasks::{BackgroundTasks, ConcurrencyLimit},
+    config::Config,
+    contact_relay::ContactRelay,
+    database::run_migrations,
+    error_reporting,
+    features::{self, login::require_admin},
+    index_cache::IndexCache,
+    security,
+    webhooks::Webhooks,
+    AppState,
+};
+use std::time::Duration;
 
-    if username == state.username && password == state.password {
-        Ok(req)
-    } else {
-        Err((ErrorUnauthorized("Invalid credentials"), req))
-    }
-}
+// Long enough for an in-flight upload to finish its OpenAI call, which is
+// bounded by the 120s client timeout below.
+const BACKGROUND_TASK_DRAIN_TIMEOUT: Duration = Duration::from_secs(150);
 
 #[actix_web::main]
 async fn main() -> Result<()> {
+    Config::validate()?;
     let config = Config::from_env();
     env_logger::init_from_env(env_logger::Env::new().default_filter_or("info"));
+    let _error_reporting = error_reporting::init(config.sentry_dsn.as_deref());
+    if !config.geocoding_enabled {
+        log::warn!("Geocoding is off; events will keep their locations as written");
+    }
+
+    if config.run_migrations_on_start {
+        // `validate` already checked that the password is there.
+        let migrator_url = config
+            .get_migrator_db_url()
+            .ok_or_else(|| anyhow::anyhow!("RUN_MIGRATIONS_ON_START needs DB_MIGRATOR_PASS"))?;
+        let migrator_pool = config
+            .pool_options()
+            .max_connections(1)
+            .connect(&migrator_url)
+            .await?;
+        run_migrations(&migrator_pool).await?;
+        migrator_pool.close().await;
+    }
 
     let db_url = config.get_db_url();
 
-    let db_connection_pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&db_url)
-        .await?;
+    let db_connection_pool = config.pool_options().connect(&db_url).await?;
 
-    log::info!("Starting server at http://localhost:8080");
+    // Bind before starting the server so we can log the real address, which
+    // differs from the config when PORT=0 asks the OS to pick one.
+    let listener = std::net::TcpListener::bind((config.host.as_str(), config.port))?;
+    log::info!("Starting server at http://{}", listener.local_addr()?);
 
-    let host = config.host.clone();
     let static_file_dir = config.static_file_dir.clone();
+    let background_tasks = BackgroundTasks::default();
 
     let state = AppState {
         openai_api_key: config.openai_api_key.clone(),
         google_maps_api_key: config.google_maps_api_key.clone(),
+        geocoding_enabled: config.geocoding_enabled,
         username: config.username.clone(),
         password: config.password.clone(),
+        timezone: config.timezone,
+        api_timeouts: config.api_timeouts,
+        geocoding_concurrency: config.geocoding_concurrency,
+        upload_slots: ConcurrencyLimit::new(config.upload_concurrency),
+        openai_structured_outputs: config.openai_structured_outputs,
+        past_event_window: config.past_event_window,
+        event_durations: config.event_durations.clone(),
+        webhooks: Webhooks::new(
+            config.webhook_urls.clone(),
+            config.webhook_secret.clone(),
+            &config.public_url,
+            background_tasks.clone(),
+        ),
+        contact_relay: match &config.smtp_url {
+            Some(smtp_url) => {
+                ContactRelay::new(smtp_url, &config.contact_from, background_tasks.clone())?
+            }
+            None => ContactRelay::default(),
+        },
+        trusted_proxies: config.trusted_proxies.clone(),
+        login_throttle: LoginThrottle::default(),
+        index_cache: IndexCache::new(config.index_cache_ttl),
         events_repo: Box::new(db_connection_pool),
     };
     let app_state = Data::new(state);
 
-    HttpServer::new(move || {
-        let auth_middleware = HttpAuthentication::basic(basic_auth_validator);
+    // Generated once, outside the worker factory, so every worker accepts
+    // the same cookies.
+    let session_key = match &config.session_key {
+        Some(key) => Key::from(key.as_bytes()),
+        None => {
+            log::warn!("SESSION_KEY is not set; admin sessions won't survive a restart");
+            Key::generate()
+        }
+    };
+    let secure_cookies = config.public_url.starts_with("https://");
+    let tasks_data = Data::new(background_tasks.clone());
 
+    let server = HttpServer::new(move || {
         let client = awc::ClientBuilder::new()
             .timeout(std::time::Duration::from_secs(120))
             .finish();
@@ -65,60 +113,244 @@ async fn main() -> Result<()> {
         App::new()
             .app_data(app_state.clone())
             .app_data(Data::new(client))
+            .app_data(tasks_data.clone())
             .wrap(QueryMethod::default())
+            .wrap(features::login::session_middleware(
+                session_key.clone(),
+                secure_cookies,
+            ))
+            .wrap(from_fn(security::security_headers))
+            .wrap(middleware::Condition::new(
+                config.hsts,
+                security::strict_transport_security(),
+            ))
+            // Inside the logger so the logged sizes are what went over the
+            // wire. Images are left alone; they're already compressed.
+            .wrap(middleware::Compress::default())
             .wrap(middleware::Logger::default())
             .service(actix_files::Files::new("/static", &static_file_dir).show_files_listing())
             .route("/", web::get().to(features::view::index))
+            .route("/today", web::get().to(features::view::today))
+            .route("/this-weekend", web::get().to(features::view::this_weekend))
+            .route("/robots.txt", web::get().to(features::view::robots_txt))
+            .route("/clock", web::post().to(features::view::set_clock))
+            .route("/lang", web::post().to(features::view::set_lang))
             .route("/events.atom", web::get().to(features::view::atom_feed))
             .route("/events.ics", web::get().to(features::view::ical_feed))
+            .route(
+                "/events/download.ics",
+                web::get().to(features::view::ical_download),
+            )
+            .route("/map", web::get().to(features::map::index))
+            .route("/about", web::get().to(features::about::index))
+            .service(
+                web::scope("/api")
+                    .route("/events", web::get().to(features::map::api_events))
+                    .route("/events/{id}", web::get().to(features::view::api_event))
+                    .route("/categories", web::get().to(features::view::api_categories))
+                    .default_service(web::to(features::common::api_not_found)),
+            )
             .route("/event/{id}.ics", web::get().to(features::view::ical))
+            .route("/event/{id}/card.png", web::get().to(features::view::card))
+            .route("/event/{id}/print", web::get().to(features::view::print))
             .route("/event/{id}", web::get().to(features::view::show))
+            .route("/venue/{place_id}", web::get().to(features::venue::show))
             .service(
                 web::resource("/upload")
-                    .wrap(auth_middleware.clone())
+                    .app_data(features::upload::multipart_config(config.max_upload_bytes))
+                    // Open to everyone with PUBLIC_UPLOADS; the handler
+                    // holds those uploads for review.
+                    .wrap(middleware::Condition::new(
+                        !config.public_uploads,
+                        from_fn(require_admin),
+                    ))
                     .route(web::get().to(features::upload::index))
                     .route(web::post().to(features::upload::save)),
             )
+            .service(
+                web::resource("/upload/page")
+                    .wrap(middleware::Condition::new(
+                        !config.public_uploads,
+                        from_fn(require_admin),
+                    ))
+                    .route(web::post().to(features::upload::save_page)),
+            )
+            .service(
+                web::resource("/upload/confirm")
+                    .wrap(from_fn(require_admin))
+                    .route(web::post().to(features::upload::confirm)),
+            )
+            .service(
+                web::resource("/create")
+                    .wrap(from_fn(require_admin))
+                    .route(web::get().to(features::create::index))
+                    .route(web::post().to(features::create::save)),
+            )
             .service(
                 web::resource("/event/{id}")
-                    .wrap(auth_middleware.clone())
+                    .wrap(from_fn(require_admin))
                     .route(web::delete().to(features::edit::delete)),
             )
+            .service(
+                web::resource("/event/{id}/merge")
+                    .wrap(from_fn(require_admin))
+                    .route(web::post().to(features::edit::merge)),
+            )
             .service(
                 web::scope("/edit")
-                    .wrap(auth_middleware)
+                    .wrap(from_fn(require_admin))
                     .route("", web::get().to(features::edit::index))
-                    .route("/event/{id}", web::get().to(features::edit::show)),
+                    .route("/export.json", web::get().to(features::edit::export))
+                    .route("/event/{id}", web::get().to(features::edit::show))
+                    .route(
+                        "/event/{id}/webhook.json",
+                        web::get().to(features::edit::webhook_preview),
+                    )
+                    .route(
+                        "/event/{id}/restore",
+                        web::post().to(features::edit::restore),
+                    )
+                    .route(
+                        "/event/{id}/featured",
+                        web::post().to(features::edit::set_featured),
+                    )
+                    .route(
+                        "/event/{id}/status",
+                        web::post().to(features::edit::set_status),
+                    )
+                    .route("/trash", web::get().to(features::edit::trash))
+                    .route("/stats", web::get().to(features::edit::stats))
+                    .route("/bulk", web::post().to(features::edit::bulk))
+                    .route(
+                        "/report/{id}/dismiss",
+                        web::post().to(features::edit::dismiss_report),
+                    ),
             )
             .route("/upload-success", web::get().to(features::upload::success))
+            .route(
+                "/event/{id}/report",
+                web::post().to(features::report::submit),
+            )
+            .route(
+                "/report-received",
+                web::get().to(features::report::received),
+            )
+            .route(
+                "/event/{id}/contact",
+                web::get().to(features::contact::form),
+            )
+            .route(
+                "/event/{id}/contact",
+                web::post().to(features::contact::submit),
+            )
+            .route("/contact-sent", web::get().to(features::contact::sent))
+            .route("/login", web::get().to(features::login::index))
+            .route("/login", web::post().to(features::login::login))
+            .route("/logout", web::post().to(features::login::logout))
+            .default_service(web::to(features::common::default_not_found))
     })
-    .bind((host, 8080))?
-    .run()
-    .await?;
+    .listen(listener)?
+    // Actix would stop the workers on SIGTERM right away, killing any
+    // upload still being processed. We drain those first, then stop.
+    .disable_signals()
+    .run();
+
+    let handle = server.handle();
+    actix_web::rt::spawn(async move {
+        wait_for_shutdown_signal().await;
+        log::info!(
+            "Shutting down, waiting for {} background tasks",
+            background_tasks.running()
+        );
+        let abandoned = background_tasks
+            .shutdown(BACKGROUND_TASK_DRAIN_TIMEOUT)
+            .await;
+        if abandoned > 0 {
+            log::warn!("Abandoning {} unfinished background tasks", abandoned);
+        }
+        handle.stop(true).await;
+    });
+
+    server.await?;
     Ok(())
 }
 
+async fn wait_for_shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use actix_web::rt::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                futures_util::future::select(
+                    Box::pin(actix_web::rt::signal::ctrl_c()),
+                    Box::pin(sigterm.recv()),
+                )
+                .await;
+                return;
+            }
+            Err(e) => log::warn!("Failed to listen for SIGTERM: {e}"),
+        }
+    }
+    if let Err(e) = actix_web::rt::signal::ctrl_c().await {
+        log::error!("Failed to listen for Ctrl-C: {e}");
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::{from_fn, require_admin, Key};
     use actix_web::web::Data;
-    use actix_web::{test, web, App};
+    use actix_web::{test, web, App, HttpRequest};
     use anyhow::Result;
     use async_trait::async_trait;
     use chrono::{DateTime, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc};
     use chrono_tz::America::New_York;
     use scraper::{Html, Selector};
-    use somerville_events::database::EventsRepo;
+    use somerville_events::auth::LoginThrottle;
+    use somerville_events::background_tasks::ConcurrencyLimit;
+    use somerville_events::config::{
+        ApiTimeouts, EventDurations, DEFAULT_GEOCODING_CONCURRENCY, DEFAULT_PAST_EVENT_WINDOW,
+        DEFAULT_TIMEZONE, DEFAULT_UPLOAD_CONCURRENCY,
+    };
+    use somerville_events::contact_relay::ContactRelay;
+    use somerville_events::database::{EventsRepo, SaveOutcome};
+    use somerville_events::features;
     use somerville_events::features::view::IndexQuery;
+    use somerville_events::index_cache::IndexCache;
     use somerville_events::models::{
-        Event, EventSource, EventType, LocationOption, NewEvent, SimpleEvent,
+        normalize_tag, DeletedEvent, Event, EventSource, EventStatus, EventType, LocationOption,
+        NewContactMessage, NewEvent, NewUserReport, PendingEvent, RelatedEvent, SimpleEvent,
+        SourceInfo, Submitter, UserReport, Venue,
     };
+    use somerville_events::webhooks::Webhooks;
     use somerville_events::AppState;
+    use std::collections::HashMap;
+    use std::net::{IpAddr, SocketAddr};
     use std::sync::{Arc, Mutex};
 
+    type ProcessedImage = (u64, Vec<i64>);
+    type TrashedEvent = (Event, DateTime<Utc>);
+    type SentContactMessage = (NewContactMessage, DateTime<Utc>);
+
     #[derive(Clone, Default)]
     pub struct MockEventsRepo {
         pub events: Arc<Mutex<Vec<Event>>>,
         pub next_id: Arc<Mutex<i64>>,
+        /// Each report alongside the address it came from.
+        pub reports: Arc<Mutex<Vec<(UserReport, String)>>>,
+        /// Each processed image's hash with the events it produced.
+        pub processed_images: Arc<Mutex<Vec<ProcessedImage>>>,
+        /// Deleted events with when they were deleted.
+        pub trash: Arc<Mutex<Vec<TrashedEvent>>>,
+        /// Submitted events waiting for approval, kept out of `events`.
+        pub pending: Arc<Mutex<Vec<Event>>>,
+        /// Who sent each submitted event in.
+        pub submitters: Arc<Mutex<Vec<(i64, Submitter)>>>,
+        /// Each claimed upload key, with whether it succeeded once it's
+        /// finished.
+        pub idempotency_keys: Arc<Mutex<HashMap<uuid::Uuid, Option<bool>>>>,
+        /// Each message relayed to an organizer, with when it was sent.
+        pub contact_messages: Arc<Mutex<Vec<SentContactMessage>>>,
     }
 
     impl MockEventsRepo {
@@ -127,6 +359,51 @@ mod tests {
             Self {
                 events: Arc::new(Mutex::new(events)),
                 next_id: Arc::new(Mutex::new(max_id)),
+                reports: Arc::default(),
+                processed_images: Arc::default(),
+                trash: Arc::default(),
+                pending: Arc::default(),
+                submitters: Arc::default(),
+                idempotency_keys: Arc::default(),
+                contact_messages: Arc::default(),
+            }
+        }
+
+        fn new_stored_event(&self, event: &NewEvent) -> Event {
+            let mut id_guard = self.next_id.lock().unwrap();
+            *id_guard += 1;
+            let id = *id_guard;
+
+            let now = Utc::now();
+            Event {
+                id,
+                created_at: now,
+                updated_at: now,
+                name: event.name.clone(),
+                description: event.description.clone(),
+                full_text: event.full_text.clone(),
+                start_date: event.start_date,
+                end_date: event.end_date,
+                all_day: event.all_day,
+                address: event.address.clone(),
+                original_location: event.original_location.clone(),
+                google_place_id: event.google_place_id.clone(),
+                lat: event.lat,
+                lng: event.lng,
+                location_name: event.location_name.clone(),
+                event_types: event.event_types.clone(),
+                tags: event.tags.clone(),
+                url: event.url.clone(),
+                confidence: event.confidence,
+                age_restrictions: event.age_restrictions.clone(),
+                price: event.price,
+                source: event.source.clone(),
+                external_id: event.external_id.clone(),
+                contact_email: event.contact_email.clone(),
+                contact_phone: event.contact_phone.clone(),
+                registration_required: event.registration_required,
+                featured: false,
+                status: EventStatus::Scheduled,
             }
         }
     }
@@ -163,16 +440,36 @@ mod tests {
                     } else {
                         true
                     };
-                    type_match && source_match && since_match && until_match
+                    let tag_match = query
+                        .tag
+                        .as_deref()
+                        .and_then(normalize_tag)
+                        .is_none_or(|tag| e.tags.contains(&tag));
+                    let location_match = query.location.is_empty()
+                        || e.google_place_id
+                            .as_ref()
+                            .is_some_and(|id| query.location.contains(id));
+                    type_match
+                        && source_match
+                        && since_match
+                        && until_match
+                        && tag_match
+                        && location_match
                 })
                 .map(|e| SimpleEvent {
                     id: e.id,
+                    updated_at: e.updated_at,
                     name: e.name,
                     start_date: e.start_date,
                     end_date: e.end_date,
+                    all_day: e.all_day,
                     original_location: e.original_location,
                     location_name: e.location_name,
+                    lat: e.lat,
+                    lng: e.lng,
                     event_types: e.event_types,
+                    confidence: e.confidence,
+                    status: e.status,
                 })
                 .collect())
         }
@@ -207,7 +504,12 @@ mod tests {
                     } else {
                         true
                     };
-                    type_match && source_match && since_match && until_match
+                    let tag_match = query
+                        .tag
+                        .as_deref()
+                        .and_then(normalize_tag)
+                        .is_none_or(|tag| e.tags.contains(&tag));
+                    type_match && source_match && since_match && until_match && tag_match
                 })
                 .collect())
         }
@@ -248,158 +550,626 @@ mod tests {
                 .cloned())
         }
 
-        async fn claim_idempotency_key(&self, _idempotency_key: uuid::Uuid) -> Result<bool> {
-            Ok(true)
+        async fn get_source(&self, _source: &EventSource) -> Result<Option<SourceInfo>> {
+            // No source table here.
+            Ok(None)
         }
 
-        async fn insert(&self, event: &NewEvent) -> Result<i64> {
-            let mut id_guard = self.next_id.lock().unwrap();
-            *id_guard += 1;
-            let id = *id_guard;
+        async fn get_venue(&self, google_place_id: &str) -> Result<Option<Venue>> {
+            // No venues table here; the first event at the place stands in.
+            Ok(self
+                .events
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|e| e.google_place_id.as_deref() == Some(google_place_id))
+                .map(|e| Venue {
+                    google_place_id: google_place_id.to_string(),
+                    name: e
+                        .location_name
+                        .clone()
+                        .or(e.address.clone())
+                        .unwrap_or_else(|| google_place_id.to_string()),
+                    address: e.address.clone(),
+                    lat: e.lat,
+                    lng: e.lng,
+                }))
+        }
 
-            let now = Utc::now();
-            let stored = Event {
-                id,
-                created_at: now,
-                updated_at: now,
-                name: event.name.clone(),
-                description: event.description.clone(),
-                full_text: event.full_text.clone(),
-                start_date: event.start_date,
-                end_date: event.end_date,
-                address: event.address.clone(),
-                original_location: event.original_location.clone(),
-                google_place_id: event.google_place_id.clone(),
-                location_name: event.location_name.clone(),
-                event_types: event.event_types.clone(),
-                url: event.url.clone(),
-                confidence: event.confidence,
-                age_restrictions: event.age_restrictions.clone(),
-                price: event.price,
-                source: event.source.clone(),
-                external_id: event.external_id.clone(),
-            };
-            self.events.lock().unwrap().push(stored);
-            Ok(id)
+        async fn list_all_after(&self, after_id: i64, limit: i64) -> Result<Vec<Event>> {
+            let mut events: Vec<Event> = self
+                .events
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|e| e.id > after_id)
+                .cloned()
+                .collect();
+            events.sort_by_key(|e| e.id);
+            events.truncate(usize::try_from(limit).unwrap_or(0));
+            Ok(events)
         }
 
-        async fn delete(&self, id: i64) -> Result<()> {
-            let mut events = self.events.lock().unwrap();
-            let len_before = events.len();
-            events.retain(|e| e.id != id);
-            if events.len() == len_before {
-                return Err(anyhow::anyhow!("Event not found"));
+        async fn claim_idempotency_key(
+            &self,
+            idempotency_key: uuid::Uuid,
+            _submitter: &Submitter,
+        ) -> Result<bool> {
+            // No clock here, so a claim never goes stale.
+            let mut keys = self.idempotency_keys.lock().unwrap();
+            match keys.get(&idempotency_key) {
+                Some(None | Some(true)) => Ok(false),
+                Some(Some(false)) | None => {
+                    keys.insert(idempotency_key, None);
+                    Ok(true)
+                }
             }
+        }
+
+        async fn finish_idempotency_key(
+            &self,
+            idempotency_key: uuid::Uuid,
+            succeeded: bool,
+        ) -> Result<()> {
+            self.idempotency_keys
+                .lock()
+                .unwrap()
+                .insert(idempotency_key, Some(succeeded));
             Ok(())
         }
-    }
 
-    #[actix_web::test]
-    async fn test_index_filters_by_category() -> Result<()> {
-        // 2025-01-15 17:00:00 UTC = 12:00:00 EST
-        let now_utc = Utc.with_ymd_and_hms(2025, 1, 15, 17, 0, 0).unwrap();
+        async fn insert(&self, event: &NewEvent) -> Result<SaveOutcome> {
+            let stored = self.new_stored_event(event);
+            let id = stored.id;
+            self.events.lock().unwrap().push(stored);
+            Ok(SaveOutcome::Inserted(id))
+        }
 
-        // Helper to create a NY datetime
-        let mk_ny = |d, h, m| New_York.with_ymd_and_hms(2025, 1, d, h, m, 0).unwrap();
+        async fn insert_submitted(
+            &self,
+            event: &NewEvent,
+            submitter: &Submitter,
+            pending: bool,
+        ) -> Result<SaveOutcome> {
+            let stored = self.new_stored_event(event);
+            let id = stored.id;
+            if pending {
+                self.pending.lock().unwrap().push(stored);
+            } else {
+                self.events.lock().unwrap().push(stored);
+            }
+            if *submitter != Submitter::default() {
+                self.submitters
+                    .lock()
+                    .unwrap()
+                    .push((id, submitter.clone()));
+            }
+            Ok(SaveOutcome::Inserted(id))
+        }
 
-        let art_event = Event {
-            id: 1,
-            created_at: now_utc,
-            updated_at: now_utc,
-            name: "Art Show".to_string(),
-            description: "Paintings galore".to_string(),
-            full_text: "Paintings galore".to_string(),
-            start_date: mk_ny(15, 11, 0).with_timezone(&Utc),
-            end_date: None,
-            address: Some("Gallery".to_string()),
-            original_location: Some("Gallery".to_string()),
-            google_place_id: None,
-            location_name: None,
-            event_types: vec![EventType::Art],
-            url: None,
-            confidence: 1.0,
-            age_restrictions: None,
-            price: None,
-            source: EventSource::ImageUpload,
-            external_id: None,
-        };
+        async fn list_pending(&self) -> Result<Vec<PendingEvent>> {
+            let submitters = self.submitters.lock().unwrap();
+            Ok(self
+                .pending
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|e| {
+                    let submitter = submitters
+                        .iter()
+                        .find(|(id, _)| *id == e.id)
+                        .map(|(_, submitter)| submitter.clone())
+                        .unwrap_or_default();
+                    PendingEvent {
+                        id: e.id,
+                        name: e.name.clone(),
+                        description: e.description.clone(),
+                        start_date: e.start_date,
+                        all_day: e.all_day,
+                        original_location: e.original_location.clone(),
+                        location_name: e.location_name.clone(),
+                        submitter_name: submitter.name,
+                        submitter_email: submitter.email,
+                        created_at: e.created_at,
+                    }
+                })
+                .collect())
+        }
 
-        let music_event = Event {
-            id: 2,
-            created_at: now_utc,
-            updated_at: now_utc,
-            name: "Music Night".to_string(),
-            description: "Jazz and blues".to_string(),
-            full_text: "Jazz and blues".to_string(),
-            start_date: mk_ny(15, 19, 0).with_timezone(&Utc),
-            end_date: None,
-            address: Some("Club".to_string()),
-            original_location: Some("Club".to_string()),
-            google_place_id: None,
-            location_name: None,
-            event_types: vec![EventType::Music],
-            url: None,
-            confidence: 1.0,
-            age_restrictions: None,
-            price: None,
-            source: EventSource::ImageUpload,
-            external_id: None,
-        };
+        async fn approve(&self, ids: &[i64]) -> Result<Vec<i64>> {
+            let mut pending = self.pending.lock().unwrap();
+            let (approved, waiting): (Vec<Event>, Vec<Event>) =
+                pending.drain(..).partition(|e| ids.contains(&e.id));
+            *pending = waiting;
+            let approved_ids = approved.iter().map(|e| e.id).collect();
+            self.events.lock().unwrap().extend(approved);
+            Ok(approved_ids)
+        }
 
-        let state = AppState {
-            openai_api_key: "dummy".to_string(),
-            google_maps_api_key: "dummy".to_string(),
-            username: "user".to_string(),
-            password: "pass".to_string(),
-            events_repo: Box::new(MockEventsRepo::new(vec![art_event.clone(), music_event])),
-        };
+        async fn get_submitter(&self, id: i64) -> Result<Option<Submitter>> {
+            Ok(self
+                .submitters
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|(event_id, _)| *event_id == id)
+                .map(|(_, submitter)| submitter.clone()))
+        }
 
-        let fixed_now_utc = now_utc;
-        let app = test::init_service(App::new().app_data(Data::new(state)).route(
-            "/",
-            web::get().to(move |state: Data<AppState>| {
-                somerville_events::features::view::index_with_now(
-                    state,
-                    fixed_now_utc,
-                    IndexQuery {
-                        event_types: vec![EventType::Art],
-                        source: vec![],
-                        past: None,
-                        ..Default::default()
-                    },
-                )
-            }),
-        ))
-        .await;
+        async fn delete(&self, id: i64) -> Result<()> {
+            // Rejecting a submission deletes it from the queue.
+            let mut pending = self.pending.lock().unwrap();
+            let mut events = self.events.lock().unwrap();
+            let event = if let Some(index) = events.iter().position(|e| e.id == id) {
+                events.remove(index)
+            } else if let Some(index) = pending.iter().position(|e| e.id == id) {
+                pending.remove(index)
+            } else {
+                return Err(anyhow::anyhow!("Event not found"));
+            };
+            self.trash.lock().unwrap().push((event, Utc::now()));
+            Ok(())
+        }
 
-        let req = test::TestRequest::get().uri("/?type=art").to_request();
-        let resp = test::call_service(&app, req).await;
-        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        async fn delete_many(&self, ids: &[i64]) -> Result<u64> {
+            let mut deleted = 0;
+            for &id in ids {
+                if self.delete(id).await.is_ok() {
+                    deleted += 1;
+                }
+            }
+            Ok(deleted)
+        }
 
-        let body = test::read_body(resp).await;
-        let body_str = std::str::from_utf8(&body)?;
+        async fn set_event_types(&self, ids: &[i64], event_types: &[EventType]) -> Result<u64> {
+            let mut events = self.events.lock().unwrap();
+            let mut updated = 0;
+            for event in events.iter_mut().filter(|e| ids.contains(&e.id)) {
+                event.event_types = event_types.to_vec();
+                event.updated_at = Utc::now();
+                updated += 1;
+            }
+            Ok(updated)
+        }
 
-        assert!(body_str.contains("Art Show"));
-        assert!(!body_str.contains("Music Night"));
-        // Icon for Art should be present
-        assert!(body_str.contains("icon-palette"));
-        assert!(!body_str.contains("Somerville Art Events"));
-        assert!(body_str.contains("Somerville Events"));
+        async fn restore(&self, id: i64) -> Result<()> {
+            let mut trash = self.trash.lock().unwrap();
+            let index = trash
+                .iter()
+                .position(|(e, _)| e.id == id)
+                .ok_or_else(|| anyhow::anyhow!("Event isn't in the trash"))?;
+            let (event, _) = trash.remove(index);
+            self.events.lock().unwrap().push(event);
+            Ok(())
+        }
 
-        Ok(())
-    }
+        async fn list_deleted(&self) -> Result<Vec<DeletedEvent>> {
+            let mut deleted: Vec<DeletedEvent> = self
+                .trash
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(e, deleted_at)| DeletedEvent {
+                    id: e.id,
+                    name: e.name.clone(),
+                    start_date: e.start_date,
+                    deleted_at: *deleted_at,
+                })
+                .collect();
+            deleted.reverse();
+            Ok(deleted)
+        }
 
-    #[actix_web::test]
-    async fn test_index() -> Result<()> {
-        let now_utc = Utc.with_ymd_and_hms(2025, 1, 15, 17, 0, 0).unwrap();
-        let today_local = now_utc.with_timezone(&New_York).date_naive();
-        let yesterday_local = today_local.pred_opt().unwrap();
-        let tomorrow_local = today_local.succ_opt().unwrap();
-        let day_after_tomorrow_local = tomorrow_local.succ_opt().unwrap();
+        async fn merge(&self, from_id: i64, into_id: i64) -> Result<()> {
+            if !self.events.lock().unwrap().iter().any(|e| e.id == into_id) {
+                return Err(anyhow::anyhow!("Event not found"));
+            }
+            self.delete(from_id).await
+        }
 
-        let mk_local = |d: NaiveDateTime| New_York.from_local_datetime(&d).single().unwrap();
-        let local_dt =
-            |date, h, m| NaiveDateTime::new(date, NaiveTime::from_hms_opt(h, m, 0).unwrap());
+        async fn list_featured(&self, now: DateTime<Utc>) -> Result<Vec<SimpleEvent>> {
+            let mut events: Vec<Event> = self
+                .events
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|e| {
+                    e.featured
+                        && e.end_date
+                            .unwrap_or(e.start_date + chrono::Duration::days(1))
+                            >= now
+                })
+                .cloned()
+                .collect();
+            events.sort_by_key(|e| (e.start_date, e.id));
+            Ok(events
+                .into_iter()
+                .map(|e| SimpleEvent {
+                    id: e.id,
+                    updated_at: e.updated_at,
+                    name: e.name,
+                    start_date: e.start_date,
+                    end_date: e.end_date,
+                    all_day: e.all_day,
+                    original_location: e.original_location,
+                    location_name: e.location_name,
+                    lat: e.lat,
+                    lng: e.lng,
+                    event_types: e.event_types,
+                    confidence: e.confidence,
+                    status: e.status,
+                })
+                .collect())
+        }
+
+        async fn list_ordered_by_confidence(&self) -> Result<Vec<SimpleEvent>> {
+            let mut events = self.list(IndexQuery::default(), None, None).await?;
+            events.sort_by(|a, b| {
+                a.confidence
+                    .total_cmp(&b.confidence)
+                    .then_with(|| (a.start_date, a.id).cmp(&(b.start_date, b.id)))
+            });
+            Ok(events)
+        }
+
+        async fn list_related(
+            &self,
+            event_id: i64,
+            now: DateTime<Utc>,
+            limit: i64,
+        ) -> Result<Vec<RelatedEvent>> {
+            let events = self.list(IndexQuery::default(), None, None).await?;
+            let stored = self.events.lock().unwrap().clone();
+            let place_id = |id: i64| {
+                stored
+                    .iter()
+                    .find(|e| e.id == id)
+                    .and_then(|e| e.google_place_id.clone())
+            };
+            let Some(current) = events.iter().find(|e| e.id == event_id).cloned() else {
+                return Ok(Vec::new());
+            };
+            let current_place = place_id(event_id);
+
+            let mut candidates: Vec<(usize, RelatedEvent)> = events
+                .into_iter()
+                .filter(|e| {
+                    e.id != event_id
+                        && e.end_date
+                            .unwrap_or(e.start_date + chrono::Duration::days(1))
+                            >= now
+                })
+                .map(|e| {
+                    let mut shared: Vec<EventType> = e
+                        .event_types
+                        .iter()
+                        .filter(|t| current.event_types.contains(t))
+                        .cloned()
+                        .collect();
+                    shared.sort_by_key(EventType::value);
+                    let same_venue = current_place.is_some() && place_id(e.id) == current_place;
+                    (
+                        shared.len(),
+                        RelatedEvent {
+                            event: e,
+                            same_venue,
+                            shared_type: shared.into_iter().next(),
+                        },
+                    )
+                })
+                .filter(|(count, r)| r.same_venue || *count > 0)
+                .collect();
+            candidates.sort_by_key(|(count, r)| {
+                let count = if r.same_venue { 0 } else { *count };
+                (
+                    !r.same_venue,
+                    std::cmp::Reverse(count),
+                    r.event.start_date,
+                    r.event.id,
+                )
+            });
+
+            let limit = usize::try_from(limit)?;
+            let (at_venue, by_type): (Vec<_>, Vec<_>) = candidates
+                .into_iter()
+                .map(|(_, r)| r)
+                .partition(|r| r.same_venue);
+            Ok(at_venue
+                .into_iter()
+                .take(limit)
+                .chain(by_type.into_iter().take(limit))
+                .collect())
+        }
+
+        async fn set_featured(&self, id: i64, featured: bool) -> Result<()> {
+            let mut events = self.events.lock().unwrap();
+            let event = events
+                .iter_mut()
+                .find(|e| e.id == id)
+                .ok_or_else(|| anyhow::anyhow!("Event not found"))?;
+            event.featured = featured;
+            event.updated_at = Utc::now();
+            Ok(())
+        }
+
+        async fn set_status(&self, id: i64, status: EventStatus) -> Result<()> {
+            let mut events = self.events.lock().unwrap();
+            let event = events
+                .iter_mut()
+                .find(|e| e.id == id)
+                .ok_or_else(|| anyhow::anyhow!("Event not found"))?;
+            event.status = status;
+            event.updated_at = Utc::now();
+            Ok(())
+        }
+
+        async fn insert_report(&self, report: &NewUserReport) -> Result<i64> {
+            let event_name = self
+                .events
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|e| e.id == report.event_id)
+                .map(|e| e.name.clone())
+                .ok_or_else(|| anyhow::anyhow!("Event not found"))?;
+            let mut reports = self.reports.lock().unwrap();
+            let id = reports.len() as i64 + 1;
+            reports.push((
+                UserReport {
+                    id,
+                    event_id: report.event_id,
+                    event_name,
+                    reason: report.reason.clone(),
+                    email: report.email.clone(),
+                    created_at: Utc::now(),
+                },
+                report.reporter_ip.clone(),
+            ));
+            Ok(id)
+        }
+
+        async fn count_reports_since(
+            &self,
+            reporter_ip: &str,
+            since: DateTime<Utc>,
+        ) -> Result<i64> {
+            Ok(self
+                .reports
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(r, ip)| ip == reporter_ip && r.created_at >= since)
+                .count() as i64)
+        }
+
+        async fn insert_contact_message(&self, message: &NewContactMessage) -> Result<i64> {
+            let mut messages = self.contact_messages.lock().unwrap();
+            messages.push((message.clone(), Utc::now()));
+            Ok(messages.len() as i64)
+        }
+
+        async fn count_contact_messages_since(
+            &self,
+            sender_ip: &str,
+            since: DateTime<Utc>,
+        ) -> Result<i64> {
+            Ok(self
+                .contact_messages
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(m, at)| m.sender_ip == sender_ip && *at >= since)
+                .count() as i64)
+        }
+
+        async fn count_contact_messages_to_event_since(
+            &self,
+            event_id: i64,
+            sender_ip: &str,
+            since: DateTime<Utc>,
+        ) -> Result<i64> {
+            Ok(self
+                .contact_messages
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(m, at)| {
+                    m.event_id == event_id && m.sender_ip == sender_ip && *at >= since
+                })
+                .count() as i64)
+        }
+
+        async fn list_reports(&self) -> Result<Vec<UserReport>> {
+            let events = self.events.lock().unwrap();
+            let mut reports: Vec<UserReport> = self
+                .reports
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(r, _)| events.iter().any(|e| e.id == r.event_id))
+                .map(|(r, _)| r.clone())
+                .collect();
+            reports.reverse();
+            Ok(reports)
+        }
+
+        async fn delete_report(&self, id: i64) -> Result<()> {
+            self.reports.lock().unwrap().retain(|(r, _)| r.id != id);
+            Ok(())
+        }
+
+        async fn find_processed_image(
+            &self,
+            dhash: u64,
+            max_distance: u32,
+        ) -> Result<Option<Vec<i64>>> {
+            // Pending and deleted events are kept out of `events`.
+            let events = self.events.lock().unwrap();
+            let listed = |event_ids: &Vec<i64>| -> Vec<i64> {
+                event_ids
+                    .iter()
+                    .copied()
+                    .filter(|id| events.iter().any(|e| e.id == *id))
+                    .collect()
+            };
+            Ok(self
+                .processed_images
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(hash, _)| (hash ^ dhash).count_ones() <= max_distance)
+                .filter(|(_, event_ids)| event_ids.is_empty() || !listed(event_ids).is_empty())
+                .min_by_key(|(hash, _)| (hash ^ dhash).count_ones())
+                .map(|(_, event_ids)| listed(event_ids)))
+        }
+
+        async fn record_processed_image(&self, dhash: u64, event_ids: &[i64]) -> Result<()> {
+            self.processed_images
+                .lock()
+                .unwrap()
+                .push((dhash, event_ids.to_vec()));
+            Ok(())
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_index_filters_by_category() -> Result<()> {
+        // 2025-01-15 17:00:00 UTC = 12:00:00 EST
+        let now_utc = Utc.with_ymd_and_hms(2025, 1, 15, 17, 0, 0).unwrap();
+
+        // Helper to create a NY datetime
+        let mk_ny = |d, h, m| New_York.with_ymd_and_hms(2025, 1, d, h, m, 0).unwrap();
+
+        let art_event = Event {
+            id: 1,
+            created_at: now_utc,
+            updated_at: now_utc,
+            name: "Art Show".to_string(),
+            description: "Paintings galore".to_string(),
+            full_text: "Paintings galore".to_string(),
+            start_date: mk_ny(15, 11, 0).with_timezone(&Utc),
+            end_date: None,
+            all_day: false,
+            address: Some("Gallery".to_string()),
+            original_location: Some("Gallery".to_string()),
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Art],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        let music_event = Event {
+            id: 2,
+            created_at: now_utc,
+            updated_at: now_utc,
+            name: "Music Night".to_string(),
+            description: "Jazz and blues".to_string(),
+            full_text: "Jazz and blues".to_string(),
+            start_date: mk_ny(15, 19, 0).with_timezone(&Utc),
+            end_date: None,
+            all_day: false,
+            address: Some("Club".to_string()),
+            original_location: Some("Club".to_string()),
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Music],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![art_event.clone(), music_event])),
+        };
+
+        let fixed_now_utc = now_utc;
+        let app = test::init_service(App::new().app_data(Data::new(state)).route(
+            "/",
+            web::get().to(move |req: HttpRequest, state: Data<AppState>| {
+                somerville_events::features::view::index_with_now(
+                    req,
+                    state,
+                    fixed_now_utc,
+                    IndexQuery {
+                        event_types: vec![EventType::Art],
+                        source: vec![],
+                        past: None,
+                        ..Default::default()
+                    },
+                )
+            }),
+        ))
+        .await;
+
+        let req = test::TestRequest::get().uri("/?type=art").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        let body = test::read_body(resp).await;
+        let body_str = std::str::from_utf8(&body)?;
+
+        assert!(body_str.contains("Art Show"));
+        assert!(!body_str.contains("Music Night"));
+        // Icon for Art should be present
+        assert!(body_str.contains("icon-palette"));
+        assert!(!body_str.contains("Somerville Art Events"));
+        assert!(body_str.contains("Somerville Events"));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_index() -> Result<()> {
+        let now_utc = Utc.with_ymd_and_hms(2025, 1, 15, 17, 0, 0).unwrap();
+        let today_local = now_utc.with_timezone(&New_York).date_naive();
+        let yesterday_local = today_local.pred_opt().unwrap();
+        let tomorrow_local = today_local.succ_opt().unwrap();
+        let day_after_tomorrow_local = tomorrow_local.succ_opt().unwrap();
+
+        let mk_local = |d: NaiveDateTime| New_York.from_local_datetime(&d).single().unwrap();
+        let local_dt =
+            |date, h, m| NaiveDateTime::new(date, NaiveTime::from_hms_opt(h, m, 0).unwrap());
 
         let past_event = Event {
             id: 1,
@@ -410,20 +1180,30 @@ mod tests {
             full_text: "Should not render".to_string(),
             start_date: mk_local(local_dt(yesterday_local, 10, 0)).with_timezone(&Utc),
             end_date: Some(mk_local(local_dt(yesterday_local, 11, 0)).with_timezone(&Utc)),
+            all_day: false,
             address: Some("Somewhere".to_string()),
             original_location: Some("Somewhere".to_string()),
             google_place_id: None,
+            lat: None,
+            lng: None,
             location_name: None,
             event_types: vec![],
+            tags: vec![],
             url: None,
             confidence: 1.0,
             age_restrictions: None,
             price: None,
             source: EventSource::ImageUpload,
             external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
         };
 
-        // No end_date: should render only on its start day.
+        // No end_date: should render only on its start day, and still be
+        // on at noon since a yard sale runs for hours.
         let ongoing_no_end = Event {
             id: 2,
             created_at: now_utc,
@@ -433,21 +1213,30 @@ mod tests {
             full_text: "Should render once".to_string(),
             start_date: mk_local(local_dt(today_local, 9, 0)).with_timezone(&Utc),
             end_date: None,
+            all_day: false,
             address: Some("Somerville".to_string()),
             original_location: Some("Somerville".to_string()),
             google_place_id: None,
+            lat: None,
+            lng: None,
             location_name: None,
-            event_types: vec![],
+            event_types: vec![EventType::YardSale],
+            tags: vec![],
             url: None,
             confidence: 1.0,
             age_restrictions: None,
             price: None,
             source: EventSource::ImageUpload,
             external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
         };
 
-        // No end_date from yesterday (within the last 24h) should still render, and should
-        // cause a "yesterday" heading to appear.
+        // No end_date, and yesterday afternoon: over by now whatever its
+        // type, so neither it nor a "yesterday" heading shows.
         let yesterday_no_end = Event {
             id: 7,
             created_at: now_utc,
@@ -457,17 +1246,26 @@ mod tests {
             full_text: "Should render under yesterday".to_string(),
             start_date: mk_local(local_dt(yesterday_local, 15, 0)).with_timezone(&Utc),
             end_date: None,
+            all_day: false,
             address: Some("Somerville".to_string()),
             original_location: Some("Somerville".to_string()),
             google_place_id: None,
+            lat: None,
+            lng: None,
             location_name: None,
             event_types: vec![],
+            tags: vec![],
             url: None,
             confidence: 1.0,
             age_restrictions: None,
             price: None,
             source: EventSource::ImageUpload,
             external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
         };
 
         // Two distinct events on the same local day should both render under the same day section.
@@ -479,19 +1277,28 @@ mod tests {
             description: "First event on the same day".to_string(),
             full_text: "First event on the same day".to_string(),
             start_date: mk_local(local_dt(today_local, 10, 0)).with_timezone(&Utc),
-            // No end_date so this test doesn't become time-of-day dependent.
+            // No end_date; a market runs well past the fixed noon "now".
             end_date: None,
+            all_day: false,
             address: Some("Union".to_string()),
             original_location: Some("Union".to_string()),
             google_place_id: None,
+            lat: None,
+            lng: None,
             location_name: None,
-            event_types: vec![],
+            event_types: vec![EventType::Market],
+            tags: vec![],
             url: None,
             confidence: 1.0,
             age_restrictions: None,
             price: None,
             source: EventSource::ImageUpload,
             external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
         };
 
         let same_day_2 = Event {
@@ -504,17 +1311,26 @@ mod tests {
             start_date: mk_local(local_dt(today_local, 12, 0)).with_timezone(&Utc),
             // No end_date so this test doesn't become time-of-day dependent.
             end_date: None,
+            all_day: false,
             address: Some("Magoun".to_string()),
             original_location: Some("Magoun".to_string()),
             google_place_id: None,
+            lat: None,
+            lng: None,
             location_name: None,
             event_types: vec![],
+            tags: vec![],
             url: None,
             confidence: 1.0,
             age_restrictions: None,
             price: None,
             source: EventSource::ImageUpload,
             external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
         };
 
         // Explicit multi-day: should appear under each day.
@@ -527,24 +1343,42 @@ mod tests {
             full_text: "Spans multiple days".to_string(),
             start_date: mk_local(local_dt(tomorrow_local, 12, 0)).with_timezone(&Utc),
             end_date: Some(mk_local(local_dt(day_after_tomorrow_local, 13, 0)).with_timezone(&Utc)),
+            all_day: false,
             address: Some("Davis".to_string()),
             original_location: Some("Davis".to_string()),
             google_place_id: None,
+            lat: None,
+            lng: None,
             location_name: None,
             event_types: vec![],
+            tags: vec![],
             url: None,
             confidence: 1.0,
             age_restrictions: None,
             price: None,
             source: EventSource::ImageUpload,
             external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
         };
 
         // Intentionally shuffled to ensure server-side sorting/grouping is doing the work.
+        // Started at the same time, but a meeting is over in an hour.
+        let morning_meeting = Event {
+            id: 8,
+            name: "Morning Meeting".to_string(),
+            event_types: vec![EventType::Meeting],
+            ..ongoing_no_end.clone()
+        };
+
         let mock_repo = MockEventsRepo::new(vec![
             multi_day,
             past_event,
             same_day_2,
+            morning_meeting,
             ongoing_no_end,
             same_day_1,
             yesterday_no_end,
@@ -553,16 +1387,30 @@ mod tests {
         let state = AppState {
             openai_api_key: "dummy".to_string(),
             google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
             username: "user".to_string(),
             password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
             events_repo: Box::new(mock_repo),
         };
 
         let fixed_now_utc = now_utc;
         let app = test::init_service(App::new().app_data(Data::new(state)).route(
             "/",
-            web::get().to(move |state: Data<AppState>| {
+            web::get().to(move |req: HttpRequest, state: Data<AppState>| {
                 somerville_events::features::view::index_with_now(
+                    req,
                     state,
                     fixed_now_utc,
                     IndexQuery {
@@ -613,15 +1461,15 @@ mod tests {
             "Missing day-after-tomorrow heading id; got day_ids={day_ids:?}"
         );
         assert!(
-            day_ids.contains(&format!("day-{}", yesterday_local.format("%Y-%m-%d"))),
-            "Expected yesterday heading due to a no-end event within 24h; got day_ids={day_ids:?}"
+            !day_ids.contains(&format!("day-{}", yesterday_local.format("%Y-%m-%d"))),
+            "Expected no yesterday heading; got day_ids={day_ids:?}"
         );
 
         // No end_date events should only render once (on their start day).
         let occurrences_ongoing = body_str.matches("Ongoing No End").count();
         assert_eq!(occurrences_ongoing, 1);
-        let occurrences_yesterday_no_end = body_str.matches("Yesterday No End").count();
-        assert_eq!(occurrences_yesterday_no_end, 1);
+        assert!(!body_str.contains("Yesterday No End"));
+        assert!(!body_str.contains("Morning Meeting"));
 
         // Multiple events on the same day should show up under the same day section.
         let today_id = format!("day-{}", today_local.format("%Y-%m-%d"));
@@ -664,6 +1512,32 @@ mod tests {
             "Expected section to contain event link"
         );
 
+        // Every card is a schema.org Event with its own name, time and URL.
+        let cards: Vec<_> = document.select(&event_link_sel).collect();
+        let item_sel =
+            Selector::parse(r#"[itemscope][itemtype="https://schema.org/Event"]"#).unwrap();
+        assert_eq!(document.select(&item_sel).count(), cards.len());
+        for prop in [
+            r#"link[itemprop="url"]"#,
+            r#"[itemprop="name"]"#,
+            r#"time[itemprop="startDate"][datetime]"#,
+        ] {
+            let sel = Selector::parse(prop).unwrap();
+            assert!(
+                cards.iter().all(|card| card.select(&sel).next().is_some()),
+                "{prop}"
+            );
+        }
+        let ongoing = cards
+            .iter()
+            .find(|card| card.value().attr("href") == Some("/event/2"))
+            .unwrap();
+        let location = ongoing
+            .select(&Selector::parse(r#"[itemprop="location"] [itemprop="name"]"#).unwrap())
+            .next()
+            .expect("location");
+        assert_eq!(location.text().collect::<String>(), "Somerville");
+
         Ok(())
     }
 
@@ -680,25 +1554,58 @@ mod tests {
             full_text: "Description for ICal".to_string(),
             start_date: today_start.with_hour(10).unwrap().with_timezone(&Utc),
             end_date: Some(today_start.with_hour(11).unwrap().with_timezone(&Utc)),
+            all_day: false,
             address: Some("Virtual".to_string()),
             original_location: Some("Virtual".to_string()),
             google_place_id: None,
+            lat: None,
+            lng: None,
             location_name: None,
             event_types: vec![],
+            tags: vec![],
             url: Some("http://example.com/event".to_string()),
             confidence: 1.0,
             age_restrictions: None,
             price: None,
             source: EventSource::ImageUpload,
             external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
         };
 
         let state = AppState {
             openai_api_key: "dummy".to_string(),
             google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
             username: "user".to_string(),
             password: "pass".to_string(),
-            events_repo: Box::new(MockEventsRepo::new(vec![event])),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![
+                event.clone(),
+                // No end given, so it runs as long as a film does.
+                Event {
+                    id: 2,
+                    name: "Film Night".to_string(),
+                    start_date: today_start.with_hour(19).unwrap().with_timezone(&Utc),
+                    end_date: None,
+                    event_types: vec![EventType::Film],
+                    ..event
+                },
+            ])),
         };
 
         let app = test::init_service(App::new().app_data(Data::new(state)).route(
@@ -735,8 +1642,10 @@ mod tests {
         // by checking that they appear on the same line or in the expected format.
         // The icalendar crate output format is typically: DTSTART;TZID=America/New_York:20250115T100000
 
+        // Skip the VTIMEZONE, whose observances have a DTSTART too.
         let start_line = body_str
             .lines()
+            .skip_while(|l| *l != "BEGIN:VEVENT")
             .find(|l| l.starts_with("DTSTART"))
             .expect("DTSTART missing");
         assert!(
@@ -757,567 +1666,854 @@ mod tests {
 
         assert!(body_str.contains("END:VCALENDAR"));
 
+        let req = test::TestRequest::get().uri("/event/2.ics").to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        let end_line = std::str::from_utf8(&body)?
+            .lines()
+            .find(|l| l.starts_with("DTEND"))
+            .expect("DTEND missing")
+            .to_string();
+        assert!(end_line.contains("20250115T210000"), "{end_line}");
+
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_event_time_display_timezone() -> Result<()> {
+    async fn test_ical_uid_stamp_and_alarm() -> Result<()> {
+        use icalendar::Component;
+
+        let created = Utc.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        let updated = created + chrono::Duration::minutes(10);
+        let start = Utc.with_ymd_and_hms(2025, 1, 15, 15, 0, 0).unwrap();
         let event = Event {
-            id: 1,
-            created_at: Utc.with_ymd_and_hms(2025, 11, 8, 15, 30, 0).unwrap(),
-            updated_at: Utc.with_ymd_and_hms(2025, 11, 8, 15, 30, 0).unwrap(),
-            name: "Pumpkin Smash".to_string(),
-            description: "Smash pumpkins".to_string(),
-            full_text: "Smash pumpkins".to_string(),
-            // Correctly stored UTC time for 10:30 AM EST is 15:30 UTC.
-            start_date: Utc.with_ymd_and_hms(2025, 11, 8, 15, 30, 0).unwrap(),
-            end_date: Some(Utc.with_ymd_and_hms(2025, 11, 8, 18, 0, 0).unwrap()),
-            address: Some("Somerville".to_string()),
-            original_location: Some("Somerville".to_string()),
+            id: 7,
+            created_at: created,
+            updated_at: updated,
+            name: "Jazz Brunch".to_string(),
+            description: "Brunch".to_string(),
+            full_text: "Brunch".to_string(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
             google_place_id: None,
+            lat: None,
+            lng: None,
             location_name: None,
             event_types: vec![],
+            tags: vec![],
             url: None,
             confidence: 1.0,
             age_restrictions: None,
             price: None,
             source: EventSource::ImageUpload,
             external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
         };
-
         let state = AppState {
             openai_api_key: "dummy".to_string(),
             google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
             username: "user".to_string(),
             password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
             events_repo: Box::new(MockEventsRepo::new(vec![event])),
         };
-
-        let fixed_now = Utc.with_ymd_and_hms(2025, 11, 8, 8, 0, 0).unwrap();
         let app = test::init_service(App::new().app_data(Data::new(state)).route(
-            "/",
-            web::get().to(move |state: Data<AppState>| {
-                // We use fixed_now to ensure the event is considered upcoming
-                somerville_events::features::view::index_with_now(
-                    state,
-                    fixed_now,
-                    IndexQuery {
-                        event_types: vec![],
-                        source: vec![],
-                        past: None,
-                        ..Default::default()
-                    },
-                )
-            }),
+            "/event/{id}.ics",
+            web::get().to(somerville_events::features::view::ical),
         ))
         .await;
+        let download = |uri: &'static str| {
+            let app = &app;
+            async move {
+                let req = test::TestRequest::get().uri(uri).to_request();
+                let resp = test::call_service(app, req).await;
+                assert_eq!(resp.status(), actix_web::http::StatusCode::OK, "{uri}");
+                let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+                let parsed = icalendar::parser::read_calendar(&icalendar::parser::unfold(&body))
+                    .map(icalendar::Calendar::from)
+                    .unwrap();
+                let event = parsed.events().next().cloned().expect("one VEVENT");
+                event
+            }
+        };
 
-        let req = test::TestRequest::get().uri("/").to_request();
-        let resp = test::call_service(&app, req).await;
-        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
-
-        let body = test::read_body(resp).await;
-        let body_str = std::str::from_utf8(&body)?;
+        let plain = download("/event/7.ics").await;
+        // The same every time, so a second download updates the first.
+        assert_eq!(plain.get_uid(), Some("somerville-events-7"));
+        assert_eq!(plain.get_timestamp(), Some(updated));
+        assert_eq!(plain.get_sequence(), Some(600));
+        assert!(plain.components().is_empty());
+
+        let reminded = download("/event/7.ics?alarm=30m").await;
+        assert_eq!(reminded.get_uid(), Some("somerville-events-7"));
+        let alarm = reminded.components().first().expect("a VALARM");
+        assert_eq!(alarm.component_kind(), "VALARM");
+        assert_eq!(alarm.property_value("ACTION"), Some("DISPLAY"));
+        assert_eq!(alarm.property_value("TRIGGER"), Some("-PT1800S"));
+
+        for bad in ["soon", "30", "-30m", "30s", "8d"] {
+            let req = test::TestRequest::get()
+                .uri(&format!("/event/7.ics?alarm={bad}"))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(
+                resp.status(),
+                actix_web::http::StatusCode::BAD_REQUEST,
+                "{bad}"
+            );
+        }
 
-        assert!(
-            body_str.contains("10:30 AM"),
-            "Body did not contain '10:30 AM'. Content: {}",
-            body_str
-        );
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_index_filters_by_source() -> Result<()> {
-        // 2025-01-15 17:00:00 UTC = 12:00:00 EST
-        let now_utc = Utc.with_ymd_and_hms(2025, 1, 15, 17, 0, 0).unwrap();
-
-        // Helper to create a NY datetime
-        let mk_ny = |d, h, m| New_York.with_ymd_and_hms(2025, 1, d, h, m, 0).unwrap();
-
-        let aeronaut_event = Event {
-            id: 1,
-            created_at: now_utc,
-            updated_at: now_utc,
-            name: "Beer Night".to_string(),
-            description: "Drink beer".to_string(),
-            full_text: "Drink beer".to_string(),
-            start_date: mk_ny(15, 18, 0).with_timezone(&Utc),
-            end_date: None,
-            address: Some("Aeronaut".to_string()),
-            original_location: Some("Aeronaut".to_string()),
-            google_place_id: None,
-            location_name: None,
-            event_types: vec![EventType::Social],
-            url: None,
-            confidence: 1.0,
-            age_restrictions: None,
-            price: None,
-            source: somerville_events::models::EventSource::AeronautBrewing,
-            external_id: None,
-        };
-
-        let library_event = Event {
-            id: 2,
-            created_at: now_utc,
-            updated_at: now_utc,
-            name: "Reading".to_string(),
-            description: "Read books".to_string(),
-            full_text: "Read books".to_string(),
-            start_date: mk_ny(15, 19, 0).with_timezone(&Utc),
-            end_date: None,
-            address: Some("Library".to_string()),
-            original_location: Some("Library".to_string()),
-            google_place_id: None,
-            location_name: None,
-            event_types: vec![EventType::Literature],
-            url: None,
-            confidence: 1.0,
-            age_restrictions: None,
-            price: None,
-            source: somerville_events::models::EventSource::CityOfCambridge,
-            external_id: None,
-        };
-
+    async fn test_about_page_and_intro() -> Result<()> {
         let state = AppState {
             openai_api_key: "dummy".to_string(),
             google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
             username: "user".to_string(),
             password: "pass".to_string(),
-            events_repo: Box::new(MockEventsRepo::new(vec![
-                aeronaut_event.clone(),
-                library_event,
-            ])),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![])),
         };
-
-        let fixed_now_utc = now_utc;
-        let filter = vec![somerville_events::models::EventSource::AeronautBrewing];
-        let app = test::init_service(App::new().app_data(Data::new(state)).route(
-            "/",
-            web::get().to(move |state: Data<AppState>| {
-                somerville_events::features::view::index_with_now(
-                    state,
-                    fixed_now_utc,
-                    IndexQuery {
-                        event_types: vec![],
-                        source: filter.clone(),
-                        past: None,
-                        ..Default::default()
-                    },
-                )
-            }),
-        ))
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route("/", web::get().to(somerville_events::features::view::index))
+                .route(
+                    "/about",
+                    web::get().to(somerville_events::features::about::index),
+                ),
+        )
         .await;
+        let page = |uri: &'static str| {
+            let app = &app;
+            async move {
+                let req = test::TestRequest::get().uri(uri).to_request();
+                let resp = test::call_service(app, req).await;
+                assert_eq!(resp.status(), actix_web::http::StatusCode::OK, "{uri}");
+                String::from_utf8(test::read_body(resp).await.to_vec()).unwrap()
+            }
+        };
 
-        let req = test::TestRequest::get()
-            .uri("/?source=aeronaut-brewing")
-            .to_request();
-        let resp = test::call_service(&app, req).await;
-        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        // Without ABOUT_FILE, the built-in text.
+        let about = page("/about").await;
+        assert!(about.contains("<h2>Adding an event</h2>"));
+        assert!(about.contains(r#"<a href="/upload" rel="noopener noreferrer">upload it</a>"#));
+        assert!(about.contains(r#"<nav aria-label="About this site">"#));
 
-        let body = test::read_body(resp).await;
-        let body_str = std::str::from_utf8(&body)?;
+        let index = page("/").await;
+        assert!(index.contains(r#"<section aria-label="About this site">"#));
+        assert!(index.contains("<p>Events in Camberville. Locally made, and locally focused.</p>"));
+        assert!(!index.contains("Adding an event"));
+        assert!(index.contains(r#"<a href="/about">About</a>"#));
 
-        assert!(body_str.contains("Beer Night"));
-        assert!(!body_str.contains("Reading"));
+        // A filtered list is for finding events, not for the welcome.
+        let filtered = page("/?type=art").await;
+        assert!(!filtered.contains(r#"<section aria-label="About this site">"#));
+        assert!(filtered.contains(r#"<a href="/about">About</a>"#));
 
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_category_deserialization_multiple_params() -> Result<()> {
+    async fn test_security_headers() -> Result<()> {
+        use actix_web::http::header;
+        use base64::{engine::general_purpose::STANDARD as b64, Engine as _};
+        use sha2::{Digest, Sha256};
+
         let state = AppState {
             openai_api_key: "dummy".to_string(),
             google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
             username: "user".to_string(),
             password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
             events_repo: Box::new(MockEventsRepo::new(vec![])),
         };
-
         let app = test::init_service(
             App::new()
                 .app_data(Data::new(state))
-                .route("/", web::get().to(somerville_events::features::view::index)),
+                .wrap(actix_web::middleware::from_fn(
+                    somerville_events::security::security_headers,
+                ))
+                .wrap(actix_web::middleware::Condition::new(
+                    false,
+                    somerville_events::security::strict_transport_security(),
+                ))
+                .route("/", web::get().to(somerville_events::features::view::index))
+                .route(
+                    "/robots.txt",
+                    web::get().to(somerville_events::features::view::robots_txt),
+                ),
         )
         .await;
 
-        // Test ?type=social&type=family
-        let req = test::TestRequest::get()
-            .uri("/?type=social&type=family")
-            .to_request();
-
+        let req = test::TestRequest::get().uri("/").to_request();
         let resp = test::call_service(&app, req).await;
-
         assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
-        Ok(())
-    }
+        let headers = resp.headers().clone();
+        let body = String::from_utf8(test::read_body(resp).await.to_vec())?;
+        let policy = headers
+            .get(header::CONTENT_SECURITY_POLICY)
+            .unwrap()
+            .to_str()?
+            .to_string();
+        // The page's own inline CSS is allowed, and nothing else inline.
+        let css = body
+            .split_once("<style>")
+            .and_then(|(_, rest)| rest.split_once("</style>"))
+            .map(|(css, _)| css)
+            .unwrap();
+        let hash = format!("'sha256-{}'", b64.encode(Sha256::digest(css)));
+        assert!(policy.contains(&hash), "{policy}");
+        assert!(!policy.contains("unsafe-inline"), "{policy}");
+        assert!(!body.contains(" style=\""));
+        assert!(!body.contains(" onclick="));
+        assert!(body.contains(r#"<label data-type="art">"#));
+        assert!(css.contains(r#"[data-type="art"] { --type-color: light-dark("#));
+        assert_eq!(
+            headers.get(header::X_CONTENT_TYPE_OPTIONS).unwrap(),
+            "nosniff"
+        );
+        assert_eq!(
+            headers.get(header::REFERRER_POLICY).unwrap(),
+            "strict-origin-when-cross-origin"
+        );
+        assert!(!headers.contains_key(header::STRICT_TRANSPORT_SECURITY));
 
-    #[actix_web::test]
-    async fn test_category_deserialization_single() -> Result<()> {
-        let state = AppState {
-            openai_api_key: "dummy".to_string(),
-            google_maps_api_key: "dummy".to_string(),
-            username: "user".to_string(),
-            password: "pass".to_string(),
-            events_repo: Box::new(MockEventsRepo::new(vec![])),
-        };
+        let req = test::TestRequest::get().uri("/robots.txt").to_request();
+        let resp = test::call_service(&app, req).await;
+        // Nothing in a response body changes what the policy allows.
+        assert_eq!(
+            resp.headers().get(header::CONTENT_SECURITY_POLICY).unwrap(),
+            policy.as_str()
+        );
 
         let app = test::init_service(
             App::new()
-                .app_data(Data::new(state))
-                .route("/", web::get().to(somerville_events::features::view::index)),
+                .wrap(actix_web::middleware::Condition::new(
+                    true,
+                    somerville_events::security::strict_transport_security(),
+                ))
+                .route(
+                    "/robots.txt",
+                    web::get().to(somerville_events::features::view::robots_txt),
+                ),
         )
         .await;
-
-        let req = test::TestRequest::get().uri("/?type=social").to_request();
-
+        let req = test::TestRequest::get().uri("/robots.txt").to_request();
         let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers()
+                .get(header::STRICT_TRANSPORT_SECURITY)
+                .unwrap(),
+            "max-age=31536000"
+        );
 
-        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_index_filters_by_multiple_categories() -> Result<()> {
-        // 2025-01-15 17:00:00 UTC = 12:00:00 EST
-        let now_utc = Utc.with_ymd_and_hms(2025, 1, 15, 17, 0, 0).unwrap();
-
-        // Helper to create a NY datetime
-        let mk_ny = |d, h, m| New_York.with_ymd_and_hms(2025, 1, d, h, m, 0).unwrap();
+    async fn test_ical_download_of_listed_events() -> Result<()> {
+        use icalendar::Component;
 
-        let art_event = Event {
-            id: 1,
+        let now_utc = Utc::now();
+        let event = |id, name: &str, start, end_date, event_type| Event {
+            id,
             created_at: now_utc,
             updated_at: now_utc,
-            name: "Art Show".to_string(),
-            description: "Paintings".to_string(),
-            full_text: "Paintings".to_string(),
-            start_date: mk_ny(15, 18, 0).with_timezone(&Utc),
-            end_date: None,
-            address: Some("Gallery".to_string()),
-            original_location: Some("Gallery".to_string()),
+            name: name.to_string(),
+            description: name.to_string(),
+            full_text: name.to_string(),
+            start_date: start,
+            end_date,
+            all_day: false,
+            address: None,
+            original_location: None,
             google_place_id: None,
+            lat: None,
+            lng: None,
             location_name: None,
-            event_types: vec![EventType::Art],
+            event_types: vec![event_type],
+            tags: vec![],
             url: None,
             confidence: 1.0,
             age_restrictions: None,
             price: None,
             source: EventSource::ImageUpload,
             external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+        let events = vec![
+            event(
+                1,
+                "Gallery Opening",
+                now_utc + chrono::Duration::days(1),
+                None,
+                EventType::Art,
+            ),
+            // Fetched by the upcoming range's two-day buffer, but over, so
+            // the page doesn't list it.
+            event(
+                2,
+                "Yesterday's Workshop",
+                now_utc - chrono::Duration::hours(30),
+                Some(now_utc - chrono::Duration::hours(26)),
+                EventType::Art,
+            ),
+            event(
+                3,
+                "Porch Concert",
+                now_utc + chrono::Duration::days(1),
+                None,
+                EventType::Music,
+            ),
+        ];
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(events)),
         };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route("/", web::get().to(somerville_events::features::view::index))
+                .route(
+                    "/events/download.ics",
+                    web::get().to(somerville_events::features::view::ical_download),
+                ),
+        )
+        .await;
 
-        let music_event = Event {
-            id: 2,
-            created_at: now_utc,
-            updated_at: now_utc,
-            name: "Music Night".to_string(),
+        let req = test::TestRequest::get().uri("/?type=art").to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        let body = std::str::from_utf8(&body)?;
+        assert!(body.contains(r#"href="/events/download.ics?type=art""#));
+        assert!(body.contains("Add these to your calendar"));
+
+        let req = test::TestRequest::get()
+            .uri("/events/download.ics?type=art")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert_eq!(
+            resp.headers().get("Content-Disposition").unwrap(),
+            "attachment; filename=\"somerville-events.ics\""
+        );
+        let body = String::from_utf8(test::read_body(resp).await.to_vec())?;
+        let calendar = icalendar::parser::read_calendar(&icalendar::parser::unfold(&body))
+            .map(icalendar::Calendar::from)
+            .unwrap();
+        let uids: Vec<_> = calendar.events().filter_map(|e| e.get_uid()).collect();
+        assert_eq!(uids, vec!["somerville-events-1"]);
+
+        let req = test::TestRequest::get()
+            .uri("/events/download.ics?lat=42.39")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_ical_uid_is_stable_across_exports() -> Result<()> {
+        use icalendar::Component;
+
+        let created = Utc::now() - chrono::Duration::days(1);
+        let event = Event {
+            id: 9,
+            created_at: created,
+            updated_at: created,
+            name: "Porch Concert".to_string(),
             description: "Music".to_string(),
             full_text: "Music".to_string(),
-            start_date: mk_ny(15, 19, 0).with_timezone(&Utc),
+            start_date: Utc::now() + chrono::Duration::days(2),
             end_date: None,
-            address: Some("Club".to_string()),
-            original_location: Some("Club".to_string()),
+            all_day: false,
+            address: None,
+            original_location: None,
             google_place_id: None,
+            lat: None,
+            lng: None,
             location_name: None,
-            event_types: vec![EventType::Music],
+            event_types: vec![],
+            tags: vec![],
             url: None,
             confidence: 1.0,
             age_restrictions: None,
             price: None,
             source: EventSource::ImageUpload,
             external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+        let state = Data::new(AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
+        });
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .route(
+                    "/event/{id}.ics",
+                    web::get().to(somerville_events::features::view::ical),
+                )
+                .route(
+                    "/events.ics",
+                    web::get().to(somerville_events::features::view::ical_feed),
+                ),
+        )
+        .await;
+        let download = |uri: &'static str| {
+            let app = &app;
+            async move {
+                let req = test::TestRequest::get().uri(uri).to_request();
+                let resp = test::call_service(app, req).await;
+                assert_eq!(resp.status(), actix_web::http::StatusCode::OK, "{uri}");
+                let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+                let parsed = icalendar::parser::read_calendar(&icalendar::parser::unfold(&body))
+                    .map(icalendar::Calendar::from)
+                    .unwrap();
+                let event = parsed.events().next().cloned().expect("one VEVENT");
+                event
+            }
         };
 
-        let food_event = Event {
-            id: 3,
-            created_at: now_utc,
-            updated_at: now_utc,
-            name: "Food Fest".to_string(),
-            description: "Food".to_string(),
-            full_text: "Food".to_string(),
-            start_date: mk_ny(15, 20, 0).with_timezone(&Utc),
+        let first = download("/event/9.ics").await;
+        let again = download("/event/9.ics").await;
+        let feed = download("/events.ics").await;
+        assert_eq!(first.get_uid(), Some("somerville-events-9"));
+        assert_eq!(again.get_uid(), first.get_uid());
+        assert_eq!(feed.get_uid(), first.get_uid());
+        assert_eq!(again.get_sequence(), first.get_sequence());
+        assert_eq!(feed.get_sequence(), first.get_sequence());
+        assert_eq!(feed.get_timestamp(), first.get_timestamp());
+
+        // An edit keeps the UID but bumps SEQUENCE, so calendars replace
+        // their copy instead of adding a second one.
+        state.events_repo.set_featured(9, true).await?;
+        let edited = download("/event/9.ics").await;
+        assert_eq!(edited.get_uid(), first.get_uid());
+        assert!(edited.get_sequence() > first.get_sequence());
+        assert!(edited.get_timestamp() > first.get_timestamp());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_ical_exports_include_vtimezone() -> Result<()> {
+        let created = Utc::now() - chrono::Duration::days(1);
+        let event = Event {
+            id: 4,
+            created_at: created,
+            updated_at: created,
+            name: "Garden Tour".to_string(),
+            description: "Plants".to_string(),
+            full_text: "Plants".to_string(),
+            start_date: Utc::now() + chrono::Duration::hours(2),
             end_date: None,
-            address: Some("Park".to_string()),
-            original_location: Some("Park".to_string()),
+            all_day: false,
+            address: None,
+            original_location: None,
             google_place_id: None,
+            lat: None,
+            lng: None,
             location_name: None,
-            event_types: vec![EventType::Food],
+            event_types: vec![],
+            tags: vec![],
             url: None,
             confidence: 1.0,
             age_restrictions: None,
             price: None,
             source: EventSource::ImageUpload,
             external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
         };
-
         let state = AppState {
             openai_api_key: "dummy".to_string(),
             google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
             username: "user".to_string(),
             password: "pass".to_string(),
-            events_repo: Box::new(MockEventsRepo::new(vec![
-                art_event.clone(),
-                music_event.clone(),
-                food_event,
-            ])),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
         };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route(
+                    "/event/{id}.ics",
+                    web::get().to(somerville_events::features::view::ical),
+                )
+                .route(
+                    "/events.ics",
+                    web::get().to(somerville_events::features::view::ical_feed),
+                )
+                .route(
+                    "/events/download.ics",
+                    web::get().to(somerville_events::features::view::ical_download),
+                ),
+        )
+        .await;
 
-        let fixed_now_utc = now_utc;
-        let filter = vec![EventType::Art, EventType::Music];
-        let app = test::init_service(App::new().app_data(Data::new(state)).route(
-            "/",
-            web::get().to(move |state: Data<AppState>| {
-                somerville_events::features::view::index_with_now(
-                    state,
-                    fixed_now_utc,
-                    IndexQuery {
-                        event_types: filter.clone(),
-                        source: vec![],
-                        past: None,
-                        ..Default::default()
-                    },
-                )
-            }),
-        ))
-        .await;
-
-        let req = test::TestRequest::get()
-            .uri("/?type=art&type=music")
-            .to_request();
-        let resp = test::call_service(&app, req).await;
-        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
-
-        let body = test::read_body(resp).await;
-        let body_str = std::str::from_utf8(&body)?;
-
-        assert!(body_str.contains("Art Show"));
-        assert!(body_str.contains("Music Night"));
-        assert!(!body_str.contains("Food Fest"));
+        let tzid = DEFAULT_TIMEZONE.name();
+        for uri in ["/event/4.ics", "/events.ics", "/events/download.ics"] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::OK, "{uri}");
+            let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+
+            // One VTIMEZONE, ahead of the events whose times refer to it.
+            assert_eq!(body.matches("BEGIN:VTIMEZONE\r\n").count(), 1, "{uri}");
+            assert!(body.contains(&format!("TZID:{tzid}\r\n")), "{uri}");
+            let vtimezone = body.find("BEGIN:VTIMEZONE").unwrap();
+            let vevent = body.find("BEGIN:VEVENT").expect("one VEVENT");
+            assert!(vtimezone < vevent, "{uri}");
+            assert!(
+                body.contains(&format!("DTSTART;TZID={tzid}:")),
+                "{uri}: {body}"
+            );
+            assert!(body.ends_with("END:VCALENDAR\r\n"), "{uri}");
+            icalendar::parser::read_calendar(&icalendar::parser::unfold(&body)).unwrap();
+        }
 
         Ok(())
     }
 
-    #[actix_web::test]
-    async fn test_query_param_deserialization_success() -> Result<()> {
-        // Test that we can deserialize valid source and type query params
-        let state = AppState {
-            openai_api_key: "dummy".to_string(),
-            google_maps_api_key: "dummy".to_string(),
-            username: "user".to_string(),
-            password: "pass".to_string(),
-            events_repo: Box::new(MockEventsRepo::new(vec![])),
-        };
-
-        let app = test::init_service(
-            App::new()
-                .app_data(Data::new(state))
-                .route("/", web::get().to(somerville_events::features::view::index)),
+    #[sqlx::test]
+    async fn test_ical_feed_streams_every_page(pool: sqlx::PgPool) -> Result<()> {
+        // Three events share each start time, so page boundaries land in the
+        // middle of a tie and the id tiebreak has to do its job.
+        sqlx::query(
+            r#"
+            INSERT INTO app.events (name, full_text, start_date, confidence, source)
+            SELECT 'Feed Event ' || n, '', now() + ((n / 3) * interval '1 hour'), 1.0, 'ImageUpload'
+            FROM generate_series(1, 450) AS n
+            "#,
         )
-        .await;
-
-        // Valid source (Variant name)
-        let req = test::TestRequest::get()
-            .uri("/?source=boston-swing-central")
-            .to_request();
-        let resp = test::call_service(&app, req).await;
-        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
-
-        // Valid type (kebab-case)
-        let req = test::TestRequest::get()
-            .uri("/?type=yard-sale")
-            .to_request();
-        let resp = test::call_service(&app, req).await;
-        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
-
-        Ok(())
-    }
+        .execute(&pool)
+        .await?;
 
-    #[actix_web::test]
-    async fn test_query_param_deserialization_failure() -> Result<()> {
-        // Test that spaces in source fail deserialization (reproducing user report behavior)
         let state = AppState {
             openai_api_key: "dummy".to_string(),
             google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
             username: "user".to_string(),
             password: "pass".to_string(),
-            events_repo: Box::new(MockEventsRepo::new(vec![])),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(pool),
         };
-
-        let app = test::init_service(
-            App::new()
-                .app_data(Data::new(state))
-                .route("/", web::get().to(somerville_events::features::view::index)),
-        )
+        let app = test::init_service(App::new().app_data(Data::new(state)).route(
+            "/events.ics",
+            web::get().to(somerville_events::features::view::ical_feed),
+        ))
         .await;
 
-        // User reported URL: /?q=&free=true&source=Boston+Swing+Central
-        // This should FAIL if the backend expects "BostonSwingCentral" but gets "Boston Swing Central"
-        let req = test::TestRequest::get()
-            .uri("/?q=&free=true&source=Boston+Swing+Central")
-            .to_request();
+        let req = test::TestRequest::get().uri("/events.ics").to_request();
         let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
 
-        // If we fixed the UI, the UI will now send source=BostonSwingCentral.
-        // But this test verifies that the OLD/BAD url indeed fails (or behaves as expected).
-        // If it returns 200, we need to know WHY.
+        let body = test::read_body(resp).await;
+        let body_str = std::str::from_utf8(&body)?;
 
-        if resp.status() == actix_web::http::StatusCode::OK {
-            let body = test::read_body(resp).await;
-            let body_str = std::str::from_utf8(&body)?;
-            // If it returns 200 OK, it implies deserialization succeeded (possibly via fallback?)
-            // or the error was ignored.
-            // However, since we expect this to fail based on user reports, we assert failure.
-            // NOTE: If this assertion fails in your local environment but passes in CI,
-            // check actix-web versions or serde settings.
-            println!(
-                "Unexpected 200 OK for source=Boston+Swing+Central. Body: {:.100}...",
-                body_str
-            );
-            // We want to ensure we don't regress on the reported bug which was a 500/400 error.
-            // If it now passes, that's "good" but unexpected.
-            // Let's assume for this test suite we WANT it to fail to confirm we understand the parser.
-            // But if it passes, maybe we shouldn't block the build.
-            // For now, let's allow 200 OK if it happens, but verify the badge is NOT "Boston Swing Central"
-            // (meaning it didn't parse as that specific source).
-            // Actually, if it deserialized to valid source, it would show up.
-            // If it deserialized to nothing/empty, it's fine.
-        } else {
-            assert!(
-                resp.status().is_client_error(),
-                "Expected client error, got {}",
-                resp.status()
-            );
-        }
+        assert!(body_str.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(body_str.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(body_str.matches("BEGIN:VCALENDAR").count(), 1);
+        assert_eq!(body_str.matches("END:VCALENDAR").count(), 1);
 
-        // Invalid type (PascalCase instead of kebab-case)
-        // EventType has #[serde(other)] -> Other, so this should actually succeed with 200 OK!
-        let req = test::TestRequest::get().uri("/?type=YardSale").to_request();
-        let resp = test::call_service(&app, req).await;
+        let summaries: std::collections::HashSet<&str> = body_str
+            .lines()
+            .filter_map(|l| l.strip_prefix("SUMMARY:"))
+            .collect();
+        assert_eq!(body_str.matches("BEGIN:VEVENT").count(), 450);
         assert_eq!(
-            resp.status(),
-            actix_web::http::StatusCode::OK,
-            "YardSale should fallback to Other"
+            summaries.len(),
+            450,
+            "Every event should appear exactly once"
         );
 
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_filter_form_rendering() -> Result<()> {
-        // Verify that the rendered HTML contains the correct value attributes for options
-        let now_utc = Utc.with_ymd_and_hms(2025, 1, 15, 17, 0, 0).unwrap();
-        let fixed_now_utc = now_utc;
+    async fn test_ical_endpoint_all_day() -> Result<()> {
+        let day_start = New_York.with_ymd_and_hms(2025, 1, 18, 0, 0, 0).unwrap();
+
+        let event = Event {
+            id: 1,
+            created_at: day_start.with_timezone(&Utc),
+            updated_at: day_start.with_timezone(&Utc),
+            name: "Yard Sale".to_string(),
+            description: "Everything must go".to_string(),
+            full_text: "Everything must go".to_string(),
+            start_date: day_start.with_timezone(&Utc),
+            end_date: None,
+            all_day: true,
+            address: Some("Driveway".to_string()),
+            original_location: Some("Driveway".to_string()),
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::YardSale],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
 
         let state = AppState {
             openai_api_key: "dummy".to_string(),
             google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
             username: "user".to_string(),
             password: "pass".to_string(),
-            events_repo: Box::new(MockEventsRepo::new(vec![])),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
         };
 
-        let app = test::init_service(App::new().app_data(Data::new(state)).route(
-            "/",
-            web::get().to(move |state: Data<AppState>| {
-                somerville_events::features::view::index_with_now(
-                    state,
-                    fixed_now_utc,
-                    IndexQuery::default(),
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route(
+                    "/event/{id}.ics",
+                    web::get().to(somerville_events::features::view::ical),
                 )
-            }),
-        ))
+                .route(
+                    "/event/{id}",
+                    web::get().to(somerville_events::features::view::show),
+                ),
+        )
         .await;
 
-        let req = test::TestRequest::get().uri("/").to_request();
+        let req = test::TestRequest::get().uri("/event/1.ics").to_request();
         let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
 
         let body = test::read_body(resp).await;
         let body_str = std::str::from_utf8(&body)?;
 
-        // Check EventSource option values
-        // Should be <option value="boston-swing-central" ...>Boston Swing Central</option>
-        if !body_str.contains("value=\"boston-swing-central\"") {
-            println!("Body missing value=\"boston-swing-central\": {}", body_str);
-        }
-        assert!(body_str.contains("value=\"boston-swing-central\""));
+        // All-day events must use a DATE value, not a midnight DATE-TIME.
+        // Skip the VTIMEZONE, whose observances have a DTSTART too.
+        let start_line = body_str
+            .lines()
+            .skip_while(|l| *l != "BEGIN:VEVENT")
+            .find(|l| l.starts_with("DTSTART"))
+            .expect("DTSTART missing");
+        assert_eq!(start_line, "DTSTART;VALUE=DATE:20250118");
 
-        if !body_str.contains(">Boston Swing Central</option>") {
-            println!("Body missing >Boston Swing Central</option>: {}", body_str);
-        }
-        assert!(body_str.contains("Boston Swing Central"));
+        // DTEND is exclusive, so a single-day event ends the following day.
+        let end_line = body_str
+            .lines()
+            .find(|l| l.starts_with("DTEND"))
+            .expect("DTEND missing");
+        assert_eq!(end_line, "DTEND;VALUE=DATE:20250119");
 
-        // Check EventType option values
-        // Should be <option value="yard-sale" ...>Yard Sale</option>
-        assert!(body_str.contains("value=\"yard-sale\""));
-        assert!(body_str.contains("Yard Sale"));
+        let req = test::TestRequest::get().uri("/event/1").to_request();
+        let resp = test::call_service(&app, req).await;
+        let body = test::read_body(resp).await;
+        let body_str = std::str::from_utf8(&body)?;
+
+        assert!(body_str.contains("All day"));
+        assert!(!body_str.contains("12:00 AM"));
 
         Ok(())
     }
 
-    #[sqlx::test]
-    async fn test_free_filter_includes_null_price(pool: sqlx::PgPool) -> Result<()> {
-        use somerville_events::database::save_event_to_db;
-
-        // 1. Setup events
-        // 2025-01-15 17:00:00 UTC = 12:00:00 EST
-        let base_time = Utc.with_ymd_and_hms(2025, 1, 15, 17, 0, 0).unwrap();
-
-        // Event with price 0 (explicitly free)
-        let free_event = NewEvent {
-            name: "Free Event".to_string(),
-            description: "Free".to_string(),
-            full_text: "Free".to_string(),
-            start_date: base_time,
-            end_date: None,
-            address: Some("Loc".to_string()),
-            original_location: Some("Loc".to_string()),
+    #[actix_web::test]
+    async fn test_event_time_display_timezone() -> Result<()> {
+        let event = Event {
+            id: 1,
+            created_at: Utc.with_ymd_and_hms(2025, 11, 8, 15, 30, 0).unwrap(),
+            updated_at: Utc.with_ymd_and_hms(2025, 11, 8, 15, 30, 0).unwrap(),
+            name: "Pumpkin Smash".to_string(),
+            description: "Smash pumpkins".to_string(),
+            full_text: "Smash pumpkins".to_string(),
+            // Correctly stored UTC time for 10:30 AM EST is 15:30 UTC.
+            start_date: Utc.with_ymd_and_hms(2025, 11, 8, 15, 30, 0).unwrap(),
+            end_date: Some(Utc.with_ymd_and_hms(2025, 11, 8, 18, 0, 0).unwrap()),
+            all_day: false,
+            address: Some("Somerville".to_string()),
+            original_location: Some("Somerville".to_string()),
             google_place_id: None,
+            lat: None,
+            lng: None,
             location_name: None,
             event_types: vec![],
+            tags: vec![],
             url: None,
             confidence: 1.0,
             age_restrictions: None,
-            price: Some(0.0),
+            price: None,
             source: EventSource::ImageUpload,
             external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
         };
-        save_event_to_db(&pool, &free_event).await?;
-
-        // Event with price NULL (implicitly free)
-        let mut null_price_event = free_event.clone();
-        null_price_event.name = "Null Price Event".to_string();
-        null_price_event.price = None;
-        save_event_to_db(&pool, &null_price_event).await?;
-
-        // Event with price > 0 (paid)
-        let mut paid_event = free_event.clone();
-        paid_event.name = "Paid Event".to_string();
-        paid_event.price = Some(10.0);
-        save_event_to_db(&pool, &paid_event).await?;
 
-        // 2. Setup App with Real DB
         let state = AppState {
             openai_api_key: "dummy".to_string(),
             google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
             username: "user".to_string(),
             password: "pass".to_string(),
-            events_repo: Box::new(pool),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
         };
 
-        // We also want to control "now" for consistency, although since we insert events at a fixed time,
-        // and index_with_now allows us to pass a fixed time, it should be fine.
-        let fixed_now_utc = base_time;
-
+        let fixed_now = Utc.with_ymd_and_hms(2025, 11, 8, 8, 0, 0).unwrap();
         let app = test::init_service(App::new().app_data(Data::new(state)).route(
             "/",
-            web::get().to(move |state: Data<AppState>| {
+            web::get().to(move |req: HttpRequest, state: Data<AppState>| {
+                // We use fixed_now to ensure the event is considered upcoming
                 somerville_events::features::view::index_with_now(
+                    req,
                     state,
-                    fixed_now_utc,
+                    fixed_now,
                     IndexQuery {
-                        free: Some(true),
+                        event_types: vec![],
+                        source: vec![],
+                        past: None,
                         ..Default::default()
                     },
                 )
@@ -1325,160 +2521,5683 @@ mod tests {
         ))
         .await;
 
-        // 3. Request
-        let req = test::TestRequest::get().uri("/?free=true").to_request();
+        let req = test::TestRequest::get().uri("/").to_request();
         let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
 
         let body = test::read_body(resp).await;
         let body_str = std::str::from_utf8(&body)?;
 
-        // 4. Verify
-        assert!(
-            body_str.contains("Free Event"),
-            "Should contain explicit free event"
-        );
         assert!(
-            body_str.contains("Null Price Event"),
-            "Should contain null price event"
+            body_str.contains("10:30 AM"),
+            "Body did not contain '10:30 AM'. Content: {}",
+            body_str
         );
-        assert!(
-            !body_str.contains("Paid Event"),
-            "Should NOT contain paid event"
-        );
-
         Ok(())
     }
 
     #[actix_web::test]
-    async fn test_date_range_filtering() -> Result<()> {
-        // 1. Setup events
+    async fn test_index_filters_by_source() -> Result<()> {
+        // 2025-01-15 17:00:00 UTC = 12:00:00 EST
+        let now_utc = Utc.with_ymd_and_hms(2025, 1, 15, 17, 0, 0).unwrap();
+
         // Helper to create a NY datetime
         let mk_ny = |d, h, m| New_York.with_ymd_and_hms(2025, 1, d, h, m, 0).unwrap();
 
-        // Past Event: Jan 1st
-        let past_event = Event {
+        let aeronaut_event = Event {
             id: 1,
-            created_at: mk_ny(1, 10, 0).with_timezone(&Utc),
-            updated_at: mk_ny(1, 10, 0).with_timezone(&Utc),
-            name: "Past Event".to_string(),
-            description: "Past".to_string(),
-            full_text: "Past".to_string(),
-            start_date: mk_ny(1, 10, 0).with_timezone(&Utc),
+            created_at: now_utc,
+            updated_at: now_utc,
+            name: "Beer Night".to_string(),
+            description: "Drink beer".to_string(),
+            full_text: "Drink beer".to_string(),
+            start_date: mk_ny(15, 18, 0).with_timezone(&Utc),
             end_date: None,
-            address: Some("Loc".to_string()),
-            original_location: Some("Loc".to_string()),
+            all_day: false,
+            address: Some("Aeronaut".to_string()),
+            original_location: Some("Aeronaut".to_string()),
             google_place_id: None,
+            lat: None,
+            lng: None,
             location_name: None,
-            event_types: vec![],
+            event_types: vec![EventType::Social],
+            tags: vec![],
             url: None,
             confidence: 1.0,
             age_restrictions: None,
             price: None,
-            source: EventSource::ImageUpload,
+            source: somerville_events::models::EventSource::AeronautBrewing,
             external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
         };
 
-        // Target Event: Jan 15th
-        let target_event = Event {
+        let library_event = Event {
             id: 2,
-            created_at: mk_ny(15, 10, 0).with_timezone(&Utc),
-            updated_at: mk_ny(15, 10, 0).with_timezone(&Utc),
-            name: "Target Event".to_string(),
-            description: "Target".to_string(),
-            full_text: "Target".to_string(),
-            start_date: mk_ny(15, 10, 0).with_timezone(&Utc),
+            created_at: now_utc,
+            updated_at: now_utc,
+            name: "Reading".to_string(),
+            description: "Read books".to_string(),
+            full_text: "Read books".to_string(),
+            start_date: mk_ny(15, 19, 0).with_timezone(&Utc),
             end_date: None,
-            address: Some("Loc".to_string()),
-            original_location: Some("Loc".to_string()),
+            all_day: false,
+            address: Some("Library".to_string()),
+            original_location: Some("Library".to_string()),
             google_place_id: None,
+            lat: None,
+            lng: None,
             location_name: None,
-            event_types: vec![],
+            event_types: vec![EventType::Literature],
+            tags: vec![],
             url: None,
             confidence: 1.0,
             age_restrictions: None,
             price: None,
-            source: EventSource::ImageUpload,
+            source: somerville_events::models::EventSource::CityOfCambridge,
             external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
         };
 
-        // Future Event: Jan 30th
-        let future_event = Event {
-            id: 3,
-            created_at: mk_ny(30, 10, 0).with_timezone(&Utc),
-            updated_at: mk_ny(30, 10, 0).with_timezone(&Utc),
-            name: "Future Event".to_string(),
-            description: "Future".to_string(),
-            full_text: "Future".to_string(),
-            start_date: mk_ny(30, 10, 0).with_timezone(&Utc),
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![
+                aeronaut_event.clone(),
+                library_event,
+            ])),
+        };
+
+        let fixed_now_utc = now_utc;
+        let filter = vec![somerville_events::models::EventSource::AeronautBrewing];
+        let app = test::init_service(App::new().app_data(Data::new(state)).route(
+            "/",
+            web::get().to(move |req: HttpRequest, state: Data<AppState>| {
+                somerville_events::features::view::index_with_now(
+                    req,
+                    state,
+                    fixed_now_utc,
+                    IndexQuery {
+                        event_types: vec![],
+                        source: filter.clone(),
+                        past: None,
+                        ..Default::default()
+                    },
+                )
+            }),
+        ))
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/?source=aeronaut-brewing")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        let body = test::read_body(resp).await;
+        let body_str = std::str::from_utf8(&body)?;
+
+        assert!(body_str.contains("Beer Night"));
+        assert!(!body_str.contains("Reading"));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_category_deserialization_multiple_params() -> Result<()> {
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![])),
+        };
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route("/", web::get().to(somerville_events::features::view::index)),
+        )
+        .await;
+
+        // Test ?type=social&type=family
+        let req = test::TestRequest::get()
+            .uri("/?type=social&type=family")
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_category_deserialization_single() -> Result<()> {
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![])),
+        };
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route("/", web::get().to(somerville_events::features::view::index)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/?type=social").to_request();
+
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_index_collapses_weekly_events() -> Result<()> {
+        // A Wednesday.
+        let now_utc = Utc.with_ymd_and_hms(2025, 1, 15, 17, 0, 0).unwrap();
+        let event = |id: i64, name: &str, month, day| Event {
+            id,
+            created_at: now_utc,
+            updated_at: now_utc,
+            name: name.to_string(),
+            description: String::new(),
+            full_text: String::new(),
+            start_date: New_York
+                .with_ymd_and_hms(2025, month, day, 19, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc),
             end_date: None,
-            address: Some("Loc".to_string()),
-            original_location: Some("Loc".to_string()),
+            all_day: false,
+            address: None,
+            original_location: Some("The Burren".to_string()),
             google_place_id: None,
+            lat: None,
+            lng: None,
             location_name: None,
-            event_types: vec![],
+            event_types: vec![EventType::Trivia],
+            tags: vec![],
             url: None,
             confidence: 1.0,
             age_restrictions: None,
             price: None,
             source: EventSource::ImageUpload,
             external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
         };
-
+        let events = vec![
+            event(1, "Trivia Night", 1, 21),
+            event(2, "Trivia Night", 1, 28),
+            event(3, "Trivia Night", 2, 4),
+            event(4, "Jazz Brunch", 1, 19),
+        ];
         let state = AppState {
             openai_api_key: "dummy".to_string(),
             google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
             username: "user".to_string(),
             password: "pass".to_string(),
-            events_repo: Box::new(MockEventsRepo::new(vec![
-                past_event,
-                target_event,
-                future_event,
-            ])),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(events)),
         };
-
-        // We set "now" to Jan 1st for the context of index_with_now,
-        // effectively making all events "upcoming" if no filters were applied.
-        let fixed_now_utc = mk_ny(1, 8, 0).with_timezone(&Utc);
-
         let app = test::init_service(App::new().app_data(Data::new(state)).route(
             "/",
             web::get().to(
-                move |state: Data<AppState>, query: web::Query<IndexQuery>| {
+                move |req: HttpRequest,
+                      state: Data<AppState>,
+                      query: actix_web_lab::extract::Query<IndexQuery>| {
                     somerville_events::features::view::index_with_now(
+                        req,
                         state,
-                        fixed_now_utc,
+                        now_utc,
                         query.into_inner(),
                     )
                 },
             ),
         ))
         .await;
+        let get = |uri: &'static str| {
+            let app = &app;
+            async move {
+                let resp =
+                    test::call_service(app, test::TestRequest::get().uri(uri).to_request()).await;
+                assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+                String::from_utf8(test::read_body(resp).await.to_vec()).unwrap()
+            }
+        };
 
-        // Filter: Jan 10th to Jan 20th
-        // Should include Target Event (Jan 15th)
-        // Should exclude Past Event (Jan 1st) and Future Event (Jan 30th)
-        let req = test::TestRequest::get()
-            .uri("/?since=2025-01-10&until=2025-01-20")
-            .to_request();
-        let resp = test::call_service(&app, req).await;
-        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let body = get("/").await;
+        assert_eq!(body.matches(">Trivia Night</h3>").count(), 3);
+        assert!(!body.contains("Every Tuesday"));
 
-        let body = test::read_body(resp).await;
-        let body_str = std::str::from_utf8(&body)?;
+        let body = get("/?collapse_weekly=true").await;
+        assert_eq!(body.matches(">Trivia Night</h3>").count(), 1);
+        assert!(body.contains("Every Tuesday"));
+        // Each date keeps its own link.
+        for id in 1..=3 {
+            assert!(body.contains(&format!("href=\"/event/{id}\"")));
+        }
+        assert!(body.contains("Tue, Feb 4"));
+        assert!(!body.contains("day-2025-01-28"));
+        assert!(body.contains(">Jazz Brunch</h3>"));
 
-        assert!(
-            body_str.contains("Target Event"),
-            "Should contain Target Event"
-        );
-        assert!(
-            !body_str.contains("Past Event"),
-            "Should NOT contain Past Event"
-        );
-        assert!(
-            !body_str.contains("Future Event"),
-            "Should NOT contain Future Event"
-        );
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_index_filters_by_multiple_categories() -> Result<()> {
+        // 2025-01-15 17:00:00 UTC = 12:00:00 EST
+        let now_utc = Utc.with_ymd_and_hms(2025, 1, 15, 17, 0, 0).unwrap();
+
+        // Helper to create a NY datetime
+        let mk_ny = |d, h, m| New_York.with_ymd_and_hms(2025, 1, d, h, m, 0).unwrap();
+
+        let art_event = Event {
+            id: 1,
+            created_at: now_utc,
+            updated_at: now_utc,
+            name: "Art Show".to_string(),
+            description: "Paintings".to_string(),
+            full_text: "Paintings".to_string(),
+            start_date: mk_ny(15, 18, 0).with_timezone(&Utc),
+            end_date: None,
+            all_day: false,
+            address: Some("Gallery".to_string()),
+            original_location: Some("Gallery".to_string()),
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Art],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        let music_event = Event {
+            id: 2,
+            created_at: now_utc,
+            updated_at: now_utc,
+            name: "Music Night".to_string(),
+            description: "Music".to_string(),
+            full_text: "Music".to_string(),
+            start_date: mk_ny(15, 19, 0).with_timezone(&Utc),
+            end_date: None,
+            all_day: false,
+            address: Some("Club".to_string()),
+            original_location: Some("Club".to_string()),
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Music],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        let food_event = Event {
+            id: 3,
+            created_at: now_utc,
+            updated_at: now_utc,
+            name: "Food Fest".to_string(),
+            description: "Food".to_string(),
+            full_text: "Food".to_string(),
+            start_date: mk_ny(15, 20, 0).with_timezone(&Utc),
+            end_date: None,
+            all_day: false,
+            address: Some("Park".to_string()),
+            original_location: Some("Park".to_string()),
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Food],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![
+                art_event.clone(),
+                music_event.clone(),
+                food_event,
+            ])),
+        };
+
+        let fixed_now_utc = now_utc;
+        let filter = vec![EventType::Art, EventType::Music];
+        let app = test::init_service(App::new().app_data(Data::new(state)).route(
+            "/",
+            web::get().to(move |req: HttpRequest, state: Data<AppState>| {
+                somerville_events::features::view::index_with_now(
+                    req,
+                    state,
+                    fixed_now_utc,
+                    IndexQuery {
+                        event_types: filter.clone(),
+                        source: vec![],
+                        past: None,
+                        ..Default::default()
+                    },
+                )
+            }),
+        ))
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/?type=art&type=music")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        let body = test::read_body(resp).await;
+        let body_str = std::str::from_utf8(&body)?;
+
+        assert!(body_str.contains("Art Show"));
+        assert!(body_str.contains("Music Night"));
+        assert!(!body_str.contains("Food Fest"));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_query_param_deserialization_success() -> Result<()> {
+        // Test that we can deserialize valid source and type query params
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![])),
+        };
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route("/", web::get().to(somerville_events::features::view::index)),
+        )
+        .await;
+
+        // Valid source (Variant name)
+        let req = test::TestRequest::get()
+            .uri("/?source=boston-swing-central")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        // Valid type (kebab-case)
+        let req = test::TestRequest::get()
+            .uri("/?type=yard-sale")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_query_param_deserialization_failure() -> Result<()> {
+        // Test that spaces in source fail deserialization (reproducing user report behavior)
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![])),
+        };
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route("/", web::get().to(somerville_events::features::view::index)),
+        )
+        .await;
+
+        // User reported URL: /?q=&free=true&source=Boston+Swing+Central
+        // This should FAIL if the backend expects "BostonSwingCentral" but gets "Boston Swing Central"
+        let req = test::TestRequest::get()
+            .uri("/?q=&free=true&source=Boston+Swing+Central")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+
+        // If we fixed the UI, the UI will now send source=BostonSwingCentral.
+        // But this test verifies that the OLD/BAD url indeed fails (or behaves as expected).
+        // If it returns 200, we need to know WHY.
+
+        if resp.status() == actix_web::http::StatusCode::OK {
+            let body = test::read_body(resp).await;
+            let body_str = std::str::from_utf8(&body)?;
+            // If it returns 200 OK, it implies deserialization succeeded (possibly via fallback?)
+            // or the error was ignored.
+            // However, since we expect this to fail based on user reports, we assert failure.
+            // NOTE: If this assertion fails in your local environment but passes in CI,
+            // check actix-web versions or serde settings.
+            println!(
+                "Unexpected 200 OK for source=Boston+Swing+Central. Body: {:.100}...",
+                body_str
+            );
+            // We want to ensure we don't regress on the reported bug which was a 500/400 error.
+            // If it now passes, that's "good" but unexpected.
+            // Let's assume for this test suite we WANT it to fail to confirm we understand the parser.
+            // But if it passes, maybe we shouldn't block the build.
+            // For now, let's allow 200 OK if it happens, but verify the badge is NOT "Boston Swing Central"
+            // (meaning it didn't parse as that specific source).
+            // Actually, if it deserialized to valid source, it would show up.
+            // If it deserialized to nothing/empty, it's fine.
+        } else {
+            assert!(
+                resp.status().is_client_error(),
+                "Expected client error, got {}",
+                resp.status()
+            );
+        }
+
+        // Invalid type (PascalCase instead of kebab-case)
+        // EventType has #[serde(other)] -> Other, so this should actually succeed with 200 OK!
+        let req = test::TestRequest::get().uri("/?type=YardSale").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::OK,
+            "YardSale should fallback to Other"
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_filter_form_rendering() -> Result<()> {
+        // Verify that the rendered HTML contains the correct value attributes for options
+        let now_utc = Utc.with_ymd_and_hms(2025, 1, 15, 17, 0, 0).unwrap();
+        let fixed_now_utc = now_utc;
+
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![])),
+        };
+
+        let app = test::init_service(App::new().app_data(Data::new(state)).route(
+            "/",
+            web::get().to(move |req: HttpRequest, state: Data<AppState>| {
+                somerville_events::features::view::index_with_now(
+                    req,
+                    state,
+                    fixed_now_utc,
+                    IndexQuery::default(),
+                )
+            }),
+        ))
+        .await;
+
+        let req = test::TestRequest::get().uri("/").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        let body = test::read_body(resp).await;
+        let body_str = std::str::from_utf8(&body)?;
+
+        // Check EventSource option values
+        // Should be <option value="boston-swing-central" ...>Boston Swing Central</option>
+        if !body_str.contains("value=\"boston-swing-central\"") {
+            println!("Body missing value=\"boston-swing-central\": {}", body_str);
+        }
+        assert!(body_str.contains("value=\"boston-swing-central\""));
+
+        if !body_str.contains(">Boston Swing Central</option>") {
+            println!("Body missing >Boston Swing Central</option>: {}", body_str);
+        }
+        assert!(body_str.contains("Boston Swing Central"));
+
+        // Check EventType option values
+        // Should be <option value="yard-sale" ...>Yard Sale</option>
+        assert!(body_str.contains("value=\"yard-sale\""));
+        assert!(body_str.contains("Yard Sale"));
+
+        // Calendar apps and browser extensions find the feed from the head,
+        // and the subscribe button goes straight to the webcal dialog.
+        assert!(body_str.contains(r#"<link rel="alternate" type="text/calendar""#));
+        assert!(body_str.contains(r#"href="webcal://"#));
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_free_filter_includes_null_price(pool: sqlx::PgPool) -> Result<()> {
+        use somerville_events::database::save_event_to_db;
+
+        // 1. Setup events
+        // 2025-01-15 17:00:00 UTC = 12:00:00 EST
+        let base_time = Utc.with_ymd_and_hms(2025, 1, 15, 17, 0, 0).unwrap();
+
+        // Event with price 0 (explicitly free)
+        let free_event = NewEvent {
+            name: "Free Event".to_string(),
+            description: "Free".to_string(),
+            full_text: "Free".to_string(),
+            start_date: base_time,
+            end_date: None,
+            all_day: false,
+            address: Some("Loc".to_string()),
+            original_location: Some("Loc".to_string()),
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: Some(0.0),
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            status: EventStatus::Scheduled,
+        };
+        save_event_to_db(&pool, &free_event).await?;
+
+        // Event with price NULL (implicitly free)
+        let mut null_price_event = free_event.clone();
+        null_price_event.name = "Null Price Event".to_string();
+        null_price_event.price = None;
+        save_event_to_db(&pool, &null_price_event).await?;
+
+        // Event with price > 0 (paid)
+        let mut paid_event = free_event.clone();
+        paid_event.name = "Paid Event".to_string();
+        paid_event.price = Some(10.0);
+        save_event_to_db(&pool, &paid_event).await?;
+
+        // 2. Setup App with Real DB
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(pool),
+        };
+
+        // We also want to control "now" for consistency, although since we insert events at a fixed time,
+        // and index_with_now allows us to pass a fixed time, it should be fine.
+        let fixed_now_utc = base_time;
+
+        let app = test::init_service(App::new().app_data(Data::new(state)).route(
+            "/",
+            web::get().to(move |req: HttpRequest, state: Data<AppState>| {
+                somerville_events::features::view::index_with_now(
+                    req,
+                    state,
+                    fixed_now_utc,
+                    IndexQuery {
+                        free: Some(true),
+                        ..Default::default()
+                    },
+                )
+            }),
+        ))
+        .await;
+
+        // 3. Request
+        let req = test::TestRequest::get().uri("/?free=true").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        let body = test::read_body(resp).await;
+        let body_str = std::str::from_utf8(&body)?;
+
+        // 4. Verify
+        assert!(
+            body_str.contains("Free Event"),
+            "Should contain explicit free event"
+        );
+        assert!(
+            body_str.contains("Null Price Event"),
+            "Should contain null price event"
+        );
+        assert!(
+            !body_str.contains("Paid Event"),
+            "Should NOT contain paid event"
+        );
+
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn test_exhausted_pool_returns_service_unavailable(
+        pool_options: sqlx::postgres::PgPoolOptions,
+        connect_options: sqlx::postgres::PgConnectOptions,
+    ) -> Result<()> {
+        let pool = pool_options
+            .max_connections(1)
+            .acquire_timeout(std::time::Duration::from_millis(100))
+            .connect_with(connect_options)
+            .await?;
+        // Tie up the only connection so the handler can't get one.
+        let _held = pool.acquire().await?;
+
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(pool.clone()),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route(
+                    "/event/{id}",
+                    web::get().to(somerville_events::features::view::show),
+                )
+                .route(
+                    "/api/events/{id}",
+                    web::get().to(somerville_events::features::view::api_event),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/event/1").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert!(resp.headers().contains_key("Retry-After"));
+
+        let req = test::TestRequest::get().uri("/api/events/1").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert!(resp.headers().contains_key("Retry-After"));
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"]["code"], "unavailable");
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_date_range_filtering() -> Result<()> {
+        // 1. Setup events
+        // Helper to create a NY datetime
+        let mk_ny = |d, h, m| New_York.with_ymd_and_hms(2025, 1, d, h, m, 0).unwrap();
+
+        // Past Event: Jan 1st
+        let past_event = Event {
+            id: 1,
+            created_at: mk_ny(1, 10, 0).with_timezone(&Utc),
+            updated_at: mk_ny(1, 10, 0).with_timezone(&Utc),
+            name: "Past Event".to_string(),
+            description: "Past".to_string(),
+            full_text: "Past".to_string(),
+            start_date: mk_ny(1, 10, 0).with_timezone(&Utc),
+            end_date: None,
+            all_day: false,
+            address: Some("Loc".to_string()),
+            original_location: Some("Loc".to_string()),
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        // Target Event: Jan 15th
+        let target_event = Event {
+            id: 2,
+            created_at: mk_ny(15, 10, 0).with_timezone(&Utc),
+            updated_at: mk_ny(15, 10, 0).with_timezone(&Utc),
+            name: "Target Event".to_string(),
+            description: "Target".to_string(),
+            full_text: "Target".to_string(),
+            start_date: mk_ny(15, 10, 0).with_timezone(&Utc),
+            end_date: None,
+            all_day: false,
+            address: Some("Loc".to_string()),
+            original_location: Some("Loc".to_string()),
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        // Future Event: Jan 30th
+        let future_event = Event {
+            id: 3,
+            created_at: mk_ny(30, 10, 0).with_timezone(&Utc),
+            updated_at: mk_ny(30, 10, 0).with_timezone(&Utc),
+            name: "Future Event".to_string(),
+            description: "Future".to_string(),
+            full_text: "Future".to_string(),
+            start_date: mk_ny(30, 10, 0).with_timezone(&Utc),
+            end_date: None,
+            all_day: false,
+            address: Some("Loc".to_string()),
+            original_location: Some("Loc".to_string()),
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![
+                past_event,
+                target_event,
+                future_event,
+            ])),
+        };
+
+        // We set "now" to Jan 1st for the context of index_with_now,
+        // effectively making all events "upcoming" if no filters were applied.
+        let fixed_now_utc = mk_ny(1, 8, 0).with_timezone(&Utc);
+
+        let app = test::init_service(App::new().app_data(Data::new(state)).route(
+            "/",
+            web::get().to(
+                move |req: HttpRequest, state: Data<AppState>, query: web::Query<IndexQuery>| {
+                    somerville_events::features::view::index_with_now(
+                        req,
+                        state,
+                        fixed_now_utc,
+                        query.into_inner(),
+                    )
+                },
+            ),
+        ))
+        .await;
+
+        // Filter: Jan 10th to Jan 20th
+        // Should include Target Event (Jan 15th)
+        // Should exclude Past Event (Jan 1st) and Future Event (Jan 30th)
+        let req = test::TestRequest::get()
+            .uri("/?since=2025-01-10&until=2025-01-20")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        let body = test::read_body(resp).await;
+        let body_str = std::str::from_utf8(&body)?;
+
+        assert!(
+            body_str.contains("Target Event"),
+            "Should contain Target Event"
+        );
+        assert!(
+            !body_str.contains("Past Event"),
+            "Should NOT contain Past Event"
+        );
+        assert!(
+            !body_str.contains("Future Event"),
+            "Should NOT contain Future Event"
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_create_event_from_form() -> Result<()> {
+        let mock_repo = MockEventsRepo::new(vec![]);
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(mock_repo.clone()),
+        };
+
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .app_data(Data::new(awc::Client::default()))
+                .route(
+                    "/create",
+                    web::post().to(somerville_events::features::create::save),
+                ),
+        )
+        .await;
+
+        // No location, so we never hit the geocoding API.
+        let req = test::TestRequest::post()
+            .uri("/create")
+            .set_form([
+                ("name", "Stoop Poetry Reading"),
+                ("description", "Bring a poem"),
+                ("start", "2025-01-15T19:30"),
+                ("end", ""),
+                ("location", ""),
+                ("event_type", "literature"),
+                ("url", "example.com/poetry"),
+                ("price", ""),
+            ])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert_eq!(resp.headers().get("Location").unwrap(), "/event/1");
+
+        let event = {
+            let events = mock_repo.events.lock().unwrap();
+            assert_eq!(events.len(), 1);
+            events[0].clone()
+        };
+        assert_eq!(event.name, "Stoop Poetry Reading");
+        assert_eq!(
+            event.start_date,
+            New_York
+                .with_ymd_and_hms(2025, 1, 15, 19, 30, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        );
+        assert_eq!(event.end_date, None);
+        assert_eq!(event.event_types, vec![EventType::Literature]);
+        assert_eq!(event.url.as_deref(), Some("https://example.com/poetry"));
+        assert_eq!(event.price, None);
+        assert_eq!(event.source, EventSource::UserSubmitted);
+
+        let req = test::TestRequest::post()
+            .uri("/create")
+            .set_form([
+                ("name", "Bad Date"),
+                ("start", "tomorrow-ish"),
+                ("event_type", "other"),
+            ])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_create_event_rejects_bad_price_and_end() -> Result<()> {
+        let mock_repo = MockEventsRepo::new(vec![]);
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(mock_repo.clone()),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .app_data(Data::new(awc::Client::default()))
+                .route("/create", web::post().to(features::create::save)),
+        )
+        .await;
+        let submit = |end: &'static str, price: &'static str| {
+            test::TestRequest::post()
+                .uri("/create")
+                .set_form([
+                    ("name", "Stoop Poetry Reading"),
+                    ("start", "2025-01-15T19:30"),
+                    ("end", end),
+                    ("event_type", "literature"),
+                    ("price", price),
+                ])
+                .to_request()
+        };
+
+        for price in ["NaN", "inf", "-inf", "-5"] {
+            let resp = test::call_service(&app, submit("", price)).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+            let body = String::from_utf8(test::read_body(resp).await.to_vec())?;
+            assert!(body.contains("Invalid price"), "{price} was accepted");
+        }
+
+        let resp = test::call_service(&app, submit("2025-01-15T18:00", "")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let body = String::from_utf8(test::read_body(resp).await.to_vec())?;
+        assert!(body.contains("can&#39;t end before it starts"));
+        // The form comes back as it was sent, so nothing has to be retyped.
+        assert!(body.contains(r#"value="Stoop Poetry Reading""#));
+        assert!(body.contains(r#"value="2025-01-15T18:00""#));
+        assert!(body.contains(r#"value="literature" selected"#));
+
+        assert!(mock_repo.events.lock().unwrap().is_empty());
+
+        // Free, and ending the moment it starts, are both fine.
+        let resp = test::call_service(&app, submit("2025-01-15T19:30", "0")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_insert_busts_the_index_cache() -> Result<()> {
+        let repo = MockEventsRepo::new(vec![]);
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(repo.clone()),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .app_data(Data::new(awc::Client::default()))
+                .route("/", web::get().to(features::view::index))
+                .route("/create", web::post().to(features::create::save)),
+        )
+        .await;
+        let index = || async {
+            let req = test::TestRequest::get().uri("/").to_request();
+            let body = test::read_body(test::call_service(&app, req).await).await;
+            String::from_utf8(body.to_vec()).unwrap()
+        };
+        let tomorrow = (Utc::now().with_timezone(&DEFAULT_TIMEZONE) + chrono::Duration::days(1))
+            .format("%Y-%m-%dT19:30")
+            .to_string();
+
+        assert!(!index().await.contains("Stoop Poetry Reading"));
+
+        // Written behind the app's back, the way the ingestor does, so only
+        // the TTL would catch it.
+        repo.insert(&NewEvent {
+            name: "Ingested Lecture".to_string(),
+            description: String::new(),
+            full_text: String::new(),
+            start_date: Utc::now() + chrono::Duration::days(1),
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Literature],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            status: EventStatus::Scheduled,
+        })
+        .await?;
+        assert!(!index().await.contains("Ingested Lecture"));
+
+        let req = test::TestRequest::post()
+            .uri("/create")
+            .set_form([
+                ("name", "Stoop Poetry Reading"),
+                ("start", tomorrow.as_str()),
+                ("event_type", "literature"),
+            ])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+
+        let body = index().await;
+        assert!(body.contains("Stoop Poetry Reading"));
+        assert!(body.contains("Ingested Lecture"));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_merge_duplicates_from_edit() -> Result<()> {
+        let start = Utc::now() + chrono::Duration::days(3);
+        let mk_event = |id, name: &str, external_id: Option<&str>| Event {
+            id,
+            created_at: start,
+            updated_at: start,
+            name: name.to_string(),
+            description: String::new(),
+            full_text: String::new(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Music],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: external_id.map(str::to_string),
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![
+                mk_event(1, "Porchfest", None),
+                mk_event(2, "PorchFest!", Some("feed-2")),
+            ])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route(
+                    "/edit",
+                    web::get().to(somerville_events::features::edit::index),
+                )
+                .route(
+                    "/event/{id}/merge",
+                    web::post().to(somerville_events::features::edit::merge),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/edit").to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        let body_str = std::str::from_utf8(&body)?;
+        assert!(body_str.contains("Possible duplicates"));
+        assert!(body_str.contains(r#"action="/event/1/merge?into=2""#));
+
+        // Deleting the feed's copy would only bring it back on the next import.
+        let req = test::TestRequest::post()
+            .uri("/event/2/merge?into=1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+
+        let req = test::TestRequest::post()
+            .uri("/event/1/merge?into=2")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert_eq!(
+            resp.headers().get("Location").and_then(|v| v.to_str().ok()),
+            Some("/edit/event/2")
+        );
+
+        let req = test::TestRequest::get().uri("/edit").to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        assert!(!std::str::from_utf8(&body)?.contains("Possible duplicates"));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_edit_list_sorts_by_confidence() -> Result<()> {
+        let start = Utc::now() + chrono::Duration::days(3);
+        let mk_event = |id, name: &str, days: i64, confidence| Event {
+            id,
+            created_at: start,
+            updated_at: start,
+            name: name.to_string(),
+            description: String::new(),
+            full_text: String::new(),
+            start_date: start + chrono::Duration::days(days),
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![],
+            tags: vec![],
+            url: None,
+            confidence,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![
+                mk_event(1, "Porchfest", 0, 1.0),
+                mk_event(2, "Zine Fair", 1, 0.35),
+                mk_event(3, "Open Mic", 2, 0.8),
+            ])),
+        };
+        let app = test::init_service(App::new().app_data(Data::new(state)).route(
+            "/edit",
+            web::get().to(somerville_events::features::edit::index),
+        ))
+        .await;
+
+        let app = &app;
+        let order = |uri: &'static str| async move {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let body = test::read_body(test::call_service(&app, req).await).await;
+            let document = Html::parse_document(std::str::from_utf8(&body).unwrap());
+            document
+                .select(&Selector::parse(".events-day > a h3").unwrap())
+                .map(|h| h.text().collect::<String>())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(order("/edit").await, ["Porchfest", "Zine Fair", "Open Mic"]);
+        assert_eq!(
+            order("/edit?sort=confidence").await,
+            ["Zine Fair", "Open Mic", "Porchfest"]
+        );
+
+        let req = test::TestRequest::get().uri("/edit").to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        assert!(std::str::from_utf8(&body)?.contains(r#"value="0.35">35%</meter>"#));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_bulk_actions_from_edit() -> Result<()> {
+        let start = Utc::now() + chrono::Duration::days(3);
+        let mk_event = |id, name: &str, days: i64| Event {
+            id,
+            created_at: start,
+            updated_at: start,
+            name: name.to_string(),
+            description: String::new(),
+            full_text: String::new(),
+            start_date: start + chrono::Duration::days(days),
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Music],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![
+                mk_event(1, "Porchfest", 0),
+                mk_event(2, "Zine Fair", 1),
+                mk_event(3, "Open Mic", 2),
+            ])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route(
+                    "/edit",
+                    web::get().to(somerville_events::features::edit::index),
+                )
+                .route(
+                    "/edit/bulk",
+                    web::post().to(somerville_events::features::edit::bulk),
+                )
+                .route(
+                    "/api/events/{id}",
+                    web::get().to(somerville_events::features::view::api_event),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/edit").to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        let body_str = std::str::from_utf8(&body)?;
+        assert!(body_str.contains(r#"<input type="checkbox" name="id" value="3">"#));
+        assert!(!body_str.contains(r#"role="status""#));
+
+        let bulk = |form: &'static str| {
+            test::TestRequest::post()
+                .uri("/edit/bulk")
+                .insert_header(("Content-Type", "application/x-www-form-urlencoded"))
+                .set_payload(form)
+                .to_request()
+        };
+
+        let resp = test::call_service(
+            &app,
+            bulk("action=recategorize&id=1&id=2&event_type=art&event_type=social"),
+        )
+        .await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert_eq!(
+            resp.headers().get("Location").and_then(|v| v.to_str().ok()),
+            Some("/edit?updated=2&selected=2")
+        );
+        for id in [1, 2] {
+            let req = test::TestRequest::get()
+                .uri(&format!("/api/events/{id}"))
+                .to_request();
+            let event: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(event["event_types"], serde_json::json!(["art", "social"]));
+        }
+
+        // Nothing to recategorize to.
+        let resp = test::call_service(&app, bulk("action=recategorize&id=3")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let resp = test::call_service(&app, bulk("action=delete")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        // 99 doesn't exist, so only two of the three go.
+        let resp = test::call_service(&app, bulk("action=delete&id=1&id=3&id=99")).await;
+        assert_eq!(
+            resp.headers().get("Location").and_then(|v| v.to_str().ok()),
+            Some("/edit?updated=2&selected=3")
+        );
+        let req = test::TestRequest::get()
+            .uri("/edit?updated=2&selected=3")
+            .to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        let body_str = std::str::from_utf8(&body)?;
+        assert!(body_str.contains("Updated 2 of 3 selected events."));
+        assert!(body_str.contains("Zine Fair"));
+        assert!(!body_str.contains("Porchfest"));
+        assert!(!body_str.contains("Open Mic"));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_venue_page_lists_its_events() -> Result<()> {
+        let start = Utc::now() + chrono::Duration::days(3);
+        let mk_event = |id, name: &str, place_id: Option<&str>| Event {
+            id,
+            created_at: start,
+            updated_at: start,
+            name: name.to_string(),
+            description: String::new(),
+            full_text: String::new(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: place_id.map(|_| "1 Davis Sq".to_string()),
+            original_location: None,
+            google_place_id: place_id.map(str::to_string),
+            lat: None,
+            lng: None,
+            location_name: place_id.map(|_| "Davis Square".to_string()),
+            event_types: vec![EventType::Music],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![
+                mk_event(1, "Porchfest", Some("place-davis")),
+                mk_event(2, "Zine Fair", Some("place-union")),
+                mk_event(3, "Open Mic", None),
+            ])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route(
+                    "/event/{id}",
+                    web::get().to(somerville_events::features::view::show),
+                )
+                .route(
+                    "/venue/{place_id}",
+                    web::get().to(somerville_events::features::venue::show),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/event/1").to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        assert!(std::str::from_utf8(&body)?
+            .contains(r#"<a href="/venue/place-davis">Davis Square</a>"#));
+
+        let req = test::TestRequest::get()
+            .uri("/venue/place-davis")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let body = test::read_body(resp).await;
+        let body_str = std::str::from_utf8(&body)?;
+        assert!(body_str.contains("<h1>Davis Square</h1>"));
+        assert!(body_str.contains("Porchfest"));
+        assert!(!body_str.contains("Zine Fair"));
+        assert!(!body_str.contains("Open Mic"));
+        assert!(body_str.contains(r#"href="/?location=place-davis""#));
+
+        let req = test::TestRequest::get()
+            .uri("/venue/place-nowhere")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_event_link_preview_card() -> Result<()> {
+        let start = Utc::now() + chrono::Duration::days(3);
+        let event = Event {
+            id: 1,
+            created_at: start,
+            updated_at: start,
+            name: "Porchfest".to_string(),
+            description: String::new(),
+            full_text: String::new(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: Some("Davis Square".to_string()),
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Music],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::CityOfSomerville,
+            external_id: Some("feed-1".to_string()),
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route(
+                    "/event/{id}",
+                    web::get().to(somerville_events::features::view::show),
+                )
+                .route(
+                    "/event/{id}/card.png",
+                    web::get().to(somerville_events::features::view::card),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/event/1").to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        let body_str = std::str::from_utf8(&body)?;
+        let card_url = format!(
+            "{}/event/1/card.png",
+            somerville_events::config::Config::from_env()
+                .public_url
+                .trim_end_matches('/')
+        );
+        assert!(body_str.contains(&format!(
+            r#"<meta property="og:image" content="{card_url}">"#
+        )));
+
+        let req = test::TestRequest::get()
+            .uri("/event/1/card.png")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert_eq!(
+            resp.headers()
+                .get("Content-Type")
+                .and_then(|v| v.to_str().ok()),
+            Some("image/png")
+        );
+        let etag = resp
+            .headers()
+            .get("ETag")
+            .cloned()
+            .expect("card has an ETag");
+        let png = test::read_body(resp).await;
+        assert_eq!(image::guess_format(&png)?, image::ImageFormat::Png);
+
+        let req = test::TestRequest::get()
+            .uri("/event/1/card.png")
+            .insert_header(("If-None-Match", etag))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_MODIFIED);
+
+        let req = test::TestRequest::get()
+            .uri("/event/2/card.png")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_24_hour_clock() -> Result<()> {
+        let start = New_York
+            .with_ymd_and_hms(2030, 6, 1, 19, 30, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        let event = Event {
+            id: 1,
+            created_at: start,
+            updated_at: start,
+            name: "Porchfest".to_string(),
+            description: "Bands on porches.".to_string(),
+            full_text: "Bands on porches.".to_string(),
+            start_date: start,
+            end_date: Some(start + chrono::Duration::hours(2)),
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Music],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route("/", web::get().to(somerville_events::features::view::index))
+                .route(
+                    "/event/{id}",
+                    web::get().to(somerville_events::features::view::show),
+                )
+                .route(
+                    "/clock",
+                    web::post().to(somerville_events::features::view::set_clock),
+                ),
+        )
+        .await;
+        let body =
+            |resp| async move { String::from_utf8(test::read_body(resp).await.to_vec()).unwrap() };
+
+        // 12-hour unless asked.
+        let req = test::TestRequest::get().uri("/event/1").to_request();
+        let page = body(test::call_service(&app, req).await).await;
+        assert!(page.contains("Sat, Jun 1, 2030 • 7:30 PM"), "{page}");
+        assert!(page.contains("9:30 PM"));
+
+        let req = test::TestRequest::get()
+            .uri("/?on=2030-06-01&clock=24")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get("Vary").unwrap(),
+            "Cookie, Accept-Language"
+        );
+        let page = body(resp).await;
+        assert!(page.contains("19:30"), "{page}");
+        assert!(!page.contains("7:30 PM"));
+        // The switch comes back to the same day, without the param that
+        // would override the choice.
+        assert!(
+            page.contains(r#"name="back" value="/?on=2030-06-01""#),
+            "{page}"
+        );
+
+        // Switching remembers the choice and goes back where it was made.
+        let req = test::TestRequest::post()
+            .uri("/clock")
+            .set_form([("clock", "24"), ("back", "/event/1")])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert_eq!(resp.headers().get("Location").unwrap(), "/event/1");
+        let cookie = resp
+            .response()
+            .cookies()
+            .find(|c| c.name() == "clock")
+            .expect("clock cookie")
+            .into_owned();
+        assert_eq!(cookie.value(), "24");
+
+        let req = test::TestRequest::get()
+            .uri("/event/1")
+            .cookie(cookie.clone())
+            .to_request();
+        let page = body(test::call_service(&app, req).await).await;
+        assert!(page.contains("Sat, Jun 1, 2030 • 19:30"), "{page}");
+        assert!(page.contains("21:30"));
+
+        // The param beats the cookie.
+        let req = test::TestRequest::get()
+            .uri("/event/1?clock=12")
+            .cookie(cookie)
+            .to_request();
+        let page = body(test::call_service(&app, req).await).await;
+        assert!(page.contains("7:30 PM"), "{page}");
+
+        // Never sent off the site.
+        let req = test::TestRequest::post()
+            .uri("/clock")
+            .set_form([("clock", "12"), ("back", "//evil.example")])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get("Location").unwrap(), "/");
+        let req = test::TestRequest::post()
+            .uri("/clock")
+            .set_form([("clock", "25"), ("back", "/")])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_pages_in_the_readers_language() -> Result<()> {
+        let start = Utc::now() + chrono::Duration::days(3);
+        let event = Event {
+            id: 1,
+            created_at: start,
+            updated_at: start,
+            name: "Porchfest".to_string(),
+            description: "Bands on porches.".to_string(),
+            full_text: "Bands on porches.".to_string(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Music],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: Some(10.0),
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route("/", web::get().to(somerville_events::features::view::index))
+                .route(
+                    "/event/{id}",
+                    web::get().to(somerville_events::features::view::show),
+                )
+                .route(
+                    "/lang",
+                    web::post().to(somerville_events::features::view::set_lang),
+                ),
+        )
+        .await;
+        let body =
+            |resp| async move { String::from_utf8(test::read_body(resp).await.to_vec()).unwrap() };
+
+        // English unless the browser asks for something we have.
+        let req = test::TestRequest::get().uri("/").to_request();
+        let page = body(test::call_service(&app, req).await).await;
+        assert!(page.contains(r#"<html lang="en">"#));
+        assert!(page.contains("Filter Events"));
+
+        let req = test::TestRequest::get()
+            .uri("/")
+            .insert_header(("Accept-Language", "pt-BR,pt;q=0.9,en;q=0.8"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get("Content-Language").unwrap(), "pt");
+        let page = body(resp).await;
+        assert!(page.contains(r#"<html lang="pt">"#), "{page}");
+        assert!(page.contains("Filtrar eventos"));
+        assert!(!page.contains("Filter Events"));
+        // The switcher offers the other two, each named in its own language.
+        assert!(page.contains(r#"<button name="lang" value="es" lang="es">Español</button>"#));
+        assert!(!page.contains(r#"value="pt""#));
+
+        // The detail page, its report form included.
+        let req = test::TestRequest::get()
+            .uri("/event/1?lang=es")
+            .insert_header(("Accept-Language", "pt"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get("Vary").unwrap(),
+            "Accept, Cookie, Accept-Language"
+        );
+        let page = body(resp).await;
+        assert!(page.contains(r#"<html lang="es">"#), "{page}");
+        assert!(page.contains("<strong>Precio:</strong>"));
+        assert!(page.contains("Informa de un problema con este evento"));
+
+        // Switching remembers the choice, which then beats the browser's.
+        let req = test::TestRequest::post()
+            .uri("/lang")
+            .set_form([("lang", "es"), ("back", "/event/1")])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert_eq!(resp.headers().get("Location").unwrap(), "/event/1");
+        let cookie = resp
+            .response()
+            .cookies()
+            .find(|c| c.name() == "lang")
+            .expect("lang cookie")
+            .into_owned();
+        let req = test::TestRequest::get()
+            .uri("/")
+            .cookie(cookie)
+            .insert_header(("Accept-Language", "pt"))
+            .to_request();
+        let page = body(test::call_service(&app, req).await).await;
+        assert!(page.contains("Aplicar filtros"));
+        assert!(page.contains("Solo eventos gratuitos"));
+
+        let req = test::TestRequest::post()
+            .uri("/lang")
+            .set_form([("lang", "fr"), ("back", "/")])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_related_events_on_the_detail_page() -> Result<()> {
+        let start = Utc::now() + chrono::Duration::days(3);
+        let porchfest = Event {
+            id: 1,
+            created_at: start,
+            updated_at: start,
+            name: "Porchfest".to_string(),
+            description: "Bands on porches.".to_string(),
+            full_text: "Bands on porches.".to_string(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: Some("1 Davis Sq, Somerville, MA".to_string()),
+            original_location: Some("Davis Square".to_string()),
+            google_place_id: Some("place-davis".to_string()),
+            lat: None,
+            lng: None,
+            location_name: Some("Davis Square".to_string()),
+            event_types: vec![EventType::Music],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+        let elsewhere = |id: i64, name: &str, event_type: EventType| Event {
+            id,
+            name: name.to_string(),
+            google_place_id: Some(format!("place-{id}")),
+            location_name: None,
+            event_types: vec![event_type],
+            ..porchfest.clone()
+        };
+        let open_mic = Event {
+            id: 2,
+            name: "Open Mic".to_string(),
+            event_types: vec![EventType::Comedy],
+            ..porchfest.clone()
+        };
+        let last_year = Event {
+            id: 5,
+            name: "Last Year's Porchfest".to_string(),
+            start_date: start - chrono::Duration::days(365),
+            ..porchfest.clone()
+        };
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![
+                elsewhere(3, "Choir", EventType::Music),
+                elsewhere(4, "Yard Sale", EventType::YardSale),
+                porchfest,
+                open_mic,
+                last_year,
+            ])),
+        };
+        let app = test::init_service(App::new().app_data(Data::new(state)).route(
+            "/event/{id}",
+            web::get().to(somerville_events::features::view::show),
+        ))
+        .await;
+
+        let req = test::TestRequest::get().uri("/event/1").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let body = test::read_body(resp).await;
+        let document = Html::parse_document(std::str::from_utf8(&body)?);
+
+        let sections: Vec<(String, Vec<String>)> = document
+            .select(&Selector::parse("section.events-day").unwrap())
+            .map(|section| {
+                let heading = section
+                    .select(&Selector::parse("h2").unwrap())
+                    .flat_map(|h| h.text())
+                    .collect();
+                let links = section
+                    .select(&Selector::parse("a").unwrap())
+                    .filter_map(|a| a.value().attr("href"))
+                    .map(str::to_string)
+                    .collect();
+                (heading, links)
+            })
+            .collect();
+        assert_eq!(
+            sections,
+            vec![
+                (
+                    "More at this venue".to_string(),
+                    vec!["/event/2".to_string()]
+                ),
+                (
+                    "Other Music events".to_string(),
+                    vec!["/event/3".to_string()]
+                ),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_plain_print_view() -> Result<()> {
+        let start = Utc::now() + chrono::Duration::days(3);
+        let event = Event {
+            id: 1,
+            created_at: start,
+            updated_at: start,
+            name: "Porchfest".to_string(),
+            description: "Bands on porches.".to_string(),
+            full_text: "Bands on porches.\n\nRain or shine.".to_string(),
+            start_date: start,
+            end_date: Some(start + chrono::Duration::hours(6)),
+            all_day: false,
+            address: Some("1 Davis Sq, Somerville, MA".to_string()),
+            original_location: Some("Davis Square".to_string()),
+            google_place_id: Some("place-davis".to_string()),
+            lat: None,
+            lng: None,
+            location_name: Some("Davis Square".to_string()),
+            event_types: vec![EventType::Music],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: Some("617-555-0100".to_string()),
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
+        };
+        let app = test::init_service(App::new().app_data(Data::new(state)).route(
+            "/event/{id}/print",
+            web::get().to(somerville_events::features::view::print),
+        ))
+        .await;
+
+        let req = test::TestRequest::get().uri("/event/1/print").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let body = test::read_body(resp).await;
+        let document = Html::parse_document(std::str::from_utf8(&body)?);
+        let count = |selector: &str| document.select(&Selector::parse(selector).unwrap()).count();
+
+        assert_eq!(count("h1"), 1);
+        assert_eq!(count("h2"), 1);
+        assert_eq!(count("svg"), 0);
+        assert_eq!(count("time[datetime]"), 2);
+        assert_eq!(count(r#"a[href^="tel:"]"#), 1);
+        assert_eq!(count(r#"a[href^="https://www.google.com/maps/"]"#), 1);
+
+        let req = test::TestRequest::get().uri("/event/2/print").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_deleted_event_can_be_restored_from_trash() -> Result<()> {
+        let start = Utc::now() + chrono::Duration::days(3);
+        let event = Event {
+            id: 1,
+            created_at: start,
+            updated_at: start,
+            name: "Porchfest".to_string(),
+            description: String::new(),
+            full_text: String::new(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Music],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route(
+                    "/event/{id}",
+                    web::get().to(somerville_events::features::view::show),
+                )
+                .route(
+                    "/event/{id}",
+                    web::delete().to(somerville_events::features::edit::delete),
+                )
+                .route(
+                    "/edit/trash",
+                    web::get().to(somerville_events::features::edit::trash),
+                )
+                .route(
+                    "/edit/event/{id}/restore",
+                    web::post().to(somerville_events::features::edit::restore),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::delete().uri("/event/1").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert_eq!(
+            resp.headers().get("Location").and_then(|v| v.to_str().ok()),
+            Some("/edit/trash")
+        );
+
+        let req = test::TestRequest::get().uri("/event/1").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::get().uri("/edit/trash").to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        let body_str = std::str::from_utf8(&body)?;
+        assert!(body_str.contains("Porchfest"));
+        assert!(body_str.contains(r#"action="/edit/event/1/restore""#));
+
+        let req = test::TestRequest::post()
+            .uri("/edit/event/1/restore")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert_eq!(
+            resp.headers().get("Location").and_then(|v| v.to_str().ok()),
+            Some("/edit/event/1")
+        );
+
+        let req = test::TestRequest::get().uri("/event/1").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        let req = test::TestRequest::get().uri("/edit/trash").to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        assert!(!std::str::from_utf8(&body)?.contains("Porchfest"));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_featured_event_is_pinned_above_its_day() -> Result<()> {
+        let start = Utc::now() + chrono::Duration::days(3);
+        let event = Event {
+            id: 1,
+            created_at: start,
+            updated_at: start,
+            name: "Porchfest".to_string(),
+            description: String::new(),
+            full_text: String::new(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Music],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route("/", web::get().to(somerville_events::features::view::index))
+                .route(
+                    "/edit/event/{id}",
+                    web::get().to(somerville_events::features::edit::show),
+                )
+                .route(
+                    "/edit/event/{id}/featured",
+                    web::post().to(somerville_events::features::edit::set_featured),
+                ),
+        )
+        .await;
+
+        let app = &app;
+        let get = |uri: &'static str| async move {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let body = test::read_body(test::call_service(&app, req).await).await;
+            String::from_utf8(body.to_vec()).unwrap()
+        };
+        let set_featured = |featured: &'static str| async move {
+            let req = test::TestRequest::post()
+                .uri("/edit/event/1/featured")
+                .set_form([("featured", featured)])
+                .to_request();
+            test::call_service(&app, req).await
+        };
+
+        let body = get("/").await;
+        assert!(!body.contains(r#"<h2 id="featured">"#));
+        assert_eq!(body.matches(r#"<a href="/event/1""#).count(), 1);
+        assert!(get("/edit/event/1").await.contains(r#"value="true""#));
+
+        let resp = set_featured("true").await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert_eq!(
+            resp.headers().get("Location").and_then(|v| v.to_str().ok()),
+            Some("/edit/event/1")
+        );
+        assert!(get("/edit/event/1").await.contains("Remove from Featured"));
+
+        // In the banner and still on its own day.
+        let body = get("/").await;
+        assert!(body.contains(r#"<h2 id="featured">"#));
+        assert_eq!(body.matches(r#"<a href="/event/1""#).count(), 2);
+
+        // Filtered views don't get the banner.
+        let body = get("/?type=music").await;
+        assert!(!body.contains(r#"<h2 id="featured">"#));
+        assert_eq!(body.matches(r#"<a href="/event/1""#).count(), 1);
+
+        set_featured("false").await;
+        assert!(!get("/").await.contains(r#"<h2 id="featured">"#));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_cancelled_event_stays_listed_but_struck() -> Result<()> {
+        let start = Utc::now() + chrono::Duration::days(3);
+        let event = Event {
+            id: 1,
+            created_at: start,
+            updated_at: start,
+            name: "Porchfest".to_string(),
+            description: String::new(),
+            full_text: String::new(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Music],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route("/", web::get().to(somerville_events::features::view::index))
+                .route(
+                    "/event/{id}.ics",
+                    web::get().to(somerville_events::features::view::ical),
+                )
+                .route(
+                    "/event/{id}",
+                    web::get().to(somerville_events::features::view::show),
+                )
+                .route(
+                    "/edit/event/{id}",
+                    web::get().to(somerville_events::features::edit::show),
+                )
+                .route(
+                    "/edit/event/{id}/status",
+                    web::post().to(somerville_events::features::edit::set_status),
+                ),
+        )
+        .await;
+
+        let app = &app;
+        let get = |uri: &'static str| async move {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let body = test::read_body(test::call_service(&app, req).await).await;
+            String::from_utf8(body.to_vec()).unwrap()
+        };
+        let set_status = |status: &'static str| async move {
+            let req = test::TestRequest::post()
+                .uri("/edit/event/1/status")
+                .set_form([("status", status)])
+                .to_request();
+            test::call_service(&app, req).await
+        };
+
+        assert!(!get("/").await.contains("<s "));
+        assert!(!get("/edit/event/1").await.contains(r#"value="scheduled""#));
+
+        let resp = set_status("cancelled").await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert_eq!(
+            resp.headers().get("Location").and_then(|v| v.to_str().ok()),
+            Some("/edit/event/1")
+        );
+
+        // Still listed, so whoever saw the flyer finds out.
+        let body = get("/").await;
+        assert_eq!(body.matches(r#"<a href="/event/1""#).count(), 1);
+        assert!(body
+            .contains(r#"<h3><strong>Cancelled</strong> <s itemprop="name">Porchfest</s></h3>"#));
+        assert!(body
+            .contains(r#"<link itemprop="eventStatus" href="https://schema.org/EventCancelled">"#));
+        assert!(get("/?lang=pt")
+            .await
+            .contains("<h3><strong>Cancelado</strong>"));
+
+        let body = get("/event/1").await;
+        assert!(body.contains("<h1><s>Porchfest</s></h1>"));
+        assert!(body.contains(r#"<p class="event-status"><strong>Cancelled</strong></p>"#));
+        assert!(body.contains(r#"<meta property="og:title" content="Cancelled: Porchfest">"#));
+
+        let req = test::TestRequest::get()
+            .uri("/event/1?format=json")
+            .to_request();
+        let json: serde_json::Value =
+            test::read_body_json(test::call_service(&app, req).await).await;
+        assert_eq!(json["status"], "cancelled");
+
+        let ics = get("/event/1.ics").await;
+        assert!(ics.contains("STATUS:CANCELLED"), "{ics}");
+        assert!(ics.contains("SUMMARY:Cancelled: Porchfest"), "{ics}");
+
+        // Back on.
+        assert!(get("/edit/event/1").await.contains(r#"value="scheduled""#));
+        set_status("scheduled").await;
+        let body = get("/").await;
+        assert!(!body.contains("<s "));
+        assert!(body.contains(r#"<h3 itemprop="name">Porchfest</h3>"#));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_map_omits_events_without_coordinates() -> Result<()> {
+        let start = Utc::now() + chrono::Duration::days(1);
+        let mk_event = |id, name: &str, coordinates: Option<(f64, f64)>| Event {
+            id,
+            created_at: start,
+            updated_at: start,
+            name: name.to_string(),
+            description: String::new(),
+            full_text: String::new(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: Some("Somewhere".to_string()),
+            google_place_id: None,
+            lat: coordinates.map(|(lat, _)| lat),
+            lng: coordinates.map(|(_, lng)| lng),
+            location_name: None,
+            event_types: vec![EventType::Music],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![
+                mk_event(1, "Placed </script> Event", Some((42.3967, -71.1226))),
+                mk_event(2, "Unplaced Event", None),
+            ])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route(
+                    "/map",
+                    web::get().to(somerville_events::features::map::index),
+                )
+                .route(
+                    "/api/events",
+                    web::get().to(somerville_events::features::map::api_events),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/events").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let events: Vec<serde_json::Value> = test::read_body_json(resp).await;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["id"], 1);
+        assert_eq!(events[0]["lat"], 42.3967);
+        assert_eq!(events[0]["url"], "/event/1");
+
+        let req = test::TestRequest::get().uri("/map").to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        let body_str = std::str::from_utf8(&body)?;
+        assert!(body_str.contains("-71.1226"));
+        assert!(!body_str.contains("Unplaced Event"));
+        // An event name mustn't be able to close the data <script> early.
+        assert!(!body_str.contains("Placed </script>"));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_api_categories_lists_every_type_once() -> Result<()> {
+        use somerville_events::features::common::get_color_for_type;
+        use strum::IntoEnumIterator;
+
+        let app = test::init_service(App::new().route(
+            "/api/categories",
+            web::get().to(somerville_events::features::view::api_categories),
+        ))
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/categories").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let categories: Vec<serde_json::Value> = test::read_body_json(resp).await;
+
+        for t in EventType::iter() {
+            let matching: Vec<_> = categories
+                .iter()
+                .filter(|c| c["value"] == t.value())
+                .collect();
+            assert_eq!(matching.len(), 1, "{t:?} listed {} times", matching.len());
+            let category = matching[0];
+            assert_eq!(category["label"], t.to_string());
+            // The same colors the pages' stylesheet gets.
+            assert_eq!(
+                format!(
+                    "light-dark({}, {})",
+                    category["color"]["light"].as_str().unwrap(),
+                    category["color"]["dark"].as_str().unwrap()
+                ),
+                get_color_for_type(&t)
+            );
+            assert!(
+                category["html_url"]
+                    .as_str()
+                    .unwrap()
+                    .ends_with(&t.get_url()),
+                "{category}"
+            );
+        }
+        assert_eq!(categories.len(), EventType::iter().count());
+
+        let yard_sale = categories.iter().find(|c| c["value"] == "yard-sale");
+        assert_eq!(yard_sale.map(|c| &c["icon"]), Some(&"icon-tag".into()));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_responses_are_compressed_when_asked() -> Result<()> {
+        use actix_web::http::header;
+        use actix_web::middleware::Compress;
+
+        let app = test::init_service(
+            App::new()
+                .wrap(Compress::default())
+                .service(actix_files::Files::new("/static", "static"))
+                .route(
+                    "/api/categories",
+                    web::get().to(somerville_events::features::view::api_categories),
+                ),
+        )
+        .await;
+
+        for uri in ["/api/categories", "/static/map.js"] {
+            let req = test::TestRequest::get()
+                .uri(uri)
+                .insert_header((header::ACCEPT_ENCODING, "gzip"))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::OK, "{uri}");
+            assert_eq!(
+                resp.headers().get(header::CONTENT_ENCODING).unwrap(),
+                "gzip",
+                "{uri}"
+            );
+            assert_eq!(
+                resp.headers().get(header::VARY).unwrap(),
+                "accept-encoding",
+                "{uri}"
+            );
+
+            // Clients that don't ask get the plain body.
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert!(
+                resp.headers().get(header::CONTENT_ENCODING).is_none(),
+                "{uri}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_api_events_near_me() -> Result<()> {
+        let start = Utc::now() + chrono::Duration::days(1);
+        let mk_event = |id, name: &str, lat, lng| Event {
+            id,
+            created_at: start,
+            updated_at: start,
+            name: name.to_string(),
+            description: String::new(),
+            full_text: String::new(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: Some(lat),
+            lng: Some(lng),
+            location_name: None,
+            event_types: vec![EventType::Music],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![
+                mk_event(1, "Union Square", 42.3794, -71.0934),
+                mk_event(2, "Davis Square", 42.3967, -71.1226),
+            ])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route("/", web::get().to(somerville_events::features::view::index))
+                .route(
+                    "/api/events",
+                    web::get().to(somerville_events::features::map::api_events),
+                ),
+        )
+        .await;
+
+        // Chronological (here: insertion) order without a point.
+        let req = test::TestRequest::get().uri("/api/events").to_request();
+        let events: Vec<serde_json::Value> =
+            test::read_body_json(test::call_service(&app, req).await).await;
+        assert_eq!(events[0]["id"], 1);
+        assert!(events[0].get("distance_km").is_none());
+
+        // From Porter Square, Davis is closer than Union.
+        let req = test::TestRequest::get()
+            .uri("/api/events?lat=42.3884&lng=-71.1191&radius_km=5")
+            .to_request();
+        let events: Vec<serde_json::Value> =
+            test::read_body_json(test::call_service(&app, req).await).await;
+        assert_eq!(events[0]["id"], 2);
+        assert_eq!(events[1]["id"], 1);
+        assert!(events[0]["distance_km"].as_f64().is_some_and(|d| d < 1.0));
+
+        for uri in [
+            "/api/events?lat=42.3884",
+            "/api/events?lat=42.3884&lng=-71.1191&radius_km=-1",
+            "/?lat=420&lng=-71.1191",
+        ] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(
+                resp.status(),
+                actix_web::http::StatusCode::BAD_REQUEST,
+                "{uri}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_api_errors_are_json() -> Result<()> {
+        let start = Utc::now() + chrono::Duration::days(1);
+        let event = Event {
+            id: 1,
+            created_at: start,
+            updated_at: start,
+            name: "Porch Concert".to_string(),
+            description: String::new(),
+            full_text: String::new(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Music],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .service(
+                    web::scope("/api")
+                        .route(
+                            "/events",
+                            web::get().to(somerville_events::features::map::api_events),
+                        )
+                        .route(
+                            "/events/{id}",
+                            web::get().to(somerville_events::features::view::api_event),
+                        )
+                        .default_service(web::to(
+                            somerville_events::features::common::api_not_found,
+                        )),
+                )
+                .default_service(web::to(
+                    somerville_events::features::common::default_not_found,
+                )),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/api/events/1").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["name"], "Porch Concert");
+
+        for (uri, status, code) in [
+            ("/api/events/404", 404, "not_found"),
+            ("/api/events/abc", 404, "not_found"),
+            ("/api/nothing-here", 404, "not_found"),
+            ("/api/events?lat=42.3884", 400, "bad_request"),
+            ("/api/events?radius_km=lots", 400, "bad_request"),
+        ] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status().as_u16(), status, "{uri}");
+            assert_eq!(
+                resp.headers()
+                    .get("Content-Type")
+                    .and_then(|v| v.to_str().ok()),
+                Some("application/json"),
+                "{uri}"
+            );
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            assert_eq!(body["error"]["code"], code, "{uri}");
+            assert!(body["error"]["message"]
+                .as_str()
+                .is_some_and(|m| !m.is_empty()));
+        }
+
+        // Pages outside /api keep their HTML error page.
+        let req = test::TestRequest::get().uri("/no-such-page").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers()
+                .get("Content-Type")
+                .and_then(|v| v.to_str().ok()),
+            Some("text/html; charset=utf-8")
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_not_found_pages() -> Result<()> {
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route(
+                    "/event/{id}",
+                    web::get().to(somerville_events::features::view::show),
+                )
+                .default_service(web::to(
+                    somerville_events::features::common::default_not_found,
+                )),
+        )
+        .await;
+
+        for uri in ["/event/404", "/no-such-page"] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+            assert_eq!(
+                resp.headers()
+                    .get("Content-Type")
+                    .and_then(|v| v.to_str().ok()),
+                Some("text/html; charset=utf-8")
+            );
+
+            let body = test::read_body(resp).await;
+            let document = Html::parse_document(std::str::from_utf8(&body)?);
+            let heading = Selector::parse("h1").unwrap();
+            let home_link = Selector::parse(r#"a[href="/"]"#).unwrap();
+            assert_eq!(
+                document
+                    .select(&heading)
+                    .next()
+                    .map(|h| h.text().collect::<String>()),
+                Some("Not Found".to_string()),
+                "{uri}"
+            );
+            assert!(document.select(&home_link).next().is_some(), "{uri}");
+        }
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_show_renders_contact_info_and_tags() -> Result<()> {
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 23, 0, 0).unwrap();
+        let event = Event {
+            id: 1,
+            created_at: start,
+            updated_at: start,
+            name: "Poetry Night".to_string(),
+            description: "Open mic".to_string(),
+            full_text: "".to_string(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![],
+            tags: vec!["open mic".to_string()],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::PorterSquareBooks,
+            external_id: Some("psb-1".to_string()),
+            contact_email: Some("events@example.org".to_string()),
+            contact_phone: Some("+1 (617) 555-0123".to_string()),
+            registration_required: true,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
+        };
+        let app = test::init_service(App::new().app_data(Data::new(state)).route(
+            "/event/{id}",
+            web::get().to(somerville_events::features::view::show),
+        ))
+        .await;
+
+        let req = test::TestRequest::get().uri("/event/1").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let body = test::read_body(resp).await;
+        let body = std::str::from_utf8(&body)?;
+        let document = Html::parse_document(body);
+
+        let mailto = Selector::parse(r#"a[href="mailto:events@example.org"]"#).unwrap();
+        let tel = Selector::parse(r#"a[href="tel:+16175550123"]"#).unwrap();
+        assert!(document.select(&mailto).next().is_some());
+        assert!(document.select(&tel).next().is_some());
+        assert!(body.contains("Registration required"));
+
+        let tag = Selector::parse(r#"a.event-tag[href="/?tag=open+mic"]"#).unwrap();
+        assert!(document.select(&tag).next().is_some());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_show_uses_configured_timezone() -> Result<()> {
+        // 10:30 PM on Mar 1 in Somerville, but already Mar 2 in Berlin.
+        let start = Utc.with_ymd_and_hms(2025, 3, 2, 3, 30, 0).unwrap();
+        let event = Event {
+            id: 1,
+            created_at: start,
+            updated_at: start,
+            name: "Late Show".to_string(),
+            description: "".to_string(),
+            full_text: "".to_string(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: chrono_tz::Europe::Berlin,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
+        };
+        let app = test::init_service(App::new().app_data(Data::new(state)).route(
+            "/event/{id}",
+            web::get().to(somerville_events::features::view::show),
+        ))
+        .await;
+
+        let req = test::TestRequest::get().uri("/event/1").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let body = test::read_body(resp).await;
+        let body = std::str::from_utf8(&body)?;
+        assert!(body.contains("Sun, Mar 2, 2025 • 4:30 AM"), "{body}");
+        assert!(!body.contains("10:30 PM"));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_all_day_festival_shows_on_every_day() -> Result<()> {
+        let festival = |id, name: &str, start, end| Event {
+            id,
+            created_at: start,
+            updated_at: start,
+            name: name.to_string(),
+            description: "".to_string(),
+            full_text: "".to_string(),
+            start_date: start,
+            end_date: Some(end),
+            all_day: true,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::CityOfSomerville,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+        let ny_midnight = |y, m, d| {
+            New_York
+                .with_ymd_and_hms(y, m, d, 0, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let utc_midnight = |y, m, d| Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap();
+
+        let cases = [
+            // A June weekend, no DST change, with the feed's dates at UTC
+            // midnight: 8pm the evening before in Somerville.
+            (
+                festival(
+                    1,
+                    "Porchfest",
+                    utc_midnight(2025, 6, 13),
+                    utc_midnight(2025, 6, 15),
+                ),
+                New_York.with_ymd_and_hms(2025, 6, 14, 18, 0, 0).unwrap(),
+                vec!["2025-06-13", "2025-06-14", "2025-06-15"],
+                vec!["2025-06-12", "2025-06-16"],
+            ),
+            // Friday to Sunday across the end of January, mixing local and
+            // UTC midnights.
+            (
+                festival(
+                    2,
+                    "Winter Fest",
+                    ny_midnight(2025, 1, 31),
+                    utc_midnight(2025, 2, 2),
+                ),
+                New_York.with_ymd_and_hms(2025, 1, 30, 12, 0, 0).unwrap(),
+                vec!["2025-01-31", "2025-02-01", "2025-02-02"],
+                vec!["2025-01-30", "2025-02-03"],
+            ),
+        ];
+
+        for (event, now, shown_on, hidden_on) in cases {
+            let name = event.name.clone();
+            let state = AppState {
+                openai_api_key: "dummy".to_string(),
+                google_maps_api_key: "dummy".to_string(),
+                geocoding_enabled: false,
+                username: "user".to_string(),
+                password: "pass".to_string(),
+                timezone: DEFAULT_TIMEZONE,
+                api_timeouts: ApiTimeouts::default(),
+                geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+                upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+                openai_structured_outputs: true,
+                past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+                event_durations: EventDurations::default(),
+                webhooks: Webhooks::default(),
+                contact_relay: ContactRelay::default(),
+                trusted_proxies: vec![],
+                login_throttle: LoginThrottle::default(),
+                index_cache: IndexCache::default(),
+                events_repo: Box::new(MockEventsRepo::new(vec![event])),
+            };
+            let now_utc = now.with_timezone(&Utc);
+            let app = test::init_service(App::new().app_data(Data::new(state)).route(
+                "/",
+                web::get().to(move |req: HttpRequest, state: Data<AppState>| {
+                    somerville_events::features::view::index_with_now(
+                        req,
+                        state,
+                        now_utc,
+                        IndexQuery::default(),
+                    )
+                }),
+            ))
+            .await;
+
+            let req = test::TestRequest::get().uri("/").to_request();
+            let body = test::read_body(test::call_service(&app, req).await).await;
+            let document = Html::parse_document(std::str::from_utf8(&body)?);
+            let day_text = |day: &str| {
+                let selector =
+                    Selector::parse(&format!(r#"section[aria-labelledby="day-{day}"]"#)).unwrap();
+                document
+                    .select(&selector)
+                    .next()
+                    .map(|section| section.text().collect::<String>())
+            };
+
+            for day in shown_on {
+                assert!(
+                    day_text(day).is_some_and(|text| text.contains(&name)),
+                    "{name} missing on {day}"
+                );
+            }
+            for day in hidden_on {
+                assert!(day_text(day).is_none(), "{name} shown on {day}");
+            }
+        }
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_show_negotiates_json() -> Result<()> {
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 23, 0, 0).unwrap();
+        let event = Event {
+            id: 7,
+            created_at: start,
+            updated_at: start,
+            name: "Jazz Brunch".to_string(),
+            description: "Eggs and bebop".to_string(),
+            full_text: "JAZZ BRUNCH".to_string(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: Some("The Burren".to_string()),
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Music],
+            tags: vec!["jazz".to_string()],
+            url: None,
+            confidence: 0.5,
+            age_restrictions: None,
+            price: Some(10.0),
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
+        };
+        let app = test::init_service(App::new().app_data(Data::new(state)).route(
+            "/event/{id}",
+            web::get().to(somerville_events::features::view::show),
+        ))
+        .await;
+
+        let get = |uri: &str, accept: Option<&str>| {
+            let mut req = test::TestRequest::get().uri(uri);
+            if let Some(accept) = accept {
+                req = req.insert_header(("Accept", accept));
+            }
+            req.to_request()
+        };
+
+        for (uri, accept) in [
+            ("/event/7", Some("application/json")),
+            ("/event/7?format=json", None),
+            ("/event/7?format=json", Some("text/html")),
+        ] {
+            let resp = test::call_service(&app, get(uri, accept)).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::OK, "{uri}");
+            assert_eq!(
+                resp.headers().get("Vary").unwrap(),
+                "Accept, Cookie, Accept-Language"
+            );
+            let json: serde_json::Value = test::read_body_json(resp).await;
+            assert_eq!(json["name"], "Jazz Brunch");
+            assert_eq!(json["event_types"], serde_json::json!(["music"]));
+            assert_eq!(json["address"], "The Burren");
+            assert!(json.get("confidence").is_none());
+        }
+
+        // What browsers send: HTML ranks above the */* fallback.
+        let browser = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+        let html = test::call_service(&app, get("/event/7", Some(browser))).await;
+        assert_eq!(
+            html.headers().get("Content-Type").unwrap(),
+            "text/html; charset=utf-8"
+        );
+        let json = test::call_service(&app, get("/event/7?format=json", None)).await;
+        assert_ne!(
+            html.headers().get("ETag").unwrap(),
+            json.headers().get("ETag").unwrap()
+        );
+
+        let resp = test::call_service(&app, get("/event/7?format=xml", None)).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_export_streams_every_event() -> Result<()> {
+        let start = Utc.with_ymd_and_hms(2020, 1, 1, 12, 0, 0).unwrap();
+        // More than two export batches, including events long past.
+        let events: Vec<Event> = (1..=1201)
+            .map(|id| Event {
+                id,
+                created_at: start,
+                updated_at: start,
+                name: format!("Event {id}"),
+                description: "".to_string(),
+                full_text: "".to_string(),
+                start_date: start + chrono::Duration::days(id),
+                end_date: None,
+                all_day: false,
+                address: None,
+                original_location: None,
+                google_place_id: None,
+                lat: None,
+                lng: None,
+                location_name: None,
+                event_types: vec![EventType::Music],
+                tags: vec![],
+                url: None,
+                confidence: 1.0,
+                age_restrictions: None,
+                price: None,
+                source: EventSource::ImageUpload,
+                external_id: None,
+                contact_email: None,
+                contact_phone: None,
+                registration_required: false,
+                featured: false,
+                status: EventStatus::Scheduled,
+            })
+            .collect();
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(events.clone())),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .wrap(features::login::session_middleware(Key::generate(), false))
+                .service(
+                    web::scope("/edit")
+                        .wrap(from_fn(require_admin))
+                        .route("/export.json", web::get().to(features::edit::export)),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/edit/export.json")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+
+        let req = test::TestRequest::get()
+            .uri("/edit/export.json")
+            .insert_header(("Authorization", "Basic dXNlcjpwYXNz"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert_eq!(
+            resp.headers().get("Content-Type").unwrap(),
+            "application/x-ndjson"
+        );
+        let body = test::read_body(resp).await;
+        let exported = std::str::from_utf8(&body)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<Event>, _>>()?;
+        assert_eq!(exported, events);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_report_event_and_review_in_edit() -> Result<()> {
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 23, 0, 0).unwrap();
+        let event = Event {
+            id: 1,
+            created_at: start,
+            updated_at: start,
+            name: "Mystery Meetup".to_string(),
+            description: "".to_string(),
+            full_text: "".to_string(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![IpAddr::from([127, 0, 0, 1])],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .wrap(features::login::session_middleware(Key::generate(), false))
+                .route("/event/{id}", web::get().to(features::view::show))
+                .route(
+                    "/event/{id}/report",
+                    web::post().to(features::report::submit),
+                )
+                .service(
+                    web::scope("/edit")
+                        .wrap(from_fn(require_admin))
+                        .route("", web::get().to(features::edit::index))
+                        .route(
+                            "/report/{id}/dismiss",
+                            web::post().to(features::edit::dismiss_report),
+                        ),
+                ),
+        )
+        .await;
+        let report = |id: i64, ip: &str, form: &[(&str, &str)]| {
+            test::TestRequest::post()
+                .uri(&format!("/event/{id}/report"))
+                .peer_addr(SocketAddr::new(ip.parse().unwrap(), 40000))
+                .set_form(form)
+                .to_request()
+        };
+        let admin = |req: test::TestRequest| {
+            req.insert_header(("Authorization", "Basic dXNlcjpwYXNz"))
+                .to_request()
+        };
+
+        // The form works without JS, straight from the event page.
+        let req = test::TestRequest::get().uri("/event/1").to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        assert!(std::str::from_utf8(&body)?.contains(r#"action="/event/1/report""#));
+
+        let resp = test::call_service(
+            &app,
+            report(
+                1,
+                "203.0.113.5",
+                &[("reason", "Wrong date"), ("email", "me@example.org")],
+            ),
+        )
+        .await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert_eq!(resp.headers().get("Location").unwrap(), "/report-received");
+
+        let long = "x".repeat(features::report::MAX_REPORT_REASON_LEN + 1);
+        for (id, form, status) in [
+            (1, vec![("reason", "  ")], 400),
+            (1, vec![("reason", long.as_str())], 400),
+            (1, vec![("reason", "Spam"), ("email", "nope")], 400),
+            (99, vec![("reason", "Spam")], 404),
+        ] {
+            let resp = test::call_service(&app, report(id, "198.51.100.7", &form)).await;
+            assert_eq!(resp.status().as_u16(), status, "{form:?}");
+        }
+
+        let resp = test::call_service(&app, admin(test::TestRequest::get().uri("/edit"))).await;
+        let body = test::read_body(resp).await;
+        let body = std::str::from_utf8(&body)?;
+        assert!(body.contains("Wrong date"));
+        assert!(body.contains("mailto:me@example.org"));
+        assert!(body.contains(r#"href="/edit/event/1""#));
+
+        let resp = test::call_service(
+            &app,
+            admin(test::TestRequest::post().uri("/edit/report/1/dismiss")),
+        )
+        .await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        let resp = test::call_service(&app, admin(test::TestRequest::get().uri("/edit"))).await;
+        let body = test::read_body(resp).await;
+        assert!(!std::str::from_utf8(&body)?.contains("Wrong date"));
+
+        // One address gets a handful per hour; others aren't affected.
+        for _ in 0..5 {
+            let resp =
+                test::call_service(&app, report(1, "203.0.113.5", &[("reason", "Spam")])).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        }
+        let resp = test::call_service(&app, report(1, "203.0.113.5", &[("reason", "Spam")])).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::TOO_MANY_REQUESTS
+        );
+        let resp = test::call_service(&app, report(1, "192.0.2.1", &[("reason", "Spam")])).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+
+        // A forwarding header straight from the client doesn't get it a
+        // fresh limit...
+        let forwarded = |peer: &str, forwarded_for: &str| {
+            test::TestRequest::post()
+                .uri("/event/1/report")
+                .peer_addr(SocketAddr::new(peer.parse().unwrap(), 40000))
+                .insert_header(("X-Forwarded-For", forwarded_for.to_string()))
+                .set_form([("reason", "Spam")])
+                .to_request()
+        };
+        let resp = test::call_service(&app, forwarded("203.0.113.5", "198.51.100.20")).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::TOO_MANY_REQUESTS
+        );
+        // ...and through the proxy, only the address the proxy added
+        // counts, not whatever the client put in front of it.
+        let resp =
+            test::call_service(&app, forwarded("127.0.0.1", "198.51.100.20, 203.0.113.5")).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::TOO_MANY_REQUESTS
+        );
+        let resp =
+            test::call_service(&app, forwarded("127.0.0.1", "203.0.113.5, 198.51.100.20")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_contact_organizer_without_seeing_their_address() -> Result<()> {
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 23, 0, 0).unwrap();
+        let event = |id: i64, contact_email: Option<&str>| Event {
+            id,
+            created_at: start,
+            updated_at: start,
+            name: format!("Event {id}"),
+            description: "".to_string(),
+            full_text: "".to_string(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: contact_email.map(str::to_string),
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+        let repo = MockEventsRepo::new(vec![
+            event(1, Some("organizer@example.org")),
+            event(2, None),
+        ]);
+        let messages = repo.contact_messages.clone();
+        // Nothing listens there, so sends fail in the background and are
+        // only logged; what's checked is what the reader sees and what's
+        // stored.
+        let tasks = somerville_events::background_tasks::BackgroundTasks::default();
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::new(
+                "smtp://127.0.0.1:1",
+                "Somerville Events <events@example.com>",
+                tasks.clone(),
+            )?,
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(repo),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route("/event/{id}", web::get().to(features::view::show))
+                .route(
+                    "/event/{id}/contact",
+                    web::get().to(features::contact::form),
+                )
+                .route(
+                    "/event/{id}/contact",
+                    web::post().to(features::contact::submit),
+                ),
+        )
+        .await;
+        let contact = |id: i64, ip: &str, form: &[(&str, &str)]| {
+            test::TestRequest::post()
+                .uri(&format!("/event/{id}/contact"))
+                .peer_addr(SocketAddr::new(ip.parse().unwrap(), 40000))
+                .set_form(form)
+                .to_request()
+        };
+        let question = [
+            ("message", "Is it still on if it rains?"),
+            ("email", "reader@example.net"),
+        ];
+
+        // The page links to the form instead of showing the address, in
+        // HTML and JSON alike.
+        let req = test::TestRequest::get().uri("/event/1").to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        let body = std::str::from_utf8(&body)?;
+        assert!(body.contains(r#"href="/event/1/contact""#));
+        assert!(!body.contains("organizer@example.org"));
+        let req = test::TestRequest::get()
+            .uri("/event/1?format=json")
+            .to_request();
+        let json: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(json["contact_email"], serde_json::Value::Null);
+        assert!(json["contact_url"]
+            .as_str()
+            .unwrap()
+            .ends_with("/event/1/contact"));
+
+        let req = test::TestRequest::get()
+            .uri("/event/1/contact")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let body = test::read_body(resp).await;
+        assert!(std::str::from_utf8(&body)?.contains(r#"action="/event/1/contact""#));
+        // No address to relay to.
+        let req = test::TestRequest::get()
+            .uri("/event/2/contact")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        let resp = test::call_service(&app, contact(1, "203.0.113.5", &question)).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert_eq!(resp.headers().get("Location").unwrap(), "/contact-sent");
+        {
+            let messages = messages.lock().unwrap();
+            assert_eq!(messages.len(), 1);
+            assert_eq!(messages[0].0.event_id, 1);
+            assert_eq!(messages[0].0.sender_email, "reader@example.net");
+            assert_eq!(messages[0].0.message, "Is it still on if it rains?");
+            assert_eq!(messages[0].0.sender_ip, "203.0.113.5");
+        }
+
+        let long = "x".repeat(features::contact::MAX_MESSAGE_LEN + 1);
+        for (id, form, status) in [
+            (
+                1,
+                vec![("message", "  "), ("email", "reader@example.net")],
+                400,
+            ),
+            (
+                1,
+                vec![("message", &long), ("email", "reader@example.net")],
+                400,
+            ),
+            (1, vec![("message", "Hi"), ("email", "nope")], 400),
+            (2, question.to_vec(), 404),
+            (99, question.to_vec(), 404),
+        ] {
+            let resp = test::call_service(&app, contact(id, "198.51.100.7", &form)).await;
+            assert_eq!(resp.status().as_u16(), status, "{id} {form:?}");
+        }
+        assert_eq!(messages.lock().unwrap().len(), 1);
+
+        // One address gets a few per hour...
+        for _ in 0..2 {
+            let resp = test::call_service(&app, contact(1, "203.0.113.5", &question)).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        }
+        let resp = test::call_service(&app, contact(1, "203.0.113.5", &question)).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::TOO_MANY_REQUESTS
+        );
+        // A forwarding header from the client doesn't make it someone new.
+        let req = test::TestRequest::post()
+            .uri("/event/1/contact")
+            .peer_addr(SocketAddr::new("203.0.113.5".parse()?, 40000))
+            .insert_header(("X-Forwarded-For", "198.51.100.20"))
+            .set_form(question)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::TOO_MANY_REQUESTS
+        );
+
+        // Over the day, one address only gets a few to the same organizer,
+        // however slowly it sends them...
+        let earlier = Utc::now() - chrono::Duration::hours(3);
+        messages.lock().unwrap().extend((0..5).map(|_| {
+            (
+                NewContactMessage {
+                    event_id: 1,
+                    sender_email: "persistent@example.net".to_string(),
+                    message: "Any news?".to_string(),
+                    sender_ip: "192.0.2.1".to_string(),
+                },
+                earlier,
+            )
+        }));
+        let resp = test::call_service(&app, contact(1, "192.0.2.1", &question)).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::TOO_MANY_REQUESTS
+        );
+        // ...which doesn't stop anyone else from writing.
+        let resp = test::call_service(&app, contact(1, "192.0.2.2", &question)).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+
+        tasks.shutdown(std::time::Duration::from_secs(10)).await;
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_edit_stats() -> Result<()> {
+        let now = Utc::now();
+        let event = |id: i64, start: DateTime<Utc>, event_types: Vec<EventType>| Event {
+            id,
+            created_at: now,
+            updated_at: now,
+            name: format!("Event {id}"),
+            description: "".to_string(),
+            full_text: "".to_string(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: Some("place-armory".to_string()),
+            lat: None,
+            lng: None,
+            location_name: Some("Arts at the Armory".to_string()),
+            event_types,
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![
+                event(1, now + chrono::Duration::days(1), vec![EventType::Music]),
+                event(2, now - chrono::Duration::days(1), vec![EventType::Music]),
+            ])),
+        };
+        let app = test::init_service(
+            App::new().app_data(Data::new(state)).service(
+                web::scope("/edit")
+                    .wrap(from_fn(require_admin))
+                    .route("/stats", web::get().to(features::edit::stats)),
+            ),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/edit/stats").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+
+        let req = test::TestRequest::get()
+            .uri("/edit/stats")
+            .insert_header(("Authorization", "Basic dXNlcjpwYXNz"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(resp.status().is_success());
+        let body = test::read_body(resp).await;
+        let body = std::str::from_utf8(&body)?;
+        assert!(body.contains("<dt>Listed events</dt>\n            <dd>2</dd>"));
+        assert!(body.contains("<dt>Upcoming</dt>\n            <dd>1</dd>"));
+        assert!(body.contains(r#"<a href="/?type=music">Music</a>"#));
+        assert!(body.contains(r#"<a href="/venue/place-armory">Arts at the Armory</a>"#));
+        assert!(body.contains(r#"<meter min="0" max="2" value="2"></meter>"#));
+        // Twelve weeks, quiet or not.
+        assert_eq!(body.matches("Week of ").count(), 12);
+
+        Ok(())
+    }
+
+    /// A listed event, as an earlier upload of a flyer left it.
+    fn uploaded_event(id: i64) -> Event {
+        let now = Utc::now();
+        Event {
+            id,
+            created_at: now,
+            updated_at: now,
+            name: format!("Event {id}"),
+            description: "".to_string(),
+            full_text: "".to_string(),
+            start_date: now + chrono::Duration::days(7),
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_repeat_upload_skips_the_llm() -> Result<()> {
+        use somerville_events::features::upload::{process_upload, Submission, UploadOutcome};
+        use somerville_events::image_processing::dhash;
+
+        let flyer = std::path::Path::new("examples/dance_flyer.jpg");
+        let repo = MockEventsRepo::new(vec![uploaded_event(7), uploaded_event(8)]);
+        // Two bits off, as a re-saved copy of the same photo would be.
+        let earlier = dhash(&image::open(flyer)?) ^ 0b101;
+        repo.processed_images
+            .lock()
+            .unwrap()
+            .push((earlier, vec![7, 8]));
+        let state = AppState {
+            // Any call to OpenAI would fail with this key.
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(repo),
+        };
+
+        let outcome = process_upload(
+            flyer,
+            &awc::Client::default(),
+            &state,
+            &Submission::default(),
+        )
+        .await?;
+        assert_eq!(outcome, UploadOutcome::AlreadyProcessed(vec![7, 8]));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_pending_flyer_does_not_block_a_repeat_upload() -> Result<()> {
+        use somerville_events::features::upload::{process_upload, Submission, UploadOutcome};
+        use somerville_events::image_processing::dhash;
+
+        let flyer = std::path::Path::new("examples/dance_flyer.jpg");
+        let repo = MockEventsRepo::new(vec![]);
+        // Someone sent the flyer in and it's waiting for an admin.
+        repo.pending.lock().unwrap().push(uploaded_event(7));
+        repo.processed_images
+            .lock()
+            .unwrap()
+            .push((dhash(&image::open(flyer)?), vec![7]));
+        let state = AppState {
+            // Any call to OpenAI would fail with this key.
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(repo),
+        };
+
+        // Nothing is listed yet, so the admin's upload goes to the LLM.
+        let client = awc::Client::default();
+        let outcome = process_upload(flyer, &client, &state, &Submission::default()).await;
+        assert!(outcome.is_err());
+
+        state.events_repo.approve(&[7]).await?;
+        let outcome = process_upload(flyer, &client, &state, &Submission::default()).await?;
+        assert_eq!(outcome, UploadOutcome::AlreadyProcessed(vec![7]));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_uploads_past_the_limit_wait_their_turn() -> Result<()> {
+        use somerville_events::features::upload::{process_upload, Submission};
+
+        let state = Data::new(AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(1),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![])),
+        });
+
+        // The one upload allowed at a time, still with OpenAI.
+        let running = state.upload_slots.acquire().await;
+
+        let queued_state = state.clone();
+        let queued = actix_web::rt::spawn(async move {
+            process_upload(
+                std::path::Path::new("examples/dance_flyer.jpg"),
+                &awc::Client::default(),
+                &queued_state,
+                &Submission::default(),
+            )
+            .await
+        });
+        // Hashing the image comes first, off the worker thread.
+        for _ in 0..200 {
+            if state.upload_slots.waiting() > 0 {
+                break;
+            }
+            actix_web::rt::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(state.upload_slots.waiting(), 1);
+        assert!(!queued.is_finished());
+
+        // Stopped before it gets a turn, which would call OpenAI.
+        queued.abort();
+        assert!(queued.await.is_err());
+        assert_eq!(state.upload_slots.waiting(), 0);
+        drop(running);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_preview_of_a_repeat_flyer() -> Result<()> {
+        use somerville_events::image_processing::dhash;
+
+        let flyer = std::fs::read("examples/dance_flyer.jpg")?;
+        let repo = MockEventsRepo::new(vec![uploaded_event(7)]);
+        repo.processed_images
+            .lock()
+            .unwrap()
+            .push((dhash(&image::load_from_memory(&flyer)?), vec![7]));
+        let state = AppState {
+            // Any call to OpenAI would fail with this key.
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(repo.clone()),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .app_data(Data::new(awc::Client::default()))
+                .app_data(Data::new(
+                    somerville_events::background_tasks::BackgroundTasks::default(),
+                ))
+                .route(
+                    "/upload",
+                    web::post().to(somerville_events::features::upload::save),
+                ),
+        )
+        .await;
+
+        let boundary = "flyer-boundary";
+        let mut body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"idempotency_key\"\r\n\r\n{}\r\n\
+             --{boundary}\r\nContent-Disposition: form-data; name=\"image\"; filename=\"flyer.jpg\"\r\n\
+             Content-Type: image/jpeg\r\n\r\n",
+            uuid::Uuid::new_v4()
+        )
+        .into_bytes();
+        body.extend_from_slice(&flyer);
+        body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+        let req = test::TestRequest::post()
+            .uri("/upload?preview=1")
+            .insert_header((
+                "Content-Type",
+                format!("multipart/form-data; boundary={boundary}"),
+            ))
+            .insert_header(("Authorization", "Basic dXNlcjpwYXNz"))
+            .set_payload(body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        // Answered in the request itself rather than redirecting to the
+        // "processing in the background" page.
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let body = test::read_body(resp).await;
+        let body_str = std::str::from_utf8(&body)?;
+        assert!(body_str.contains(r#"href="/event/7""#));
+        assert!(!body_str.contains("/upload/confirm"));
+        assert_eq!(repo.events.lock().unwrap().len(), 1);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_rejects_oversized_and_non_image_files() -> Result<()> {
+        let repo = MockEventsRepo::new(vec![]);
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(repo.clone()),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .app_data(Data::new(awc::Client::default()))
+                .app_data(Data::new(
+                    somerville_events::background_tasks::BackgroundTasks::default(),
+                ))
+                .service(
+                    web::resource("/upload")
+                        .app_data(somerville_events::features::upload::multipart_config(
+                            64 * 1024,
+                        ))
+                        .route(web::post().to(somerville_events::features::upload::save)),
+                ),
+        )
+        .await;
+
+        let upload = |file_name: &str, content_type: &str, contents: &[u8]| {
+            let boundary = "flyer-boundary";
+            let mut body = format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"idempotency_key\"\r\n\r\n{}\r\n\
+                 --{boundary}\r\nContent-Disposition: form-data; name=\"image\"; filename=\"{file_name}\"\r\n\
+                 Content-Type: {content_type}\r\n\r\n",
+                uuid::Uuid::new_v4()
+            )
+            .into_bytes();
+            body.extend_from_slice(contents);
+            body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+            test::TestRequest::post()
+                .uri("/upload?preview=1")
+                .insert_header((
+                    "Content-Type",
+                    format!("multipart/form-data; boundary={boundary}"),
+                ))
+                .set_payload(body)
+                .to_request()
+        };
+
+        let resp =
+            test::call_service(&app, upload("big.jpg", "image/jpeg", &[0xFF; 100 * 1024])).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        let resp = test::call_service(&app, upload("notes.txt", "text/plain", b"Porchfest!")).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+
+        // The declared type is only a claim; the bytes have to agree.
+        let resp = test::call_service(&app, upload("flyer.jpg", "image/jpeg", b"Porchfest!")).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::UNSUPPORTED_MEDIA_TYPE
+        );
+
+        assert!(repo.events.lock().unwrap().is_empty());
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_upload_confirm_publishes_selected_events() -> Result<()> {
+        let mk_event = |name: &str| NewEvent {
+            name: name.to_string(),
+            description: String::new(),
+            full_text: format!("{name} flyer text"),
+            start_date: Utc.with_ymd_and_hms(2025, 3, 1, 23, 0, 0).unwrap(),
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: Some("Davis Square".to_string()),
+            google_place_id: None,
+            lat: Some(42.396),
+            lng: Some(-71.122),
+            location_name: None,
+            event_types: vec![EventType::Music],
+            tags: vec!["folk".to_string()],
+            url: None,
+            confidence: 0.9,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            status: EventStatus::Scheduled,
+        };
+        let garbage = mk_event("Garbled Txt");
+        let concert = mk_event("Porch Concert");
+
+        let repo = MockEventsRepo::new(vec![]);
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(repo.clone()),
+        };
+        let app = test::init_service(App::new().app_data(Data::new(state)).route(
+            "/upload/confirm",
+            web::post().to(somerville_events::features::upload::confirm),
+        ))
+        .await;
+
+        // The preview page serializes each event as a full `Event`.
+        use somerville_events::features::upload::unsaved_event;
+        let garbage_json = serde_json::to_string(&unsaved_event(garbage))?;
+        let concert_json = serde_json::to_string(&unsaved_event(concert.clone()))?;
+        let key = uuid::Uuid::new_v4().to_string();
+        let req = test::TestRequest::post()
+            .uri("/upload/confirm")
+            .set_form([
+                ("idempotency_key", key.as_str()),
+                ("dhash", "42"),
+                ("event", garbage_json.as_str()),
+                ("event", concert_json.as_str()),
+                ("publish", "1"),
+            ])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+
+        let events = repo.events.lock().unwrap().clone();
+        assert_eq!(events.len(), 1);
+        assert_eq!(NewEvent::from(events[0].clone()), concert);
+        assert_eq!(
+            resp.headers().get("Location").and_then(|v| v.to_str().ok()),
+            Some(format!("/event/{}", events[0].id).as_str())
+        );
+        assert_eq!(
+            repo.processed_images.lock().unwrap().as_slice(),
+            &[(42, vec![events[0].id])]
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/upload/confirm")
+            .set_form([
+                ("idempotency_key", key.as_str()),
+                ("event", concert_json.as_str()),
+                ("publish", "3"),
+            ])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_failed_upload_can_be_retried_with_its_key() -> Result<()> {
+        use somerville_events::image_processing::dhash;
+
+        let flyer = std::fs::read("examples/dance_flyer.jpg")?;
+        let repo = MockEventsRepo::new(vec![]);
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            // So reading the flyer fails at once, network or not.
+            api_timeouts: ApiTimeouts {
+                openai: std::time::Duration::from_millis(1),
+                ..ApiTimeouts::default()
+            },
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(repo.clone()),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .app_data(Data::new(awc::Client::default()))
+                .app_data(Data::new(
+                    somerville_events::background_tasks::BackgroundTasks::default(),
+                ))
+                .route("/upload", web::post().to(features::upload::save)),
+        )
+        .await;
+
+        let key = uuid::Uuid::new_v4();
+        let upload = || {
+            let boundary = "flyer-boundary";
+            let mut body = format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"idempotency_key\"\r\n\r\n{key}\r\n\
+                 --{boundary}\r\nContent-Disposition: form-data; name=\"image\"; filename=\"flyer.jpg\"\r\n\
+                 Content-Type: image/jpeg\r\n\r\n"
+            )
+            .into_bytes();
+            body.extend_from_slice(&flyer);
+            body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+            test::TestRequest::post()
+                .uri("/upload")
+                .insert_header((
+                    "Content-Type",
+                    format!("multipart/form-data; boundary={boundary}"),
+                ))
+                .set_payload(body)
+                .to_request()
+        };
+        let finished = || async {
+            for _ in 0..500 {
+                if let Some(Some(succeeded)) = repo.idempotency_keys.lock().unwrap().get(&key) {
+                    return *succeeded;
+                }
+                actix_web::rt::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+            panic!("upload never finished");
+        };
+
+        let resp = test::call_service(&app, upload()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert!(!finished().await);
+
+        // Seen before this time, so the retry goes through without OpenAI.
+        repo.processed_images
+            .lock()
+            .unwrap()
+            .push((dhash(&image::load_from_memory(&flyer)?), vec![]));
+        let resp = test::call_service(&app, upload()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert!(finished().await);
+
+        let resp = test::call_service(&app, upload()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::CONFLICT);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_page_links_that_cant_be_read_are_turned_away() -> Result<()> {
+        let repo = MockEventsRepo::new(vec![]);
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(repo.clone()),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .app_data(Data::new(awc::Client::default()))
+                .app_data(Data::new(
+                    somerville_events::background_tasks::BackgroundTasks::default(),
+                ))
+                .route("/upload/page", web::post().to(features::upload::save_page)),
+        )
+        .await;
+
+        let key = uuid::Uuid::new_v4();
+        let submit = |url: &str| {
+            test::TestRequest::post()
+                .uri("/upload/page")
+                .set_form([("url", url), ("idempotency_key", &key.to_string())])
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, submit("not a link")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        assert!(repo.idempotency_keys.lock().unwrap().is_empty());
+
+        // The server's own admin pages aren't fair game.
+        let resp = test::call_service(&app, submit("http://localhost:8080/edit")).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::UNPROCESSABLE_ENTITY
+        );
+        let body = String::from_utf8(test::read_body(resp).await.to_vec())?;
+        assert!(body.contains("public web address"), "{body}");
+        // Nothing was read, so the same key can send a better link.
+        assert_eq!(
+            repo.idempotency_keys.lock().unwrap().get(&key),
+            Some(&Some(false))
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_public_uploads_wait_for_review() -> Result<()> {
+        use somerville_events::image_processing::dhash;
+
+        // A repeat flyer, so processing it never gets as far as OpenAI.
+        let flyer = std::fs::read("examples/dance_flyer.jpg")?;
+        let repo = MockEventsRepo::new(vec![]);
+        repo.processed_images
+            .lock()
+            .unwrap()
+            .push((dhash(&image::load_from_memory(&flyer)?), vec![]));
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(repo.clone()),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .app_data(Data::new(awc::Client::default()))
+                .app_data(Data::new(
+                    somerville_events::background_tasks::BackgroundTasks::default(),
+                ))
+                .route("/upload", web::post().to(features::upload::save))
+                .route("/edit", web::get().to(features::edit::index))
+                .route("/edit/bulk", web::post().to(features::edit::bulk))
+                .route("/edit/event/{id}", web::get().to(features::edit::show))
+                .route("/event/{id}", web::get().to(features::view::show)),
+        )
+        .await;
+
+        let upload = |uri: &str, email: &str| {
+            let boundary = "flyer-boundary";
+            let mut body = format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"idempotency_key\"\r\n\r\n{}\r\n\
+                 --{boundary}\r\nContent-Disposition: form-data; name=\"submitter_name\"\r\n\r\nPat\r\n\
+                 --{boundary}\r\nContent-Disposition: form-data; name=\"submitter_email\"\r\n\r\n{email}\r\n\
+                 --{boundary}\r\nContent-Disposition: form-data; name=\"image\"; filename=\"flyer.jpg\"\r\n\
+                 Content-Type: image/jpeg\r\n\r\n",
+                uuid::Uuid::new_v4()
+            )
+            .into_bytes();
+            body.extend_from_slice(&flyer);
+            body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+            test::TestRequest::post()
+                .uri(uri)
+                .insert_header((
+                    "Content-Type",
+                    format!("multipart/form-data; boundary={boundary}"),
+                ))
+                .set_payload(body)
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, upload("/upload", "pat@example.com")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert_eq!(
+            resp.headers().get("Location").and_then(|v| v.to_str().ok()),
+            Some("/upload-success?pending=1")
+        );
+        let resp = test::call_service(&app, upload("/upload?preview=1", "pat@example.com")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+        let resp = test::call_service(&app, upload("/upload", "not an email")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        // What a parsed public upload leaves behind.
+        let submitter = Submitter {
+            name: Some("Pat".to_string()),
+            email: Some("pat@example.com".to_string()),
+        };
+        let event = NewEvent {
+            name: "Stoop Sale".to_string(),
+            description: "Everything must go".to_string(),
+            full_text: String::new(),
+            start_date: Utc::now() + chrono::Duration::days(2),
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: Some("Highland Ave".to_string()),
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Other],
+            tags: vec![],
+            url: None,
+            confidence: 0.9,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            status: EventStatus::Scheduled,
+        };
+        let id = repo.insert_submitted(&event, &submitter, true).await?.id();
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/event/{id}"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::get().uri("/edit").to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        let body_str = std::str::from_utf8(&body)?;
+        assert!(body_str.contains("Waiting for review"));
+        assert!(body_str.contains("Stoop Sale"));
+        assert!(body_str.contains(r#"<a href="mailto:pat@example.com">"#));
+
+        let req = test::TestRequest::post()
+            .uri("/edit/bulk")
+            .set_form([("action", "approve"), ("id", &id.to_string())])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert_eq!(
+            resp.headers().get("Location").and_then(|v| v.to_str().ok()),
+            Some("/edit?updated=1&selected=1")
+        );
+
+        let req = test::TestRequest::get()
+            .uri(&format!("/event/{id}"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let req = test::TestRequest::get()
+            .uri(&format!("/edit/event/{id}"))
+            .to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        assert!(std::str::from_utf8(&body)?.contains("Sent in by Pat"));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_edit_show_has_raw_extracted_text() -> Result<()> {
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 23, 0, 0).unwrap();
+        let event = Event {
+            id: 1,
+            created_at: start,
+            updated_at: start,
+            name: "Swing Night".to_string(),
+            description: "Dancing".to_string(),
+            full_text: "SWING NIGHT\nlessons 7pm <free>".to_string(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route("/edit/event/{id}", web::get().to(features::edit::show)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/edit/event/1").to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        let body = std::str::from_utf8(&body)?;
+        assert!(
+            body.contains("<pre>SWING NIGHT\nlessons 7pm &#60;free&#62;</pre>"),
+            "{body}"
+        );
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_webhook_preview() -> Result<()> {
+        let start = Utc.with_ymd_and_hms(2025, 3, 1, 23, 0, 0).unwrap();
+        let event = Event {
+            id: 1,
+            created_at: start,
+            updated_at: start,
+            name: "Swing Night".to_string(),
+            description: "Dancing".to_string(),
+            full_text: "".to_string(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Dance],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::new(
+                vec!["https://hooks.example/events".to_string()],
+                "secret".to_string(),
+                "https://somerville.events/",
+                somerville_events::background_tasks::BackgroundTasks::default(),
+            ),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
+        };
+        let app = test::init_service(App::new().app_data(Data::new(state)).service(
+            web::scope("/edit").wrap(from_fn(require_admin)).route(
+                "/event/{id}/webhook.json",
+                web::get().to(features::edit::webhook_preview),
+            ),
+        ))
+        .await;
+        let get = |uri: &str| {
+            test::TestRequest::get()
+                .uri(uri)
+                .insert_header(("Authorization", "Basic dXNlcjpwYXNz"))
+                .to_request()
+        };
+
+        let resp = test::call_service(&app, get("/edit/event/1/webhook.json")).await;
+        assert!(resp.status().is_success());
+        assert_eq!(
+            resp.headers().get("Content-Type").unwrap(),
+            "application/json"
+        );
+        let body = test::read_body(resp).await;
+        let body = std::str::from_utf8(&body)?;
+        assert!(body.contains("\n  \"id\": 1,"), "{body}");
+        let payload: serde_json::Value = serde_json::from_str(body)?;
+        assert_eq!(payload["url"], "https://somerville.events/event/1");
+        assert_eq!(payload["event"]["name"], "Swing Night");
+        assert_eq!(
+            payload["event"]["event_types"],
+            serde_json::json!(["dance"])
+        );
+
+        let resp = test::call_service(&app, get("/edit/event/99/webhook.json")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+
+        let req = test::TestRequest::get()
+            .uri("/edit/event/1/webhook.json")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+
+        Ok(())
+    }
+
+    /// The pages and calendars readers see, against Postgres rather than the
+    /// mock, so the SQL behind them is covered too.
+    #[sqlx::test]
+    async fn test_pages_and_calendars_from_postgres(pool: sqlx::PgPool) -> Result<()> {
+        use somerville_events::database::save_event_to_db;
+
+        let today = Utc::now().with_timezone(&DEFAULT_TIMEZONE).date_naive();
+        let local = |days: i64, hour: u32| {
+            DEFAULT_TIMEZONE
+                .from_local_datetime(
+                    &(today + chrono::Duration::days(days))
+                        .and_hms_opt(hour, 0, 0)
+                        .unwrap(),
+                )
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let event = |name: &str, start: DateTime<Utc>, event_types: Vec<EventType>| NewEvent {
+            name: name.to_string(),
+            description: format!("About {name}"),
+            full_text: String::new(),
+            start_date: start,
+            end_date: None,
+            all_day: false,
+            address: Some("1 Davis Sq, Somerville, MA 02144".to_string()),
+            original_location: Some("Davis Square".to_string()),
+            google_place_id: Some("place-davis".to_string()),
+            lat: Some(42.396),
+            lng: Some(-71.122),
+            location_name: Some("Davis Square".to_string()),
+            event_types,
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            status: EventStatus::Scheduled,
+        };
+        let jam = save_event_to_db(
+            &pool,
+            &NewEvent {
+                source: EventSource::SomervilleTheatre,
+                ..event("Porch Jam", local(2, 19), vec![EventType::Music])
+            },
+        )
+        .await?;
+        save_event_to_db(
+            &pool,
+            &event("Book Swap", local(3, 10), vec![EventType::Literature]),
+        )
+        .await?;
+        save_event_to_db(&pool, &event("Last Month's Show", local(-30, 20), vec![])).await?;
+
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(pool),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route("/", web::get().to(features::view::index))
+                .route("/events.ics", web::get().to(features::view::ical_feed))
+                .route("/event/{id}.ics", web::get().to(features::view::ical))
+                .route("/event/{id}", web::get().to(features::view::show)),
+        )
+        .await;
+        let app = &app;
+        let get = |uri: String| async move {
+            let resp =
+                test::call_service(app, test::TestRequest::get().uri(&uri).to_request()).await;
+            assert!(resp.status().is_success(), "{uri}: {}", resp.status());
+            String::from_utf8(test::read_body(resp).await.to_vec()).unwrap()
+        };
+
+        let body = get("/".to_string()).await;
+        assert!(body.contains("Porch Jam"));
+        assert!(body.contains("Book Swap"));
+        assert!(!body.contains("Last Month"));
+        let body = get("/?type=music".to_string()).await;
+        assert!(body.contains("Porch Jam"));
+        assert!(!body.contains("Book Swap"));
+
+        let body = get(format!("/event/{jam}")).await;
+        assert!(body.contains("Porch Jam"));
+        assert!(body.contains("About Porch Jam"));
+        assert!(body.contains("1 Davis Sq, Somerville, MA 02144"));
+        assert!(
+            body.contains(r#"<a href="https://www.somervilletheatre.com">Somerville Theatre</a>"#)
+        );
+
+        let calendar: icalendar::Calendar = get(format!("/event/{jam}.ics"))
+            .await
+            .parse()
+            .map_err(|e| anyhow::anyhow!("{e}"))?;
+        let summaries: Vec<_> = calendar
+            .components
+            .iter()
+            .filter_map(|c| c.as_event())
+            .filter_map(icalendar::Component::get_summary)
+            .collect();
+        assert_eq!(summaries, vec!["Porch Jam"]);
+
+        let feed = get("/events.ics".to_string()).await;
+        let calendar: icalendar::Calendar = feed.parse().map_err(|e| anyhow::anyhow!("{e}"))?;
+        let mut summaries: Vec<_> = calendar
+            .components
+            .iter()
+            .filter_map(|c| c.as_event())
+            .filter_map(icalendar::Component::get_summary)
+            .collect();
+        summaries.sort();
+        assert_eq!(summaries, vec!["Book Swap", "Porch Jam"]);
+        assert!(feed.contains(&format!(
+            "DTSTART;TZID=America/New_York:{}T190000",
+            (today + chrono::Duration::days(2)).format("%Y%m%d")
+        )));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_basic_auth_accepts_only_correct_password() -> Result<()> {
+        use base64::Engine;
+
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .wrap(features::login::session_middleware(Key::generate(), false))
+                .service(
+                    web::scope("/edit")
+                        .wrap(from_fn(require_admin))
+                        .route("", web::get().to(somerville_events::features::edit::index)),
+                ),
+        )
+        .await;
+
+        let basic = |credentials: &str| {
+            format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(credentials)
+            )
+        };
+
+        let req = test::TestRequest::get()
+            .uri("/edit")
+            .insert_header(("Authorization", basic("user:pass")))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        for credentials in ["user:wrong", "user:pas", "other:pass"] {
+            let req = test::TestRequest::get()
+                .uri("/edit")
+                .insert_header(("Authorization", basic(credentials)))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(
+                resp.status(),
+                actix_web::http::StatusCode::UNAUTHORIZED,
+                "{credentials}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_session_login_and_logout() -> Result<()> {
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .wrap(features::login::session_middleware(Key::generate(), false))
+                .route("/login", web::post().to(features::login::login))
+                .route("/logout", web::post().to(features::login::logout))
+                .service(
+                    web::scope("/edit")
+                        .wrap(from_fn(require_admin))
+                        .route("", web::get().to(somerville_events::features::edit::index)),
+                )
+                .service(
+                    web::resource("/event/{id}")
+                        .wrap(from_fn(require_admin))
+                        .route(web::delete().to(features::edit::delete)),
+                ),
+        )
+        .await;
+
+        // Browsers get sent to the form, remembering where they were going.
+        let req = test::TestRequest::get().uri("/edit").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert_eq!(
+            resp.headers().get("Location").unwrap(),
+            "/login?next=%2Fedit"
+        );
+
+        // Non-GET requests can't be redirected usefully.
+        let req = test::TestRequest::delete().uri("/event/1").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+
+        let req = test::TestRequest::post()
+            .uri("/login")
+            .set_form([
+                ("username", "user"),
+                ("password", "wrong"),
+                ("next", "/edit"),
+            ])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+        assert!(resp.response().cookies().next().is_none());
+
+        let req = test::TestRequest::post()
+            .uri("/login")
+            .set_form([
+                ("username", "user"),
+                ("password", "pass"),
+                ("next", "/edit"),
+            ])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert_eq!(resp.headers().get("Location").unwrap(), "/edit");
+        let cookie = resp
+            .response()
+            .cookies()
+            .find(|c| c.name() == "session")
+            .expect("login should set a session cookie")
+            .into_owned();
+        assert_eq!(cookie.http_only(), Some(true));
+
+        let req = test::TestRequest::get()
+            .uri("/edit")
+            .cookie(cookie.clone())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        // A tampered cookie doesn't verify.
+        let mut forged = cookie.clone();
+        forged.set_value(format!("x{}", cookie.value()));
+        let req = test::TestRequest::get()
+            .uri("/edit")
+            .cookie(forged)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+
+        let req = test::TestRequest::post()
+            .uri("/logout")
+            .cookie(cookie)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        let cleared = resp
+            .response()
+            .cookies()
+            .find(|c| c.name() == "session")
+            .expect("logout should clear the session cookie");
+        assert_eq!(cleared.value(), "");
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_wrong_passwords_lock_out_the_address() -> Result<()> {
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .wrap(features::login::session_middleware(Key::generate(), false))
+                .route("/login", web::post().to(features::login::login))
+                .service(
+                    web::scope("/edit")
+                        .wrap(from_fn(require_admin))
+                        .route("", web::get().to(somerville_events::features::edit::index)),
+                ),
+        )
+        .await;
+        let login = |ip: &str, password: &str| {
+            test::TestRequest::post()
+                .uri("/login")
+                .peer_addr(SocketAddr::new(ip.parse().unwrap(), 40000))
+                .set_form([("username", "user"), ("password", password)])
+                .to_request()
+        };
+        // "user:pass" and "user:nope".
+        let basic = |ip: &str, credentials: &str| {
+            test::TestRequest::get()
+                .uri("/edit")
+                .peer_addr(SocketAddr::new(ip.parse().unwrap(), 40000))
+                .insert_header(("Authorization", format!("Basic {credentials}")))
+                .to_request()
+        };
+
+        // Guesses through either door count together.
+        for _ in 0..5 {
+            let resp = test::call_service(&app, login("203.0.113.5", "guess")).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+            let resp = test::call_service(&app, basic("203.0.113.5", "dXNlcjpub3Bl")).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+        }
+
+        // Past the limit even the right password is turned away...
+        let resp = test::call_service(&app, login("203.0.113.5", "pass")).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::TOO_MANY_REQUESTS
+        );
+        let resp = test::call_service(&app, basic("203.0.113.5", "dXNlcjpwYXNz")).await;
+        assert_eq!(
+            resp.status(),
+            actix_web::http::StatusCode::TOO_MANY_REQUESTS
+        );
+        assert!(resp.headers().contains_key("Retry-After"));
+
+        // ...but only from that address.
+        let resp = test::call_service(&app, login("192.0.2.1", "pass")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        let resp = test::call_service(&app, basic("192.0.2.1", "dXNlcjpwYXNz")).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_robots_txt() -> Result<()> {
+        let app = test::init_service(App::new().route(
+            "/robots.txt",
+            web::get().to(somerville_events::features::view::robots_txt),
+        ))
+        .await;
+
+        let req = test::TestRequest::get().uri("/robots.txt").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+
+        let body = test::read_body(resp).await;
+        let body_str = std::str::from_utf8(&body)?;
+        let lines: Vec<&str> = body_str.lines().collect();
+
+        assert!(lines.contains(&"Allow: /"));
+        assert!(lines.contains(&"Disallow: /edit"));
+        assert!(lines.contains(&"Disallow: /upload"));
+        assert!(lines.contains(&"Disallow: /activitypub/"));
+        // Nothing serves /sitemap.xml, so don't send crawlers looking for it.
+        assert!(!lines.iter().any(|l| l.starts_with("Sitemap:")));
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_repeated_request_with_etag_is_not_modified() -> Result<()> {
+        let now_utc = Utc::now();
+        let event = Event {
+            id: 1,
+            created_at: now_utc,
+            updated_at: now_utc,
+            name: "Porch Concert".to_string(),
+            description: "Folk on the porch".to_string(),
+            full_text: "Folk on the porch".to_string(),
+            start_date: now_utc + chrono::Duration::days(1),
+            end_date: None,
+            all_day: false,
+            address: Some("Porch".to_string()),
+            original_location: Some("Porch".to_string()),
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![EventType::Music],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![event])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route("/", web::get().to(somerville_events::features::view::index))
+                .route(
+                    "/event/{id}",
+                    web::get().to(somerville_events::features::view::show),
+                )
+                .route(
+                    "/events.atom",
+                    web::get().to(somerville_events::features::view::atom_feed),
+                ),
+        )
+        .await;
+
+        for uri in ["/", "/event/1", "/events.atom"] {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+            assert!(resp
+                .headers()
+                .get("Cache-Control")
+                .is_some_and(|v| v.to_str().is_ok_and(|v| v.contains("public"))));
+            let etag = resp
+                .headers()
+                .get("ETag")
+                .expect("ETag header should be set")
+                .clone();
+            assert!(etag.to_str()?.starts_with("W/"));
+
+            let req = test::TestRequest::get()
+                .uri(uri)
+                .insert_header(("If-None-Match", etag))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_MODIFIED);
+            assert!(test::read_body(resp).await.is_empty());
+
+            let req = test::TestRequest::get()
+                .uri(uri)
+                .insert_header(("If-None-Match", "W/\"stale\""))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        }
+
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_today_and_weekend_shortcuts() -> Result<()> {
+        use somerville_events::features::view::{shortcut_with_now, Shortcut};
+
+        // Noon on Wednesday, January 15, 2025 in Somerville.
+        let now_utc = Utc.with_ymd_and_hms(2025, 1, 15, 17, 0, 0).unwrap();
+        let at = |day, hour| {
+            New_York
+                .with_ymd_and_hms(2025, 1, day, hour, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let mk_event = |id, name: &str, start_date: DateTime<Utc>| Event {
+            id,
+            created_at: now_utc,
+            updated_at: now_utc,
+            name: name.to_string(),
+            description: String::new(),
+            full_text: String::new(),
+            start_date,
+            end_date: Some(start_date + chrono::Duration::hours(2)),
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            featured: false,
+            status: EventStatus::Scheduled,
+        };
+
+        let state = AppState {
+            openai_api_key: "dummy".to_string(),
+            google_maps_api_key: "dummy".to_string(),
+            geocoding_enabled: false,
+            username: "user".to_string(),
+            password: "pass".to_string(),
+            timezone: DEFAULT_TIMEZONE,
+            api_timeouts: ApiTimeouts::default(),
+            geocoding_concurrency: DEFAULT_GEOCODING_CONCURRENCY,
+            upload_slots: ConcurrencyLimit::new(DEFAULT_UPLOAD_CONCURRENCY),
+            openai_structured_outputs: true,
+            past_event_window: DEFAULT_PAST_EVENT_WINDOW,
+            event_durations: EventDurations::default(),
+            webhooks: Webhooks::default(),
+            contact_relay: ContactRelay::default(),
+            trusted_proxies: vec![],
+            login_throttle: LoginThrottle::default(),
+            index_cache: IndexCache::default(),
+            events_repo: Box::new(MockEventsRepo::new(vec![
+                mk_event(1, "Wednesday Breakfast", at(15, 8)),
+                mk_event(2, "Wednesday Trivia", at(15, 19)),
+                mk_event(3, "Friday Lunch", at(17, 12)),
+                mk_event(4, "Friday Show", at(17, 20)),
+                mk_event(5, "Sunday Brunch", at(19, 11)),
+                mk_event(6, "Monday Class", at(20, 18)),
+            ])),
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(Data::new(state))
+                .route(
+                    "/today",
+                    web::get().to(move |req: HttpRequest, state: Data<AppState>| {
+                        shortcut_with_now(req, state, now_utc, Shortcut::Today)
+                    }),
+                )
+                .route(
+                    "/this-weekend",
+                    web::get().to(move |req: HttpRequest, state: Data<AppState>| {
+                        shortcut_with_now(req, state, now_utc, Shortcut::ThisWeekend)
+                    }),
+                ),
+        )
+        .await;
+
+        let app = &app;
+        let get = |uri: &'static str| async move {
+            let req = test::TestRequest::get().uri(uri).to_request();
+            let body = test::read_body(test::call_service(&app, req).await).await;
+            String::from_utf8(body.to_vec()).unwrap()
+        };
+
+        // Breakfast is already over.
+        let body = get("/today").await;
+        assert!(body.contains("<h2>Today</h2>"));
+        assert!(body.contains("Wednesday Trivia"));
+        for name in ["Wednesday Breakfast", "Friday Lunch", "Monday Class"] {
+            assert!(!body.contains(name), "{name} shouldn't be on /today");
+        }
+
+        // Friday evening through the end of Sunday.
+        let body = get("/this-weekend").await;
+        assert!(body.contains("<h2>This weekend</h2>"));
+        assert!(body.contains("Friday Show"));
+        assert!(body.contains("Sunday Brunch"));
+        for name in ["Wednesday Trivia", "Friday Lunch", "Monday Class"] {
+            assert!(!body.contains(name), "{name} shouldn't be on /this-weekend");
+        }
 
         Ok(())
     }
@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -6,30 +6,128 @@ use std::str::FromStr;
 use strum::{AsRefStr, EnumIter, EnumString};
 use url::Url;
 
+/// Query parameters that only record where a click came from.
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "dclid", "msclkid", "igshid", "mc_cid", "mc_eid", "_hsenc", "_hsmi",
+];
+
+fn is_tracking_param(name: &str) -> bool {
+    name.starts_with("utm_") || TRACKING_PARAMS.contains(&name)
+}
+
+/// Returns the link as an http(s) URL, adding `https://` when the scheme is
+/// missing, in the form `normalize_url` gives it.
 pub fn sanitize_url(url: Option<String>) -> Option<String> {
-    let url_str = url?.trim().to_string();
+    normalize_url(&url?)
+}
+
+/// Puts a link in one canonical form, so the same page read off a QR code,
+/// by the LLM or from a feed compares equal: the host is lowercased, and
+/// the fragment, tracking parameters and any trailing slash are dropped.
+/// `None` unless it's an http(s) URL.
+pub fn normalize_url(url: &str) -> Option<String> {
+    let url_str = url.trim();
     if url_str.is_empty() {
         return None;
     }
 
-    // Check if it parses as is
-    if let Ok(u) = Url::parse(&url_str) {
-        if u.scheme() == "http" || u.scheme() == "https" {
-            return Some(u.to_string());
+    let mut url = Url::parse(url_str)
+        .ok()
+        .filter(|u| u.scheme() == "http" || u.scheme() == "https")
+        .or_else(|| {
+            // Try adding https://, as long as the host looks real (e.g.,
+            // contains a dot)
+            Url::parse(&format!("https://{}", url_str))
+                .ok()
+                .filter(|u| u.host_str().is_some_and(|host| host.contains('.')))
+        })?;
+
+    url.set_fragment(None);
+    if url.query_pairs().any(|(name, _)| is_tracking_param(&name)) {
+        let kept: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(name, _)| !is_tracking_param(name))
+            .map(|(name, value)| (name.into_owned(), value.into_owned()))
+            .collect();
+        if kept.is_empty() {
+            url.set_query(None);
+        } else {
+            url.query_pairs_mut().clear().extend_pairs(kept);
         }
     }
+    let path = url.path().trim_end_matches('/').to_string();
+    if !path.is_empty() {
+        url.set_path(&path);
+    }
+
+    Some(url.into())
+}
+
+/// Returns the trimmed address if it looks deliverable. This is a sanity
+/// check against feed junk ("N/A", "see website"), not full RFC 5322.
+pub fn sanitize_email(email: Option<String>) -> Option<String> {
+    let email = email?.trim().to_string();
+    let (local, domain) = email.split_once('@')?;
+    let valid = !local.is_empty()
+        && !domain.contains('@')
+        && domain.contains('.')
+        && !domain.starts_with('.')
+        && !domain.ends_with('.')
+        && !email
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '<' | '>' | '"' | ',' | ';'));
+    valid.then_some(email)
+}
+
+/// Returns the trimmed number if it's made of the usual phone punctuation
+/// and has a plausible number of digits (a local US number up to E.164's
+/// 15).
+pub fn sanitize_phone(phone: Option<String>) -> Option<String> {
+    let phone = phone?.trim().to_string();
+    let allowed = phone
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, ' ' | '(' | ')' | '-' | '.' | '+'));
+    let digits = phone.chars().filter(char::is_ascii_digit).count();
+    (allowed && (10..=15).contains(&digits)).then_some(phone)
+}
+
+/// Longest tag we keep; anything longer is a sentence, not a keyword.
+const MAX_TAG_LEN: usize = 40;
+/// Flyers and feeds sometimes dump a whole keyword list on us.
+const MAX_TAGS: usize = 10;
+
+/// Lowercases a tag and collapses its whitespace, so "Queer  Dance Night"
+/// and "queer dance night" are the same tag. A leading `#` is dropped since
+/// people write hashtags on flyers.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag
+        .trim()
+        .trim_start_matches('#')
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    (!tag.is_empty() && tag.chars().count() <= MAX_TAG_LEN).then_some(tag)
+}
 
-    // Try adding https://
-    if let Ok(u) = Url::parse(&format!("https://{}", url_str)) {
-        // Ensure that the host is valid (e.g., contains a dot)
-        if let Some(host) = u.host_str() {
-            if host.contains('.') {
-                return Some(u.to_string());
-            }
+/// Normalizes and dedupes tags, keeping the first-seen order.
+pub fn normalize_tags<S: AsRef<str>>(tags: impl IntoIterator<Item = S>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags.into_iter().filter_map(|t| normalize_tag(t.as_ref())) {
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
         }
     }
+    normalized.truncate(MAX_TAGS);
+    normalized
+}
 
-    None
+/// `tel:` URI for a number that passed `sanitize_phone`, keeping only the
+/// digits and a leading `+`.
+pub fn tel_link(phone: &str) -> String {
+    let plus = if phone.starts_with('+') { "+" } else { "" };
+    let digits: String = phone.chars().filter(char::is_ascii_digit).collect();
+    format!("tel:{plus}{digits}")
 }
 
 #[derive(
@@ -39,6 +137,7 @@ pub fn sanitize_url(url: Option<String>) -> Option<String> {
     JsonSchema,
     PartialEq,
     Eq,
+    Hash,
     Clone,
     sqlx::Type,
     EnumString,
@@ -141,6 +240,39 @@ impl EventType {
             self.get_url()
         }
     }
+
+    /// How long an event of this type usually runs, for one that only gave
+    /// a start time. `EVENT_DURATIONS` can override it; see
+    /// `config::EventDurations`.
+    pub fn default_duration(&self) -> TimeDelta {
+        let minutes = match self {
+            EventType::Meeting
+            | EventType::Government
+            | EventType::PersonalService
+            | EventType::Fitness
+            | EventType::Other => 60,
+            EventType::Literature | EventType::Religious => 90,
+            EventType::Film
+            | EventType::Comedy
+            | EventType::Workshop
+            | EventType::Trivia
+            | EventType::Sports
+            | EventType::Bikes
+            | EventType::ChildFriendly => 120,
+            EventType::Theater | EventType::Performance => 150,
+            EventType::Music
+            | EventType::Dance
+            | EventType::Art
+            | EventType::Food
+            | EventType::Social
+            | EventType::BoardGames
+            | EventType::Fundraiser
+            | EventType::Volunteer => 180,
+            EventType::Exhibition | EventType::Market | EventType::Holiday => 240,
+            EventType::YardSale => 300,
+        };
+        TimeDelta::minutes(minutes)
+    }
 }
 
 // Support conversion for sqlx query_as! compatibility
@@ -157,6 +289,7 @@ impl From<String> for EventType {
     JsonSchema,
     PartialEq,
     Eq,
+    Hash,
     Clone,
     sqlx::Type,
     EnumString,
@@ -168,7 +301,8 @@ impl From<String> for EventType {
 /// IMPORTANT: This enum is coupled to the `app.source_names` table in the database.
 /// If you add a new variant here, you MUST create a migration to insert the corresponding
 /// string value into the `app.source_names` table. Otherwise, inserting events with
-/// the new source will fail due to foreign key constraints.
+/// the new source will fail due to foreign key constraints. Its display name, homepage
+/// and feed name go in that row too, see `SourceInfo`.
 pub enum EventSource {
     AeronautBrewing,
     AmericanRepertoryTheater,
@@ -178,6 +312,7 @@ pub enum EventSource {
     BrattleTheatre,
     CentralSquareTheater,
     CityOfCambridge,
+    CityOfSomerville,
     FirstParishInCambridge,
     GrolierPoetryBookShop,
     HarvardArtMuseums,
@@ -206,6 +341,7 @@ impl fmt::Display for EventSource {
             EventSource::BrattleTheatre => write!(f, "Brattle Theatre"),
             EventSource::CentralSquareTheater => write!(f, "Central Square Theater"),
             EventSource::CityOfCambridge => write!(f, "City of Cambridge"),
+            EventSource::CityOfSomerville => write!(f, "City of Somerville"),
             EventSource::FirstParishInCambridge => write!(f, "First Parish in Cambridge"),
             EventSource::GrolierPoetryBookShop => write!(f, "Grolier Poetry Book Shop"),
             EventSource::HarvardArtMuseums => write!(f, "Harvard Art Museums"),
@@ -244,6 +380,76 @@ impl From<String> for EventSource {
     }
 }
 
+/// Whether an event is still on. Cancelled and postponed events stay
+/// listed, marked as such, since someone who saw the flyer needs to find
+/// out it's off; deleting is for events that shouldn't have been listed.
+#[derive(
+    Debug,
+    Default,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+    PartialEq,
+    Eq,
+    Hash,
+    Clone,
+    Copy,
+    sqlx::Type,
+    EnumString,
+    AsRefStr,
+    EnumIter,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum EventStatus {
+    #[default]
+    Scheduled,
+    Cancelled,
+    Postponed,
+}
+
+impl EventStatus {
+    /// Reads a source's own word for it ("Canceled", "CANCELLED",
+    /// "Postponed"). Anything else is taken to mean it's still on.
+    pub fn from_source(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "cancelled" | "canceled" => EventStatus::Cancelled,
+            "postponed" => EventStatus::Postponed,
+            _ => EventStatus::Scheduled,
+        }
+    }
+
+    /// Splits a "CANCELLED: " or "Postponed - " off the front of a title,
+    /// which is how calendars without a field for it mark it.
+    pub fn from_title(title: &str) -> (Self, &str) {
+        let Some((prefix, rest)) = title.split_once([':', '-', '\u{2013}', '\u{2014}']) else {
+            return (EventStatus::Scheduled, title);
+        };
+        match EventStatus::from_source(prefix) {
+            EventStatus::Scheduled => (EventStatus::Scheduled, title),
+            status if rest.trim().is_empty() => (status, title),
+            status => (status, rest.trim()),
+        }
+    }
+
+    /// The schema.org `EventStatusType` for the event's microdata.
+    pub fn schema_org_url(&self) -> &'static str {
+        match self {
+            EventStatus::Scheduled => "https://schema.org/EventScheduled",
+            EventStatus::Cancelled => "https://schema.org/EventCancelled",
+            EventStatus::Postponed => "https://schema.org/EventPostponed",
+        }
+    }
+}
+
+// Support conversion for sqlx query_as! compatibility
+impl From<String> for EventStatus {
+    fn from(s: String) -> Self {
+        EventStatus::from_str(&s).unwrap_or_default()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, JsonSchema, PartialEq, Clone, sqlx::FromRow)]
 pub struct Event {
     pub id: i64,
@@ -254,26 +460,87 @@ pub struct Event {
     pub full_text: String,
     pub start_date: DateTime<Utc>,
     pub end_date: Option<DateTime<Utc>>,
+    /// Date-only events (e.g. "Saturday, all day"). The start_date is
+    /// midnight local time and the time of day should never be shown.
+    #[serde(default)]
+    pub all_day: bool,
     pub address: Option<String>,
-    #[serde(skip_deserializing)]
     pub original_location: Option<String>,
     pub google_place_id: Option<String>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
     pub location_name: Option<String>,
     pub event_types: Vec<EventType>,
+    /// Lowercase free-form keywords, see `normalize_tags`.
+    #[serde(default)]
+    pub tags: Vec<String>,
     pub url: Option<String>,
     /// Confidence level of the extraction (0.0 to 1.0)
     pub confidence: f64,
     pub age_restrictions: Option<String>,
     pub price: Option<f64>,
+    pub contact_email: Option<String>,
+    pub contact_phone: Option<String>,
+    #[serde(default)]
+    pub registration_required: bool,
+    /// Picked by an admin to go in the "Featured" section at the top of the
+    /// front page, see `EventsRepo::set_featured`.
+    #[serde(default)]
+    pub featured: bool,
+    #[serde(default)]
+    pub status: EventStatus,
     /// Must match a value in the `app.source_names` table.
     /// If you introduce a new source, you must add it to that table first.
     pub source: EventSource,
-    /// External ID for idempotency/updates
-    #[serde(skip, default)]
-    #[schemars(skip)]
+    /// External ID for idempotency/updates. Kept in exports so a restored
+    /// event is still matched up with its feed on the next ingest.
     pub external_id: Option<String>,
 }
 
+/// Drops what the database assigns, for re-inserting an exported event.
+impl From<Event> for NewEvent {
+    fn from(event: Event) -> Self {
+        Self {
+            name: event.name,
+            description: event.description,
+            full_text: event.full_text,
+            start_date: event.start_date,
+            end_date: event.end_date,
+            all_day: event.all_day,
+            address: event.address,
+            original_location: event.original_location,
+            google_place_id: event.google_place_id,
+            lat: event.lat,
+            lng: event.lng,
+            location_name: event.location_name,
+            event_types: event.event_types,
+            tags: event.tags,
+            url: event.url,
+            confidence: event.confidence,
+            age_restrictions: event.age_restrictions,
+            price: event.price,
+            contact_email: event.contact_email,
+            contact_phone: event.contact_phone,
+            registration_required: event.registration_required,
+            status: event.status,
+            source: event.source,
+            external_id: event.external_id,
+        }
+    }
+}
+
+impl NewEvent {
+    /// For sources with no field for it: moves a "Cancelled: " off the
+    /// front of the name and into `status`.
+    pub fn take_status_from_name(&mut self) {
+        let (status, name) = EventStatus::from_title(&self.name);
+        if status != EventStatus::Scheduled {
+            self.name = name.to_string();
+            self.status = status;
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, JsonSchema, PartialEq, Clone)]
 pub struct NewEvent {
     pub name: String,
@@ -281,17 +548,46 @@ pub struct NewEvent {
     pub full_text: String,
     pub start_date: DateTime<Utc>,
     pub end_date: Option<DateTime<Utc>>,
+    /// Date-only events (e.g. "Saturday, all day"). The start_date is
+    /// midnight local time and the time of day should never be shown.
+    #[serde(default)]
+    pub all_day: bool,
     pub address: Option<String>,
     #[serde(skip_deserializing)]
     pub original_location: Option<String>,
     pub google_place_id: Option<String>,
+    /// Filled in from geocoding or the source feed, never by the LLM.
+    #[serde(skip, default)]
+    #[schemars(skip)]
+    pub lat: Option<f64>,
+    #[serde(skip, default)]
+    #[schemars(skip)]
+    pub lng: Option<f64>,
     pub location_name: Option<String>,
     pub event_types: Vec<EventType>,
+    /// Lowercase free-form keywords, see `normalize_tags`.
+    #[serde(default)]
+    pub tags: Vec<String>,
     pub url: Option<String>,
     /// Confidence level of the extraction (0.0 to 1.0)
     pub confidence: f64,
     pub age_restrictions: Option<String>,
     pub price: Option<f64>,
+    /// Contact details only come from the ingest feed, never the LLM.
+    #[serde(skip, default)]
+    #[schemars(skip)]
+    pub contact_email: Option<String>,
+    #[serde(skip, default)]
+    #[schemars(skip)]
+    pub contact_phone: Option<String>,
+    #[serde(skip, default)]
+    #[schemars(skip)]
+    pub registration_required: bool,
+    /// Set by feeds and scrapers that say so, never by the LLM. Still
+    /// serialized, so webhook receivers see it.
+    #[serde(skip_deserializing, default)]
+    #[schemars(skip)]
+    pub status: EventStatus,
     /// Must match a value in the `app.source_names` table.
     /// If you introduce a new source, you must add it to that table first.
     pub source: EventSource,
@@ -304,12 +600,142 @@ pub struct NewEvent {
 #[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
 pub struct SimpleEvent {
     pub id: i64,
+    pub updated_at: DateTime<Utc>,
     pub name: String,
     pub start_date: DateTime<Utc>,
     pub end_date: Option<DateTime<Utc>>,
+    pub all_day: bool,
     pub original_location: Option<String>,
     pub location_name: Option<String>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
     pub event_types: Vec<EventType>,
+    /// See `Event::confidence`. Shown on the edit list so admins know which
+    /// events to double-check.
+    pub confidence: f64,
+    pub status: EventStatus,
+}
+
+/// An upcoming event to suggest from another event's page, see
+/// `EventsRepo::list_related`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RelatedEvent {
+    pub event: SimpleEvent,
+    /// Held at the same venue.
+    pub same_venue: bool,
+    /// The first event type (alphabetically) the two have in common.
+    pub shared_type: Option<EventType>,
+}
+
+/// How the site is doing, for the admin stats page. Event counts are of
+/// listed events, leaving out pending ones and the trash.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SiteStats {
+    pub total: i64,
+    /// Events that haven't ended yet.
+    pub upcoming: i64,
+    /// Submissions waiting for an admin.
+    pub pending: i64,
+    pub open_reports: i64,
+    /// Most events first.
+    pub by_source: Vec<(EventSource, i64)>,
+    /// Most events first. An event counts once for each of its types.
+    pub by_type: Vec<(EventType, i64)>,
+    /// Events added in each local week, keyed by its Monday, oldest first.
+    /// Weeks with none are left out.
+    pub added_per_week: Vec<(NaiveDate, i64)>,
+    /// Venues with the most events, as place id, name and count.
+    pub top_venues: Vec<(String, String, i64)>,
+}
+
+/// An event in the trash, see `EventsRepo::delete`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeletedEvent {
+    pub id: i64,
+    pub name: String,
+    pub start_date: DateTime<Utc>,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// A visitor's note that something about an event is wrong.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewUserReport {
+    pub event_id: i64,
+    pub reason: String,
+    pub email: Option<String>,
+    pub reporter_ip: String,
+}
+
+/// A reader's message to an event's organizer, relayed by email. See
+/// `contact_relay`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewContactMessage {
+    pub event_id: i64,
+    pub sender_email: String,
+    pub message: String,
+    pub sender_ip: String,
+}
+
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct UserReport {
+    pub id: i64,
+    pub event_id: i64,
+    pub event_name: String,
+    pub reason: String,
+    pub email: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What's kept about a source in `app.source_names`, so it can be
+/// changed there rather than in code. Everything but the source itself is
+/// optional; `display_name` falls back to `EventSource`'s `Display`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceInfo {
+    pub source: EventSource,
+    pub display_name: String,
+    pub homepage_url: Option<String>,
+    /// What the aggregator feed calls the source, see `ingest_events`.
+    pub feed_name: Option<String>,
+    /// For feed events whose category doesn't say.
+    pub default_event_type: Option<EventType>,
+    /// Used when `SOURCE_CONFIDENCE` doesn't name the source.
+    pub default_confidence: Option<f64>,
+}
+
+/// A place events happen at, one per Google place id. Events carry their
+/// own copy of the location as the source wrote it; this is the one to show
+/// when talking about the venue itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Venue {
+    pub google_place_id: String,
+    pub name: String,
+    pub address: Option<String>,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+}
+
+/// Who sent a flyer in through the upload form, as they gave it. Both
+/// parts are optional; an anonymous flyer is still a flyer.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Submitter {
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
+/// A publicly submitted event waiting for an admin, see
+/// `EventsRepo::list_pending`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingEvent {
+    pub id: i64,
+    pub name: String,
+    pub description: String,
+    pub start_date: DateTime<Utc>,
+    pub all_day: bool,
+    pub original_location: Option<String>,
+    pub location_name: Option<String>,
+    pub submitter_name: Option<String>,
+    pub submitter_email: Option<String>,
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -322,6 +748,80 @@ pub struct LocationOption {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_sanitize_email() {
+        let ok = |s: &str| sanitize_email(Some(s.to_string()));
+        assert_eq!(
+            ok(" info@example.org "),
+            Some("info@example.org".to_string())
+        );
+        assert_eq!(
+            ok("a.b+c@mail.example.co.uk"),
+            Some("a.b+c@mail.example.co.uk".to_string())
+        );
+        assert_eq!(ok("N/A"), None);
+        assert_eq!(ok("@example.org"), None);
+        assert_eq!(ok("info@localhost"), None);
+        assert_eq!(ok("info@example."), None);
+        assert_eq!(ok("a@b@example.org"), None);
+        assert_eq!(ok("info @example.org"), None);
+        assert_eq!(ok("Info <info@example.org>"), None);
+        assert_eq!(sanitize_email(None), None);
+    }
+
+    #[test]
+    fn test_event_status_from_title() {
+        use EventStatus::*;
+        assert_eq!(
+            EventStatus::from_title("CANCELLED: Porch Jazz"),
+            (Cancelled, "Porch Jazz")
+        );
+        assert_eq!(
+            EventStatus::from_title("Canceled - Porch Jazz"),
+            (Cancelled, "Porch Jazz")
+        );
+        assert_eq!(
+            EventStatus::from_title("Postponed \u{2013} Fluff Fest"),
+            (Postponed, "Fluff Fest")
+        );
+        assert_eq!(
+            EventStatus::from_title("Jazz-Funk Night"),
+            (Scheduled, "Jazz-Funk Night")
+        );
+        assert_eq!(
+            EventStatus::from_title("Cancelled:"),
+            (Cancelled, "Cancelled:")
+        );
+        assert_eq!(EventStatus::from_source(" Canceled "), Cancelled);
+        assert_eq!(EventStatus::from_source("confirmed"), Scheduled);
+        assert_eq!(EventStatus::from("postponed".to_string()), Postponed);
+        assert_eq!(Cancelled.as_ref(), "cancelled");
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        assert_eq!(
+            normalize_tags(["Vegan", " #vegan ", "Queer  Dance Night", "", "pop-up"]),
+            vec!["vegan", "queer dance night", "pop-up"]
+        );
+        assert_eq!(normalize_tag("#"), None);
+        assert_eq!(normalize_tag(&"x".repeat(41)), None);
+        assert_eq!(normalize_tags((0..20).map(|i| i.to_string())).len(), 10);
+    }
+
+    #[test]
+    fn test_sanitize_phone() {
+        let ok = |s: &str| sanitize_phone(Some(s.to_string()));
+        assert_eq!(ok("(617) 555-0123"), Some("(617) 555-0123".to_string()));
+        assert_eq!(ok("+1 617.555.0123"), Some("+1 617.555.0123".to_string()));
+        assert_eq!(ok("555-0123"), None);
+        assert_eq!(ok("call the box office"), None);
+        assert_eq!(ok("617-555-0123 x12"), None);
+        assert_eq!(sanitize_phone(None), None);
+        assert_eq!(tel_link("(617) 555-0123"), "tel:6175550123");
+        assert_eq!(tel_link("+1 617.555.0123"), "tel:+16175550123");
+    }
+
     #[test]
     fn test_sanitize_url() {
         assert_eq!(sanitize_url(None), None);
@@ -348,4 +848,35 @@ mod tests {
         // Invalid URLs
         assert_eq!(sanitize_url(Some("not a url".to_string())), None);
     }
+
+    #[test]
+    fn test_normalize_url_collapses_variants() {
+        let canonical = Some("https://somervillema.gov/events/porchfest".to_string());
+        for variant in [
+            "https://somervillema.gov/events/porchfest",
+            "HTTPS://SomervilleMA.gov/events/porchfest/",
+            "somervillema.gov/events/porchfest",
+            "  https://somervillema.gov/events/porchfest#details ",
+            "https://somervillema.gov/events/porchfest?utm_source=flyer&utm_medium=qr",
+            "https://somervillema.gov:443/events/porchfest/?fbclid=abc123",
+        ] {
+            assert_eq!(normalize_url(variant), canonical, "{variant}");
+        }
+
+        // Parameters that pick the page are kept, in order.
+        assert_eq!(
+            normalize_url("https://example.com/event?id=5&utm_campaign=fall&day=2").as_deref(),
+            Some("https://example.com/event?id=5&day=2")
+        );
+        // The path is case-sensitive on most servers, and the root keeps its slash.
+        assert_eq!(
+            normalize_url("https://Example.com/Porchfest").as_deref(),
+            Some("https://example.com/Porchfest")
+        );
+        assert_eq!(
+            normalize_url("https://example.com/").as_deref(),
+            Some("https://example.com/")
+        );
+        assert_eq!(normalize_url("see flyer"), None);
+    }
 }
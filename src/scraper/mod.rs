@@ -0,0 +1,388 @@
+//! Shared plumbing for the binaries in `src/bin` that scrape event listings
+//! straight from a venue's website.
+//!
+//! NOTE: The `scraper` crate has the same name as this module, so always
+//! refer to it as `::scraper` in here.
+
+pub mod somerville_gov;
+pub mod somerville_theatre;
+
+use crate::background_tasks::BackgroundTasks;
+use crate::config::Config;
+use crate::database::{prune_stale_events, save_event_with_outcome, EventsRepo, SaveOutcome};
+use crate::error_reporting;
+use crate::features::upload::hydrate_event_locations;
+use crate::models::{EventSource, NewEvent};
+use crate::webhooks::{self, Webhooks};
+use ::scraper::{Html, Selector};
+use actix_web::rt::time::{sleep, Instant};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::collections::HashSet;
+use std::future::Future;
+use std::time::Duration;
+use url::Url;
+
+// Some sites (looking at you, Drupal + Cloudflare) reject requests that don't
+// look like they come from a browser.
+pub const USER_AGENT: &str =
+    "Mozilla/5.0 (compatible; SomervilleEventsBot/1.0; +https://somerville.events)";
+
+/// Runs `op` up to `max_attempts` times, doubling the delay between attempts.
+/// Returns the last error if every attempt fails.
+pub async fn retry_with_backoff<T, F, Fut>(
+    max_attempts: u32,
+    initial_backoff: Duration,
+    mut op: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut backoff = initial_backoff;
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt >= max_attempts => return Err(e),
+            Err(e) => {
+                log::warn!(
+                    "Attempt {}/{} failed: {:#}. Retrying in {:?}",
+                    attempt,
+                    max_attempts,
+                    e,
+                    backoff
+                );
+                sleep(backoff).await;
+                backoff *= 2;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+pub struct Scraper {
+    client: awc::Client,
+    /// How many times to try each request before giving up.
+    pub max_attempts: u32,
+    /// Delay before the first retry. Doubles on each subsequent retry.
+    pub initial_backoff: Duration,
+    /// How long to wait after each page load before the next request.
+    /// Being polite keeps us off Cloudflare's naughty list.
+    pub page_wait: Duration,
+}
+
+impl Scraper {
+    pub fn new(client: awc::Client) -> Self {
+        Self {
+            client,
+            max_attempts: 4,
+            initial_backoff: Duration::from_secs(2),
+            page_wait: Duration::from_millis(1000),
+        }
+    }
+
+    pub fn client(&self) -> &awc::Client {
+        &self.client
+    }
+
+    pub async fn fetch_html(&self, url: &str) -> Result<String> {
+        let html = retry_with_backoff(self.max_attempts, self.initial_backoff, || {
+            fetch_html_once(&self.client, url)
+        })
+        .await?;
+        sleep(self.page_wait).await;
+        Ok(html)
+    }
+
+    /// Fetches `url` until the page contains `selector` or `timeout` elapses.
+    ///
+    /// When Cloudflare is feeling slow it answers with a 200 "checking your
+    /// browser" page instead of the real listing. A plain fetch would parse
+    /// that as zero events and the run would silently save nothing, so we
+    /// poll until the markup we're after actually shows up.
+    pub async fn wait_for_selector(
+        &self,
+        url: &str,
+        selector: &Selector,
+        timeout: Duration,
+    ) -> Result<String> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let html = self.fetch_html(url).await?;
+            if Html::parse_document(&html)
+                .select(selector)
+                .next()
+                .is_some()
+            {
+                return Ok(html);
+            }
+            if Instant::now() >= deadline {
+                return Err(anyhow!(
+                    "Timed out after {:?} waiting for {:?} on {}",
+                    timeout,
+                    selector,
+                    url
+                ));
+            }
+            log::info!("{} isn't ready yet, trying again", url);
+        }
+    }
+}
+
+/// One website we pull events from. Implementors only need to know how to
+/// turn the site into events; `run_scraper` does everything else.
+// ?Send because awc's futures aren't Send.
+#[async_trait(?Send)]
+pub trait SourceScraper {
+    fn source(&self) -> EventSource;
+
+    /// Every event currently listed on the site. Each one should have an
+    /// `external_id` so re-runs can skip events we already have.
+    async fn scrape_events(&mut self, scraper: &Scraper) -> Result<Vec<NewEvent>>;
+}
+
+/// Shared `main` for the scraper binaries. Understands `--dry-run`,
+/// `--prune` and `--page-wait-ms=N`, skips events that are already saved, geocodes the
+/// rest and writes them to the database.
+pub async fn run_scraper<T: SourceScraper>(mut source_scraper: T) -> Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    let dry_run = args.contains(&"--dry-run".to_string());
+    let prune = args.contains(&"--prune".to_string());
+    let page_wait_ms = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--page-wait-ms="))
+        .map(|ms| ms.parse::<u64>())
+        .transpose()
+        .map_err(|e| anyhow!("Invalid --page-wait-ms: {}", e))?;
+
+    if dry_run {
+        log::info!("Running in DRY-RUN mode. No changes will be saved to DB and no Geocoding API calls will be made.");
+    }
+
+    let config = Config::from_env();
+    let _error_reporting = error_reporting::init(config.sentry_dsn.as_deref());
+    let pool = config
+        .pool_options()
+        .connect(&config.get_db_url())
+        .await
+        .map_err(|e| anyhow!("Failed to connect to database: {}", e))?;
+
+    log::info!("Connected to database");
+
+    let source = source_scraper.source();
+
+    // Skip events we've already saved so re-runs don't pay for geocoding again
+    // (or trip the unique index on (source, external_id)).
+    let existing_ids: HashSet<String> = sqlx::query_scalar!(
+        "SELECT external_id FROM app.events WHERE source = $1 AND external_id IS NOT NULL",
+        source.as_ref()
+    )
+    .fetch_all(&pool)
+    .await?
+    .into_iter()
+    .flatten()
+    .collect();
+
+    log::info!("Found {} existing events in database", existing_ids.len());
+
+    let client = awc::ClientBuilder::new()
+        .timeout(Duration::from_secs(30))
+        .finish();
+    let mut scraper = Scraper::new(client);
+    if let Some(ms) = page_wait_ms {
+        scraper.page_wait = Duration::from_millis(ms);
+    }
+
+    // A site redesign breaks its scraper without anyone noticing, unless
+    // it's reported.
+    let mut scraped = source_scraper
+        .scrape_events(&scraper)
+        .await
+        .inspect_err(|e| error_reporting::capture(e, &[("source", &source)]))?;
+    log::info!("Scraped {} events from {}", scraped.len(), source);
+    // How far to trust a site is a deployment call, not the parser's.
+    let stored = pool
+        .get_source(&source)
+        .await?
+        .and_then(|info| info.default_confidence);
+    let confidence = config.default_confidence(&source, stored);
+    for event in &mut scraped {
+        event.confidence = confidence;
+        // Neither site has a field for it; they retitle the listing.
+        event.take_status_from_name();
+    }
+
+    let scraped_ids: Vec<String> = scraped
+        .iter()
+        .filter_map(|event| event.external_id.clone())
+        .collect();
+
+    let mut seen_ids = HashSet::new();
+    let mut events: Vec<NewEvent> = scraped
+        .into_iter()
+        .filter(|event| match &event.external_id {
+            // The same event can show up twice if a listing shifts while
+            // we're paging through it.
+            Some(id) => !existing_ids.contains(id) && seen_ids.insert(id.clone()),
+            None => {
+                log::warn!("Skipping '{}' because it has no external id", event.name);
+                false
+            }
+        })
+        .collect();
+
+    log::info!("Identified {} new events to process", events.len());
+
+    if dry_run {
+        for event in &events {
+            log::info!(
+                "DRY-RUN: Would insert '{}' at {}",
+                event.name,
+                event.start_date
+            );
+        }
+        return Ok(());
+    }
+
+    // An empty scrape almost certainly means the site broke, not that every
+    // event was cancelled.
+    if prune && !scraped_ids.is_empty() {
+        let pruned_count = prune_stale_events(&pool, &source, &scraped_ids).await?;
+        log::info!("Moved {} stale events to the trash", pruned_count);
+    }
+
+    hydrate_event_locations(
+        &mut events,
+        scraper.client(),
+        config.geocoding_key(),
+        config.api_timeouts.geocoding,
+        config.geocoding_concurrency,
+    )
+    .await;
+
+    let tasks = BackgroundTasks::default();
+    let webhooks = Webhooks::new(
+        config.webhook_urls.clone(),
+        config.webhook_secret.clone(),
+        &config.public_url,
+        tasks.clone(),
+    );
+
+    let mut success_count = 0;
+    let mut db_error_count = 0;
+
+    for event in &events {
+        match save_event_with_outcome(&pool, event).await {
+            Ok(outcome) => {
+                if let SaveOutcome::Inserted(id) = outcome {
+                    webhooks.notify_new_event(id, event);
+                }
+                success_count += 1;
+            }
+            Err(e) => {
+                log::error!("Failed to save event '{}': {}", event.name, e);
+                error_reporting::capture(
+                    &e,
+                    &[
+                        ("source", &source),
+                        (
+                            "external_id",
+                            &event.external_id.as_deref().unwrap_or_default(),
+                        ),
+                    ],
+                );
+                db_error_count += 1;
+            }
+        }
+    }
+
+    log::info!(
+        "Scrape complete. Success: {}, DB Errors: {}",
+        success_count,
+        db_error_count
+    );
+
+    // Deliveries still retrying would die with the process.
+    let undelivered = tasks.shutdown(webhooks::DRAIN_TIMEOUT).await;
+    if undelivered > 0 {
+        log::warn!("Gave up waiting on {} webhook deliveries", undelivered);
+    }
+
+    Ok(())
+}
+
+async fn fetch_html_once(client: &awc::Client, url: &str) -> Result<String> {
+    let mut response = client
+        .get(url)
+        .insert_header(("User-Agent", USER_AGENT))
+        .send()
+        .await
+        .map_err(|e| anyhow!("Failed to fetch {}: {}", url, e))?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "Request to {} failed with status: {}",
+            url,
+            response.status()
+        ));
+    }
+
+    let body = response
+        .body()
+        .limit(10 * 1024 * 1024) // 10MB limit
+        .await
+        .map_err(|e| anyhow!("Failed to read body from {}: {}", url, e))?;
+
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// Event pages have stable URLs, so the path makes a good external ID.
+/// Re-running a scraper then hits the `(source, external_id)` unique index
+/// instead of creating duplicates. We drop the query string and fragment
+/// because those tend to carry tracking junk.
+pub fn external_id_from_url(url: &Url) -> String {
+    url.path().trim_end_matches('/').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[actix_web::test]
+    async fn test_retry_with_backoff_eventually_succeeds() -> Result<()> {
+        let attempts = Cell::new(0);
+
+        let value = retry_with_backoff(3, Duration::from_millis(1), || {
+            attempts.set(attempts.get() + 1);
+            let attempt = attempts.get();
+            async move {
+                if attempt < 3 {
+                    Err(anyhow!("flaky"))
+                } else {
+                    Ok(attempt)
+                }
+            }
+        })
+        .await?;
+
+        assert_eq!(value, 3);
+        assert_eq!(attempts.get(), 3);
+        Ok(())
+    }
+
+    #[actix_web::test]
+    async fn test_retry_with_backoff_gives_up() {
+        let attempts = Cell::new(0);
+
+        let result: Result<()> = retry_with_backoff(2, Duration::from_millis(1), || {
+            attempts.set(attempts.get() + 1);
+            async { Err(anyhow!("down")) }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.get(), 2);
+    }
+}
@@ -0,0 +1,218 @@
+use super::{external_id_from_url, Scraper, SourceScraper};
+use crate::config::DEFAULT_SOURCE_CONFIDENCE;
+use crate::models::{sanitize_url, EventSource, EventStatus, NewEvent};
+use ::scraper::{ElementRef, Html, Selector};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+// The city's own site, so its times are Somerville's whatever TIMEZONE the
+// deployment is set to.
+use chrono_tz::America::New_York;
+use std::sync::LazyLock;
+use std::time::Duration;
+use url::Url;
+
+pub const EVENTS_URL: &str = "https://www.somervillema.gov/events";
+
+// The city lists a few months out at ~10 events per page. This is a safety
+// net in case the pager ever loops back on itself.
+const MAX_PAGES: usize = 20;
+
+const PAGE_TIMEOUT: Duration = Duration::from_secs(60);
+
+// The listing is a stock Drupal view: one `.views-row` per event, with the
+// date(s) in <time> elements. If the city redesigns the site these are what
+// will break, and the fixture test should be refreshed from the new markup.
+/// Present on every listing page, including an empty last page, so it tells
+/// us the real page loaded rather than a Cloudflare interstitial.
+static LISTING: LazyLock<Selector> = LazyLock::new(|| Selector::parse(".view-events").unwrap());
+static ROW: LazyLock<Selector> = LazyLock::new(|| Selector::parse(".views-row").unwrap());
+static TITLE_LINK: LazyLock<Selector> = LazyLock::new(|| Selector::parse("h3 a").unwrap());
+static TIME: LazyLock<Selector> = LazyLock::new(|| Selector::parse(".event-date time").unwrap());
+static LOCATION: LazyLock<Selector> = LazyLock::new(|| Selector::parse(".event-location").unwrap());
+static SUMMARY: LazyLock<Selector> = LazyLock::new(|| Selector::parse(".event-summary").unwrap());
+
+pub struct SomervilleGov;
+
+#[async_trait(?Send)]
+impl SourceScraper for SomervilleGov {
+    fn source(&self) -> EventSource {
+        EventSource::CityOfSomerville
+    }
+
+    async fn scrape_events(&mut self, scraper: &Scraper) -> Result<Vec<NewEvent>> {
+        let mut events = Vec::new();
+        for page in 0..MAX_PAGES {
+            let mut page_url = Url::parse(EVENTS_URL)?;
+            if page > 0 {
+                page_url.set_query(Some(&format!("page={}", page)));
+            }
+
+            log::info!("Fetching {}", page_url);
+            let html = scraper
+                .wait_for_selector(page_url.as_str(), &LISTING, PAGE_TIMEOUT)
+                .await?;
+            let page_events = parse_events(&html, &page_url);
+
+            if page_events.is_empty() {
+                break;
+            }
+            events.extend(page_events);
+        }
+        Ok(events)
+    }
+}
+
+pub fn parse_events(html: &str, page_url: &Url) -> Vec<NewEvent> {
+    let document = Html::parse_document(html);
+
+    document
+        .select(&ROW)
+        .filter_map(|row| {
+            let event = parse_row(row, page_url);
+            if event.is_none() {
+                log::warn!(
+                    "Skipping unparseable event row: {}",
+                    collapse_whitespace(&row.text().collect::<String>())
+                );
+            }
+            event
+        })
+        .collect()
+}
+
+fn parse_row(row: ElementRef, page_url: &Url) -> Option<NewEvent> {
+    let link = row.select(&TITLE_LINK).next()?;
+    let name = collapse_whitespace(&link.text().collect::<String>());
+    if name.is_empty() {
+        return None;
+    }
+    let url = page_url.join(link.value().attr("href")?).ok()?;
+
+    let mut times = row
+        .select(&TIME)
+        .filter_map(|t| t.value().attr("datetime"))
+        .map(parse_datetime_attr);
+    let (start_date, all_day) = times.next()??;
+    let end_date = times.next().flatten().map(|(end, _)| end);
+
+    let location = row
+        .select(&LOCATION)
+        .next()
+        .map(|l| collapse_whitespace(&l.text().collect::<String>()))
+        .filter(|l| !l.is_empty());
+
+    let description = row
+        .select(&SUMMARY)
+        .next()
+        .map(|s| collapse_whitespace(&s.text().collect::<String>()))
+        .unwrap_or_default();
+
+    Some(NewEvent {
+        name,
+        full_text: description.clone(),
+        description,
+        start_date,
+        end_date,
+        all_day,
+        address: None,
+        original_location: location,
+        google_place_id: None,
+        lat: None,
+        lng: None,
+        location_name: None,
+        event_types: vec![],
+        tags: vec![],
+        external_id: Some(external_id_from_url(&url)),
+        contact_email: None,
+        contact_phone: None,
+        registration_required: false,
+        status: EventStatus::Scheduled,
+        url: sanitize_url(Some(url.to_string())),
+        confidence: DEFAULT_SOURCE_CONFIDENCE,
+        age_restrictions: None,
+        price: None,
+        source: EventSource::CityOfSomerville,
+    })
+}
+
+/// Timed events carry a full RFC 3339 timestamp. All-day events (e.g. city
+/// holidays) only have a date, which we pin to local midnight.
+fn parse_datetime_attr(value: &str) -> Option<(DateTime<Utc>, bool)> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some((dt.with_timezone(&Utc), false));
+    }
+
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    let midnight = New_York
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+        .earliest()?;
+    Some((midnight.with_timezone(&Utc), true))
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_events_fixture() {
+        let html = std::fs::read_to_string("examples/somerville_gov_events.html").unwrap();
+        let page_url = Url::parse(EVENTS_URL).unwrap();
+
+        let events = parse_events(&html, &page_url);
+
+        // The fixture has four rows, one of which is missing a date.
+        assert_eq!(events.len(), 3);
+
+        let smash = &events[0];
+        assert_eq!(smash.name, "Pumpkin Smash");
+        assert_eq!(
+            smash.url.as_deref(),
+            Some("https://www.somervillema.gov/events/2025/11/08/pumpkin-smash")
+        );
+        assert_eq!(
+            smash.external_id.as_deref(),
+            Some("/events/2025/11/08/pumpkin-smash")
+        );
+        // 10:30 AM EST = 15:30 UTC
+        assert_eq!(
+            smash.start_date,
+            Utc.with_ymd_and_hms(2025, 11, 8, 15, 30, 0).unwrap()
+        );
+        assert_eq!(
+            smash.end_date,
+            Some(Utc.with_ymd_and_hms(2025, 11, 8, 18, 0, 0).unwrap())
+        );
+        assert!(!smash.all_day);
+        assert_eq!(
+            smash.original_location.as_deref(),
+            Some("Somerville DPW Yard, 1 Franey Road")
+        );
+        assert!(smash.description.starts_with("Bring your jack-o'-lanterns"));
+        assert_eq!(smash.source, EventSource::CityOfSomerville);
+
+        let council = &events[1];
+        assert_eq!(council.name, "City Council Regular Meeting");
+        assert_eq!(council.end_date, None);
+        assert_eq!(
+            council.external_id.as_deref(),
+            Some("/events/2025/11/13/city-council-regular-meeting")
+        );
+
+        let holiday = &events[2];
+        assert_eq!(holiday.name, "Thanksgiving Day - City Offices Closed");
+        assert!(holiday.all_day);
+        assert_eq!(
+            holiday.start_date,
+            New_York
+                .with_ymd_and_hms(2025, 11, 27, 0, 0, 0)
+                .unwrap()
+                .with_timezone(&Utc)
+        );
+        assert_eq!(holiday.original_location, None);
+    }
+}
@@ -0,0 +1,231 @@
+use super::{external_id_from_url, Scraper, SourceScraper};
+use crate::config::DEFAULT_SOURCE_CONFIDENCE;
+use crate::models::{sanitize_url, EventSource, EventStatus, EventType, NewEvent};
+use ::scraper::{ElementRef, Html, Selector};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+// The theatre is in Davis Square, so its showtimes are always Eastern time.
+// This is fixed on purpose rather than read from TIMEZONE: the venue doesn't
+// move with the deployment, and external IDs have to stay stable.
+use chrono_tz::America::New_York;
+use std::sync::LazyLock;
+use url::Url;
+
+pub const SHOWTIMES_URL: &str = "https://www.somervilletheatre.com/now-showing/";
+
+// Every showtime is at the same place, so there's no point reading a
+// location off the page. We don't hardcode a place ID because we'd have to
+// make one up; geocoding this string gives the real one, and it's the same
+// string for every event so it's a single lookup per run.
+const VENUE_NAME: &str = "Somerville Theatre";
+const VENUE_ADDRESS: &str = "55 Davis Square, Somerville, MA 02144";
+
+const PAGE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+// One `.film` per movie with its showtimes listed underneath. Live shows
+// are on a separate page and aren't covered here. If the theatre redesigns
+// the site these are what will break, and the fixture test should be
+// refreshed from the new markup.
+/// Present even when nothing is showing, so it tells us the real page loaded
+/// rather than a Cloudflare interstitial.
+static LISTING: LazyLock<Selector> = LazyLock::new(|| Selector::parse(".now-showing").unwrap());
+static FILM: LazyLock<Selector> = LazyLock::new(|| Selector::parse(".film").unwrap());
+static TITLE_LINK: LazyLock<Selector> = LazyLock::new(|| Selector::parse(".film-title a").unwrap());
+static RUNTIME: LazyLock<Selector> = LazyLock::new(|| Selector::parse(".film-runtime").unwrap());
+static SYNOPSIS: LazyLock<Selector> = LazyLock::new(|| Selector::parse(".film-synopsis").unwrap());
+static SHOWTIME: LazyLock<Selector> = LazyLock::new(|| Selector::parse(".showtimes time").unwrap());
+
+pub struct SomervilleTheatre;
+
+#[async_trait(?Send)]
+impl SourceScraper for SomervilleTheatre {
+    fn source(&self) -> EventSource {
+        EventSource::SomervilleTheatre
+    }
+
+    async fn scrape_events(&mut self, scraper: &Scraper) -> Result<Vec<NewEvent>> {
+        let page_url = Url::parse(SHOWTIMES_URL)?;
+        log::info!("Fetching {}", page_url);
+        let html = scraper
+            .wait_for_selector(page_url.as_str(), &LISTING, PAGE_TIMEOUT)
+            .await?;
+        Ok(parse_showtimes(&html, &page_url))
+    }
+}
+
+/// One event per showtime, since that's what someone can actually go to.
+pub fn parse_showtimes(html: &str, page_url: &Url) -> Vec<NewEvent> {
+    let document = Html::parse_document(html);
+
+    document
+        .select(&FILM)
+        .flat_map(|film| {
+            let events = parse_film(film, page_url);
+            if events.is_empty() {
+                log::warn!(
+                    "No showtimes found for: {}",
+                    collapse_whitespace(&film.text().collect::<String>())
+                );
+            }
+            events
+        })
+        .collect()
+}
+
+fn parse_film(film: ElementRef, page_url: &Url) -> Vec<NewEvent> {
+    let Some(link) = film.select(&TITLE_LINK).next() else {
+        return Vec::new();
+    };
+    let name = collapse_whitespace(&link.text().collect::<String>());
+    let Some(url) = link
+        .value()
+        .attr("href")
+        .and_then(|href| page_url.join(href).ok())
+    else {
+        return Vec::new();
+    };
+    if name.is_empty() {
+        return Vec::new();
+    }
+
+    let runtime = film
+        .select(&RUNTIME)
+        .next()
+        .and_then(|r| parse_runtime(&r.text().collect::<String>()));
+    let description = film
+        .select(&SYNOPSIS)
+        .next()
+        .map(|s| collapse_whitespace(&s.text().collect::<String>()))
+        .unwrap_or_default();
+    let film_id = external_id_from_url(&url);
+
+    film.select(&SHOWTIME)
+        .filter_map(|t| parse_showtime(t.value().attr("datetime")?))
+        .map(|start_date| NewEvent {
+            name: name.clone(),
+            description: description.clone(),
+            full_text: description.clone(),
+            start_date,
+            end_date: runtime.map(|minutes| start_date + minutes),
+            all_day: false,
+            address: Some(VENUE_ADDRESS.to_string()),
+            original_location: Some(format!("{VENUE_NAME}, {VENUE_ADDRESS}")),
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: Some(VENUE_NAME.to_string()),
+            event_types: vec![EventType::Film],
+            tags: vec![],
+            // Every screening of a film shares its page, so the showtime is
+            // what tells them apart. Written in local time so the IDs read
+            // the same as the listing.
+            external_id: Some(format!(
+                "{film_id}@{}",
+                start_date.with_timezone(&New_York).format("%Y-%m-%dT%H:%M")
+            )),
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            status: EventStatus::Scheduled,
+            url: sanitize_url(Some(url.to_string())),
+            confidence: DEFAULT_SOURCE_CONFIDENCE,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::SomervilleTheatre,
+        })
+        .collect()
+}
+
+/// Showtimes usually come as local wall-clock times with no offset, but
+/// accept a full timestamp too in case the site starts sending one.
+fn parse_showtime(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    if let Ok(dt) = DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M%:z") {
+        return Some(dt.with_timezone(&Utc));
+    }
+
+    let local = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S"))
+        .ok()?;
+    Some(
+        local
+            .and_local_timezone(New_York)
+            .earliest()?
+            .with_timezone(&Utc),
+    )
+}
+
+/// "104 min" -> 104 minutes.
+fn parse_runtime(text: &str) -> Option<Duration> {
+    let minutes: i64 = text
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+        .filter(|m| *m > 0)?;
+    Some(Duration::minutes(minutes))
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_showtimes_fixture() {
+        let html = std::fs::read_to_string("examples/somerville_theatre_showtimes.html").unwrap();
+        let page_url = Url::parse(SHOWTIMES_URL).unwrap();
+
+        let events = parse_showtimes(&html, &page_url);
+
+        // Two screenings of one film and one of another. The sold-out
+        // showtime has no usable time and the last film has none at all.
+        assert_eq!(events.len(), 3);
+
+        let saturday = &events[0];
+        assert_eq!(saturday.name, "The Third Man");
+        assert_eq!(
+            saturday.url.as_deref(),
+            Some("https://www.somervilletheatre.com/movie/the-third-man?ref=listing")
+        );
+        assert_eq!(
+            saturday.external_id.as_deref(),
+            Some("/movie/the-third-man@2025-11-08T19:30")
+        );
+        // 7:30 PM EST = 00:30 UTC the next day
+        assert_eq!(
+            saturday.start_date,
+            Utc.with_ymd_and_hms(2025, 11, 9, 0, 30, 0).unwrap()
+        );
+        assert_eq!(
+            saturday.end_date,
+            Some(saturday.start_date + Duration::minutes(104))
+        );
+        assert_eq!(saturday.event_types, vec![EventType::Film]);
+        assert_eq!(saturday.location_name.as_deref(), Some(VENUE_NAME));
+        assert!(saturday.description.starts_with("Pulp novelist"));
+        assert_eq!(saturday.source, EventSource::SomervilleTheatre);
+
+        let sunday = &events[1];
+        assert_eq!(
+            sunday.start_date,
+            Utc.with_ymd_and_hms(2025, 11, 9, 21, 0, 0).unwrap()
+        );
+        assert_eq!(
+            sunday.external_id.as_deref(),
+            Some("/movie/the-third-man@2025-11-09T16:00")
+        );
+
+        let secret = &events[2];
+        assert_eq!(secret.name, "Secret Movie Night");
+        assert_eq!(secret.end_date, None);
+        assert_eq!(secret.description, "");
+    }
+}
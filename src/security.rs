@@ -0,0 +1,173 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::middleware::{DefaultHeaders, Next};
+use actix_web::Error;
+use askama::Template;
+use base64::{engine::general_purpose::STANDARD as b64, Engine as _};
+use sha2::{Digest, Sha256};
+use std::sync::LazyLock;
+
+/// Where the map page loads Leaflet's script, stylesheet and marker icons
+/// from.
+const LEAFLET_ORIGIN: &str = "https://unpkg.com";
+
+/// The map's background tiles.
+const TILE_ORIGIN: &str = "https://tile.openstreetmap.org";
+
+/// A year. Browsers only keep to HTTPS as long as they were last told to.
+const HSTS_MAX_AGE_SECS: u32 = 365 * 24 * 60 * 60;
+
+/// Adds a Content-Security-Policy and the other security headers to every
+/// response. Pages inline their CSS to save a request, so the policy lists a
+/// hash of every page's `<style>` block, worked out once from the templates:
+/// those apply, and a block or `style` attribute that slipped past escaping
+/// doesn't.
+pub async fn security_headers(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    static POLICY: LazyLock<HeaderValue> = LazyLock::new(|| {
+        HeaderValue::from_str(&content_security_policy(&page_style_hashes()))
+            .expect("the policy is plain ASCII")
+    });
+
+    let mut res = next.call(req).await?;
+    let headers = res.headers_mut();
+    headers.insert(header::CONTENT_SECURITY_POLICY, POLICY.clone());
+    headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+    headers.insert(
+        header::REFERRER_POLICY,
+        HeaderValue::from_static("strict-origin-when-cross-origin"),
+    );
+    Ok(res)
+}
+
+/// Tells browsers to only reach us over HTTPS from now on. Only for a site
+/// that's served over HTTPS for good; see `Config::hsts`.
+pub fn strict_transport_security() -> DefaultHeaders {
+    DefaultHeaders::new().add((
+        header::STRICT_TRANSPORT_SECURITY,
+        format!("max-age={HSTS_MAX_AGE_SECS}"),
+    ))
+}
+
+fn content_security_policy(style_hashes: &[String]) -> String {
+    let mut style_src = format!("'self' {LEAFLET_ORIGIN}");
+    for hash in style_hashes {
+        style_src.push(' ');
+        style_src.push_str(hash);
+    }
+    [
+        "default-src 'self'".to_string(),
+        format!("script-src 'self' {LEAFLET_ORIGIN}"),
+        format!("style-src {style_src}"),
+        // The upload form previews the chosen photo from a blob: URL.
+        format!("img-src 'self' data: blob: {LEAFLET_ORIGIN} {TILE_ORIGIN}"),
+        "object-src 'none'".to_string(),
+        "base-uri 'self'".to_string(),
+        "form-action 'self'".to_string(),
+        "frame-ancestors 'none'".to_string(),
+    ]
+    .join("; ")
+}
+
+/// Renders the `style` block of each listed template, and lists the paths
+/// too so the test can check none are missing.
+macro_rules! page_styles {
+    ($($path:tt),* $(,)?) => {
+        #[cfg(test)]
+        const STYLED_TEMPLATES: &[&str] = &[$($path),*];
+
+        fn page_styles() -> Vec<String> {
+            vec![$({
+                #[derive(Template)]
+                #[template(path = $path, block = "style")]
+                struct Style;
+                Style.render().expect("page CSS has no fallible parts")
+            }),*]
+        }
+    };
+}
+
+// Every page with a `<style>` element, which is every page that extends
+// common/index.html. A page missing here has its CSS blocked.
+page_styles!(
+    "about/about.html",
+    "common/error.html",
+    "contact/form.html",
+    "contact/sent.html",
+    "create/create.html",
+    "edit/index.html",
+    "edit/show.html",
+    "edit/stats.html",
+    "edit/trash.html",
+    "login/login.html",
+    "map/index.html",
+    "report/received.html",
+    "upload/preview.html",
+    "upload/success.html",
+    "upload/upload.html",
+    "venue/show.html",
+    "view/index.html",
+    "view/print.html",
+    "view/show.html",
+);
+
+/// CSP source expressions for the contents of each page's `<style>`
+/// element. The CSS is all in the templates, with nothing from the request
+/// or the database, so each page's block is the same every time.
+fn page_style_hashes() -> Vec<String> {
+    let mut hashes: Vec<String> = page_styles()
+        .iter()
+        .map(|css| format!("'sha256-{}'", b64.encode(Sha256::digest(css))))
+        .collect();
+    hashes.sort();
+    hashes.dedup();
+    hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_styled_template_is_hashed() {
+        let mut missing = Vec::new();
+        for entry in std::fs::read_dir("src/features").unwrap() {
+            let dir = entry.unwrap().path();
+            if !dir.is_dir() {
+                continue;
+            }
+            for file in std::fs::read_dir(&dir).unwrap() {
+                let file = file.unwrap().path();
+                if file.extension().is_none_or(|ext| ext != "html") {
+                    continue;
+                }
+                let html = std::fs::read_to_string(&file).unwrap();
+                let styled = html.contains("<style")
+                    || html.contains(r#"{% extends "common/index.html" %}"#);
+                let path = file.strip_prefix("src/features").unwrap();
+                let path = path.to_str().unwrap();
+                if styled && path != "common/index.html" && !STYLED_TEMPLATES.contains(&path) {
+                    missing.push(path.to_string());
+                }
+            }
+        }
+        assert!(missing.is_empty(), "not in page_styles!: {missing:?}");
+    }
+
+    #[test]
+    fn test_policy_only_allows_page_styles() {
+        let policy = content_security_policy(&page_style_hashes());
+        let hash = |css: &str| format!("'sha256-{}'", b64.encode(Sha256::digest(css)));
+        for css in page_styles() {
+            assert!(policy.contains(&hash(&css)));
+        }
+        assert!(!policy.contains(&hash("p { color: red; }")));
+        assert!(!policy.contains("unsafe-inline"));
+    }
+}
@@ -0,0 +1,479 @@
+//! Reading an event's web page for a submission by URL: fetching it the way
+//! a polite robot would, and boiling it down to the text worth sending to
+//! the LLM.
+
+use crate::scraper::USER_AGENT;
+use ::scraper::{Html, Node, Selector};
+use actix_web::web;
+use anyhow::{anyhow, bail, Result};
+use awc::error::PayloadError;
+use awc::http::header;
+use awc::Client;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use std::time::Duration;
+use url::{Host, Url};
+
+/// The name we go by in robots.txt, the product token in `USER_AGENT`.
+const ROBOTS_AGENT: &str = "SomervilleEventsBot";
+
+/// Largest page read, in bytes. An event page is rarely over a few hundred
+/// KB of HTML; anything much bigger is a file download or a trap.
+pub const MAX_PAGE_BYTES: usize = 2 * 1024 * 1024;
+
+/// Largest robots.txt read, the same cap Google uses.
+const MAX_ROBOTS_BYTES: usize = 500 * 1024;
+
+/// Link shorteners and `http` to `https` hops are fine; a chain longer
+/// than this is going in circles.
+const MAX_REDIRECTS: usize = 5;
+
+/// For each request. The submitter is waiting on the form meanwhile.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How much of a page's text goes to the LLM, in characters. Enough for an
+/// event listing, without paying to send a whole blog's archive.
+const MAX_TEXT_CHARS: usize = 20_000;
+
+/// Elements whose text isn't something a reader sees.
+const HIDDEN_ELEMENTS: &[&str] = &["script", "style", "noscript", "template", "svg", "head"];
+
+/// Elements that sit inside a line of text rather than starting a new one.
+const INLINE_ELEMENTS: &[&str] = &[
+    "a", "abbr", "b", "cite", "code", "em", "i", "mark", "q", "s", "small", "span", "strong",
+    "sub", "sup", "time", "u",
+];
+
+/// A fetched event page.
+#[derive(Debug)]
+pub struct Page {
+    /// Where the page ended up after any redirects.
+    pub url: Url,
+    /// See `page_text`.
+    pub text: String,
+}
+
+/// Fetches an event page someone submitted, following redirects by hand so
+/// each hop is checked: only public `http` and `https` addresses, and only
+/// pages the site's robots.txt lets us read. The errors are written to be
+/// shown to the submitter.
+pub async fn fetch_page(url: &Url) -> Result<Page> {
+    let client = Client::builder()
+        .disable_redirects()
+        .timeout(FETCH_TIMEOUT)
+        .finish();
+    let mut url = url.clone();
+
+    for _ in 0..=MAX_REDIRECTS {
+        check_public(&url).await?;
+        if !robots_allow(&client, &url).await {
+            bail!("that site asks robots not to read the page");
+        }
+
+        let mut response = client
+            .get(url.as_str())
+            .insert_header(("User-Agent", USER_AGENT))
+            .insert_header(("Accept", "text/html,application/xhtml+xml"))
+            .send()
+            .await
+            .map_err(|e| anyhow!("couldn't reach the page ({e})"))?;
+
+        if response.status().is_redirection() {
+            let location = response
+                .headers()
+                .get(header::LOCATION)
+                .and_then(|value| value.to_str().ok())
+                .ok_or_else(|| anyhow!("the page redirected nowhere"))?;
+            url = url
+                .join(location)
+                .map_err(|_| anyhow!("the page redirected to a broken link"))?;
+            continue;
+        }
+        if !response.status().is_success() {
+            bail!("the page answered with {}", response.status());
+        }
+        let is_html = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_none_or(|value| value.contains("html"));
+        if !is_html {
+            bail!("that link isn't to a web page");
+        }
+
+        let body = response
+            .body()
+            .limit(MAX_PAGE_BYTES)
+            .await
+            .map_err(|e| match e {
+                PayloadError::Overflow => anyhow!(
+                    "the page is larger than {} MB",
+                    MAX_PAGE_BYTES / 1024 / 1024
+                ),
+                e => anyhow!("couldn't read the page ({e})"),
+            })?;
+        let text = page_text(&String::from_utf8_lossy(&body));
+        return Ok(Page { url, text });
+    }
+
+    bail!("the page redirected too many times")
+}
+
+/// Refuses anything but `http` and `https` URLs whose host is on the
+/// public internet, so a submitted link can't have the server read its own
+/// admin pages or the cloud provider's metadata service. The host is looked
+/// up again when connecting, so a DNS record changed in between could still
+/// slip through; this keeps out the plain cases.
+async fn check_public(url: &Url) -> Result<()> {
+    if !matches!(url.scheme(), "http" | "https") {
+        bail!("only http and https links can be read");
+    }
+    let port = url.port_or_known_default().unwrap_or(80);
+    let addrs: Vec<SocketAddr> = match url.host() {
+        Some(Host::Ipv4(ip)) => vec![SocketAddr::new(ip.into(), port)],
+        Some(Host::Ipv6(ip)) => vec![SocketAddr::new(ip.into(), port)],
+        Some(Host::Domain(domain)) => {
+            let domain = domain.to_string();
+            web::block(move || (domain.as_str(), port).to_socket_addrs())
+                .await
+                .map_err(|e| anyhow!("Blocking task failed: {e}"))?
+                .map_err(|_| anyhow!("couldn't find that site"))?
+                .collect()
+        }
+        None => bail!("that link has no site in it"),
+    };
+    if addrs.is_empty() || !addrs.iter().all(|addr| is_public(addr.ip())) {
+        bail!("that isn't a public web address");
+    }
+    Ok(())
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            // 100.64.0.0/10 is carrier-grade NAT, private in all but name.
+            let shared = a == 100 && (64..128).contains(&b);
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || shared)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(ip.into()),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+/// Whether the site's robots.txt lets us read `url`. A site without one,
+/// or whose robots.txt can't be read, is taken to allow it, as crawlers
+/// generally do.
+async fn robots_allow(client: &Client, url: &Url) -> bool {
+    let Ok(robots_url) = url.join("/robots.txt") else {
+        return true;
+    };
+    let Ok(mut response) = client
+        .get(robots_url.as_str())
+        .insert_header(("User-Agent", USER_AGENT))
+        .send()
+        .await
+    else {
+        return true;
+    };
+    if !response.status().is_success() {
+        return true;
+    }
+    match response.body().limit(MAX_ROBOTS_BYTES).await {
+        Ok(body) => {
+            let path = match url.query() {
+                Some(query) => format!("{}?{query}", url.path()),
+                None => url.path().to_string(),
+            };
+            robots_allows(&String::from_utf8_lossy(&body), &path)
+        }
+        Err(_) => true,
+    }
+}
+
+/// The rules a robots.txt gives some user agents.
+#[derive(Default)]
+struct RobotsGroup {
+    /// Lowercased.
+    agents: Vec<String>,
+    /// Whether each is an `Allow`, and its path pattern.
+    rules: Vec<(bool, String)>,
+}
+
+/// Checks `path` against a robots.txt, following RFC 9309: the group for
+/// our agent if there is one, otherwise the `*` group, and within it the
+/// longest matching rule, `Allow` winning a tie.
+pub fn robots_allows(robots_txt: &str, path: &str) -> bool {
+    let mut groups: Vec<RobotsGroup> = Vec::new();
+    let mut in_agent_lines = false;
+    for line in robots_txt.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "user-agent" => {
+                if !in_agent_lines {
+                    groups.push(RobotsGroup::default());
+                }
+                in_agent_lines = true;
+                if let Some(group) = groups.last_mut() {
+                    group.agents.push(value.to_ascii_lowercase());
+                }
+            }
+            key @ ("allow" | "disallow") => {
+                in_agent_lines = false;
+                // An empty Disallow allows everything, same as no rule.
+                if let (Some(group), false) = (groups.last_mut(), value.is_empty()) {
+                    group.rules.push((key == "allow", value.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let ours = ROBOTS_AGENT.to_ascii_lowercase();
+    let mut rules: Vec<&(bool, String)> = groups
+        .iter()
+        .filter(|group| group.agents.contains(&ours))
+        .flat_map(|group| &group.rules)
+        .collect();
+    if rules.is_empty() {
+        rules = groups
+            .iter()
+            .filter(|group| group.agents.iter().any(|agent| agent == "*"))
+            .flat_map(|group| &group.rules)
+            .collect();
+    }
+
+    rules
+        .into_iter()
+        .filter(|(_, pattern)| rule_matches(pattern, path))
+        .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+        .is_none_or(|(allow, _)| *allow)
+}
+
+/// A robots.txt path pattern: a prefix, where `*` matches anything and a
+/// trailing `$` anchors the end.
+fn rule_matches(pattern: &str, path: &str) -> bool {
+    match pattern.strip_suffix('$') {
+        Some(pattern) => glob(pattern.as_bytes(), path.as_bytes(), true),
+        None => glob(pattern.as_bytes(), path.as_bytes(), false),
+    }
+}
+
+fn glob(pattern: &[u8], path: &[u8], anchored: bool) -> bool {
+    match pattern.split_first() {
+        None => !anchored || path.is_empty(),
+        Some((b'*', rest)) => (0..=path.len()).any(|at| glob(rest, &path[at..], anchored)),
+        Some((c, rest)) => path
+            .split_first()
+            .is_some_and(|(p, path)| p == c && glob(rest, path, anchored)),
+    }
+}
+
+/// What the LLM is given of a page: its title and summary from the
+/// OpenGraph and description tags, any schema.org data (often a full
+/// `Event`), and the text a reader would see, up to `MAX_TEXT_CHARS`.
+pub fn page_text(html: &str) -> String {
+    let document = Html::parse_document(html);
+    let mut sections: Vec<String> = Vec::new();
+
+    let mut add = |label: &str, text: &str| {
+        let text = collapse_whitespace(text);
+        if !text.is_empty() && !sections.iter().any(|section| section.ends_with(&text)) {
+            sections.push(format!("{label}: {text}"));
+        }
+    };
+    if let Some(title) = document.select(&selector("title")).next() {
+        add("Title", &title.text().collect::<String>());
+    }
+    for (label, css) in [
+        ("Title", "meta[property='og:title']"),
+        ("Description", "meta[property='og:description']"),
+        ("Description", "meta[name='description']"),
+    ] {
+        for meta in document.select(&selector(css)) {
+            add(label, meta.value().attr("content").unwrap_or_default());
+        }
+    }
+    for script in document.select(&selector("script[type='application/ld+json']")) {
+        add("Structured data", &script.text().collect::<String>());
+    }
+
+    let mut body = String::new();
+    let mut last_inline = false;
+    // Whether the source had a space before the next text, which matters
+    // where inline elements meet: "from <b>Bob</b>, with" has one, not two.
+    let mut space = false;
+    for node in document.root_element().descendants() {
+        let Node::Text(text) = node.value() else {
+            continue;
+        };
+        let mut elements = node.ancestors().filter_map(|a| a.value().as_element());
+        let parent = elements
+            .clone()
+            .next()
+            .map(|e| e.name())
+            .unwrap_or_default();
+        if elements.any(|e| HIDDEN_ELEMENTS.contains(&e.name())) {
+            continue;
+        }
+        let raw: &str = text;
+        let text = collapse_whitespace(raw);
+        space |= raw.starts_with(char::is_whitespace);
+        if text.is_empty() {
+            continue;
+        }
+        let inline = INLINE_ELEMENTS.contains(&parent);
+        if !body.is_empty() {
+            if !(inline || last_inline) {
+                body.push('\n');
+            } else if space {
+                body.push(' ');
+            }
+        }
+        body.push_str(&text);
+        last_inline = inline;
+        space = raw.ends_with(char::is_whitespace);
+    }
+    if !body.is_empty() {
+        sections.push(format!("Page text:\n{body}"));
+    }
+
+    let text = sections.join("\n\n");
+    match text.char_indices().nth(MAX_TEXT_CHARS) {
+        Some((end, _)) => text[..end].to_string(),
+        None => text,
+    }
+}
+
+fn selector(css: &str) -> Selector {
+    Selector::parse(css).expect("selectors here are valid")
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_text_from_fixture() {
+        let html = std::fs::read_to_string("examples/event_page.html").unwrap();
+        let text = page_text(&html);
+
+        assert!(
+            text.starts_with("Title: Fall Contra Dance | Somerville Dance Collective\n\n"),
+            "{text}"
+        );
+        assert!(
+            text.contains("Title: Fall Contra Dance at the Armory"),
+            "{text}"
+        );
+        assert!(
+            text.contains("Description: Join us Saturday, November 8"),
+            "{text}"
+        );
+        assert!(
+            text.contains(r#""startDate": "2025-11-08T19:00""#),
+            "{text}"
+        );
+        assert!(
+            text.contains("Live music from The Gallery Players, calling by Lisa Greenleaf."),
+            "{text}"
+        );
+        assert!(
+            text.contains("Saturday, November 8, 7:00–10:30 PM\nArts at the Armory"),
+            "{text}"
+        );
+        // Scripts, styles and the like aren't read.
+        for hidden in [
+            "gtag",
+            "font-family",
+            "tracking.example.com",
+            "logo",
+            "classList",
+        ] {
+            assert!(!text.contains(hidden), "{hidden} in {text}");
+        }
+    }
+
+    #[test]
+    fn test_page_text_is_capped() {
+        let html = format!("<p>{}</p>", "é".repeat(MAX_TEXT_CHARS * 2));
+        assert_eq!(page_text(&html).chars().count(), MAX_TEXT_CHARS);
+    }
+
+    #[test]
+    fn test_robots_allows() {
+        let robots = "\
+            User-agent: *\n\
+            Disallow: /private/\n\
+            Disallow: /*.pdf$\n\
+            Allow: /private/events/\n\
+            \n\
+            User-agent: GPTBot\n\
+            Disallow: /\n";
+        assert!(robots_allows(robots, "/events/contra"));
+        assert!(!robots_allows(robots, "/private/members"));
+        assert!(robots_allows(robots, "/private/events/contra"));
+        assert!(!robots_allows(robots, "/flyers/contra.pdf"));
+        assert!(robots_allows(robots, "/flyers/contra.pdf?page=1"));
+
+        // Our own group replaces the `*` one.
+        let robots = "\
+            User-agent: *\n\
+            Disallow: /\n\
+            \n\
+            User-agent: somervilleeventsbot\n\
+            User-agent: OtherBot\n\
+            Disallow: /admin # staff only\n";
+        assert!(robots_allows(robots, "/events/contra"));
+        assert!(!robots_allows(robots, "/admin/events"));
+
+        assert!(robots_allows("", "/anything"));
+        assert!(robots_allows("User-agent: *\nDisallow:\n", "/anything"));
+    }
+
+    #[actix_web::test]
+    async fn test_only_public_addresses_are_fetched() {
+        for url in [
+            "http://127.0.0.1/",
+            "http://localhost:8080/edit",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://10.0.0.5/",
+            "http://[::1]/",
+            "http://[::ffff:192.168.1.1]/",
+            "file:///etc/passwd",
+        ] {
+            let err = fetch_page(&Url::parse(url).unwrap()).await.expect_err(url);
+            assert!(
+                err.to_string().contains("public web address")
+                    || err.to_string().contains("only http and https"),
+                "{url}: {err:#}"
+            );
+        }
+        assert!(is_public("93.184.215.14".parse().unwrap()));
+        assert!(is_public(
+            "2606:2800:21f:cb07:6820:80da:af6b:8b2c".parse().unwrap()
+        ));
+    }
+}
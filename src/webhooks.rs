@@ -0,0 +1,315 @@
+//! Tells other systems (a Discord bot, a Slack channel...) about new events,
+//! and about ones that get cancelled or postponed, by POSTing each one as
+//! JSON to the URLs in `WEBHOOK_URLS`. Delivery runs in the background so a
+//! slow receiver never holds up an upload or ingest.
+
+use crate::background_tasks::BackgroundTasks;
+use crate::error_reporting;
+use crate::models::NewEvent;
+use actix_web::http::header::ContentType;
+use actix_web::rt::time::sleep;
+use anyhow::{anyhow, Result};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use std::time::Duration;
+
+/// Hex HMAC-SHA256 of the body, keyed with `WEBHOOK_SECRET`, as
+/// `sha256=<hex>`. Receivers recompute it to check the POST came from us.
+pub const SIGNATURE_HEADER: &str = "X-Somerville-Events-Signature";
+
+/// Tries per receiver before the event is given up on for it.
+const ATTEMPTS: u32 = 3;
+/// Doubled after each failed attempt.
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Long enough for every attempt against a receiver that times out each
+/// time, with the waits between them.
+pub const DRAIN_TIMEOUT: Duration = Duration::from_secs(40);
+
+/// What happened to the event, so a receiver can edit the post it made
+/// for it instead of posting it again.
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum Action {
+    Created,
+    /// Its `status` changed. Sent again with the same `id`, so receivers
+    /// can find what they posted the first time.
+    Updated,
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    action: Action,
+    id: i64,
+    /// The event's page on this site.
+    url: String,
+    event: &'a NewEvent,
+}
+
+/// Where to send new events. The default has no receivers, so notifying
+/// does nothing.
+#[derive(Clone, Default)]
+pub struct Webhooks {
+    urls: Vec<String>,
+    secret: String,
+    public_url: String,
+    tasks: BackgroundTasks,
+}
+
+impl Webhooks {
+    /// Deliveries are spawned on `tasks`, so whoever drains those on
+    /// shutdown waits for them too.
+    pub fn new(
+        urls: Vec<String>,
+        secret: String,
+        public_url: &str,
+        tasks: BackgroundTasks,
+    ) -> Self {
+        Self {
+            urls,
+            secret,
+            public_url: public_url.trim_end_matches('/').to_string(),
+            tasks,
+        }
+    }
+
+    /// The JSON receivers get for the event, pretty-printed, for checking
+    /// what a receiver was sent without sending anything. What's signed
+    /// and sent is the same payload without the whitespace.
+    pub fn preview(&self, id: i64, event: &NewEvent) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.payload(Action::Created, id, event))
+    }
+
+    fn payload<'a>(&self, action: Action, id: i64, event: &'a NewEvent) -> Payload<'a> {
+        Payload {
+            action,
+            id,
+            url: format!("{}/event/{id}", self.public_url),
+            event,
+        }
+    }
+
+    /// Sends the event just saved as `id` to every receiver, in the
+    /// background. Only call this for events that are actually new, not
+    /// for a duplicate that resolved to an existing id.
+    pub fn notify_new_event(&self, id: i64, event: &NewEvent) {
+        self.notify(Action::Created, id, event);
+    }
+
+    /// Sends event `id` again after it was cancelled, postponed or put back
+    /// on, so receivers don't leave people thinking it's still happening.
+    pub fn notify_updated_event(&self, id: i64, event: &NewEvent) {
+        self.notify(Action::Updated, id, event);
+    }
+
+    fn notify(&self, action: Action, id: i64, event: &NewEvent) {
+        if self.urls.is_empty() {
+            return;
+        }
+
+        let body = match serde_json::to_vec(&self.payload(action, id, event)) {
+            Ok(body) => body,
+            Err(e) => {
+                log::error!("Failed to serialize webhook payload for event {id}: {e}");
+                return;
+            }
+        };
+        let signature = sign(&self.secret, &body);
+
+        for url in &self.urls {
+            let task_url = url.clone();
+            let body = body.clone();
+            let signature = signature.clone();
+            let spawned = self.tasks.spawn(async move {
+                let url = task_url;
+                if let Err(e) = deliver(&url, &signature, body).await {
+                    log::error!("Gave up sending event {id} to webhook {url}: {e:#}");
+                    error_reporting::capture(&e, &[("event_id", &id), ("webhook_url", &url)]);
+                }
+            });
+            if !spawned {
+                log::warn!("Shutting down, so event {id} wasn't sent to webhook {url}");
+            }
+        }
+    }
+}
+
+/// `sha256=` and the hex HMAC-SHA256 of `body`, for `SIGNATURE_HEADER`.
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes a key of any length");
+    mac.update(body);
+    let hex: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    format!("sha256={hex}")
+}
+
+async fn deliver(url: &str, signature: &str, body: Vec<u8>) -> Result<()> {
+    // awc clients can't cross threads, and this runs on whichever worker
+    // saved the event.
+    let client = awc::Client::default();
+    let mut delay = FIRST_RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        match post(&client, url, signature, body.clone()).await {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt < ATTEMPTS => {
+                log::warn!("Webhook {url} failed (attempt {attempt} of {ATTEMPTS}): {e:#}");
+                sleep(delay).await;
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+async fn post(client: &awc::Client, url: &str, signature: &str, body: Vec<u8>) -> Result<()> {
+    let response = client
+        .post(url)
+        .timeout(REQUEST_TIMEOUT)
+        .insert_header(ContentType::json())
+        .insert_header((SIGNATURE_HEADER, signature))
+        .send_body(body)
+        .await
+        .map_err(|e| anyhow!("Request failed: {e}"))?;
+
+    if !response.status().is_success() {
+        return Err(anyhow!("Receiver returned status {}", response.status()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{EventSource, EventStatus};
+    use actix_web::{web, App, HttpRequest, HttpResponse, HttpServer};
+    use chrono::Utc;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_sign_matches_rfc_4231() {
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    fn porchfest() -> NewEvent {
+        NewEvent {
+            name: "Porchfest".to_string(),
+            description: "Bands on porches".to_string(),
+            full_text: String::new(),
+            start_date: Utc::now(),
+            end_date: None,
+            all_day: false,
+            address: None,
+            original_location: None,
+            google_place_id: None,
+            lat: None,
+            lng: None,
+            location_name: None,
+            event_types: vec![],
+            tags: vec![],
+            url: None,
+            confidence: 1.0,
+            age_restrictions: None,
+            price: None,
+            source: EventSource::ImageUpload,
+            external_id: None,
+            contact_email: None,
+            contact_phone: None,
+            registration_required: false,
+            status: EventStatus::Scheduled,
+        }
+    }
+
+    #[test]
+    fn test_cancellation_is_sent_as_an_update_not_a_removal() -> Result<()> {
+        let webhooks = Webhooks::new(
+            vec![],
+            String::new(),
+            "https://example.com",
+            BackgroundTasks::default(),
+        );
+        let mut event = porchfest();
+        event.status = EventStatus::Cancelled;
+
+        let payload = serde_json::to_value(webhooks.payload(Action::Updated, 7, &event))?;
+        assert_eq!(payload["action"], "updated");
+        assert_eq!(payload["id"], 7);
+        assert_eq!(payload["url"], "https://example.com/event/7");
+        assert_eq!(payload["event"]["name"], "Porchfest");
+        assert_eq!(payload["event"]["status"], "cancelled");
+        Ok(())
+    }
+
+    type Received = Arc<Mutex<Vec<(Option<String>, web::Bytes)>>>;
+
+    #[actix_web::test]
+    async fn test_new_event_is_signed_and_retried_until_delivered() -> Result<()> {
+        // Fails the first POST so the retry gets exercised too.
+        let received: Received = Arc::default();
+        let handler_received = received.clone();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        let receiver_url = format!("http://{}/hook", listener.local_addr()?);
+        let server = HttpServer::new(move || {
+            let received = handler_received.clone();
+            App::new().route(
+                "/hook",
+                web::post().to(move |req: HttpRequest, body: web::Bytes| {
+                    let received = received.clone();
+                    async move {
+                        let signature = req
+                            .headers()
+                            .get(SIGNATURE_HEADER)
+                            .and_then(|v| v.to_str().ok())
+                            .map(str::to_string);
+                        let mut received = received.lock().unwrap();
+                        received.push((signature, body));
+                        if received.len() == 1 {
+                            HttpResponse::ServiceUnavailable().finish()
+                        } else {
+                            HttpResponse::NoContent().finish()
+                        }
+                    }
+                }),
+            )
+        })
+        .workers(1)
+        .listen(listener)?
+        .run();
+        let server_handle = server.handle();
+        actix_web::rt::spawn(server);
+
+        let tasks = BackgroundTasks::default();
+        let webhooks = Webhooks::new(
+            vec![receiver_url],
+            "hunter2".to_string(),
+            "https://example.com/",
+            tasks.clone(),
+        );
+        webhooks.notify_new_event(7, &porchfest());
+        assert_eq!(tasks.shutdown(Duration::from_secs(10)).await, 0);
+        server_handle.stop(true).await;
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        let (signature, body) = &received[1];
+        assert_eq!(signature.as_deref(), Some(sign("hunter2", body).as_str()));
+        let payload: serde_json::Value = serde_json::from_slice(body)?;
+        assert_eq!(payload["action"], "created");
+        assert_eq!(payload["id"], 7);
+        assert_eq!(payload["url"], "https://example.com/event/7");
+        assert_eq!(payload["event"]["name"], "Porchfest");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+const mapElement = document.querySelector("#map");
+const dataElement = document.querySelector("#map-events");
+
+if (!mapElement || !dataElement || !window.L) {
+    throw new Error("Map element, event data or Leaflet not found");
+}
+
+const events = JSON.parse(dataElement.textContent);
+
+// Roughly the center of Cambridge + Somerville, same as geocoding uses.
+const map = L.map(mapElement).setView([42.383971, -71.1086], 13);
+
+L.tileLayer("https://tile.openstreetmap.org/{z}/{x}/{y}.png", {
+    maxZoom: 19,
+    attribution: '&copy; <a href="https://www.openstreetmap.org/copyright">OpenStreetMap</a> contributors',
+}).addTo(map);
+
+const dateFormat = new Intl.DateTimeFormat(undefined, {
+    weekday: "short",
+    month: "short",
+    day: "numeric",
+    hour: "numeric",
+    minute: "2-digit",
+    timeZone: "America/New_York",
+});
+
+for (const event of events) {
+    // Build the popup from DOM nodes so event names are never parsed as HTML.
+    const popup = document.createElement("div");
+    const link = document.createElement("a");
+    link.href = event.url;
+    link.textContent = event.name;
+    popup.append(link, document.createElement("br"), dateFormat.format(new Date(event.start)));
+    if (event.location_name) {
+        popup.append(document.createElement("br"), event.location_name);
+    }
+
+    L.circleMarker([event.lat, event.lng], { radius: 8 }).bindPopup(popup).addTo(map);
+}
+
+if (events.length > 0) {
+    map.fitBounds(events.map((event) => [event.lat, event.lng]), { padding: [24, 24], maxZoom: 15 });
+}